@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Run with `cargo +nightly fuzz run decompress_untrusted`; seed the corpus
+// with the blobs `cydec::test_vectors::write_dir` writes.
+fuzz_target!(|data: &[u8]| {
+    let _ = cydec::decompress_untrusted(data, 1 << 20);
+    let _ = cydec::validate(data);
+});
@@ -0,0 +1,202 @@
+#ifndef CYDEC_H
+#define CYDEC_H
+
+/* Generated by cbindgen from src/ffi.rs; build with `--features ffi` to refresh. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// The call succeeded
+#define CYDEC_OK 0
+
+// A pointer was null or the parameters cannot be encoded
+#define CYDEC_ERR_INVALID_ARGUMENT 1
+
+// The blob is truncated, damaged or not a cydec blob
+#define CYDEC_ERR_CORRUPT 2
+
+// The blob was written by a newer format version
+#define CYDEC_ERR_UNSUPPORTED_VERSION 3
+
+// The blob holds a different element type than the function decodes
+#define CYDEC_ERR_TYPE_MISMATCH 4
+
+// The payload does not match its checksum or signature
+#define CYDEC_ERR_CHECKSUM 5
+
+// Rust code panicked; the panic stopped at the boundary
+#define CYDEC_ERR_PANIC 6
+
+// Values or blob bytes returned to C.
+//
+// `data` holds `len` elements of `elem_size` bytes each. Release it with
+// [`cydec_buffer_free`]; an empty buffer has a null `data`.
+typedef struct CydecBuffer {
+  void *data;
+  size_t len;
+  size_t elem_size;
+} CydecBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Release a buffer filled by a cydec function. Empty buffers are ignored.
+//
+// # Safety
+//
+// `buffer` must come unchanged from a cydec function and not have been
+// freed before.
+void cydec_buffer_free(struct CydecBuffer buffer);
+
+// Message of the last failed call on this thread, empty when none has
+// failed. The string stays valid until the next failure on the thread.
+const char *cydec_last_error(void);
+
+// Compress `len` timestamps or other i64 values at `data` into `out`.
+//
+// # Safety
+//
+// `data` must point to `len` readable values, or be null with `len` 0,
+// and `out` to a writable buffer.
+int32_t cydec_compress_i64(const int64_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode an i64 blob of `len` bytes at `blob` into `out`.
+//
+// # Safety
+//
+// `blob` must point to `len` readable bytes, or be null with `len` 0, and
+// `out` to a writable buffer.
+int32_t cydec_decompress_i64(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` u64 values at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_u64(const uint64_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode a u64 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_u64(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` i32 values at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_i32(const int32_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode an i32 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_i32(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` u32 values at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_u32(const uint32_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode a u32 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_u32(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` i16 values at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_i16(const int16_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode an i16 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_i16(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` u16 values at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_u16(const uint16_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode a u16 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_u16(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` i8 values at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_i8(const int8_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode an i8 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_i8(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` raw bytes at `data` into `out`.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_bytes(const uint8_t *data, size_t len, struct CydecBuffer *out);
+
+// Decode a byte blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_bytes(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` f64 values at `data` into `out` at the default scale.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_f64(const double *data, size_t len, struct CydecBuffer *out);
+
+// Decode an f64 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_f64(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+// Compress `len` f32 values at `data` into `out` at the default scale.
+//
+// # Safety
+//
+// As for [`cydec_compress_i64`].
+int32_t cydec_compress_f32(const float *data, size_t len, struct CydecBuffer *out);
+
+// Decode an f32 blob into `out`.
+//
+// # Safety
+//
+// As for [`cydec_decompress_i64`].
+int32_t cydec_decompress_f32(const uint8_t *blob, size_t len, struct CydecBuffer *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CYDEC_H */
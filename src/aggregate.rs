@@ -0,0 +1,169 @@
+use crate::blocks::BlockStats;
+use crate::error::Result;
+use crate::floating_codec::BLOCKED_F64;
+use crate::{FloatingCodec, IntegerCodec};
+
+/// Rollup of a column, computed without materializing it.
+///
+/// Integer sums are kept as `i128` so adding up a long i64 column cannot
+/// overflow; float rollups use `f64` throughout.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Aggregate<T, S = T> {
+    pub count: usize,
+    pub sum: S,
+    /// `None` for an empty column
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl Aggregate<i64, i128> {
+    fn push(&mut self, x: i64) {
+        self.count += 1;
+        self.sum += x as i128;
+        self.min = Some(self.min.map_or(x, |m| m.min(x)));
+        self.max = Some(self.max.map_or(x, |m| m.max(x)));
+    }
+
+    fn merge(&mut self, stats: &BlockStats) {
+        self.count += stats.count;
+        self.sum += stats.sum;
+        self.min = Some(self.min.map_or(stats.min, |m| m.min(stats.min)));
+        self.max = Some(self.max.map_or(stats.max, |m| m.max(stats.max)));
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+    }
+}
+
+impl Aggregate<f64> {
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        self.sum += x;
+        self.min = Some(self.min.map_or(x, |m| m.min(x)));
+        self.max = Some(self.max.map_or(x, |m| m.max(x)));
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+impl IntegerCodec {
+    /// Count, sum, min and max of an i64 blob.
+    ///
+    /// Blocks with stored [`BlockStats`] are merged without decoding;
+    /// the rest are decoded one at a time and folded as they go, so memory
+    /// stays at one block however long the column is.
+    pub fn aggregate_i64(&self, blob: &[u8]) -> Result<Aggregate<i64, i128>> {
+        let mut agg = Aggregate::default();
+        if blob.is_empty() {
+            return Ok(agg);
+        }
+        let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+        for block in blocks.iter().filter(|b| b.count > 0) {
+            match block.stats {
+                Some(stats) => agg.merge(&stats),
+                None => {
+                    Self::decode_i64_payload_each(block.payload, block.count, backend, |_, x| {
+                        agg.push(x)
+                    })?
+                }
+            }
+        }
+        Ok(agg)
+    }
+
+    /// Number of values in an i64 blob, read from its headers alone
+    pub fn count_i64(&self, blob: &[u8]) -> Result<usize> {
+        if blob.is_empty() {
+            return Ok(0);
+        }
+        Ok(Self::open_i64_blob(blob)?.0)
+    }
+}
+
+impl FloatingCodec {
+    /// Count, sum, min and max of an f64 blob.
+    ///
+    /// Fixed-scale blobs are folded on their scaled integers block by
+    /// block, dividing by the scale once at the end; other layouts are
+    /// decoded first. `scale` overrides the stored one as in
+    /// [`Self::decompress_f64`].
+    pub fn aggregate_f64(&self, blob: &[u8], scale: Option<f64>) -> Result<Aggregate<f64>> {
+        if !matches!(blob.get(7), Some(&(4 | BLOCKED_F64))) {
+            let mut agg = Aggregate::<f64>::default();
+            for x in self.decompress_f64(blob, scale)? {
+                agg.push(x);
+            }
+            return Ok(agg);
+        }
+
+        let (_, backend, stored, blocks) = Self::open_scaled_f64(blob)?;
+        let scale = scale.unwrap_or(stored);
+        let mut ints = Aggregate::<i64, i128>::default();
+        for block in &blocks {
+            IntegerCodec::decode_i64_payload_each(block.payload, block.count, backend, |_, x| {
+                ints.push(x)
+            })?;
+        }
+        let (a, b) = (
+            ints.min.map(|m| m as f64 / scale),
+            ints.max.map(|m| m as f64 / scale),
+        );
+        // a negative scale override flips the order
+        let (min, max) = if scale < 0.0 { (b, a) } else { (a, b) };
+        Ok(Aggregate {
+            count: ints.count,
+            sum: ints.sum as f64 / scale,
+            min,
+            max,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_rollups_match_the_decoded_column() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..200_000)
+            .map(|i| (i * 7919) % 100_003 - 50_000)
+            .collect();
+        let blob = c.compress_i64(&data)?;
+        let agg = c.aggregate_i64(&blob)?;
+        assert_eq!(agg.count, data.len());
+        assert_eq!(agg.sum, data.iter().map(|&x| x as i128).sum::<i128>());
+        assert_eq!(agg.min, data.iter().copied().min());
+        assert_eq!(agg.max, data.iter().copied().max());
+        assert_eq!(agg.mean(), Some(agg.sum as f64 / data.len() as f64));
+        assert_eq!(c.count_i64(&blob)?, data.len());
+
+        let big = c.compress_i64(&[i64::MAX, i64::MAX])?;
+        assert_eq!(c.aggregate_i64(&big)?.sum, 2 * i64::MAX as i128);
+        let empty = c.aggregate_i64(&[])?;
+        assert_eq!((empty.count, empty.min, empty.mean()), (0, None, None));
+        Ok(())
+    }
+
+    #[test]
+    fn float_rollups_use_the_scaled_integers() -> Result<()> {
+        let c = FloatingCodec::default();
+        let data: Vec<f64> = (0..100_000)
+            .map(|i| (i % 1_000) as f64 * 0.25 - 10.0)
+            .collect();
+        let agg = c.aggregate_f64(&c.compress_f64(&data, None)?, None)?;
+        assert_eq!(agg.count, data.len());
+        assert_eq!(agg.min, Some(-10.0));
+        assert_eq!(agg.max, Some(239.75));
+        assert!((agg.sum - data.iter().sum::<f64>()).abs() < 1e-6);
+
+        let groomed = c.compress_f64_groomed(&data, 20)?;
+        let other = c.aggregate_f64(&groomed, None)?;
+        assert_eq!(other.count, data.len());
+        assert!((other.mean().unwrap() - agg.mean().unwrap()).abs() < 1e-3);
+        Ok(())
+    }
+}
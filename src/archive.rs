@@ -0,0 +1,292 @@
+use crate::CydecError;
+use crate::error::{Result, bail, corrupt, invalid};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+/// Archive magic + format version, at the start of the file
+const ARCHIVE_MAGIC: &[u8; 5] = b"CYARC";
+const ARCHIVE_VERSION: u8 = 1;
+const ARCHIVE_HEADER_LEN: usize = 8;
+
+/// Footer: index offset u64 + entry count u32 + footer magic
+const FOOTER_MAGIC: &[u8; 4] = b"CYAF";
+const FOOTER_LEN: usize = 16;
+
+/// Where one blob lives inside an archive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    /// Type byte of the stored blob's header; 0 for an empty blob
+    pub type_tag: u8,
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// Writes many named blobs into one archive file.
+///
+/// Layout: an 8-byte header (`CYARC` + version + 2 reserved bytes), the
+/// blobs back to back, then an index of `[u16 name len][name][u8 type]
+/// [u64 offset][u64 len]` entries and a 16-byte footer
+/// `[u64 index offset][u32 entry count][CYAF]`. Blobs are copied as is, so
+/// each one can still be decoded on its own.
+#[derive(Debug)]
+pub struct ArchiveWriter<W: Write> {
+    inner: W,
+    offset: u64,
+    entries: Vec<ArchiveEntry>,
+    names: HashMap<String, usize>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(mut inner: W) -> Result<Self> {
+        let mut header = [0u8; ARCHIVE_HEADER_LEN];
+        header[..5].copy_from_slice(ARCHIVE_MAGIC);
+        header[5] = ARCHIVE_VERSION;
+        inner.write_all(&header)?;
+        Ok(Self {
+            inner,
+            offset: ARCHIVE_HEADER_LEN as u64,
+            entries: Vec::new(),
+            names: HashMap::new(),
+        })
+    }
+
+    /// Store `blob` under `name`; names must be unique and at most 64 KiB
+    pub fn add(&mut self, name: &str, blob: &[u8]) -> Result<()> {
+        if u16::try_from(name.len()).is_err() {
+            return Err(invalid!("entry name of {} bytes is too long", name.len()));
+        }
+        if self.names.contains_key(name) {
+            return Err(invalid!("duplicate archive entry {name:?}"));
+        }
+        self.inner.write_all(blob)?;
+        self.names.insert(name.to_owned(), self.entries.len());
+        self.entries.push(ArchiveEntry {
+            name: name.to_owned(),
+            type_tag: blob.get(7).copied().unwrap_or(0),
+            offset: self.offset,
+            len: blob.len() as u64,
+        });
+        self.offset += blob.len() as u64;
+        Ok(())
+    }
+
+    /// Write the index and footer and return the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        let mut index = Vec::new();
+        for e in &self.entries {
+            index.extend_from_slice(&(e.name.len() as u16).to_le_bytes());
+            index.extend_from_slice(e.name.as_bytes());
+            index.push(e.type_tag);
+            index.extend_from_slice(&e.offset.to_le_bytes());
+            index.extend_from_slice(&e.len.to_le_bytes());
+        }
+        let count =
+            u32::try_from(self.entries.len()).map_err(|_| invalid!("too many archive entries"))?;
+        index.extend_from_slice(&self.offset.to_le_bytes());
+        index.extend_from_slice(&count.to_le_bytes());
+        index.extend_from_slice(FOOTER_MAGIC);
+        self.inner.write_all(&index)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Read side of [`ArchiveWriter`]: parses the index once, then hands out
+/// blobs by name without copying.
+///
+/// Works over any byte container, e.g. a `Vec<u8>` from
+/// [`Archive::open`] or, with the `mmap` feature, a
+/// [`crate::MappedBlob`] from `Archive::open_mapped`.
+#[derive(Debug)]
+pub struct Archive<B: AsRef<[u8]>> {
+    data: B,
+    entries: Vec<ArchiveEntry>,
+    names: HashMap<String, usize>,
+}
+
+impl Archive<Vec<u8>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Archive<crate::MappedBlob> {
+    pub fn open_mapped(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(crate::MappedBlob::open(path)?)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Archive<bytes::Bytes> {
+    /// The blob stored under `name`, as a view into the archive's buffer
+    /// rather than a copy
+    pub fn get_bytes(&self, name: &str) -> Option<bytes::Bytes> {
+        self.get(name).map(|blob| self.data.slice_ref(blob))
+    }
+}
+
+impl<B: AsRef<[u8]>> Archive<B> {
+    pub fn from_bytes(data: B) -> Result<Self> {
+        let bytes = data.as_ref();
+        if bytes.len() < ARCHIVE_HEADER_LEN + FOOTER_LEN {
+            return Err(CydecError::Truncated {
+                needed: ARCHIVE_HEADER_LEN + FOOTER_LEN,
+                got: bytes.len(),
+            });
+        }
+        if &bytes[..5] != ARCHIVE_MAGIC || &bytes[bytes.len() - 4..] != FOOTER_MAGIC {
+            return Err(CydecError::BadMagic);
+        }
+        if bytes[5] != ARCHIVE_VERSION {
+            return Err(CydecError::UnsupportedVersion {
+                found: bytes[5],
+                max: ARCHIVE_VERSION,
+            });
+        }
+
+        let footer = &bytes[bytes.len() - FOOTER_LEN..];
+        let index_start = u64::from_le_bytes(footer[0..8].try_into().unwrap());
+        let count = u32::from_le_bytes(footer[8..12].try_into().unwrap()) as usize;
+        let index_end = bytes.len() - FOOTER_LEN;
+        if index_start < ARCHIVE_HEADER_LEN as u64 || index_start > index_end as u64 {
+            bail!("corrupt archive index offset {index_start}");
+        }
+
+        let mut index = &bytes[index_start as usize..index_end];
+        let mut take = |k: usize| -> Result<&[u8]> {
+            if index.len() < k {
+                bail!("corrupt archive index");
+            }
+            let (head, rest) = index.split_at(k);
+            index = rest;
+            Ok(head)
+        };
+        let mut entries = Vec::with_capacity(count.min(index_end / 19));
+        let mut names = HashMap::with_capacity(entries.capacity());
+        for _ in 0..count {
+            let name_len = u16::from_le_bytes(take(2)?.try_into().unwrap()) as usize;
+            let name = std::str::from_utf8(take(name_len)?)
+                .map_err(|_| corrupt!("archive entry name is not utf-8"))?
+                .to_owned();
+            let type_tag = take(1)?[0];
+            let offset = u64::from_le_bytes(take(8)?.try_into().unwrap());
+            let len = u64::from_le_bytes(take(8)?.try_into().unwrap());
+            if offset < ARCHIVE_HEADER_LEN as u64
+                || offset.checked_add(len).is_none_or(|end| end > index_start)
+            {
+                bail!("archive entry {name:?} out of bounds");
+            }
+            if names.insert(name.clone(), entries.len()).is_some() {
+                bail!("duplicate archive entry {name:?}");
+            }
+            entries.push(ArchiveEntry {
+                name,
+                type_tag,
+                offset,
+                len,
+            });
+        }
+        if !index.is_empty() {
+            bail!("corrupt archive index");
+        }
+        Ok(Self {
+            data,
+            entries,
+            names,
+        })
+    }
+
+    /// Entries in the order they were added
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The blob stored under `name`
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        let e = &self.entries[*self.names.get(name)?];
+        Some(&self.data.as_ref()[e.offset as usize..(e.offset + e.len) as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatingCodec, IntegerCodec};
+
+    #[test]
+    fn archive_roundtrip_and_lookup() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut w = ArchiveWriter::new(Vec::new())?;
+        for s in 0..1_000i64 {
+            let series: Vec<i64> = (0..s).map(|i| i * s).collect();
+            w.add(&format!("sensor/{s:04}"), &c.compress_i64(&series)?)?;
+        }
+        let prices = FloatingCodec::default().compress_f64(&[1.25, 1.5, 1.75], None)?;
+        w.add("prices", &prices)?;
+        assert!(w.add("prices", &prices).is_err());
+        let file = w.finish()?;
+
+        let archive = Archive::from_bytes(file)?;
+        assert_eq!(archive.len(), 1_001);
+        assert_eq!(
+            c.decompress_i64(archive.get("sensor/0042").unwrap())?,
+            (0..42).map(|i| i * 42).collect::<Vec<i64>>()
+        );
+        assert_eq!(archive.get("sensor/0000"), Some(&[][..]));
+        assert_eq!(archive.get("prices"), Some(prices.as_slice()));
+        assert_eq!(archive.entries()[1_000].type_tag, 4);
+        assert!(archive.get("missing").is_none());
+        Ok(())
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn shared_buffers_are_not_copied() -> Result<()> {
+        let ts: Vec<i64> = (0..1_000).map(|i| 1_700_000_000 + i * 60).collect();
+        let blob = crate::compress_to_bytes(&ts)?;
+        let mut w = ArchiveWriter::new(Vec::new())?;
+        w.add("ts", &blob)?;
+        w.add("empty", &[])?;
+        let file = bytes::Bytes::from(w.finish()?);
+
+        let archive = Archive::from_bytes(file.clone())?;
+        let stored = archive.get_bytes("ts").unwrap();
+        assert_eq!(stored, blob);
+        assert!(file.as_ptr_range().contains(&stored.as_ptr()));
+        assert!(archive.get_bytes("empty").unwrap().is_empty());
+        assert!(archive.get_bytes("missing").is_none());
+
+        let c = IntegerCodec::default();
+        let decoded = c.decompress_many_i64(&[stored, blob])?;
+        assert_eq!(decoded, [ts.clone(), ts]);
+        Ok(())
+    }
+
+    #[test]
+    fn damaged_archives_are_rejected() -> Result<()> {
+        let mut w = ArchiveWriter::new(Vec::new())?;
+        w.add("a", &IntegerCodec::default().compress_i64(&[1, 2, 3])?)?;
+        let file = w.finish()?;
+        assert!(Archive::from_bytes(&file[..file.len() - 1]).is_err());
+
+        let mut bad = file.clone();
+        let at = bad.len() - FOOTER_LEN;
+        bad[at..at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Archive::from_bytes(bad).is_err());
+
+        let empty = ArchiveWriter::new(Vec::new())?.finish()?;
+        assert!(Archive::from_bytes(empty)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,593 @@
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::format::TypeTag;
+use crate::header::{self, ARROW_SCHEMA, Extension, NULLS};
+use crate::{
+    Backend, Codec, ColumnEncoding, ColumnStats, CydecElement, Frame, FrameBuilder, Header,
+};
+use arrow_array::cast::AsArray;
+use arrow_array::types::ArrowPrimitiveType;
+use arrow_array::{Array, ArrayRef, PrimitiveArray, RecordBatch, RecordBatchOptions};
+use arrow_buffer::{BooleanBuffer, BooleanBufferBuilder, Buffer, NullBuffer, ScalarBuffer};
+use arrow_schema::{DataType, Field, Schema};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Evaluate `$body` with `$t` naming the Arrow primitive type behind
+/// `$data_type`; types without a cydec pipeline are an error for column
+/// `$name`
+macro_rules! with_arrow_type {
+    ($data_type:expr, $name:expr, $t:ident => $body:expr) => {{
+        use arrow_array::types::*;
+        use arrow_schema::TimeUnit::*;
+        match $data_type {
+            DataType::Int8 => {
+                type $t = Int8Type;
+                $body
+            }
+            DataType::Int16 => {
+                type $t = Int16Type;
+                $body
+            }
+            DataType::Int32 => {
+                type $t = Int32Type;
+                $body
+            }
+            DataType::Int64 => {
+                type $t = Int64Type;
+                $body
+            }
+            DataType::UInt8 => {
+                type $t = UInt8Type;
+                $body
+            }
+            DataType::UInt16 => {
+                type $t = UInt16Type;
+                $body
+            }
+            DataType::UInt32 => {
+                type $t = UInt32Type;
+                $body
+            }
+            DataType::UInt64 => {
+                type $t = UInt64Type;
+                $body
+            }
+            #[cfg(feature = "half")]
+            DataType::Float16 => {
+                type $t = Float16Type;
+                $body
+            }
+            DataType::Float32 => {
+                type $t = Float32Type;
+                $body
+            }
+            DataType::Float64 => {
+                type $t = Float64Type;
+                $body
+            }
+            DataType::Decimal128(..) => {
+                type $t = Decimal128Type;
+                $body
+            }
+            DataType::Date32 => {
+                type $t = Date32Type;
+                $body
+            }
+            DataType::Date64 => {
+                type $t = Date64Type;
+                $body
+            }
+            DataType::Time32(Second) => {
+                type $t = Time32SecondType;
+                $body
+            }
+            DataType::Time32(Millisecond) => {
+                type $t = Time32MillisecondType;
+                $body
+            }
+            DataType::Time64(Microsecond) => {
+                type $t = Time64MicrosecondType;
+                $body
+            }
+            DataType::Time64(Nanosecond) => {
+                type $t = Time64NanosecondType;
+                $body
+            }
+            DataType::Timestamp(Second, _) => {
+                type $t = TimestampSecondType;
+                $body
+            }
+            DataType::Timestamp(Millisecond, _) => {
+                type $t = TimestampMillisecondType;
+                $body
+            }
+            DataType::Timestamp(Microsecond, _) => {
+                type $t = TimestampMicrosecondType;
+                $body
+            }
+            DataType::Timestamp(Nanosecond, _) => {
+                type $t = TimestampNanosecondType;
+                $body
+            }
+            DataType::Duration(Second) => {
+                type $t = DurationSecondType;
+                $body
+            }
+            DataType::Duration(Millisecond) => {
+                type $t = DurationMillisecondType;
+                $body
+            }
+            DataType::Duration(Microsecond) => {
+                type $t = DurationMicrosecondType;
+                $body
+            }
+            DataType::Duration(Nanosecond) => {
+                type $t = DurationNanosecondType;
+                $body
+            }
+            other => Err(invalid!(
+                "column {:?}: arrow type {other} is not supported",
+                $name
+            )),
+        }
+    }};
+}
+
+impl Codec {
+    /// Compress an Arrow primitive array such as an `Int64Array` or a
+    /// `Float64Array` straight from its value buffer, with the same
+    /// pipeline as [`Codec::compress`]; floats use their default scale.
+    ///
+    /// The validity bitmap, if the array has nulls, is stored next to the
+    /// values, so [`Codec::decompress_arrow`] restores the nulls. Other
+    /// decoders read the blob as plain values, with whatever the array
+    /// held under the null slots.
+    pub fn compress_arrow<T>(&self, array: &PrimitiveArray<T>) -> Result<Vec<u8>>
+    where
+        T: ArrowPrimitiveType,
+        T::Native: CydecElement,
+    {
+        let blob = T::Native::compress_slice(array.values())?;
+        match array.nulls().filter(|nulls| nulls.null_count() > 0) {
+            Some(nulls) => with_nulls(blob, nulls),
+            None => Ok(blob),
+        }
+    }
+
+    /// Decode a blob into an Arrow array without copying the values, with
+    /// the nulls stored by [`Codec::compress_arrow`]. The array takes the
+    /// default data type of `T`; a timezone or decimal precision is not
+    /// stored with the blob.
+    pub fn decompress_arrow<T>(&self, blob: &[u8]) -> Result<PrimitiveArray<T>>
+    where
+        T: ArrowPrimitiveType,
+        T::Native: CydecElement,
+    {
+        let values = ScalarBuffer::from(T::Native::decompress_blob(blob)?);
+        let nulls = nulls(blob, values.len())?;
+        PrimitiveArray::try_new(values, nulls).map_err(|e| corrupt!("{e}"))
+    }
+}
+
+impl FrameBuilder {
+    /// Compress an Arrow primitive array as column `name`, nulls included;
+    /// statistics skip the null slots
+    pub(crate) fn add_arrow(&mut self, name: &str, array: &dyn Array) -> Result<()> {
+        self.check_column(name, array.len())?;
+        let encoding = self.encoding(name);
+        let (blob, stats) = with_arrow_type!(array.data_type(), name, T => {
+            column_blob(array.as_primitive::<T>(), encoding)
+        })?;
+        self.push_column(name, array.len(), blob, stats)
+    }
+}
+
+impl Frame<Vec<u8>> {
+    /// Compress every column of `batch` into one frame.
+    ///
+    /// Columns must be primitive: integers, floats, decimals, dates, times,
+    /// timestamps or durations. The schema, with its data types,
+    /// nullability and metadata, is stored with the frame, so
+    /// [`Frame::to_record_batch`] rebuilds an identical batch.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<Self> {
+        let schema = batch.schema();
+        let mut builder = FrameBuilder::new();
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            builder.add_arrow(field.name(), column.as_ref())?;
+        }
+        let value = write_schema(&schema)?;
+        let blob = header::with_extensions(
+            builder.finish()?,
+            &[Extension {
+                kind: ARROW_SCHEMA,
+                value: &value,
+            }],
+        )?;
+        Frame::from_bytes(blob)
+    }
+}
+
+impl<B: AsRef<[u8]>> Frame<B> {
+    /// Decode every column into an Arrow record batch. Frames built by
+    /// [`Frame::from_record_batch`] get their schema back; for other frames
+    /// it follows from the column types.
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        let extensions = header::extensions(self.as_bytes())?;
+        let schema = match extensions.iter().find(|e| e.kind == ARROW_SCHEMA) {
+            Some(entry) => read_schema(entry.value)?,
+            None => self.inferred_schema()?,
+        };
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| self.arrow_column(field))
+            .collect::<Result<Vec<_>>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(self.rows()));
+        RecordBatch::try_new_with_options(Arc::new(schema), columns, &options)
+            .map_err(|e| corrupt!("{e}"))
+    }
+
+    fn arrow_column(&self, field: &Field) -> Result<ArrayRef> {
+        let name = field.name();
+        let blob = self
+            .column_blob(name)
+            .ok_or_else(|| corrupt!("schema names column {name:?}, the frame lacks it"))?;
+        let array = decode_array(name, blob, field.data_type())?;
+        if array.len() != self.rows() {
+            bail!(
+                "column {name:?} holds {} rows, the frame has {}",
+                array.len(),
+                self.rows()
+            );
+        }
+        Ok(array)
+    }
+
+    fn inferred_schema(&self) -> Result<Schema> {
+        let fields = self.columns().iter().map(|column| {
+            let blob = self.column_blob(&column.name).unwrap_or_default();
+            inferred_field(&column.name, blob)
+        });
+        Ok(Schema::new(fields.collect::<Result<Vec<_>>>()?))
+    }
+}
+
+/// Compress any supported Arrow array with [`Codec::compress_arrow`]
+pub(crate) fn compress_array(name: &str, array: &dyn Array) -> Result<Vec<u8>> {
+    with_arrow_type!(array.data_type(), name, T => {
+        Codec::default().compress_arrow(array.as_primitive::<T>())
+    })
+}
+
+/// Decode `blob` into an Arrow array of `data_type`
+pub(crate) fn decode_array(name: &str, blob: &[u8], data_type: &DataType) -> Result<ArrayRef> {
+    with_arrow_type!(data_type, name, T => {
+        Codec::default()
+            .decompress_arrow::<T>(blob)
+            .map(|array| Arc::new(array.with_data_type(data_type.clone())) as ArrayRef)
+    })
+}
+
+/// Field for a blob written without an Arrow schema: the type follows
+/// from the element type, and the field is nullable if the blob has nulls
+pub(crate) fn inferred_field(name: &str, blob: &[u8]) -> Result<Field> {
+    if blob.is_empty() {
+        return Ok(Field::new(name, DataType::Int64, false));
+    }
+    let header = Header::parse(blob)?;
+    let data_type = match TypeTag::of(header.type_tag)? {
+        TypeTag::I64 | TypeTag::DodI64 | TypeTag::VarintI64 => DataType::Int64,
+        TypeTag::U64 | TypeTag::VarintU64 => DataType::UInt64,
+        TypeTag::I32 => DataType::Int32,
+        TypeTag::U32 => DataType::UInt32,
+        TypeTag::I16 => DataType::Int16,
+        TypeTag::U16 => DataType::UInt16,
+        TypeTag::I8 => DataType::Int8,
+        TypeTag::I128 => DataType::Decimal128(38, 0),
+        TypeTag::F32 | TypeTag::BlockedF32 => DataType::Float32,
+        #[cfg(feature = "half")]
+        TypeTag::F16 => DataType::Float16,
+        // raw bytes share the tag, without a scale
+        TypeTag::F64OrBytes if header.scale.is_none() => DataType::UInt8,
+        TypeTag::F64OrBytes
+        | TypeTag::SignificantF64
+        | TypeTag::AdaptiveF64
+        | TypeTag::GroomedF64
+        | TypeTag::BlockedF64 => DataType::Float64,
+        other => {
+            return Err(invalid!(
+                "column {name:?}: {} has no arrow equivalent",
+                other.name()
+            ));
+        }
+    };
+    let nullable = header.extensions.iter().any(|(kind, _)| *kind == NULLS);
+    Ok(Field::new(name, data_type, nullable))
+}
+
+/// Blob and footer statistics of an Arrow column
+fn column_blob<T>(
+    array: &PrimitiveArray<T>,
+    encoding: ColumnEncoding,
+) -> Result<(Vec<u8>, ColumnStats)>
+where
+    T: ArrowPrimitiveType,
+    T::Native: CydecElement,
+{
+    let values: &[T::Native] = array.values();
+    let mut blob = T::Native::compress_encoded(values, encoding)?;
+    let mut stats = ColumnStats {
+        min: None,
+        max: None,
+        null_count: 0,
+        compressed_size: 0,
+    };
+    let valid: Vec<(usize, usize)> = match array.nulls().filter(|n| n.null_count() > 0) {
+        Some(nulls) => {
+            blob = with_nulls(blob, nulls)?;
+            stats.null_count = nulls.null_count() as u64;
+            nulls.valid_slices().collect()
+        }
+        None => vec![(0, values.len())],
+    };
+    for (start, end) in valid {
+        let run = &values[start..end];
+        stats.null_count += T::Native::null_count(run);
+        if let Some((lo, hi)) = T::Native::value_range(run) {
+            stats.min = Some(stats.min.filter(|&min| min <= lo).unwrap_or(lo));
+            stats.max = Some(stats.max.filter(|&max| max >= hi).unwrap_or(hi));
+        }
+    }
+    stats.compressed_size = blob.len() as u64;
+    Ok((blob, stats))
+}
+
+// The schema extension holds `[u32 fields]`, then per field its name, data
+// type as Arrow displays it, a nullable byte and its metadata, then the
+// schema's metadata. Strings are `[u16 len][utf-8]`; metadata is `[u32
+// pairs]` followed by sorted keys and values, so equal schemas write equal
+// bytes.
+
+pub(crate) fn write_schema(schema: &Schema) -> Result<Vec<u8>> {
+    let mut out = (schema.fields().len() as u32).to_le_bytes().to_vec();
+    for field in schema.fields() {
+        write_str(&mut out, field.name())?;
+        write_str(&mut out, &field.data_type().to_string())?;
+        out.push(field.is_nullable() as u8);
+        write_map(&mut out, field.metadata())?;
+    }
+    write_map(&mut out, schema.metadata())?;
+    Ok(out)
+}
+
+pub(crate) fn read_schema(mut bytes: &[u8]) -> Result<Schema> {
+    let count = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap());
+    let mut fields = Vec::new();
+    for _ in 0..count {
+        let name = read_str(&mut bytes)?;
+        let data_type: DataType = read_str(&mut bytes)?
+            .parse()
+            .map_err(|e| corrupt!("arrow schema: {e}"))?;
+        let nullable = take(&mut bytes, 1)?[0] != 0;
+        let metadata = read_map(&mut bytes)?;
+        fields.push(Field::new(name, data_type, nullable).with_metadata(metadata));
+    }
+    let metadata = read_map(&mut bytes)?;
+    if !bytes.is_empty() {
+        bail!("{} bytes after the arrow schema", bytes.len());
+    }
+    Ok(Schema::new_with_metadata(fields, metadata))
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) -> Result<()> {
+    let len = u16::try_from(s.len()).map_err(|_| invalid!("schema string of {} bytes", s.len()))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn write_map(out: &mut Vec<u8>, map: &HashMap<String, String>) -> Result<()> {
+    out.extend_from_slice(&(map.len() as u32).to_le_bytes());
+    for (key, value) in map.iter().collect::<BTreeMap<_, _>>() {
+        write_str(out, key)?;
+        write_str(out, value)?;
+    }
+    Ok(())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], k: usize) -> Result<&'a [u8]> {
+    if bytes.len() < k {
+        bail!("truncated arrow schema");
+    }
+    let (head, rest) = bytes.split_at(k);
+    *bytes = rest;
+    Ok(head)
+}
+
+fn read_str(bytes: &mut &[u8]) -> Result<String> {
+    let len = u16::from_le_bytes(take(bytes, 2)?.try_into().unwrap()) as usize;
+    String::from_utf8(take(bytes, len)?.to_vec())
+        .map_err(|_| corrupt!("arrow schema string is not utf-8"))
+}
+
+fn read_map(bytes: &mut &[u8]) -> Result<HashMap<String, String>> {
+    let pairs = u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap());
+    let mut map = HashMap::new();
+    for _ in 0..pairs {
+        let key = read_str(bytes)?;
+        map.insert(key, read_str(bytes)?);
+    }
+    Ok(map)
+}
+
+/// Attach the validity bitmap `nulls` to `blob`
+pub(crate) fn with_nulls(blob: Vec<u8>, nulls: &NullBuffer) -> Result<Vec<u8>> {
+    let len = nulls.len();
+    // realigned to bit 0, with the bits past the end cleared so equal
+    // arrays write equal blobs
+    let mut bits = nulls.inner().sliced().as_slice()[..len.div_ceil(8)].to_vec();
+    if !len.is_multiple_of(8) {
+        *bits.last_mut().unwrap() &= (1 << (len % 8)) - 1;
+    }
+    let mut value = (len as u64).to_le_bytes().to_vec();
+    value.extend_from_slice(&Backend::Lz4.pack(&bits, 0));
+    header::with_extensions(
+        blob,
+        &[Extension {
+            kind: NULLS,
+            value: &value,
+        }],
+    )
+}
+
+/// Validity of the `n` values of `blob`, `None` when all are valid. Values
+/// appended after the bitmap was stored count as valid.
+pub(crate) fn nulls(blob: &[u8], n: usize) -> Result<Option<NullBuffer>> {
+    let extensions = header::extensions(blob)?;
+    let Some(entry) = extensions.iter().find(|e| e.kind == NULLS) else {
+        return Ok(None);
+    };
+    if entry.value.len() < 8 {
+        bail!("truncated validity bitmap");
+    }
+    let len = header::usize_at(entry.value, 0)?;
+    if len > n {
+        bail!("validity bitmap covers {len} values, the blob holds {n}");
+    }
+    let bits = Backend::Lz4.unpack(&entry.value[8..])?;
+    if bits.len() != len.div_ceil(8) {
+        bail!("validity bitmap of {} bytes for {len} values", bits.len());
+    }
+    let mut validity = BooleanBuffer::new(Buffer::from_vec(bits), 0, len);
+    if len < n {
+        let mut padded = BooleanBufferBuilder::new(n);
+        padded.append_buffer(&validity);
+        padded.append_n(n - len, true);
+        validity = padded.finish();
+    }
+    Ok(Some(NullBuffer::new(validity)).filter(|nulls| nulls.null_count() > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::types::{Float64Type, Int64Type, UInt32Type};
+    use arrow_array::{Float64Array, Int64Array, UInt32Array};
+
+    #[test]
+    fn arrays_keep_their_nulls() -> Result<()> {
+        let c = Codec::default();
+        let ints: Int64Array = (0..10_000)
+            .map(|i| (i % 7 != 3).then_some(i * 1_000))
+            .collect();
+        let blob = c.compress_arrow(&ints)?;
+        assert_eq!(c.decompress_arrow::<Int64Type>(&blob)?, ints);
+        // still an ordinary i64 blob
+        let plain = c.decompress::<i64>(&blob)?;
+        assert_eq!(plain[1], 1_000);
+
+        // a slice starts mid-byte in the parent's bitmap
+        let sliced = ints.slice(5, 4_001);
+        let blob = c.compress_arrow(&sliced)?;
+        assert_eq!(c.decompress_arrow::<Int64Type>(&blob)?, sliced);
+
+        let prices = Float64Array::from(vec![Some(101.25), None, Some(99.5), None]);
+        let blob = c.compress_arrow(&prices)?;
+        assert_eq!(c.decompress_arrow::<Float64Type>(&blob)?, prices);
+
+        // without nulls the blob is the plain one
+        let counts = UInt32Array::from(vec![4, 8, 15, 16, 23, 42]);
+        let blob = c.compress_arrow(&counts)?;
+        assert_eq!(blob, c.compress(&[4u32, 8, 15, 16, 23, 42])?);
+        assert_eq!(c.decompress_arrow::<UInt32Type>(&blob)?, counts);
+        assert!(c.decompress_arrow::<Int64Type>(&[])?.is_empty());
+
+        // values appended later are valid
+        let mut blob = c.compress_arrow(&Int64Array::from(vec![Some(1), None]))?;
+        crate::IntegerCodec::default().append_i64(&mut blob, &[3, 4])?;
+        let grown = c.decompress_arrow::<Int64Type>(&blob)?;
+        assert_eq!(
+            grown,
+            Int64Array::from(vec![Some(1), None, Some(3), Some(4)])
+        );
+
+        // a bitmap longer than the values is damage
+        let short = c.compress(&[1i64, 2])?;
+        let bitmap = NullBuffer::from(vec![true, false, true]);
+        let damaged = with_nulls(short, &bitmap)?;
+        assert!(c.decompress_arrow::<Int64Type>(&damaged).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn record_batches_survive_frames() -> Result<()> {
+        use crate::StatValue;
+        use arrow_array::{Decimal128Array, StringArray, TimestampMillisecondArray};
+
+        let ts = TimestampMillisecondArray::from(
+            (0..1_000)
+                .map(|i| 1_700_000_000_000 + i * 60_000)
+                .collect::<Vec<i64>>(),
+        )
+        .with_timezone("UTC");
+        let close: Float64Array = (0..1_000)
+            .map(|i| (i % 10 != 0).then_some(100.0 + i as f64 / 8.0))
+            .collect();
+        let notional =
+            Decimal128Array::from((0..1_000).map(|i| i as i128 * 12_345).collect::<Vec<_>>())
+                .with_precision_and_scale(18, 4)
+                .unwrap();
+        let volume = UInt32Array::from((0..1_000).map(|i| i * 3).collect::<Vec<u32>>());
+        let schema = Schema::new_with_metadata(
+            vec![
+                Field::new("ts", ts.data_type().clone(), false),
+                Field::new("close", DataType::Float64, true)
+                    .with_metadata(HashMap::from([("unit".to_string(), "USD".to_string())])),
+                Field::new("notional", notional.data_type().clone(), false),
+                Field::new("volume", DataType::UInt32, false),
+            ],
+            HashMap::from([("source".to_string(), "feed-a".to_string())]),
+        );
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(ts),
+                Arc::new(close),
+                Arc::new(notional),
+                Arc::new(volume),
+            ],
+        )
+        .unwrap();
+
+        let frame = Frame::from_record_batch(&batch)?;
+        assert_eq!(frame.to_record_batch()?, batch);
+        let reopened = Frame::from_bytes(frame.as_bytes())?;
+        assert_eq!(reopened.to_record_batch()?, batch);
+        assert_eq!(frame.column::<u32>("volume")?[2], 6);
+
+        // statistics skip the null slots
+        let stats = frame.column_stats("close").unwrap();
+        assert_eq!(stats.null_count, 100);
+        assert_eq!(stats.min, Some(StatValue::Float(100.125)));
+        assert_eq!(stats.max, Some(StatValue::Float(100.0 + 999.0 / 8.0)));
+
+        // frames built column by column get a schema from their types
+        let mut builder = FrameBuilder::new();
+        builder.add("ts", &[1i64, 2, 3])?;
+        builder.add("close", &[9.5f64, 9.75, 10.0])?;
+        let plain = Frame::from_bytes(builder.finish()?)?.to_record_batch()?;
+        assert_eq!(
+            plain.schema().field(0),
+            &Field::new("ts", DataType::Int64, false)
+        );
+        assert_eq!(plain.column(1).as_primitive::<Float64Type>().value(2), 10.0);
+
+        let names = StringArray::from(vec!["a", "b"]);
+        let strings = RecordBatch::try_from_iter([("name", Arc::new(names) as ArrayRef)]).unwrap();
+        assert!(matches!(
+            Frame::from_record_batch(&strings),
+            Err(crate::CydecError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use crate::error::Result;
+use crate::header;
+use crate::stream::{StreamState, block_header, encode_stream_block};
+use crate::{Backend, CydecError, CydecWriter, StreamElement};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Async counterpart of [`CydecWriter`] for tokio writers such as sockets or
+/// object-store uploads.
+///
+/// Blocks are encoded on the calling task before each write; the output is
+/// byte for byte what the blocking writer produces.
+#[derive(Debug)]
+pub struct AsyncCydecWriter<W: AsyncWrite + Unpin, T: StreamElement = i64> {
+    inner: W,
+    backend: Backend,
+    block_len: usize,
+    pending: Vec<T>,
+    started: bool,
+}
+
+impl<W: AsyncWrite + Unpin, T: StreamElement> AsyncCydecWriter<W, T> {
+    pub fn new(inner: W) -> Self {
+        Self::with_backend(inner, Backend::Lz4)
+    }
+
+    pub fn with_backend(inner: W, backend: Backend) -> Self {
+        Self {
+            inner,
+            backend,
+            block_len: CydecWriter::<Vec<u8>, T>::DEFAULT_BLOCK_LEN,
+            pending: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Values per block; larger blocks compress better but buffer more
+    pub fn with_block_len(mut self, block_len: usize) -> Self {
+        assert!(
+            block_len > 0 && block_len <= u32::MAX as usize,
+            "block length must fit in a block header"
+        );
+        self.block_len = block_len;
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub async fn write_values(&mut self, values: &[T]) -> Result<()> {
+        let mut values = values;
+        while !values.is_empty() {
+            let take = (self.block_len - self.pending.len()).min(values.len());
+            self.pending.extend_from_slice(&values[..take]);
+            values = &values[take..];
+            if self.pending.len() == self.block_len {
+                self.write_block().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the buffered partial block, flush, and return the inner writer
+    pub async fn finish(mut self) -> Result<W> {
+        if !self.pending.is_empty() {
+            self.write_block().await?;
+        }
+        self.inner.flush().await?;
+        Ok(self.inner)
+    }
+
+    async fn write_block(&mut self) -> Result<()> {
+        let buf = encode_stream_block(&self.pending, self.backend, !self.started)?;
+        self.inner.write_all(&buf).await?;
+        self.started = true;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Fill `buf` from `r`, stopping early only at end of input
+async fn read_full(r: &mut (impl AsyncRead + Unpin), buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]).await? {
+            0 => break,
+            k => filled += k,
+        }
+    }
+    Ok(filled)
+}
+
+/// Async counterpart of [`crate::CydecReader`]; decodes one block at a time
+/// as it arrives from a tokio reader.
+#[derive(Debug)]
+pub struct AsyncCydecReader<R: AsyncRead + Unpin, T: StreamElement = i64> {
+    inner: R,
+    state: StreamState<T>,
+}
+
+impl<R: AsyncRead + Unpin, T: StreamElement> AsyncCydecReader<R, T> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            state: StreamState::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Next value, or `None` at the end of the stream
+    pub async fn next_value(&mut self) -> Result<Option<T>> {
+        match self.fill_block().await {
+            Ok(true) => {
+                let x = self.state.buffered()[0];
+                self.state.consume(1);
+                Ok(Some(x))
+            }
+            Ok(false) => Ok(None),
+            Err(e) => {
+                self.state.fail();
+                Err(e)
+            }
+        }
+    }
+
+    /// Copy the next values into `out`; returns how many were written,
+    /// 0 once the stream is exhausted
+    pub async fn read_values(&mut self, out: &mut [T]) -> Result<usize> {
+        let mut written = 0;
+        while written < out.len() && self.fill_block().await? {
+            let buffered = self.state.buffered();
+            let take = buffered.len().min(out.len() - written);
+            out[written..written + take].copy_from_slice(&buffered[..take]);
+            self.state.consume(take);
+            written += take;
+        }
+        Ok(written)
+    }
+
+    /// Make sure a value is buffered; `false` at the end of the stream
+    async fn fill_block(&mut self) -> Result<bool> {
+        while self.state.buffered().is_empty() && !self.state.done {
+            let Some(backend) = self.state.backend else {
+                let mut header = [0u8; 16];
+                match read_full(&mut self.inner, &mut header).await? {
+                    // empty input is an empty blob
+                    0 => self.state.end()?,
+                    // skip the extension area; the wrapped blob's header follows
+                    16 if header::is_v2(&header) => {
+                        let mut len = [0u8; 4];
+                        let got = read_full(&mut self.inner, &mut len).await?;
+                        if got < 4 {
+                            return Err(CydecError::Truncated { needed: 4, got });
+                        }
+                        let area = u32::from_le_bytes(len) as u64;
+                        let skipped = tokio::io::copy(
+                            &mut (&mut self.inner).take(area),
+                            &mut tokio::io::sink(),
+                        )
+                        .await?;
+                        if skipped < area {
+                            return Err(CydecError::Truncated {
+                                needed: area as usize,
+                                got: skipped as usize,
+                            });
+                        }
+                    }
+                    16 => self.state.start(&header)?,
+                    got => return Err(CydecError::Truncated { needed: 16, got }),
+                }
+                continue;
+            };
+            if !self.state.blocked {
+                let mut payload = Vec::new();
+                self.inner.read_to_end(&mut payload).await?;
+                let n = self.state.declared as usize;
+                self.state.push(T::decode_block(&payload, n, backend)?);
+                self.state.end()?;
+                continue;
+            }
+            if self.state.at_trailer() {
+                self.state.end()?;
+                continue;
+            }
+            let mut header = [0u8; 8];
+            match read_full(&mut self.inner, &mut header).await? {
+                0 => self.state.end()?,
+                8 => {
+                    let (count, len) = block_header(&header);
+                    let skip = self.state.stats_len;
+                    let mut payload = vec![0u8; skip + len];
+                    let got = read_full(&mut self.inner, &mut payload).await?;
+                    if got < skip + len {
+                        return Err(CydecError::Truncated {
+                            needed: skip + len,
+                            got,
+                        });
+                    }
+                    self.state
+                        .push(T::decode_block(&payload[skip..], count, backend)?);
+                }
+                got => return Err(CydecError::Truncated { needed: 8, got }),
+            }
+        }
+        Ok(!self.state.buffered().is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntegerCodec;
+
+    #[tokio::test]
+    async fn async_roundtrip_matches_blocking_stream() -> Result<()> {
+        let data: Vec<i64> = (0..30_000).map(|i| i * 3 - (i % 11) * 7).collect();
+        let mut w = AsyncCydecWriter::new(Vec::new()).with_block_len(4_096);
+        for chunk in data.chunks(1_000) {
+            w.write_values(chunk).await?;
+        }
+        let blob = w.finish().await?;
+
+        let mut sync = CydecWriter::new(Vec::new()).with_block_len(4_096);
+        sync.write_values(&data)?;
+        assert_eq!(blob, sync.finish()?);
+        assert_eq!(data, IntegerCodec::default().decompress_i64(&blob)?);
+
+        let mut r = AsyncCydecReader::<_, i64>::new(blob.as_slice());
+        let mut out = Vec::new();
+        while let Some(x) = r.next_value().await? {
+            out.push(x);
+        }
+        assert_eq!(data, out);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn async_bytes_over_a_duplex_pipe() -> Result<()> {
+        let text = "sent over an async pipe\n".repeat(2_000).into_bytes();
+        let (client, server) = tokio::io::duplex(1_024);
+
+        let payload = text.clone();
+        let send = tokio::spawn(async move {
+            let mut w = AsyncCydecWriter::<_, u8>::new(client).with_block_len(2_000);
+            w.write_values(&payload).await?;
+            w.finish().await?.shutdown().await?;
+            Ok::<_, CydecError>(())
+        });
+
+        let mut r = AsyncCydecReader::<_, u8>::new(server);
+        let mut out = Vec::new();
+        let mut buf = [0u8; 700];
+        loop {
+            let k = r.read_values(&mut buf).await?;
+            if k == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..k]);
+        }
+        send.await.unwrap()?;
+        assert_eq!(text, out);
+        Ok(())
+    }
+}
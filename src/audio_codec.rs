@@ -0,0 +1,228 @@
+use crate::error::{Result, bail, corrupt};
+use crate::header;
+use crate::{Backend, CydecError, IntegerCodec};
+use integer_encoding::{VarIntReader, VarIntWriter};
+use std::io::Cursor;
+
+/// Samples per block; each block picks its own predictor order
+const PCM_BLOCK_LEN: usize = 4096;
+
+/// Highest fixed predictor order, as in FLAC
+const MAX_ORDER: usize = 4;
+
+/// Fixed polynomial predictor of `order` over the preceding samples
+/// (`history[0]` is the most recent one). Wrapping, so corrupt input
+/// surfaces as an out-of-range sample instead of an overflow panic.
+fn predict(order: usize, history: &[i64; MAX_ORDER]) -> i64 {
+    const COEFFS: [[i64; MAX_ORDER]; MAX_ORDER + 1] = [
+        [0, 0, 0, 0],
+        [1, 0, 0, 0],
+        [2, -1, 0, 0],
+        [3, -3, 1, 0],
+        [4, -6, 4, -1],
+    ];
+    COEFFS[order]
+        .iter()
+        .zip(history)
+        .fold(0i64, |acc, (&c, &x)| acc.wrapping_add(c.wrapping_mul(x)))
+}
+
+fn push_history(history: &mut [i64; MAX_ORDER], x: i64) {
+    history.rotate_right(1);
+    history[0] = x;
+}
+
+/// Residuals of every sample, choosing per block the predictor order with
+/// the smallest total magnitude. Orders are capped by the available
+/// history so the first samples of the signal need no warm-up frame.
+fn encode_pcm(samples: &[i64]) -> Vec<u8> {
+    let mut tmp = Vec::with_capacity(samples.len() * 2);
+    let mut history = [0i64; MAX_ORDER];
+    for (b, block) in samples.chunks(PCM_BLOCK_LEN).enumerate() {
+        let start = b * PCM_BLOCK_LEN;
+        let cost = |order: usize| {
+            let mut h = history;
+            block.iter().enumerate().fold(0u64, |acc, (i, &x)| {
+                let r = x - predict(order.min(start + i), &h);
+                push_history(&mut h, x);
+                acc.saturating_add(r.unsigned_abs())
+            })
+        };
+        let order = (0..=MAX_ORDER).min_by_key(|&o| cost(o)).unwrap();
+
+        tmp.push(order as u8);
+        for (i, &x) in block.iter().enumerate() {
+            let r = x - predict(order.min(start + i), &history);
+            tmp.write_varint(IntegerCodec::zigzag_i64(r)).unwrap();
+            push_history(&mut history, x);
+        }
+    }
+    lz4_flex::block::compress_prepend_size(&tmp)
+}
+
+fn decode_pcm(payload: &[u8], n: usize) -> Result<Vec<i64>> {
+    let raw = Backend::Lz4.unpack(payload)?;
+    let mut cur = Cursor::new(raw.as_slice());
+    let mut out = Vec::with_capacity(n.min(raw.len()));
+    let mut history = [0i64; MAX_ORDER];
+    while out.len() < n {
+        let start = out.len();
+        let mut order = [0u8; 1];
+        std::io::Read::read_exact(&mut cur, &mut order)
+            .map_err(|e| corrupt!("pcm block header: {e}"))?;
+        let order = order[0] as usize;
+        if order > MAX_ORDER {
+            bail!("invalid predictor order {order}");
+        }
+        for i in 0..PCM_BLOCK_LEN.min(n - start) {
+            let r: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let x =
+                IntegerCodec::unzigzag_i64(r).wrapping_add(predict(order.min(start + i), &history));
+            push_history(&mut history, x);
+            out.push(x);
+        }
+    }
+    Ok(out)
+}
+
+fn write_header(buf: &mut Vec<u8>, type_tag: u8, n: usize) {
+    // header: magic + version + len + type
+    buf.extend_from_slice(b"CYDEC"); // 0..5
+    buf.push(1); // 5: version
+    buf.push(1); // 6: codec LZ4
+    buf.push(type_tag); // 7: type (31 = i16 PCM, 32 = i32 PCM)
+    buf.extend_from_slice(&(n as u64).to_le_bytes()); // 8..16
+}
+
+fn read_header(blob: &[u8], type_tag: u8, type_name: &'static str) -> Result<usize> {
+    if blob.len() < 16 {
+        return Err(CydecError::Truncated {
+            needed: 16,
+            got: blob.len(),
+        });
+    }
+    if &blob[0..5] != b"CYDEC" {
+        return Err(CydecError::BadMagic);
+    }
+    if blob[5] != 1 {
+        return Err(CydecError::unsupported_version(blob[5]));
+    }
+    if blob[6] != 1 {
+        return Err(CydecError::UnsupportedBackend { found: blob[6] });
+    }
+    if blob[7] != type_tag {
+        return Err(CydecError::TypeMismatch {
+            expected: type_name,
+            found: blob[7],
+        });
+    }
+    header::usize_at(blob, 8)
+}
+
+impl IntegerCodec {
+    /// Compress 16-bit PCM audio with FLAC-style fixed linear prediction.
+    ///
+    /// Plain first-order deltas leave most of an oscillating signal in the
+    /// residual; a polynomial predictor of order up to 4, picked per block,
+    /// follows the waveform much more closely.
+    pub fn compress_pcm_i16(&self, samples: &[i16]) -> Result<Vec<u8>> {
+        if samples.is_empty() {
+            return Ok(Vec::new());
+        }
+        let wide: Vec<i64> = samples.iter().map(|&s| s as i64).collect();
+        let mut buf = Vec::with_capacity(samples.len() + 16);
+        write_header(&mut buf, 31, samples.len());
+        buf.extend_from_slice(&encode_pcm(&wide));
+        Ok(buf)
+    }
+
+    pub fn decompress_pcm_i16(&self, blob: &[u8]) -> Result<Vec<i16>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let n = read_header(blob, 31, "i16 pcm")?;
+        decode_pcm(&blob[16..], n)?
+            .into_iter()
+            .map(|x| i16::try_from(x).map_err(|_| corrupt!("sample {x} out of i16 range")))
+            .collect()
+    }
+
+    /// Compress 32-bit PCM audio; see [`Self::compress_pcm_i16`]
+    pub fn compress_pcm_i32(&self, samples: &[i32]) -> Result<Vec<u8>> {
+        if samples.is_empty() {
+            return Ok(Vec::new());
+        }
+        let wide: Vec<i64> = samples.iter().map(|&s| s as i64).collect();
+        let mut buf = Vec::with_capacity(samples.len() * 2 + 16);
+        write_header(&mut buf, 32, samples.len());
+        buf.extend_from_slice(&encode_pcm(&wide));
+        Ok(buf)
+    }
+
+    pub fn decompress_pcm_i32(&self, blob: &[u8]) -> Result<Vec<i32>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let n = read_header(blob, 32, "i32 pcm")?;
+        decode_pcm(&blob[16..], n)?
+            .into_iter()
+            .map(|x| i32::try_from(x).map_err(|_| corrupt!("sample {x} out of i32 range")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    /// Two-partial tone with a little dither, so LZ4 cannot just match periods
+    fn tone(n: usize, amplitude: f64) -> Vec<f64> {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / 44_100.0;
+                amplitude
+                    * (0.6 * (2.0 * std::f64::consts::PI * 440.0 * t).sin()
+                        + 0.3 * (2.0 * std::f64::consts::PI * 1_320.0 * t).sin()
+                        + rng.gen_range(-1e-4..1e-4))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lpc_beats_plain_delta_on_audio() -> Result<()> {
+        let c = IntegerCodec::default();
+        let samples: Vec<i16> = tone(44_100, 30_000.0)
+            .into_iter()
+            .map(|x| x.round() as i16)
+            .collect();
+        let blob = c.compress_pcm_i16(&samples)?;
+        assert_eq!(samples, c.decompress_pcm_i16(&blob)?);
+        let delta = c.compress_i16(&samples)?.len();
+        assert!(
+            blob.len() * 4 < delta * 3,
+            "lpc {}B vs delta {delta}B",
+            blob.len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_pcm_i32_extremes() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut samples: Vec<i32> = tone(10_000, 2e9)
+            .into_iter()
+            .map(|x| x.round() as i32)
+            .collect();
+        samples.extend([i32::MAX, i32::MIN, i32::MAX, 0, i32::MIN]);
+        let blob = c.compress_pcm_i32(&samples)?;
+        assert_eq!(samples, c.decompress_pcm_i32(&blob)?);
+        assert!(c.decompress_pcm_i16(&blob).is_err());
+        Ok(())
+    }
+}
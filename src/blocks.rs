@@ -0,0 +1,1011 @@
+use crate::bloom::{self, Bloom};
+use crate::error::{Result, bail, invalid};
+use crate::header;
+use crate::integer_codec::{at_index, reserve_len};
+use crate::{Backend, CompressOptions, CydecError, IntegerCodec, Strided};
+use std::ops::{Bound, Range, RangeBounds};
+
+pub(crate) use crate::format::{BLOCKED_FLAG, FILTER_FLAG, STATS_FLAG};
+
+/// Per-block header: u32 value count + u32 payload size
+const BLOCK_HEADER_LEN: usize = 8;
+
+const STATS_LEN: usize = 32;
+
+/// Whether the type byte `tag` has `flag` set. The flags only exist on
+/// blocked blobs; without [`BLOCKED_FLAG`] those bits are part of the tag.
+pub(crate) fn has_flag(tag: u8, flag: u8) -> bool {
+    tag & BLOCKED_FLAG != 0 && tag & flag != 0
+}
+
+/// Length stored in the header of a blocked blob written as a stream,
+/// where the total is only known once every block is out
+pub(crate) const STREAMED_LEN: u64 = u64::MAX;
+
+/// One self-contained block of a blob
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Block<'a> {
+    /// Offset of the block header within the blob
+    pub offset: usize,
+    pub count: usize,
+    /// Stored statistics, for blobs written with them
+    pub stats: Option<BlockStats>,
+    pub payload: &'a [u8],
+}
+
+/// Statistics of one block of an i64 blob.
+///
+/// Long blobs from [`IntegerCodec::compress_i64`] store them next to each
+/// block header, so query layers can skip blocks that cannot match
+/// without decompressing anything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockStats {
+    pub count: usize,
+    pub min: i64,
+    pub max: i64,
+    pub sum: i128,
+}
+
+impl BlockStats {
+    /// Statistics of a non-empty block
+    pub(crate) fn of(values: &[i64]) -> Self {
+        Self::of_values(values.iter().copied())
+    }
+
+    pub(crate) fn of_values(values: impl ExactSizeIterator<Item = i64>) -> Self {
+        let mut stats = Self {
+            count: values.len(),
+            min: i64::MAX,
+            max: i64::MIN,
+            sum: 0,
+        };
+        for x in values {
+            stats.min = stats.min.min(x);
+            stats.max = stats.max.max(x);
+            stats.sum += x as i128;
+        }
+        stats
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.min.to_le_bytes());
+        buf.extend_from_slice(&self.max.to_le_bytes());
+        buf.extend_from_slice(&self.sum.to_le_bytes());
+    }
+
+    fn read(count: usize, bytes: &[u8]) -> Self {
+        Self {
+            count,
+            min: i64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            max: i64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            sum: i128::from_le_bytes(bytes[16..32].try_into().unwrap()),
+        }
+    }
+}
+
+/// Walk the blocks after the 16-byte header, checking that they are in
+/// bounds and that their counts add up to the `n` stored in the header.
+/// Returns the total value count, which streamed blobs only store here.
+pub(crate) fn read_blocks(blob: &[u8], n: u64) -> Result<(usize, Vec<Block<'_>>)> {
+    read_blocks_at(blob, 16, n)
+}
+
+/// Like [`read_blocks`], for layouts with more header fields before the
+/// first block
+pub(crate) fn read_blocks_at(blob: &[u8], start: usize, n: u64) -> Result<(usize, Vec<Block<'_>>)> {
+    let header_len = block_header_len(blob[7]);
+    let end = blob.len() - bloom::trailer_len(blob)?;
+    let blob = &blob[..end];
+    let mut blocks = Vec::new();
+    let (mut offset, mut total) = (start, 0usize);
+    while offset < blob.len() {
+        if blob.len() - offset < header_len {
+            return Err(CydecError::Truncated {
+                needed: header_len,
+                got: blob.len() - offset,
+            });
+        }
+        let count = u32::from_le_bytes(blob[offset..offset + 4].try_into().unwrap()) as usize;
+        let len = u32::from_le_bytes(blob[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let stats = (header_len > BLOCK_HEADER_LEN)
+            .then(|| BlockStats::read(count, &blob[offset + BLOCK_HEADER_LEN..]));
+        let start = offset + header_len;
+        if blob.len() - start < len {
+            return Err(CydecError::Truncated {
+                needed: len,
+                got: blob.len() - start,
+            });
+        }
+        blocks.push(Block {
+            offset,
+            count,
+            stats,
+            payload: &blob[start..start + len],
+        });
+        total += count;
+        offset = start + len;
+    }
+    if n != STREAMED_LEN && total as u64 != n {
+        bail!("block counts add up to {total}, header says {n}");
+    }
+    Ok((total, blocks))
+}
+
+/// Bytes in front of each block's payload for a blob with type byte `tag`
+pub(crate) fn block_header_len(tag: u8) -> usize {
+    if has_flag(tag, STATS_FLAG) {
+        BLOCK_HEADER_LEN + STATS_LEN
+    } else {
+        BLOCK_HEADER_LEN
+    }
+}
+
+/// Compress and append one block, with its statistics if `stats` is set
+pub(crate) fn write_block(buf: &mut Vec<u8>, values: &[i64], backend: Backend, stats: bool) {
+    let payload = IntegerCodec::encode_i64_payload_with(values, backend, 0);
+    let stats = stats.then(|| BlockStats::of(values));
+    push_block(buf, values.len(), stats.as_ref(), &payload).expect("appended blocks are small");
+}
+
+/// Append an already compressed block
+pub(crate) fn push_block(
+    buf: &mut Vec<u8>,
+    count: usize,
+    stats: Option<&BlockStats>,
+    payload: &[u8],
+) -> Result<()> {
+    let (Ok(count), Ok(len)) = (u32::try_from(count), u32::try_from(payload.len())) else {
+        return Err(invalid!("block too large"));
+    };
+    buf.extend_from_slice(&count.to_le_bytes());
+    buf.extend_from_slice(&len.to_le_bytes());
+    if let Some(stats) = stats {
+        stats.write(buf);
+    }
+    buf.extend_from_slice(payload);
+    Ok(())
+}
+
+/// Whole blocked blob for `data`, `block_len` values per block
+pub(crate) fn encode_blocked_i64(
+    data: Strided<'_, i64>,
+    block_len: usize,
+    backend: Backend,
+    level: i32,
+) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(data.len() + 16);
+    write_blocked_header(&mut buf, backend, data.len(), true);
+    for start in (0..data.len()).step_by(block_len) {
+        let chunk = data.slice(start..data.len().min(start + block_len));
+        let payload = IntegerCodec::encode_i64_values(chunk.iter(), backend, level);
+        let stats = BlockStats::of_values(chunk.iter());
+        push_block(&mut buf, chunk.len(), Some(&stats), &payload)
+            .expect("block length fits in a u32");
+    }
+    buf
+}
+
+pub(crate) fn write_blocked_header(buf: &mut Vec<u8>, backend: Backend, n: usize, stats: bool) {
+    let tag = if stats {
+        BLOCKED_FLAG | STATS_FLAG
+    } else {
+        BLOCKED_FLAG
+    };
+    // header: magic + version + len + type
+    buf.extend_from_slice(b"CYDEC"); // 0..5
+    buf.push(1); // 5: version
+    buf.push(backend.id()); // 6: codec
+    buf.push(tag); // 7: type (0 = i64, blocked, maybe with stats)
+    buf.extend_from_slice(&(n as u64).to_le_bytes()); // 8..16
+}
+
+/// Decode the i64 values at `range`, unpacking only the blocks it overlaps
+pub(crate) fn decode_i64_range(blob: &[u8], range: Range<usize>) -> Result<Vec<i64>> {
+    let (n, backend, blocks) = if blob.is_empty() {
+        (0, Backend::Lz4, Vec::new())
+    } else {
+        IntegerCodec::open_i64_blob(blob)?
+    };
+    decode_blocks_range(&blocks, n, backend, range)
+}
+
+/// Values at `range` of `n` values stored as i64 payload blocks
+pub(crate) fn decode_blocks_range(
+    blocks: &[Block<'_>],
+    n: usize,
+    backend: Backend,
+    range: Range<usize>,
+) -> Result<Vec<i64>> {
+    check_range(&range, n)?;
+    let mut out = Vec::with_capacity(range.len());
+    let mut start = 0;
+    for block in blocks {
+        if start >= range.end {
+            break;
+        }
+        let end = start + block.count;
+        if end > range.start {
+            let wanted = range.start.saturating_sub(start)..(range.end - start).min(block.count);
+            IntegerCodec::decode_i64_payload_each(block.payload, block.count, backend, |i, x| {
+                if wanted.contains(&i) {
+                    out.push(x);
+                }
+            })?;
+        }
+        start = end;
+    }
+    Ok(out)
+}
+
+pub(crate) fn check_range(range: &Range<usize>, n: usize) -> Result<()> {
+    if range.start > range.end || range.end > n {
+        return Err(invalid!(
+            "range {}..{} out of bounds for {n} values",
+            range.start,
+            range.end
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `range` holds any value of `min..=max`
+fn range_overlaps(range: &impl RangeBounds<i64>, min: i64, max: i64) -> bool {
+    let above_start = match range.start_bound() {
+        Bound::Included(&a) => max >= a,
+        Bound::Excluded(&a) => max > a,
+        Bound::Unbounded => true,
+    };
+    let below_end = match range.end_bound() {
+        Bound::Included(&b) => min <= b,
+        Bound::Excluded(&b) => min < b,
+        Bound::Unbounded => true,
+    };
+    above_start && below_end
+}
+
+/// Decode a blocked raw-bytes blob, as written by a byte [`crate::CydecWriter`]
+pub(crate) fn decode_blocked_bytes(blob: &[u8]) -> Result<Vec<u8>> {
+    let backend = Backend::from_id(blob[6])?;
+    let n = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+    let (total, blocks) = read_blocks(blob, n)?;
+    let mut out = Vec::with_capacity(reserve_len(total, blob.len()));
+    for block in &blocks {
+        let raw = backend.unpack(block.payload)?;
+        if raw.len() != block.count {
+            bail!("decompressed length mismatch");
+        }
+        out.extend_from_slice(&raw);
+    }
+    Ok(out)
+}
+
+impl IntegerCodec {
+    /// Values per block of the blocked layout [`Self::compress_i64`] uses
+    /// for longer inputs, so lookups decode a bounded amount
+    pub const BLOCK_LEN: usize = 65_536;
+
+    /// Values per block written by [`Self::append_i64`]
+    pub const APPEND_BLOCK_LEN: usize = 4096;
+
+    /// Append values to an i64 blob in place.
+    ///
+    /// The first append switches a blob to the blocked layout, which only
+    /// rewrites its header. After that, each append decompresses just the
+    /// last block if it is not full, and adds new blocks of at most
+    /// [`Self::APPEND_BLOCK_LEN`] values. [`Self::decompress_i64`] reads both
+    /// layouts.
+    pub fn append_i64(&self, blob: &mut Vec<u8>, values: &[i64]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        if blob.is_empty() {
+            write_blocked_header(blob, self.codec, 0, true);
+        }
+        if header::is_v2(blob) {
+            // append to the wrapped blob and wrap it again
+            let mut inner = header::strip(blob)?.to_vec();
+            self.append_i64(&mut inner, values)?;
+            *blob = header::with_extensions(inner, &header::extensions(blob)?)?;
+            return Ok(());
+        }
+        let mut filter = match has_flag(blob[7], FILTER_FLAG) {
+            true => Some(Bloom::take(blob)?),
+            false => None,
+        };
+
+        if blob[7] & BLOCKED_FLAG == 0 {
+            let (n, _, _) = Self::open_i64_blob(blob)?;
+            // the single payload becomes the first block
+            let mut block_header = Vec::with_capacity(BLOCK_HEADER_LEN);
+            push_block(&mut block_header, n, None, &[])?;
+            let len = (blob.len() - 16) as u32;
+            block_header[4..8].copy_from_slice(&len.to_le_bytes());
+            blob.splice(16..16, block_header);
+            blob[7] |= BLOCKED_FLAG;
+        }
+
+        let (n, backend, blocks) = Self::open_i64_blob(blob)?;
+        let (mut pending, keep) = match blocks.last() {
+            Some(last) if last.count < Self::APPEND_BLOCK_LEN => (
+                Self::decode_i64_payload_with(last.payload, last.count, backend)?,
+                last.offset,
+            ),
+            _ => (Vec::new(), blob.len()),
+        };
+        blob.truncate(keep);
+        pending.extend_from_slice(values);
+        let stats = has_flag(blob[7], STATS_FLAG);
+        for chunk in pending.chunks(Self::APPEND_BLOCK_LEN) {
+            write_block(blob, chunk, backend, stats);
+        }
+        blob[8..16].copy_from_slice(&((n + values.len()) as u64).to_le_bytes());
+        if let Some(filter) = &mut filter {
+            for &x in values {
+                filter.insert(x);
+            }
+            filter.write(blob);
+        }
+        Ok(())
+    }
+
+    /// Compress into the blocked layout, `block_len` values at a time.
+    ///
+    /// [`Self::compress_i64`] builds the varint stream for the whole input
+    /// before packing it; here only one block's intermediate buffers exist
+    /// at a time, so memory beyond the input and output stays O(block).
+    /// To bound the output as well, stream through [`crate::CydecWriter`].
+    pub fn compress_i64_chunked(&self, data: &[i64], block_len: usize) -> Result<Vec<u8>> {
+        if block_len == 0 || u32::try_from(block_len).is_err() {
+            return Err(invalid!("block length must be between 1 and {}", u32::MAX));
+        }
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(encode_blocked_i64(data.into(), block_len, self.codec, 0))
+    }
+
+    /// Value at `index`, decoding only the block that holds it.
+    ///
+    /// Finding the block reads the block headers but decodes nothing, so a
+    /// lookup costs one block of at most [`Self::BLOCK_LEN`] values for
+    /// blobs from [`Self::compress_i64`]. A plain blob is a single block.
+    pub fn get_i64(&self, blob: &[u8], index: usize) -> Result<i64> {
+        let values = decode_i64_range(blob, index..index + 1)?;
+        Ok(values[0])
+    }
+
+    /// Values at `range`, decoding only the blocks that overlap it.
+    ///
+    /// Reading the tail of a long blob from [`Self::compress_i64`] costs
+    /// the last block or two instead of the whole series.
+    pub fn decompress_i64_range(&self, blob: &[u8], range: Range<usize>) -> Result<Vec<i64>> {
+        decode_i64_range(blob, range)
+    }
+
+    /// Values from `offset` to the end, for paginated reads.
+    ///
+    /// Whole blocks before `offset` are skipped by their headers; only the
+    /// block it falls into is decoded and trimmed. An offset past the end
+    /// is an error, one at the end gives an empty page.
+    pub fn decompress_i64_from(&self, blob: &[u8], offset: usize) -> Result<Vec<i64>> {
+        let total = self.count_i64(blob)?;
+        decode_i64_range(blob, offset..total)
+    }
+
+    /// The last `n` values (all of them if the blob is shorter), decoding
+    /// only the trailing blocks
+    pub fn decompress_i64_tail(&self, blob: &[u8], n: usize) -> Result<Vec<i64>> {
+        let total = self.count_i64(blob)?;
+        decode_i64_range(blob, total - n.min(total)..total)
+    }
+
+    /// Statistics of every non-empty block.
+    ///
+    /// Blobs written with statistics answer from their block headers
+    /// alone; for the others each block is decoded once to compute them.
+    pub fn block_stats(&self, blob: &[u8]) -> Result<Vec<BlockStats>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+        blocks
+            .iter()
+            .filter(|block| block.count > 0)
+            .map(|block| match block.stats {
+                Some(stats) => Ok(stats),
+                None => Ok(BlockStats::of(&Self::decode_i64_payload_with(
+                    block.payload,
+                    block.count,
+                    backend,
+                )?)),
+            })
+            .collect()
+    }
+
+    /// Binary search a blob of ascending values, like [`slice::binary_search`].
+    ///
+    /// Probing a block reads its stored minimum, or else unpacks it and
+    /// decodes only its first value, and only the block that can hold
+    /// `target` is decoded in full, so a lookup touches O(log n) blocks at
+    /// most. Meant for sorted columns such as timestamps; on unsorted data
+    /// the result is unspecified.
+    pub fn search_i64(&self, blob: &[u8], target: i64) -> Result<Result<usize, usize>> {
+        if blob.is_empty() {
+            return Ok(Err(0));
+        }
+        let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+        let mut start = 0;
+        let blocks: Vec<(usize, Block<'_>)> = blocks
+            .into_iter()
+            .map(|block| {
+                start += block.count;
+                (start - block.count, block)
+            })
+            .filter(|(_, block)| block.count > 0)
+            .collect();
+        let first = |block: &Block<'_>| -> Result<i64> {
+            if let Some(stats) = block.stats {
+                return Ok(stats.min);
+            }
+            let mut first = 0;
+            Self::decode_i64_payload_each(block.payload, 1, backend, |_, x| first = x)?;
+            Ok(first)
+        };
+
+        // the first block starting above `target`; the one before may hold it
+        let (mut lo, mut hi) = (0, blocks.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if first(&blocks[mid].1)? <= target {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let Some(&(start, block)) = lo.checked_sub(1).map(|i| &blocks[i]) else {
+            return Ok(Err(0));
+        };
+        let values = Self::decode_i64_payload_with(block.payload, block.count, backend)?;
+        Ok(values
+            .binary_search(&target)
+            .map(|i| start + i)
+            .map_err(|i| start + i))
+    }
+
+    /// Indices of the values of an i64 blob that fall in `range`, e.g.
+    /// `threshold + 1..` for "greater than `threshold`".
+    ///
+    /// Blocks whose stored min and max lie outside `range` are skipped and
+    /// blocks lying entirely inside it are taken whole, neither being
+    /// decompressed; only blocks straddling a bound are decoded.
+    pub fn filter_indices_i64(
+        &self,
+        blob: &[u8],
+        range: impl RangeBounds<i64>,
+    ) -> Result<Vec<usize>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+        let mut out = Vec::new();
+        let mut start = 0;
+        for block in &blocks {
+            match block.stats {
+                Some(s) if !range_overlaps(&range, s.min, s.max) => {}
+                Some(s) if range.contains(&s.min) && range.contains(&s.max) => {
+                    out.extend(start..start + block.count)
+                }
+                _ => Self::decode_i64_payload_each(block.payload, block.count, backend, |i, x| {
+                    if range.contains(&x) {
+                        out.push(start + i);
+                    }
+                })?,
+            }
+            start += block.count;
+        }
+        Ok(out)
+    }
+
+    /// Concatenate i64 blobs into one blocked blob.
+    ///
+    /// Blocks are self-contained, so each input's payload is copied as is
+    /// and no value is decoded. Inputs compressed with a different backend
+    /// than the first one are only re-run through the final stage.
+    pub fn concat_i64(&self, blobs: &[&[u8]]) -> Result<Vec<u8>> {
+        let mut parts = Vec::new();
+        let (mut total, mut target) = (0usize, None);
+        for (i, blob) in blobs.iter().enumerate().filter(|(_, b)| !b.is_empty()) {
+            let (n, backend, blocks) = Self::open_i64_blob(blob).map_err(|e| at_index(i, e))?;
+            target.get_or_insert(backend);
+            parts.push((backend, blocks));
+            total += n;
+        }
+        let Some(target) = target else {
+            return Ok(Vec::new());
+        };
+
+        // statistics survive only if every input carries them
+        let blocks = || {
+            parts
+                .iter()
+                .flat_map(|(b, blocks)| blocks.iter().map(move |x| (*b, x)))
+        };
+        let stats = blocks().all(|(_, block)| block.count == 0 || block.stats.is_some());
+        let mut buf = Vec::with_capacity(16 + blobs.iter().map(|b| b.len()).sum::<usize>());
+        write_blocked_header(&mut buf, target, total, stats);
+        for (backend, block) in blocks().filter(|(_, b)| b.count > 0) {
+            let stats = if stats { block.stats.as_ref() } else { None };
+            if backend == target {
+                push_block(&mut buf, block.count, stats, block.payload)?;
+            } else {
+                let raw = backend.unpack(block.payload)?;
+                push_block(&mut buf, block.count, stats, &target.pack(&raw, 0))?;
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Split an i64 blob into independent blobs of `chunk_len` values (the
+    /// last one may be shorter).
+    ///
+    /// Works one block at a time, so memory stays bounded by the chunk and
+    /// block sizes. A block that lines up exactly with a chunk is copied
+    /// without decoding.
+    pub fn split_i64(&self, blob: &[u8], chunk_len: usize) -> Result<Vec<Vec<u8>>> {
+        if chunk_len == 0 {
+            return Err(invalid!("chunk length must be positive"));
+        }
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+        let options = CompressOptions {
+            backend,
+            ..Default::default()
+        };
+
+        let mut parts = Vec::new();
+        let mut pending: Vec<i64> = Vec::with_capacity(chunk_len);
+        for block in blocks.iter().filter(|b| b.count > 0) {
+            if pending.is_empty() && block.count == chunk_len {
+                let mut part = Vec::with_capacity(block.payload.len() + 16);
+                // header: magic + version + len + type
+                part.extend_from_slice(b"CYDEC"); // 0..5
+                part.push(1); // 5: version
+                part.push(backend.id()); // 6: codec
+                part.push(0); // 7: type (0 = i64)
+                part.extend_from_slice(&(chunk_len as u64).to_le_bytes()); // 8..16
+                part.extend_from_slice(block.payload);
+                parts.push(part);
+                continue;
+            }
+            Self::decode_i64_payload_each(block.payload, block.count, backend, |_, x| {
+                pending.push(x)
+            })?;
+            let full = pending.len() - pending.len() % chunk_len;
+            for chunk in pending[..full].chunks(chunk_len) {
+                parts.push(self.compress_i64_with(chunk, &options)?);
+            }
+            pending.drain(..full);
+        }
+        if !pending.is_empty() {
+            parts.push(self.compress_i64_with(&pending, &options)?);
+        }
+        Ok(parts)
+    }
+
+    /// Re-compress a blob's final stage with another backend.
+    ///
+    /// Type, length and scale metadata are kept as they are; only the
+    /// backend payloads are unpacked and packed again, so no value is
+    /// decoded. Supports the i64 (plain or blocked), u64 and f64 blobs that
+    /// can use other backends.
+    pub fn transcode(&self, blob: &[u8], target: Backend) -> Result<Vec<u8>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if header::is_v2(blob) {
+            let inner = self.transcode(header::strip(blob)?, target)?;
+            return header::with_extensions(inner, &header::extensions(blob)?);
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        let backend = Backend::from_id(blob[6])?;
+        if backend == target {
+            return Ok(blob.to_vec());
+        }
+
+        let repack =
+            |payload: &[u8]| -> Result<Vec<u8>> { Ok(target.pack(&backend.unpack(payload)?, 0)) };
+        let mut out = Vec::with_capacity(blob.len());
+        match blob[7] {
+            0 | 1 => {
+                out.extend_from_slice(&blob[..16]);
+                out.extend_from_slice(&repack(&blob[16..])?);
+            }
+            t if t & !(STATS_FLAG | FILTER_FLAG) == BLOCKED_FLAG => {
+                let (_, _, blocks) = Self::open_i64_blob(blob)?;
+                out.extend_from_slice(&blob[..16]);
+                for block in &blocks {
+                    let stats = block.stats.as_ref();
+                    push_block(&mut out, block.count, stats, &repack(block.payload)?)?;
+                }
+                // the filter holds values, not backend output
+                out.extend_from_slice(&blob[blob.len() - bloom::trailer_len(blob)?..]);
+            }
+            4 => {
+                // raw bytes share this tag; an f64 payload holds exactly one
+                // varint per value
+                let n = header::usize_at(blob, 8)?;
+                let raw = match blob.get(24..).map(|p| backend.unpack(p)) {
+                    Some(Ok(raw)) if raw.iter().filter(|&&b| b & 0x80 == 0).count() == n => raw,
+                    _ => return Err(invalid!("transcoding raw bytes is not supported")),
+                };
+                out.extend_from_slice(&blob[..24]);
+                out.extend_from_slice(&target.pack(&raw, 0));
+            }
+            33 => {
+                // blocked f64: blocks follow the scale
+                let n = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+                let (_, blocks) = read_blocks_at(blob, 24, n)?;
+                out.extend_from_slice(&blob[..24]);
+                for block in &blocks {
+                    push_block(&mut out, block.count, None, &repack(block.payload)?)?;
+                }
+            }
+            t => return Err(invalid!("transcoding type {t} is not supported")),
+        }
+        out[6] = target.id();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn append_matches_full_compression() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let data: Vec<i64> = (0..20_000)
+            .scan(1_000_000i64, |x, _| {
+                *x += rng.gen_range(-500..500);
+                Some(*x)
+            })
+            .collect();
+        let mut blob = c.compress_i64(&data[..1_000])?;
+        for chunk in data[1_000..].chunks(333) {
+            c.append_i64(&mut blob, chunk)?;
+        }
+        assert_eq!(data, c.decompress_i64(&blob)?);
+        assert_eq!(data, c.decompress_i64_iter(&blob)?.collect::<Vec<_>>());
+
+        // blocks stay full, so appending costs little compared to one blob
+        let whole = c.compress_i64(&data)?.len();
+        assert!(blob.len() < whole + whole / 10, "{} vs {whole}", blob.len());
+        Ok(())
+    }
+
+    #[test]
+    fn append_to_empty_and_large_blobs() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut blob = Vec::new();
+        c.append_i64(&mut blob, &[])?;
+        assert!(blob.is_empty());
+        c.append_i64(&mut blob, &[i64::MIN, i64::MAX])?;
+        c.append_i64(&mut blob, &[0])?;
+        assert_eq!(vec![i64::MIN, i64::MAX, 0], c.decompress_i64(&blob)?);
+
+        // a full first block is kept as is and followed by a new one
+        let data: Vec<i64> = (0..10_000).collect();
+        let mut blob = c.compress_i64(&data)?;
+        c.append_i64(&mut blob, &[10_000])?;
+        let mut out = Vec::new();
+        c.decompress_i64_into(&blob, &mut out)?;
+        assert_eq!((0..10_001).collect::<Vec<i64>>(), out);
+        Ok(())
+    }
+
+    #[test]
+    fn chunked_compression_roundtrip() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..100_500).map(|i| (i * 37) % 10_007 - 5_000).collect();
+        let blob = c.compress_i64_chunked(&data, 10_000)?;
+        assert_eq!(data, c.decompress_i64(&blob)?);
+        assert_eq!(c.split_i64(&blob, 10_000)?.len(), 11);
+        assert!(c.compress_i64_chunked(&[], 10)?.is_empty());
+        assert!(c.compress_i64_chunked(&data, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn point_lookups_decode_one_block() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..200_000).map(|i| i * 3 - (i % 17) * 5).collect();
+        let blob = c.compress_i64(&data)?;
+        assert_eq!(blob[7], BLOCKED_FLAG | STATS_FLAG);
+        assert_eq!(data, c.decompress_i64(&blob)?);
+        for i in [0, 65_535, 65_536, 123_457, 199_999] {
+            assert_eq!(c.get_i64(&blob, i)?, data[i]);
+        }
+        assert!(c.get_i64(&blob, 200_000).is_err());
+
+        let small = c.compress_i64(&data[..1_000])?;
+        assert_eq!(small[7], 0);
+        assert_eq!(c.get_i64(&small, 999)?, data[999]);
+        assert!(c.get_i64(&[], 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn range_decompression_spans_blocks() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..365 * 1_000).map(|i| i * 7 - (i % 13) * 3).collect();
+        let blob = c.compress_i64(&data)?;
+        let tail = data.len() - 1_000;
+        assert_eq!(
+            c.decompress_i64_range(&blob, tail..data.len())?,
+            data[tail..]
+        );
+        assert_eq!(
+            c.decompress_i64_range(&blob, 60_000..140_000)?,
+            data[60_000..140_000]
+        );
+        assert!(c.decompress_i64_range(&blob, 5..5)?.is_empty());
+        assert!(c.decompress_i64_range(&blob, 0..data.len() + 1).is_err());
+        assert!(c.decompress_i64_range(&[], 0..0)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn block_stats_are_stored_and_kept() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..150_000).map(|i| (i * 7_919) % 10_007 - i / 3).collect();
+        let blob = c.compress_i64(&data)?;
+        let stats = c.block_stats(&blob)?;
+        assert_eq!(stats.len(), 3);
+        for (stats, chunk) in stats.iter().zip(data.chunks(IntegerCodec::BLOCK_LEN)) {
+            assert_eq!(*stats, BlockStats::of(chunk));
+            assert_eq!(stats.count, chunk.len());
+        }
+
+        // appends and concat carry the statistics along
+        let mut grown = blob.clone();
+        c.append_i64(&mut grown, &data[..10_000])?;
+        let joined = c.concat_i64(&[&blob, &grown])?;
+        for blob in [&grown, &joined] {
+            assert_eq!(blob[7], BLOCKED_FLAG | STATS_FLAG);
+            assert!(
+                IntegerCodec::open_i64_blob(blob)?
+                    .2
+                    .iter()
+                    .all(|b| b.stats.is_some())
+            );
+        }
+        let mut expected = data.clone();
+        expected.extend_from_slice(&data);
+        expected.extend_from_slice(&data[..10_000]);
+        assert_eq!(expected, c.decompress_i64(&joined)?);
+        let streamed: Vec<i64> =
+            crate::CydecReader::new(joined.as_slice()).collect::<Result<_>>()?;
+        assert_eq!(expected, streamed);
+
+        // blobs without stored statistics compute them
+        let small = c.compress_i64(&data[..1_000])?;
+        assert_eq!(c.block_stats(&small)?, [BlockStats::of(&data[..1_000])]);
+        let mixed = c.concat_i64(&[&small, &blob])?;
+        assert_eq!(mixed[7], BLOCKED_FLAG);
+        assert!(c.block_stats(&[])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn tail_reads_the_most_recent_window() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..200_000).map(|i| i * 11 % 4_099).collect();
+        let blob = c.compress_i64(&data)?;
+        assert_eq!(c.decompress_i64_tail(&blob, 1_000)?, data[199_000..]);
+        assert_eq!(c.decompress_i64_tail(&blob, 70_000)?, data[130_000..]);
+        assert_eq!(c.decompress_i64_tail(&blob, 500_000)?, data);
+        assert!(c.decompress_i64_tail(&blob, 0)?.is_empty());
+        assert!(c.decompress_i64_tail(&[], 10)?.is_empty());
+
+        let floats = crate::FloatingCodec::default();
+        let v: Vec<f64> = data.iter().map(|&x| x as f64 * 0.5).collect();
+        let blob = floats.compress_f64(&v, None)?;
+        assert_eq!(floats.decompress_f64_tail(&blob, 10, None)?, v[199_990..]);
+        let small = floats.compress_f64_significant(&v[..100], 9)?;
+        assert_eq!(floats.decompress_f64_tail(&small, 3, None)?, v[97..100]);
+        Ok(())
+    }
+
+    #[test]
+    fn pages_start_at_an_offset() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..150_000).map(|i| i * i % 7_001).collect();
+        let blob = c.compress_i64(&data)?;
+        for offset in [0, 1, 65_536, 100_000, 150_000] {
+            assert_eq!(c.decompress_i64_from(&blob, offset)?, data[offset..]);
+        }
+        assert!(c.decompress_i64_from(&blob, 150_001).is_err());
+        assert!(c.decompress_i64_from(&[], 0)?.is_empty());
+
+        let floats = crate::FloatingCodec::default();
+        let v: Vec<f64> = data.iter().map(|&x| x as f64 / 4.0).collect();
+        let blob = floats.compress_f64(&v, None)?;
+        assert_eq!(
+            floats.decompress_f64_from(&blob, 140_000, None)?,
+            v[140_000..]
+        );
+        assert!(floats.decompress_f64_from(&blob, 150_001, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn search_sorted_blocks() -> Result<()> {
+        let c = IntegerCodec::default();
+        // one timestamp every 10ms, with a gap
+        let ts: Vec<i64> = (0..300_000)
+            .map(|i| 1_700_000_000_000 + i * 10 + if i >= 150_000 { 5_000 } else { 0 })
+            .collect();
+        let blob = c.compress_i64(&ts)?;
+        for &probe in &[0, 1, 65_535, 65_536, 149_999, 150_000, 299_999] {
+            assert_eq!(c.search_i64(&blob, ts[probe])?, Ok(probe));
+            assert_eq!(c.search_i64(&blob, ts[probe] + 1)?, Err(probe + 1));
+        }
+        assert_eq!(c.search_i64(&blob, 0)?, Err(0));
+        assert_eq!(c.search_i64(&blob, i64::MAX)?, Err(ts.len()));
+        assert_eq!(c.search_i64(&blob, ts[149_999] + 100)?, Err(150_000));
+
+        let small = c.compress_i64(&[1, 3, 5])?;
+        assert_eq!(c.search_i64(&small, 3)?, Ok(1));
+        assert_eq!(c.search_i64(&small, 4)?, Err(2));
+        assert_eq!(c.search_i64(&[], 4)?, Err(0));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_indices_prunes_blocks() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..300_000).map(|i| (i / 1_000) * 10 + i % 7).collect();
+        let blob = c.compress_i64(&data)?;
+        let expected = |f: &dyn Fn(i64) -> bool| -> Vec<usize> {
+            (0..data.len()).filter(|&i| f(data[i])).collect()
+        };
+        assert_eq!(
+            c.filter_indices_i64(&blob, 2_991..)?,
+            expected(&|x| x > 2_990)
+        );
+        assert_eq!(c.filter_indices_i64(&blob, ..3)?, expected(&|x| x < 3));
+        assert_eq!(
+            c.filter_indices_i64(&blob, 655..=1_312)?,
+            expected(&|x| (655..=1_312).contains(&x))
+        );
+        assert_eq!(c.filter_indices_i64(&blob, ..)?.len(), data.len());
+        assert!(c.filter_indices_i64(&blob, 5_000..)?.is_empty());
+
+        // blobs without stats are decoded
+        let mut appended = Vec::new();
+        c.append_i64(&mut appended, &[5, -1, 9, 3])?;
+        assert_eq!(c.filter_indices_i64(&appended, 3..9)?, [0, 3]);
+        assert!(c.filter_indices_i64(&[], 0..1)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_block_counts_are_rejected() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut blob = Vec::new();
+        c.append_i64(&mut blob, &[1, 2, 3])?;
+        blob[8..16].copy_from_slice(&4u64.to_le_bytes());
+        assert!(c.decompress_i64(&blob).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn concat_stitches_blocks_without_decoding() -> Result<()> {
+        let c = IntegerCodec::default();
+        let days: Vec<Vec<i64>> = (0..30)
+            .map(|d| (0..1_440).map(|m| d * 1_440 + m).collect())
+            .collect();
+        let mut blobs: Vec<Vec<u8>> = days
+            .iter()
+            .map(|d| c.compress_i64(d))
+            .collect::<Result<_>>()?;
+        c.append_i64(&mut blobs[3], &[-1, -2])?;
+        blobs.insert(5, Vec::new());
+
+        let refs: Vec<&[u8]> = blobs.iter().map(Vec::as_slice).collect();
+        let month = c.concat_i64(&refs)?;
+        let mut expected = days.concat();
+        expected.splice(4 * 1_440..4 * 1_440, [-1, -2]);
+        assert_eq!(expected, c.decompress_i64(&month)?);
+        // payloads are copied, so the result is no larger than its inputs
+        assert!(month.len() <= blobs.iter().map(Vec::len).sum::<usize>());
+
+        assert!(c.concat_i64(&[])?.is_empty());
+        let floats = crate::FloatingCodec::default().compress_f64(&[1.0], None)?;
+        assert!(c.concat_i64(&[refs[0], &floats]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn split_into_independent_parts() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..10_000).map(|i| i * i % 1_009).collect();
+        let mut blob = c.compress_i64(&data[..2_500])?;
+        c.append_i64(&mut blob, &data[2_500..])?;
+
+        for chunk_len in [1, 999, 2_500, 4_096, 20_000] {
+            let parts = c.split_i64(&blob, chunk_len)?;
+            assert_eq!(parts.len(), data.len().div_ceil(chunk_len));
+            for (part, expected) in parts.iter().zip(data.chunks(chunk_len)) {
+                assert_eq!(expected, c.decompress_i64(part)?);
+            }
+        }
+        assert!(c.split_i64(&blob, 0).is_err());
+        assert!(c.split_i64(&[], 10)?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn transcode_between_backends() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..5_000).map(|i| i * 3 % 101).collect();
+        let mut blocked = c.compress_i64(&data[..100])?;
+        c.append_i64(&mut blocked, &data[100..])?;
+        for blob in [c.compress_i64(&data)?, blocked] {
+            let zstd = c.transcode(&blob, Backend::Zstd)?;
+            assert_eq!(zstd[6], 2);
+            assert_eq!(data, c.decompress_i64(&zstd)?);
+            assert_eq!(blob, c.transcode(&zstd, Backend::Lz4)?);
+        }
+
+        let floats = crate::FloatingCodec::default();
+        let v = vec![1.5, -2.25, 1e-9];
+        let blob = floats.compress_f64(&v, None)?;
+        let zstd = c.transcode(&blob, Backend::Zstd)?;
+        assert_eq!(v, floats.decompress_f64(&zstd, None)?);
+        let ints: Vec<i64> = (0..100_000).collect();
+        let zstd = c.transcode(&c.compress_i64(&ints)?, Backend::Zstd)?;
+        assert_eq!(
+            c.block_stats(&zstd)?,
+            c.block_stats(&c.compress_i64(&ints)?)?
+        );
+        assert_eq!(ints, c.decompress_i64(&zstd)?);
+        let long: Vec<f64> = (0..100_000).map(|i| i as f64 * 0.5).collect();
+        let zstd = c.transcode(&floats.compress_f64(&long, None)?, Backend::Zstd)?;
+        assert_eq!(long, floats.decompress_f64(&zstd, None)?);
+
+        let u = c.compress_u64(&[1, 2, 3])?;
+        assert_eq!(
+            vec![1, 2, 3],
+            c.decompress_u64(&c.transcode(&u, Backend::Zstd)?)?
+        );
+        assert!(c.transcode(&c.compress_i32(&[1])?, Backend::Zstd).is_err());
+        let bytes = c.compress_bytes(b"raw bytes, not scaled floats")?;
+        assert!(c.transcode(&bytes, Backend::Zstd).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn transcode_to_same_backend_is_a_copy() -> Result<()> {
+        let c = IntegerCodec::default();
+        let blob = c.compress_i32(&[1, 2, 3])?;
+        assert_eq!(blob, c.transcode(&blob, Backend::Lz4)?);
+        Ok(())
+    }
+}
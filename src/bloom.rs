@@ -0,0 +1,213 @@
+use crate::blocks::{BLOCKED_FLAG, FILTER_FLAG, has_flag};
+use crate::error::{Result, bail};
+use crate::{CydecError, IntegerCodec};
+
+/// Filter bit count + hash count at the very end of a filtered blob
+const TRAILER_LEN: usize = 5;
+
+/// Bloom filter over i64 values, stored after the last block of a blob as
+/// `[bits][u32 bit bytes][u8 hash count]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Bloom {
+    bits: Vec<u8>,
+    hashes: u8,
+}
+
+/// splitmix64 finalizer; fixed so filters read the same on every platform
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl Bloom {
+    /// Empty filter sized for `n` values at `bits_per_value`, with the hash
+    /// count that minimizes false positives for that ratio
+    pub fn new(n: usize, bits_per_value: u32) -> Self {
+        let bytes = (n * bits_per_value as usize).div_ceil(8).max(8);
+        let hashes = (bits_per_value as f64 * std::f64::consts::LN_2).round() as u8;
+        Self {
+            bits: vec![0; bytes],
+            hashes: hashes.clamp(1, 16),
+        }
+    }
+
+    /// Bit positions of `value`, by double hashing
+    fn positions(&self, value: i64) -> impl Iterator<Item = usize> + use<> {
+        let h1 = mix(value as u64);
+        let h2 = mix(h1) | 1;
+        let m = self.bits.len() as u64 * 8;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+
+    pub fn insert(&mut self, value: i64) {
+        for bit in self.positions(value) {
+            self.bits[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.positions(value)
+            .all(|bit| self.bits[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    /// Append the filter to `blob` and flag it in the header
+    pub fn write(&self, blob: &mut Vec<u8>) {
+        blob.extend_from_slice(&self.bits);
+        blob.extend_from_slice(&(self.bits.len() as u32).to_le_bytes());
+        blob.push(self.hashes);
+        blob[7] |= FILTER_FLAG;
+    }
+
+    /// Filter of a blob flagged with one, and the offset where it starts
+    pub fn read(blob: &[u8]) -> Result<(Self, usize)> {
+        let len = trailer_len(blob)?;
+        let start = blob.len() - len;
+        let filter = Self {
+            bits: blob[start..blob.len() - TRAILER_LEN].to_vec(),
+            hashes: blob[blob.len() - 1],
+        };
+        Ok((filter, start))
+    }
+
+    /// Remove the filter from `blob`, leaving a plain blocked blob
+    pub fn take(blob: &mut Vec<u8>) -> Result<Self> {
+        let (filter, start) = Self::read(blob)?;
+        blob.truncate(start);
+        blob[7] &= !FILTER_FLAG;
+        Ok(filter)
+    }
+}
+
+/// Bytes taken by the filter at the end of `blob`, 0 if it has none
+pub(crate) fn trailer_len(blob: &[u8]) -> Result<usize> {
+    if !has_flag(blob[7], FILTER_FLAG) {
+        return Ok(0);
+    }
+    if blob.len() < 16 + TRAILER_LEN {
+        return Err(CydecError::Truncated {
+            needed: 16 + TRAILER_LEN,
+            got: blob.len(),
+        });
+    }
+    let at = blob.len() - TRAILER_LEN;
+    let bytes = u32::from_le_bytes(blob[at..at + 4].try_into().unwrap()) as usize;
+    if bytes == 0 || blob[at + 4] == 0 {
+        bail!("empty bloom filter");
+    }
+    if at - 16 < bytes {
+        return Err(CydecError::Truncated {
+            needed: bytes,
+            got: at - 16,
+        });
+    }
+    Ok(bytes + TRAILER_LEN)
+}
+
+/// Filtered blob for `data`: the blocked layout with a filter at the end
+pub(crate) fn with_filter(
+    mut blob: Vec<u8>,
+    data: impl ExactSizeIterator<Item = i64>,
+    bits_per_value: u32,
+) -> Vec<u8> {
+    debug_assert!(blob[7] & BLOCKED_FLAG != 0);
+    let mut filter = Bloom::new(data.len(), bits_per_value);
+    for x in data {
+        filter.insert(x);
+    }
+    filter.write(&mut blob);
+    blob
+}
+
+impl IntegerCodec {
+    /// Whether an i64 blob may hold `value`.
+    ///
+    /// `false` is definite. Blobs compressed with
+    /// [`crate::CompressOptions::filter_bits`] answer from their Bloom filter
+    /// and block statistics without decompressing anything, at the filter's
+    /// false positive rate. Other blobs are checked exactly, decoding only
+    /// the blocks whose statistics do not rule the value out.
+    pub fn might_contain_i64(&self, blob: &[u8], value: i64) -> Result<bool> {
+        if blob.is_empty() {
+            return Ok(false);
+        }
+        let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+        let candidates = blocks.iter().filter(|block| {
+            block.count > 0 && block.stats.is_none_or(|s| (s.min..=s.max).contains(&value))
+        });
+        if has_flag(blob[7], FILTER_FLAG) {
+            let (filter, _) = Bloom::read(blob)?;
+            return Ok(candidates.count() > 0 && filter.contains(value));
+        }
+        for block in candidates {
+            let mut found = false;
+            Self::decode_i64_payload_each(block.payload, block.count, backend, |_, x| {
+                found |= x == value
+            })?;
+            if found {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompressOptions;
+
+    #[test]
+    fn filtered_blobs_answer_membership() -> Result<()> {
+        let c = IntegerCodec::default();
+        let ids: Vec<i64> = (0..20_000).map(|i| i * 3 + 1_000_000).collect();
+        let options = CompressOptions {
+            filter_bits: 10,
+            ..Default::default()
+        };
+        let blob = c.compress_i64_with(&ids, &options)?;
+        assert!(has_flag(blob[7], FILTER_FLAG));
+        assert_eq!(ids, c.decompress_i64(&blob)?);
+        assert!(
+            ids.iter()
+                .all(|&id| c.might_contain_i64(&blob, id).unwrap())
+        );
+
+        // inside the min/max range, only the filter rules values out
+        let false_positives = (0..10_000)
+            .map(|i| 1_000_001 + i * 6)
+            .filter(|&id| c.might_contain_i64(&blob, id).unwrap())
+            .count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+        assert!(!c.might_contain_i64(&blob, -5)?);
+        Ok(())
+    }
+
+    #[test]
+    fn appends_keep_the_filter_up_to_date() -> Result<()> {
+        let c = IntegerCodec::default();
+        let options = CompressOptions {
+            filter_bits: 12,
+            ..Default::default()
+        };
+        let mut blob = c.compress_i64_with(&[10, 20, 30], &options)?;
+        c.append_i64(&mut blob, &[40, 7])?;
+        assert!(has_flag(blob[7], FILTER_FLAG));
+        assert_eq!(c.decompress_i64(&blob)?, [10, 20, 30, 40, 7]);
+        assert!(c.might_contain_i64(&blob, 7)?);
+        assert_eq!(c.get_i64(&blob, 4)?, 7);
+        let streamed: Vec<i64> = crate::CydecReader::new(blob.as_slice()).collect::<Result<_>>()?;
+        assert_eq!(streamed, [10, 20, 30, 40, 7]);
+        // concatenation keeps the values but drops the filter
+        let joined = c.concat_i64(&[&blob, &blob])?;
+        assert!(!has_flag(joined[7], FILTER_FLAG));
+        assert_eq!(c.decompress_i64(&joined)?.len(), 10);
+
+        // unfiltered blobs are checked exactly
+        let plain = c.compress_i64(&[1, 2, 3])?;
+        assert!(c.might_contain_i64(&plain, 2)?);
+        assert!(!c.might_contain_i64(&plain, 4)?);
+        assert!(!c.might_contain_i64(&[], 4)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,359 @@
+use crate::CydecError;
+use crate::error::{Result, corrupt, invalid};
+use crate::raw_chunk;
+
+/// Blosc2 chunk format version written, `BLOSC2_VERSION_FORMAT_STABLE`
+const VERSION: u8 = 5;
+const MIN_HEADER: usize = 16;
+const EXTENDED_HEADER: usize = 32;
+
+const DO_SHUFFLE: u8 = 0x01;
+const MEMCPYED: u8 = 0x02;
+const DO_BITSHUFFLE: u8 = 0x04;
+const DO_DELTA: u8 = 0x08;
+const DONT_SPLIT: u8 = 0x10;
+
+/// Codec formats in the top three bits of the flags
+const LZ4_FORMAT: u8 = 1;
+#[cfg(feature = "zstd")]
+const ZSTD_FORMAT: u8 = 4;
+const UDCODEC_FORMAT: u8 = 6;
+
+/// Blosc2 filter codes in the extended header
+const NO_FILTER: u8 = 0;
+const SHUFFLE: u8 = 1;
+const TRUNC_PREC: u8 = 4;
+
+/// Id cydec streams are written under, in the range Blosc2 leaves to
+/// user-registered codecs
+pub const BLOSC_CYDEC_CODEC: u8 = 200;
+
+/// Wrap `blob`, from any integer or float pipeline, in a Blosc2 chunk.
+///
+/// The chunk holds one block with the blob as its only stream, under the
+/// user-defined codec [`BLOSC_CYDEC_CODEC`], so Blosc2 tools read its sizes
+/// and type size from the header; decompressing it with c-blosc2 needs
+/// cydec registered under that id. Blobs no smaller than their values are
+/// stored as a plain copy that any Blosc reader decodes.
+pub fn compress_blosc(blob: &[u8]) -> Result<Vec<u8>> {
+    let (raw, typesize) = raw_chunk::decode_values(blob)?;
+    let nbytes = u32::try_from(raw.len())
+        .ok()
+        .filter(|&n| n <= i32::MAX as u32 - EXTENDED_HEADER as u32 - 8)
+        .ok_or_else(|| invalid!("blosc chunks hold at most 2 GiB, got {} bytes", raw.len()))?;
+    let memcpyed = blob.len() >= raw.len();
+    let mut flags = DO_SHUFFLE | DO_BITSHUFFLE | DONT_SPLIT | UDCODEC_FORMAT << 5;
+    if memcpyed {
+        flags |= MEMCPYED;
+    }
+    let mut out = Vec::with_capacity(EXTENDED_HEADER + 8 + blob.len().min(raw.len()));
+    out.extend_from_slice(&[VERSION, 1, flags, typesize as u8]);
+    out.extend_from_slice(&nbytes.to_le_bytes());
+    out.extend_from_slice(&nbytes.to_le_bytes()); // blocksize: one block
+    out.extend_from_slice(&[0; 4]); // cbytes, set below
+    out.extend_from_slice(&[NO_FILTER; 6]);
+    out.push(BLOSC_CYDEC_CODEC);
+    out.push(0); // codec meta
+    out.extend_from_slice(&[0; 6]); // filter meta
+    out.extend_from_slice(&[0, 0]); // reserved, blosc2 flags
+    if memcpyed {
+        out.extend_from_slice(&raw);
+    } else if !raw.is_empty() {
+        out.extend_from_slice(&(EXTENDED_HEADER as i32 + 4).to_le_bytes());
+        out.extend_from_slice(&(blob.len() as i32).to_le_bytes());
+        out.extend_from_slice(blob);
+    }
+    let cbytes = out.len() as u32;
+    out[12..16].copy_from_slice(&cbytes.to_le_bytes());
+    Ok(out)
+}
+
+/// Decompress a Blosc1 or Blosc2 chunk into the bytes it holds.
+///
+/// Reads chunks from [`compress_blosc`] and chunks Blosc wrote as plain
+/// copies or with the LZ4 codec, or Zstd with the `zstd` feature, with or
+/// without byte shuffling. BloscLZ, Snappy and Zlib streams, bit
+/// shuffling, the delta filter and dictionaries are not supported.
+pub fn decompress_blosc(chunk: &[u8]) -> Result<Vec<u8>> {
+    if chunk.len() < MIN_HEADER {
+        return Err(CydecError::Truncated {
+            needed: MIN_HEADER,
+            got: chunk.len(),
+        });
+    }
+    let version = chunk[0];
+    if version == 0 || version > VERSION {
+        return Err(corrupt!("unknown blosc format version {version}"));
+    }
+    let flags = chunk[2];
+    let typesize = chunk[3].max(1) as usize;
+    let word = |at: usize| u32::from_le_bytes(chunk[at..at + 4].try_into().unwrap()) as usize;
+    let (nbytes, blocksize, cbytes) = (word(4), word(8), word(12));
+    let extended =
+        version >= 3 && flags & (DO_SHUFFLE | DO_BITSHUFFLE) == DO_SHUFFLE | DO_BITSHUFFLE;
+    let header_len = if extended {
+        EXTENDED_HEADER
+    } else {
+        MIN_HEADER
+    };
+    if chunk.len() < cbytes.max(header_len) {
+        return Err(CydecError::Truncated {
+            needed: cbytes.max(header_len),
+            got: chunk.len(),
+        });
+    }
+    let chunk = &chunk[..cbytes.max(header_len)];
+
+    let (filters, udcodec) = if extended {
+        if let Some(values) = special_values(chunk, nbytes, typesize)? {
+            return Ok(values);
+        }
+        (chunk[16..22].to_vec(), chunk[22])
+    } else {
+        if flags & (DO_BITSHUFFLE | DO_DELTA) != 0 {
+            return Err(invalid!("blosc bit shuffling and delta are not supported"));
+        }
+        let shuffle = if flags & DO_SHUFFLE != 0 {
+            SHUFFLE
+        } else {
+            NO_FILTER
+        };
+        (vec![shuffle], 0)
+    };
+    if flags & MEMCPYED != 0 {
+        let end = header_len + nbytes;
+        if chunk.len() < end {
+            return Err(CydecError::Truncated {
+                needed: end,
+                got: chunk.len(),
+            });
+        }
+        return Ok(chunk[header_len..end].to_vec());
+    }
+    if nbytes == 0 {
+        return Ok(Vec::new());
+    }
+    if blocksize == 0 {
+        return Err(corrupt!("blosc chunk of {nbytes} bytes has empty blocks"));
+    }
+
+    let codec = StreamCodec::of(flags >> 5, udcodec)?;
+    let nblocks = nbytes.div_ceil(blocksize);
+    let mut out = Vec::with_capacity(nbytes);
+    for i in 0..nblocks {
+        let at = header_len + 4 * i;
+        let start = chunk
+            .get(at..at + 4)
+            .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| corrupt!("blosc block offsets are truncated"))?;
+        let bsize = blocksize.min(nbytes - i * blocksize);
+        let streams = if flags & DONT_SPLIT != 0 || bsize < blocksize {
+            1
+        } else {
+            typesize
+        };
+        if !bsize.is_multiple_of(streams) {
+            return Err(corrupt!("blosc block of {bsize} bytes splits unevenly"));
+        }
+        let mut block = Vec::with_capacity(bsize);
+        let mut pos =
+            usize::try_from(start).map_err(|_| corrupt!("negative blosc block offset"))?;
+        for _ in 0..streams {
+            let csize = chunk
+                .get(pos..pos + 4)
+                .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| corrupt!("blosc stream at {pos} is truncated"))?;
+            pos += 4;
+            let len = bsize / streams;
+            match csize {
+                // Blosc2 runs: all zeros, or one repeated byte
+                0 => block.resize(block.len() + len, 0),
+                ..0 => block.resize(block.len() + len, (-csize) as u8),
+                _ => {
+                    let end = pos + csize as usize;
+                    let stream = chunk
+                        .get(pos..end)
+                        .ok_or_else(|| corrupt!("blosc stream at {pos} is truncated"))?;
+                    if csize as usize == len {
+                        block.extend_from_slice(stream);
+                    } else {
+                        block.extend(codec.decompress(stream, len)?);
+                    }
+                    pos = end;
+                }
+            }
+        }
+        for &filter in filters.iter().rev() {
+            match filter {
+                NO_FILTER | TRUNC_PREC => {}
+                SHUFFLE if typesize > 1 => block = unshuffle(&block, typesize),
+                SHUFFLE => {}
+                other => return Err(invalid!("blosc filter {other} is not supported")),
+            }
+        }
+        out.extend_from_slice(&block);
+    }
+    Ok(out)
+}
+
+/// Values of a Blosc2 chunk made of one repeated value, which stores no
+/// blocks
+fn special_values(chunk: &[u8], nbytes: usize, typesize: usize) -> Result<Option<Vec<u8>>> {
+    let blosc2_flags = chunk[31];
+    if blosc2_flags & 0x01 != 0 {
+        return Err(invalid!("blosc dictionaries are not supported"));
+    }
+    let value: Vec<u8> = match (blosc2_flags >> 4) & 0x07 {
+        0 => return Ok(None),
+        1 | 3 => vec![0],
+        2 if typesize == 4 => f32::NAN.to_le_bytes().to_vec(),
+        2 if typesize == 8 => f64::NAN.to_le_bytes().to_vec(),
+        4 => chunk
+            .get(EXTENDED_HEADER..EXTENDED_HEADER + typesize)
+            .ok_or_else(|| corrupt!("blosc repeated value is truncated"))?
+            .to_vec(),
+        other => return Err(corrupt!("unknown blosc special value {other}")),
+    };
+    Ok(Some(value.iter().copied().cycle().take(nbytes).collect()))
+}
+
+/// Undo Blosc's byte shuffle, which stores byte `j` of every element
+/// together; trailing bytes short of an element stay in place
+fn unshuffle(block: &[u8], typesize: usize) -> Vec<u8> {
+    let n = block.len() / typesize;
+    let mut out = vec![0; block.len()];
+    for i in 0..n {
+        for j in 0..typesize {
+            out[i * typesize + j] = block[j * n + i];
+        }
+    }
+    out[n * typesize..].copy_from_slice(&block[n * typesize..]);
+    out
+}
+
+#[derive(Clone, Copy)]
+enum StreamCodec {
+    Lz4,
+    #[cfg(feature = "zstd")]
+    Zstd,
+    Cydec,
+}
+
+impl StreamCodec {
+    fn of(format: u8, udcodec: u8) -> Result<Self> {
+        match (format, udcodec) {
+            (LZ4_FORMAT, _) => Ok(Self::Lz4),
+            #[cfg(feature = "zstd")]
+            (ZSTD_FORMAT, _) => Ok(Self::Zstd),
+            (UDCODEC_FORMAT, BLOSC_CYDEC_CODEC) => Ok(Self::Cydec),
+            (UDCODEC_FORMAT, found) | (found, _) => Err(CydecError::UnsupportedBackend { found }),
+        }
+    }
+
+    fn decompress(self, stream: &[u8], len: usize) -> Result<Vec<u8>> {
+        let out = match self {
+            Self::Lz4 => lz4_flex::block::decompress(stream, len)
+                .map_err(|e| corrupt!("blosc lz4 stream: {e}"))?,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::bulk::decompress(stream, len)
+                .map_err(|e| corrupt!("blosc zstd stream: {e}"))?,
+            Self::Cydec => raw_chunk::decode_values(stream)?.0,
+        };
+        if out.len() != len {
+            return Err(corrupt!(
+                "blosc stream decoded to {} bytes, expected {len}",
+                out.len()
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Blosc1 chunk as c-blosc writes it with LZ4 and byte shuffling,
+    /// split into one stream per byte of the type
+    fn blosc1_lz4(raw: &[u8], typesize: usize, blocksize: usize) -> Vec<u8> {
+        let mut body = Vec::new();
+        let nblocks = raw.len().div_ceil(blocksize);
+        let mut starts = Vec::new();
+        for block in raw.chunks(blocksize) {
+            starts.push((MIN_HEADER + 4 * nblocks + body.len()) as i32);
+            let n = block.len() / typesize;
+            let mut shuffled = block.to_vec();
+            for i in 0..n {
+                for j in 0..typesize {
+                    shuffled[j * n + i] = block[i * typesize + j];
+                }
+            }
+            let streams = if block.len() == blocksize {
+                typesize
+            } else {
+                1
+            };
+            for stream in shuffled.chunks(block.len() / streams) {
+                let packed = lz4_flex::block::compress(stream);
+                body.extend_from_slice(&(packed.len() as i32).to_le_bytes());
+                body.extend_from_slice(&packed);
+            }
+        }
+        let cbytes = MIN_HEADER + 4 * nblocks + body.len();
+        let mut out = vec![2, 1, DO_SHUFFLE | LZ4_FORMAT << 5, typesize as u8];
+        for word in [raw.len(), blocksize, cbytes] {
+            out.extend_from_slice(&(word as u32).to_le_bytes());
+        }
+        starts
+            .iter()
+            .for_each(|s| out.extend_from_slice(&s.to_le_bytes()));
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn blosc_chunks_roundtrip_and_read() -> Result<()> {
+        let ts: Vec<i64> = (0..20_000).map(|i| 1_700_000_000_000 + i * 1_000).collect();
+        let raw: Vec<u8> = ts.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let blob = crate::compress(&ts)?;
+        let chunk = compress_blosc(&blob)?;
+        assert_eq!(chunk[3], 8);
+        assert_eq!(
+            u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as usize,
+            raw.len()
+        );
+        assert_eq!(chunk.len(), EXTENDED_HEADER + 8 + blob.len());
+        assert_eq!(decompress_blosc(&chunk)?, raw);
+
+        // noise does not compress, so the chunk holds a plain copy
+        let noise: Vec<u8> = (0..3_000)
+            .scan(0x9e37_79b9u32, |x, _| {
+                *x ^= *x << 13;
+                *x ^= *x >> 17;
+                *x ^= *x << 5;
+                Some((*x >> 24) as u8)
+            })
+            .collect();
+        let chunk = compress_blosc(&crate::compress(&noise)?)?;
+        assert_ne!(chunk[2] & MEMCPYED, 0);
+        assert_eq!(decompress_blosc(&chunk)?, noise);
+        assert_eq!(decompress_blosc(&compress_blosc(&[])?)?, Vec::<u8>::new());
+
+        assert_eq!(decompress_blosc(&blosc1_lz4(&raw, 8, 32_768))?, raw);
+        let odd = &raw[..raw.len() - 5];
+        assert_eq!(decompress_blosc(&blosc1_lz4(odd, 8, 4_096))?, odd);
+
+        let mut zeros = compress_blosc(&blob)?;
+        zeros[31] = 1 << 4;
+        assert_eq!(decompress_blosc(&zeros)?, vec![0; raw.len()]);
+
+        let mut blosclz = blosc1_lz4(&raw, 8, 32_768);
+        blosclz[2] &= 0x1f;
+        assert!(matches!(
+            decompress_blosc(&blosclz),
+            Err(CydecError::UnsupportedBackend { found: 0 })
+        ));
+        assert!(decompress_blosc(&chunk[..20]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+use crate::error::{Result, corrupt, invalid};
+use crate::header;
+use crate::{Backend, CydecError, FloatingCodec, IntegerCodec};
+use integer_encoding::{VarIntReader, VarIntWriter};
+use std::io::Cursor;
+
+/// One OHLCV bar
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Candle {
+    pub timestamp: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// Joint codec for OHLCV candles.
+///
+/// Instead of five independent float columns it stores what is left after
+/// the usual bar invariants: open relative to the previous close, close
+/// relative to open, and high/low as their excursion beyond the open-close
+/// body. Timestamps are delta-of-delta encoded, so a regular bar interval
+/// costs one byte per bar.
+#[derive(Clone, Debug)]
+pub struct CandleCodec {
+    pub price_scale: f64,
+    pub volume_scale: f64,
+}
+
+impl Default for CandleCodec {
+    fn default() -> Self {
+        Self {
+            price_scale: FloatingCodec::DEFAULT_F64_SCALE,
+            volume_scale: 1_000_000.0,
+        }
+    }
+}
+
+impl CandleCodec {
+    pub fn compress(&self, candles: &[Candle]) -> Result<Vec<u8>> {
+        if candles.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let price = |f: f64| (f * self.price_scale).round() as i64;
+        let n = candles.len();
+        let mut tmp = Vec::with_capacity(n * 8);
+        let mut write = |v: i64| tmp.write_varint(IntegerCodec::zigzag_i64(v)).unwrap();
+
+        // timestamps: delta of delta
+        let (mut prev, mut prev_delta) = (0i64, 0i64);
+        for c in candles {
+            let delta = c.timestamp.wrapping_sub(prev);
+            write(delta.wrapping_sub(prev_delta));
+            (prev, prev_delta) = (c.timestamp, delta);
+        }
+        // open against the previous close, which is usually equal
+        let mut prev_close = 0i64;
+        for c in candles {
+            write(price(c.open).wrapping_sub(prev_close));
+            prev_close = price(c.close);
+        }
+        // close against open
+        for c in candles {
+            write(price(c.close).wrapping_sub(price(c.open)));
+        }
+        // wicks beyond the body, non-negative for well-formed bars
+        for c in candles {
+            write(price(c.high).wrapping_sub(price(c.open).max(price(c.close))));
+        }
+        for c in candles {
+            write(price(c.open).min(price(c.close)).wrapping_sub(price(c.low)));
+        }
+        let mut prev_volume = 0i64;
+        for c in candles {
+            let volume = (c.volume * self.volume_scale).round() as i64;
+            write(volume.wrapping_sub(prev_volume));
+            prev_volume = volume;
+        }
+
+        let mut buf = Vec::with_capacity(tmp.len() / 2 + 32);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(26); // 7: type (26 = OHLCV candles)
+        buf.extend_from_slice(&(n as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&self.price_scale.to_le_bytes()); // 16..24
+        buf.extend_from_slice(&self.volume_scale.to_le_bytes()); // 24..32
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    /// Compress candles given as separate columns of equal length
+    pub fn compress_columns(
+        &self,
+        timestamp: &[i64],
+        open: &[f64],
+        high: &[f64],
+        low: &[f64],
+        close: &[f64],
+        volume: &[f64],
+    ) -> Result<Vec<u8>> {
+        let n = timestamp.len();
+        if [open.len(), high.len(), low.len(), close.len(), volume.len()]
+            .iter()
+            .any(|&len| len != n)
+        {
+            return Err(invalid!("candle columns have different lengths"));
+        }
+        let candles: Vec<Candle> = (0..n)
+            .map(|i| Candle {
+                timestamp: timestamp[i],
+                open: open[i],
+                high: high[i],
+                low: low[i],
+                close: close[i],
+                volume: volume[i],
+            })
+            .collect();
+        self.compress(&candles)
+    }
+
+    /// Decompress candles; scales are read from the blob
+    pub fn decompress(&self, blob: &[u8]) -> Result<Vec<Candle>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 32 {
+            return Err(CydecError::Truncated {
+                needed: 32,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 26 {
+            return Err(CydecError::TypeMismatch {
+                expected: "candles",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let price_scale = f64::from_le_bytes(blob[16..24].try_into().unwrap());
+        let volume_scale = f64::from_le_bytes(blob[24..32].try_into().unwrap());
+
+        let packed = Backend::Lz4.unpack(&blob[32..])?;
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut column = || -> Result<Vec<i64>> {
+            (0..n)
+                .map(|_| {
+                    cur.read_varint()
+                        .map(IntegerCodec::unzigzag_i64)
+                        .map_err(|e| corrupt!("varint decode: {e}"))
+                })
+                .collect()
+        };
+        let (ts, open, body, upper, lower, volume) = (
+            column()?,
+            column()?,
+            column()?,
+            column()?,
+            column()?,
+            column()?,
+        );
+
+        let mut out = Vec::with_capacity(n.min(ts.len()));
+        let (mut t, mut t_delta, mut prev_close, mut v) = (0i64, 0i64, 0i64, 0i64);
+        for i in 0..n {
+            t_delta = t_delta.wrapping_add(ts[i]);
+            t = t.wrapping_add(t_delta);
+            let o = prev_close.wrapping_add(open[i]);
+            let c = o.wrapping_add(body[i]);
+            let h = o.max(c).wrapping_add(upper[i]);
+            let l = o.min(c).wrapping_sub(lower[i]);
+            v = v.wrapping_add(volume[i]);
+            prev_close = c;
+            out.push(Candle {
+                timestamp: t,
+                open: o as f64 / price_scale,
+                high: h as f64 / price_scale,
+                low: l as f64 / price_scale,
+                close: c as f64 / price_scale,
+                volume: v as f64 / volume_scale,
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn random_walk_candles(n: usize) -> Vec<Candle> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut close = 117_000.0f64;
+        (0..n)
+            .map(|i| {
+                let open = close;
+                close = ((open + rng.gen_range(-25.0..25.0)) * 100.0).round() / 100.0;
+                let high = open.max(close) + (rng.gen_range(0.0..10.0f64) * 100.0).round() / 100.0;
+                let low = open.min(close) - (rng.gen_range(0.0..10.0f64) * 100.0).round() / 100.0;
+                Candle {
+                    timestamp: 1_700_000_000_000 + i as i64 * 60_000,
+                    open,
+                    high,
+                    low,
+                    close,
+                    volume: (rng.gen_range(0.0..50.0f64) * 1e4).round() / 1e4,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip_candles() -> Result<()> {
+        let codec = CandleCodec {
+            price_scale: 100.0,
+            volume_scale: 1e4,
+        };
+        let candles = random_walk_candles(10_000);
+        let blob = codec.compress(&candles)?;
+        let back = CandleCodec::default().decompress(&blob)?;
+        assert_eq!(candles.len(), back.len());
+        for (a, b) in candles.iter().zip(back.iter()) {
+            assert_eq!(a.timestamp, b.timestamp);
+            for (x, y) in [
+                (a.open, b.open),
+                (a.high, b.high),
+                (a.low, b.low),
+                (a.close, b.close),
+                (a.volume, b.volume),
+            ] {
+                assert!((x - y).abs() < 1e-9, "Values differ: {} vs {}", x, y);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn joint_encoding_beats_independent_columns() -> Result<()> {
+        let codec = CandleCodec {
+            price_scale: 100.0,
+            volume_scale: 1e4,
+        };
+        let candles = random_walk_candles(10_000);
+        let joint = codec.compress(&candles)?.len();
+
+        let ints = IntegerCodec::default();
+        let floats = FloatingCodec::default();
+        let ts: Vec<i64> = candles.iter().map(|c| c.timestamp).collect();
+        let column = |f: fn(&Candle) -> f64, scale| {
+            let v: Vec<f64> = candles.iter().map(f).collect();
+            floats.compress_f64(&v, Some(scale)).unwrap().len()
+        };
+        let separate = ints.compress_i64(&ts)?.len()
+            + column(|c| c.open, 100.0)
+            + column(|c| c.high, 100.0)
+            + column(|c| c.low, 100.0)
+            + column(|c| c.close, 100.0)
+            + column(|c| c.volume, 1e4);
+        assert!(joint < separate, "joint {joint}B vs separate {separate}B");
+        Ok(())
+    }
+
+    #[test]
+    fn column_lengths_must_match() {
+        let codec = CandleCodec::default();
+        let r = codec.compress_columns(&[1, 2], &[1.0; 2], &[1.0; 2], &[1.0], &[1.0; 2], &[1.0; 2]);
+        assert!(r.is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::error::Result;
+use crate::{
+    Backend, CompressOptions, CompressedBlob, CydecElement, FloatingCodec, IntegerCodec, Strided,
+};
+
+/// Single entry point for every supported element type.
+///
+/// Delegates to the integer and float pipelines, so callers need not hold
+/// both codecs or remember which one owns a given method. The specialised
+/// codecs stay reachable through [`Codec::integers`] and [`Codec::floats`].
+#[derive(Clone, Debug)]
+pub struct Codec {
+    integers: IntegerCodec,
+    floats: FloatingCodec,
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Self::new(Backend::Lz4)
+    }
+}
+
+macro_rules! delegate {
+    ($field:ident: $($compress:ident / $decompress:ident: $t:ty),* $(,)?) => {
+        $(
+            pub fn $compress(&self, data: &[$t]) -> Result<Vec<u8>> {
+                self.$field.$compress(data)
+            }
+
+            pub fn $decompress(&self, blob: &[u8]) -> Result<Vec<$t>> {
+                self.$field.$decompress(blob)
+            }
+        )*
+    };
+}
+
+impl Codec {
+    pub const fn new(backend: Backend) -> Self {
+        Self {
+            integers: IntegerCodec::with_backend(backend),
+            floats: FloatingCodec::with_backend(backend),
+        }
+    }
+
+    pub fn integers(&self) -> &IntegerCodec {
+        &self.integers
+    }
+
+    pub fn floats(&self) -> &FloatingCodec {
+        &self.floats
+    }
+
+    delegate!(integers:
+        compress_bytes / decompress_bytes: u8,
+        compress_i8 / decompress_i8: i8,
+        compress_i16 / decompress_i16: i16,
+        compress_u16 / decompress_u16: u16,
+        compress_i32 / decompress_i32: i32,
+        compress_u32 / decompress_u32: u32,
+        compress_i64 / decompress_i64: i64,
+        compress_u64 / decompress_u64: u64,
+        compress_i128 / decompress_i128: i128,
+        compress_u128 / decompress_u128: u128,
+        compress_uuid_bytes / decompress_uuid_bytes: [u8; 16],
+        compress_ipv4 / decompress_ipv4: std::net::Ipv4Addr,
+        compress_ipv6 / decompress_ipv6: std::net::Ipv6Addr,
+    );
+
+    #[cfg(feature = "uuid")]
+    delegate!(integers: compress_uuids / decompress_uuids: uuid::Uuid);
+
+    #[cfg(feature = "half")]
+    delegate!(floats:
+        compress_f16 / decompress_f16: half::f16,
+        compress_bf16 / decompress_bf16: half::bf16,
+    );
+
+    pub fn compress_f32(&self, data: &[f32], scale: Option<f32>) -> Result<Vec<u8>> {
+        self.floats.compress_f32(data, scale)
+    }
+
+    pub fn decompress_f32(&self, blob: &[u8], scale: Option<f32>) -> Result<Vec<f32>> {
+        self.floats.decompress_f32(blob, scale)
+    }
+
+    pub fn compress_f64(&self, data: &[f64], scale: Option<f64>) -> Result<Vec<u8>> {
+        self.floats.compress_f64(data, scale)
+    }
+
+    pub fn decompress_f64(&self, blob: &[u8], scale: Option<f64>) -> Result<Vec<f64>> {
+        self.floats.decompress_f64(blob, scale)
+    }
+
+    pub fn compress_i64_with(&self, data: &[i64], options: &CompressOptions) -> Result<Vec<u8>> {
+        self.integers.compress_i64_with(data, options)
+    }
+
+    pub fn compress_i64_strided<'a>(&self, data: impl Into<Strided<'a, i64>>) -> Result<Vec<u8>> {
+        self.integers.compress_i64_strided(data)
+    }
+
+    pub fn compress_f64_strided<'a>(
+        &self,
+        data: impl Into<Strided<'a, f64>>,
+        scale: Option<f64>,
+    ) -> Result<Vec<u8>> {
+        self.floats.compress_f64_strided(data, scale)
+    }
+
+    pub fn compress_u64_with(&self, data: &[u64], options: &CompressOptions) -> Result<Vec<u8>> {
+        self.integers.compress_u64_with(data, options)
+    }
+
+    pub fn compress_f64_with(&self, data: &[f64], options: &CompressOptions) -> Result<Vec<u8>> {
+        self.floats.compress_f64_with(data, options)
+    }
+
+    /// Compress any [`CydecElement`]; floats use their default scale
+    pub fn compress<T: CydecElement>(&self, data: &[T]) -> Result<Vec<u8>> {
+        T::compress_slice(data)
+    }
+
+    pub fn decompress<T: CydecElement>(&self, blob: &[u8]) -> Result<Vec<T>> {
+        T::decompress_blob(blob)
+    }
+
+    /// Like [`Codec::compress`], but the blob carries its element type
+    pub fn compress_typed<T: CydecElement>(&self, data: &[T]) -> Result<CompressedBlob<T>> {
+        crate::compress_typed(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn facade_covers_both_pipelines() -> Result<()> {
+        let c = Codec::default();
+        let ints: Vec<i64> = (0..1_000).collect();
+        assert_eq!(ints, c.decompress_i64(&c.compress_i64(&ints)?)?);
+        let floats: Vec<f64> = (0..1_000).map(|i| i as f64 * 0.5).collect();
+        assert_eq!(
+            floats,
+            c.decompress_f64(&c.compress_f64(&floats, None)?, None)?
+        );
+        let bytes = b"raw bytes".to_vec();
+        assert_eq!(bytes, c.decompress_bytes(&c.compress_bytes(&bytes)?)?);
+        assert_eq!(ints, c.decompress::<i64>(&c.compress(&ints)?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn facade_blobs_match_the_underlying_codecs() -> Result<()> {
+        let c = Codec::new(Backend::Lz4);
+        let data: Vec<u32> = (0..500).map(|i| i * 3).collect();
+        assert_eq!(
+            c.compress_u32(&data)?,
+            IntegerCodec::default().compress_u32(&data)?
+        );
+        assert!(c.decompress_f64(&c.compress_u32(&data)?, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn codecs_and_options_are_const() -> Result<()> {
+        static CODEC: Codec = Codec::new(Backend::Lz4);
+        static INTEGERS: IntegerCodec = IntegerCodec::new();
+        static FLOATS: FloatingCodec = FloatingCodec::new();
+        const OPTIONS: CompressOptions = CompressOptions::new();
+
+        assert_eq!(OPTIONS, CompressOptions::default());
+        let data: Vec<i64> = (0..100).collect();
+        assert_eq!(
+            CODEC.compress_i64_with(&data, &OPTIONS)?,
+            INTEGERS.compress_i64(&data)?
+        );
+        assert_eq!(FLOATS.codec, Backend::Lz4);
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use crate::error::{Result, bail, corrupt};
+use crate::format::{HEADER_LEN, MAGIC, TypeTag, VERSION_1};
+use crate::{Backend, CydecError, IntegerCodec};
+use integer_encoding::{VarIntReader, VarIntWriter};
+use std::io::Cursor;
+
+/// How a frame column is encoded, chosen per column with
+/// [`crate::FrameBuilder::with_encodings`].
+///
+/// Every encoding writes an ordinary blob that the element type's own
+/// decompressor reads back, so columns stay readable on their own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ColumnEncoding {
+    /// The element type's default pipeline
+    #[default]
+    Auto,
+    /// i64 only: delta-of-delta, bit-packed in runs of 128 at the width of
+    /// the widest value. Regularly sampled timestamps pack to almost
+    /// nothing.
+    DeltaOfDelta,
+    /// f64 only: each value XORed with the previous one, lossless. Suits
+    /// prices that move in small steps but have no fixed decimal scale.
+    Xor,
+    /// i64 or u64: each value on its own as a varint, without deltas.
+    /// Suits volumes and counts that jump around.
+    Varint,
+}
+
+pub(crate) const DOD_I64: u8 = TypeTag::DodI64 as u8;
+pub(crate) const VARINT_I64: u8 = TypeTag::VarintI64 as u8;
+pub(crate) const VARINT_U64: u8 = TypeTag::VarintU64 as u8;
+
+/// Values per bit-packed run
+const RUN_LEN: usize = 128;
+
+/// Pack `values` in runs of [`RUN_LEN`], each `[u8 width][values]` with
+/// every value taking `width` bits, least significant first
+fn bitpack(values: &[u64], out: &mut Vec<u8>) {
+    for run in values.chunks(RUN_LEN) {
+        let width = run
+            .iter()
+            .map(|v| 64 - v.leading_zeros())
+            .max()
+            .unwrap_or(0);
+        out.push(width as u8);
+        let (mut acc, mut bits) = (0u128, 0u32);
+        for &v in run {
+            acc |= (v as u128) << bits;
+            bits += width;
+            while bits >= 8 {
+                out.push(acc as u8);
+                acc >>= 8;
+                bits -= 8;
+            }
+        }
+        if bits > 0 {
+            out.push(acc as u8);
+        }
+    }
+}
+
+/// Inverse of [`bitpack`] for `n` values
+fn bitunpack(bytes: &[u8], n: usize) -> Result<Vec<u64>> {
+    // a run of zero width takes one byte
+    let mut out = Vec::with_capacity(n.min(bytes.len().saturating_mul(RUN_LEN)));
+    let mut pos = 0;
+    while out.len() < n {
+        let Some(&width) = bytes.get(pos) else {
+            bail!("bit-packed run missing after {} values", out.len());
+        };
+        let width = width as u32;
+        if width > 64 {
+            bail!("bit width {width} is too large");
+        }
+        let k = (n - out.len()).min(RUN_LEN);
+        let len = (k * width as usize).div_ceil(8);
+        let Some(run) = bytes.get(pos + 1..pos + 1 + len) else {
+            bail!("bit-packed run overruns the payload");
+        };
+        pos += 1 + len;
+        let mask = u64::MAX >> (64 - width.max(1));
+        let (mut acc, mut bits, mut i) = (0u128, 0u32, 0);
+        for _ in 0..k {
+            while bits < width {
+                acc |= (run[i] as u128) << bits;
+                i += 1;
+                bits += 8;
+            }
+            out.push(if width == 0 { 0 } else { acc as u64 & mask });
+            acc >>= width;
+            bits -= width;
+        }
+    }
+    if pos != bytes.len() {
+        bail!("{} bytes after the last bit-packed run", bytes.len() - pos);
+    }
+    Ok(out)
+}
+
+/// Fixed header of a blob in one of the column encodings
+fn header(tag: TypeTag, n: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN + n);
+    buf.extend_from_slice(&MAGIC); // 0..5
+    buf.push(VERSION_1); // 5: version
+    buf.push(Backend::Lz4.id()); // 6: codec
+    buf.push(tag as u8); // 7: type
+    buf.extend_from_slice(&(n as u64).to_le_bytes()); // 8..16
+    buf
+}
+
+impl IntegerCodec {
+    /// Blob for [`ColumnEncoding::DeltaOfDelta`]: the first value and
+    /// first delta as i64, then the zigzagged delta-of-deltas bit-packed
+    pub(crate) fn compress_i64_dod(data: &[i64]) -> Vec<u8> {
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let mut raw = Vec::with_capacity(16 + data.len() / 4);
+        raw.extend_from_slice(&data[0].to_le_bytes());
+        let first_delta = data.get(1).map_or(0, |x| x.wrapping_sub(data[0]));
+        raw.extend_from_slice(&first_delta.to_le_bytes());
+        let dods: Vec<u64> = data
+            .windows(3)
+            .map(|w| {
+                let dod = w[2]
+                    .wrapping_sub(w[1])
+                    .wrapping_sub(w[1].wrapping_sub(w[0]));
+                Self::zigzag_i64(dod)
+            })
+            .collect();
+        bitpack(&dods, &mut raw);
+        let mut buf = header(TypeTag::DodI64, data.len());
+        buf.extend_from_slice(&Backend::Lz4.pack(&raw, 0));
+        buf
+    }
+
+    /// Blob for [`ColumnEncoding::Varint`] over i64: zigzag varints, no
+    /// deltas
+    pub(crate) fn compress_i64_varint(data: &[i64]) -> Vec<u8> {
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let mut raw = Vec::with_capacity(data.len() * 2);
+        for &x in data {
+            raw.write_varint(Self::zigzag_i64(x)).unwrap();
+        }
+        let mut buf = header(TypeTag::VarintI64, data.len());
+        buf.extend_from_slice(&Backend::Lz4.pack(&raw, 0));
+        buf
+    }
+
+    /// Blob for [`ColumnEncoding::Varint`] over u64
+    pub(crate) fn compress_u64_varint(data: &[u64]) -> Vec<u8> {
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let mut raw = Vec::with_capacity(data.len() * 2);
+        for &x in data {
+            raw.write_varint(x).unwrap();
+        }
+        let mut buf = header(TypeTag::VarintU64, data.len());
+        buf.extend_from_slice(&Backend::Lz4.pack(&raw, 0));
+        buf
+    }
+
+    /// Values of a v1 i64 blob written by [`Self::compress_i64_dod`] or
+    /// [`Self::compress_i64_varint`]; the caller has checked the header
+    pub(crate) fn decode_column_i64(blob: &[u8]) -> Result<Vec<i64>> {
+        let n = crate::header::usize_at(blob, 8)?;
+        let raw = Backend::from_id(blob[6])?.unpack(&blob[HEADER_LEN..])?;
+        if blob[7] == TypeTag::VarintI64 as u8 {
+            return Ok(read_varints(&raw, n)?
+                .into_iter()
+                .map(Self::unzigzag_i64)
+                .collect());
+        }
+        if raw.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: raw.len(),
+            });
+        }
+        let mut x = i64::from_le_bytes(raw[0..8].try_into().unwrap());
+        let mut delta = i64::from_le_bytes(raw[8..16].try_into().unwrap());
+        let dods = bitunpack(&raw[16..], n.saturating_sub(2))?;
+        let mut out = Vec::with_capacity(dods.len() + 2);
+        out.extend((n > 0).then_some(x));
+        if n > 1 {
+            x = x.wrapping_add(delta);
+            out.push(x);
+        }
+        for dod in dods {
+            delta = delta.wrapping_add(Self::unzigzag_i64(dod));
+            x = x.wrapping_add(delta);
+            out.push(x);
+        }
+        Ok(out)
+    }
+
+    /// Values of a v1 blob written by [`Self::compress_u64_varint`]; the
+    /// caller has checked the header
+    pub(crate) fn decode_column_u64(blob: &[u8]) -> Result<Vec<u64>> {
+        let n = crate::header::usize_at(blob, 8)?;
+        let raw = Backend::from_id(blob[6])?.unpack(&blob[HEADER_LEN..])?;
+        read_varints(&raw, n)
+    }
+}
+
+/// Exactly `n` varints filling `raw`
+fn read_varints(raw: &[u8], n: usize) -> Result<Vec<u64>> {
+    let mut cur = Cursor::new(raw);
+    let mut out = Vec::with_capacity(n.min(raw.len()));
+    for _ in 0..n {
+        let v: u64 = cur
+            .read_varint()
+            .map_err(|e| corrupt!("varint decode: {e}"))?;
+        out.push(v);
+    }
+    if cur.position() as usize != raw.len() {
+        bail!("payload holds more than {n} values");
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_encodings_roundtrip() -> Result<()> {
+        let c = IntegerCodec::default();
+        let ts: Vec<i64> = (0..10_000).map(|i| 1_700_000_000_000 + i * 1_000).collect();
+        let dod = IntegerCodec::compress_i64_dod(&ts);
+        assert_eq!(c.decompress_i64(&dod)?, ts);
+        assert!(dod.len() < c.compress_i64(&ts)?.len());
+
+        let mut jittered = ts.clone();
+        jittered[500] += 7;
+        jittered[9_999] = i64::MIN;
+        for data in [
+            &jittered[..],
+            &ts[..1],
+            &ts[..2],
+            &ts[..3],
+            &[i64::MAX, i64::MIN, 0],
+        ] {
+            assert_eq!(
+                c.decompress_i64(&IntegerCodec::compress_i64_dod(data))?,
+                data
+            );
+            assert_eq!(
+                c.decompress_i64(&IntegerCodec::compress_i64_varint(data))?,
+                data
+            );
+        }
+
+        let volume: Vec<u64> = (0..5_000).map(|i| (i * 7_919) % 100_000).collect();
+        let blob = IntegerCodec::compress_u64_varint(&volume);
+        assert_eq!(c.decompress_u64(&blob)?, volume);
+        crate::validate(&blob)?;
+        crate::validate(&dod)?;
+
+        assert!(c.decompress_i64(&dod[..dod.len() - 1]).is_err());
+        Ok(())
+    }
+}
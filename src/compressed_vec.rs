@@ -0,0 +1,147 @@
+use crate::CydecElement;
+
+/// In-memory column that keeps its values compressed.
+///
+/// Values are stored in compressed blocks of [`CompressedVec::block_len`]
+/// elements plus an uncompressed tail that `push` appends to; the tail is
+/// compressed once it fills a block. Reads decompress one block at a time.
+/// Floats go through their default scale, so reads return the quantized
+/// values.
+#[derive(Clone, Debug)]
+pub struct CompressedVec<T> {
+    block_len: usize,
+    blocks: Vec<Vec<u8>>,
+    tail: Vec<T>,
+}
+
+impl<T: CydecElement> Default for CompressedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: CydecElement> CompressedVec<T> {
+    pub const DEFAULT_BLOCK_LEN: usize = 4096;
+
+    pub fn new() -> Self {
+        Self::with_block_len(Self::DEFAULT_BLOCK_LEN)
+    }
+
+    /// Larger blocks compress better, smaller blocks make `get` cheaper
+    pub fn with_block_len(block_len: usize) -> Self {
+        assert!(block_len > 0, "block length must be positive");
+        Self {
+            block_len,
+            blocks: Vec::new(),
+            tail: Vec::with_capacity(block_len),
+        }
+    }
+
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len() * self.block_len + self.tail.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes held by compressed blocks, excluding the uncompressed tail
+    pub fn compressed_bytes(&self) -> usize {
+        self.blocks.iter().map(Vec::len).sum()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.tail.push(value);
+        if self.tail.len() == self.block_len {
+            self.seal_tail();
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        for &v in values {
+            self.push(v);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        let (block, offset) = (index / self.block_len, index % self.block_len);
+        match self.blocks.get(block) {
+            Some(bytes) => Some(Self::open_block(bytes)[offset]),
+            None => self
+                .tail
+                .get(index - self.blocks.len() * self.block_len)
+                .copied(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.blocks
+            .iter()
+            .flat_map(|bytes| Self::open_block(bytes))
+            .chain(self.tail.iter().copied())
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+
+    fn seal_tail(&mut self) {
+        let bytes = T::compress_slice(&self.tail).expect("element pipelines accept any slice");
+        self.blocks.push(bytes);
+        self.tail.clear();
+    }
+
+    fn open_block(bytes: &[u8]) -> Vec<T> {
+        T::decompress_blob(bytes).expect("blocks are written by this container")
+    }
+}
+
+impl<T: CydecElement> FromIterator<T> for CompressedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut v = Self::new();
+        for x in iter {
+            v.push(x);
+        }
+        v
+    }
+}
+
+impl<T: CydecElement> Extend<T> for CompressedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_get_iter() {
+        let mut v = CompressedVec::with_block_len(100);
+        let data: Vec<i64> = (0..1_050).map(|i| i * 10 + i % 3).collect();
+        v.extend_from_slice(&data);
+        assert_eq!(v.len(), 1_050);
+        assert_eq!(v.get(0), Some(0));
+        assert_eq!(v.get(999), Some(data[999]));
+        assert_eq!(v.get(1_049), Some(data[1_049]));
+        assert_eq!(v.get(1_050), None);
+        assert_eq!(v.to_vec(), data);
+        assert!(v.compressed_bytes() < 1_000 * 8);
+    }
+
+    #[test]
+    fn collects_floats() {
+        let v: CompressedVec<f64> = (0..5_000).map(|i| i as f64 * 0.25).collect();
+        assert_eq!(v.len(), 5_000);
+        assert_eq!(v.get(4_999), Some(4_999.0 * 0.25));
+        assert!(v.iter().zip(0..).all(|(x, i)| x == i as f64 * 0.25));
+        assert!(CompressedVec::<u32>::default().is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::blocks::{BLOCKED_FLAG, FILTER_FLAG, STREAMED_LEN, block_header_len, has_flag};
+use crate::error::{Result, bail};
+use crate::floating_codec::{BLOCKED_F32, BLOCKED_F64};
+use crate::format::TYPE_MASK;
+use crate::{Backend, CydecError, FloatingCodec, Header, IntegerCodec};
+
+/// The values of one blob found by [`decompress_all`]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum DecodedArray {
+    I64(Vec<i64>),
+    U64(Vec<u64>),
+    I32(Vec<i32>),
+    U32(Vec<u32>),
+    I16(Vec<i16>),
+    U16(Vec<u16>),
+    I8(Vec<i8>),
+    I128(Vec<i128>),
+    U128(Vec<u128>),
+    Bytes(Vec<u8>),
+    F64(Vec<f64>),
+    F32(Vec<f32>),
+}
+
+/// Whether a blob header starts at `bytes`
+fn at_blob(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"CYDEC")
+}
+
+/// Length of the blob at the start of `bytes`, which may be followed by
+/// more blobs.
+///
+/// Streamed blobs do not record where they end, so they run until the
+/// input does or another blob header starts. Bloom filters record their
+/// size at their end, so the first candidate size followed by the end of
+/// the input or another blob header is taken.
+pub(crate) fn blob_len(bytes: &[u8]) -> Result<usize> {
+    let header = Header::parse(bytes)?;
+    let start = header.encoded_len();
+    let tag = header.type_tag;
+    if tag & BLOCKED_FLAG == 0 && !matches!(tag, BLOCKED_F64 | BLOCKED_F32) {
+        let backend = Backend::from_id(header.codec)?;
+        return Ok(start + backend.payload_len(&bytes[start..])?);
+    }
+
+    let header_len = block_header_len(tag);
+    let (mut offset, mut total) = (start, 0u64);
+    loop {
+        let done = match header.len {
+            STREAMED_LEN => offset == bytes.len() || at_blob(&bytes[offset..]),
+            n => total >= n,
+        };
+        if done {
+            break;
+        }
+        if bytes.len() - offset < header_len {
+            return Err(CydecError::Truncated {
+                needed: header_len,
+                got: bytes.len() - offset,
+            });
+        }
+        let count = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let len = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap());
+        total += count as u64;
+        offset += header_len + len as usize;
+        if offset > bytes.len() {
+            return Err(CydecError::Truncated {
+                needed: offset,
+                got: bytes.len(),
+            });
+        }
+    }
+    if header.len != STREAMED_LEN && total != header.len {
+        bail!("block counts add up to {total}, header says {}", header.len);
+    }
+    if !has_flag(tag, FILTER_FLAG) {
+        return Ok(offset);
+    }
+    // filter bits, then their u32 byte count and a u8 hash count
+    for bits in 8.. {
+        let end = offset + bits + 5;
+        if end > bytes.len() {
+            break;
+        }
+        let stored = u32::from_le_bytes(bytes[end - 5..end - 1].try_into().unwrap());
+        if stored as usize == bits && (end == bytes.len() || at_blob(&bytes[end..])) {
+            return Ok(end);
+        }
+    }
+    bail!("bloom filter at {offset} has no end")
+}
+
+/// Decode one blob of any type [`DecodedArray`] can hold
+pub(crate) fn decode(blob: &[u8]) -> Result<DecodedArray> {
+    let header = Header::parse(blob)?;
+    let ints = IntegerCodec::default();
+    let floats = FloatingCodec::default();
+    let tag = match header.type_tag {
+        tag if tag & BLOCKED_FLAG != 0 => tag & TYPE_MASK,
+        tag => tag,
+    };
+    Ok(match tag {
+        0 | 36 | 37 => DecodedArray::I64(ints.decompress_i64(blob)?),
+        1 | 38 => DecodedArray::U64(ints.decompress_u64(blob)?),
+        2 => DecodedArray::I32(ints.decompress_i32(blob)?),
+        3 => DecodedArray::U32(ints.decompress_u32(blob)?),
+        4 if header.scale.is_none() => DecodedArray::Bytes(ints.decompress_bytes(blob)?),
+        4 | 6 | 7 | 10 | BLOCKED_F64 => DecodedArray::F64(floats.decompress_f64(blob, None)?),
+        5 | BLOCKED_F32 => DecodedArray::F32(floats.decompress_f32(blob, None)?),
+        11 => DecodedArray::I16(ints.decompress_i16(blob)?),
+        12 => DecodedArray::U16(ints.decompress_u16(blob)?),
+        13 => DecodedArray::I8(ints.decompress_i8(blob)?),
+        14 => DecodedArray::I128(ints.decompress_i128(blob)?),
+        15 => DecodedArray::U128(ints.decompress_u128(blob)?),
+        _ => {
+            return Err(CydecError::TypeMismatch {
+                expected: "an integer, float or byte array",
+                found: header.type_tag,
+            });
+        }
+    })
+}
+
+/// Decode every blob of a buffer holding several back to back, as an
+/// append-only writer leaves them, without the caller tracking where each
+/// one ends.
+///
+/// Handles integer, byte and fixed-scale float arrays, blocked or not,
+/// with or without an extension area. Empty inputs compress to empty
+/// blobs, which leave nothing in the buffer to find. A failing blob is
+/// reported as [`CydecError::Batch`] with its position.
+pub fn decompress_all(bytes: &[u8]) -> Result<Vec<DecodedArray>> {
+    let mut out = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let index = out.len();
+        let batch = |error| CydecError::Batch {
+            index,
+            error: Box::new(error),
+        };
+        let len = blob_len(rest).map_err(batch)?;
+        out.push(decode(&rest[..len]).map_err(batch)?);
+        rest = &rest[len..];
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompressOptions, CydecWriter};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn back_to_back_blobs_decode() -> Result<()> {
+        let ints = IntegerCodec::default();
+        let floats = FloatingCodec::default();
+        let long: Vec<i64> = (0..150_000).map(|i| i * 7 % 1_003).collect();
+        let prices: Vec<f64> = (0..1_000).map(|i| 100.0 + i as f64 * 0.25).collect();
+        let filtered = CompressOptions {
+            filter_bits: 10,
+            ..Default::default()
+        };
+        let tagged = CompressOptions {
+            metadata: BTreeMap::from([("name".into(), "ramp".into())]),
+            ..Default::default()
+        };
+        let frame = CompressOptions {
+            backend: Backend::Lz4Frame,
+            ..Default::default()
+        };
+
+        let blobs = [
+            ints.compress_i64(&[5, 3, 8])?,
+            ints.compress_i64(&long)?,
+            ints.compress_i64_with(&long[..1_000], &filtered)?,
+            ints.compress_u64_with(&[1, 2, 3], &tagged)?,
+            ints.compress_i64_with(&long[..500], &frame)?,
+            ints.compress_bytes(b"raw bytes between numbers")?,
+            floats.compress_f64(&prices, Some(100.0))?,
+            floats.compress_f32(&[0.5, 1.5], None)?,
+            ints.compress_i16(&[-3, 0, 3])?,
+            ints.compress_u128(&[u128::MAX, 0])?,
+        ];
+        let mut w = CydecWriter::new(Vec::new()).with_block_len(100);
+        w.write_values(&long[..250])?;
+        let streamed = w.finish()?;
+
+        let mut buffer = blobs.concat();
+        buffer.extend_from_slice(&streamed);
+        buffer.extend_from_slice(&blobs[0]);
+        let arrays = decompress_all(&buffer)?;
+        assert_eq!(
+            arrays,
+            [
+                DecodedArray::I64(vec![5, 3, 8]),
+                DecodedArray::I64(long.clone()),
+                DecodedArray::I64(long[..1_000].to_vec()),
+                DecodedArray::U64(vec![1, 2, 3]),
+                DecodedArray::I64(long[..500].to_vec()),
+                DecodedArray::Bytes(b"raw bytes between numbers".to_vec()),
+                DecodedArray::F64(prices),
+                DecodedArray::F32(vec![0.5, 1.5]),
+                DecodedArray::I16(vec![-3, 0, 3]),
+                DecodedArray::U128(vec![u128::MAX, 0]),
+                DecodedArray::I64(long[..250].to_vec()),
+                DecodedArray::I64(vec![5, 3, 8]),
+            ]
+        );
+        assert!(decompress_all(&[])?.is_empty());
+
+        #[cfg(feature = "zstd")]
+        {
+            let zstd = CompressOptions {
+                backend: Backend::Zstd,
+                ..Default::default()
+            };
+            let blob = ints.compress_i64_with(&long[..500], &zstd)?;
+            let twice = [blob.as_slice(), &blob].concat();
+            assert_eq!(decompress_all(&twice)?.len(), 2);
+        }
+
+        let cut = &buffer[..blobs[0].len() + 40];
+        assert!(matches!(
+            decompress_all(cut),
+            Err(CydecError::Batch { index: 1, .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::error::{Result, invalid};
+use crate::floating_codec::BLOCKED_F64;
+use crate::integer_codec::reserve_len;
+use crate::{FloatingCodec, IntegerCodec};
+
+/// Largest-Triangle-Three-Buckets over points arriving in order.
+///
+/// Only the bucket being decided and the one after it are kept, since
+/// choosing a point needs the average of the following bucket.
+struct Lttb {
+    n: usize,
+    target: usize,
+    every: f64,
+    out: Vec<(usize, f64)>,
+    /// Last selected point
+    anchor: (usize, f64),
+    bucket: usize,
+    current: Vec<(usize, f64)>,
+    next: Vec<(usize, f64)>,
+}
+
+impl Lttb {
+    fn new(n: usize, target: usize) -> Self {
+        Self {
+            n,
+            target,
+            every: (n - 2) as f64 / (target - 2) as f64,
+            out: Vec::with_capacity(target),
+            anchor: (0, 0.0),
+            bucket: 0,
+            current: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+
+    /// Bucket holding point `x`; the last point is a bucket of its own
+    fn bucket_of(&self, x: usize) -> usize {
+        if x == self.n - 1 {
+            return self.target - 2;
+        }
+        let mut b = self.bucket;
+        while b + 1 < self.target - 2 && x >= (((b + 1) as f64 * self.every) as usize + 1) {
+            b += 1;
+        }
+        b
+    }
+
+    fn push(&mut self, x: usize, y: f64) {
+        if x == 0 {
+            self.anchor = (0, y);
+            self.out.push(self.anchor);
+            return;
+        }
+        match self.bucket_of(x) - self.bucket {
+            0 => self.current.push((x, y)),
+            1 => self.next.push((x, y)),
+            _ => {
+                self.select();
+                self.current = std::mem::take(&mut self.next);
+                self.bucket += 1;
+                self.next.push((x, y));
+            }
+        }
+    }
+
+    /// Pick the point of the current bucket spanning the largest triangle
+    /// with the anchor and the next bucket's average
+    fn select(&mut self) {
+        let k = self.next.len() as f64;
+        let avg_x = self.next.iter().map(|p| p.0 as f64).sum::<f64>() / k;
+        let avg_y = self.next.iter().map(|p| p.1).sum::<f64>() / k;
+        let (ax, ay) = (self.anchor.0 as f64, self.anchor.1);
+        let mut best = (self.current[0], -1.0);
+        for &(x, y) in &self.current {
+            let area = ((ax - avg_x) * (y - ay) - (ax - x as f64) * (avg_y - ay)).abs();
+            if area > best.1 {
+                best = ((x, y), area);
+            }
+        }
+        self.anchor = best.0;
+        self.out.push(best.0);
+    }
+
+    fn finish(mut self) -> Vec<(usize, f64)> {
+        self.select();
+        self.out.extend(self.next.pop());
+        self.out
+    }
+}
+
+impl FloatingCodec {
+    /// Call `f` with every value of an f64 blob and its index, one block at
+    /// a time for fixed-scale blobs; returns the value count
+    fn for_each_f64(&self, blob: &[u8], mut f: impl FnMut(usize, f64)) -> Result<usize> {
+        if !matches!(blob.get(7), Some(&(4 | BLOCKED_F64))) {
+            let values = self.decompress_f64(blob, None)?;
+            values.iter().enumerate().for_each(|(i, &x)| f(i, x));
+            return Ok(values.len());
+        }
+        let (n, backend, scale, blocks) = Self::open_scaled_f64(blob)?;
+        let mut start = 0;
+        for block in &blocks {
+            IntegerCodec::decode_i64_payload_each(block.payload, block.count, backend, |i, x| {
+                f(start + i, x as f64 / scale)
+            })?;
+            start += block.count;
+        }
+        Ok(n)
+    }
+
+    /// Decompress at most `target_points` `(index, value)` pairs chosen by
+    /// Largest-Triangle-Three-Buckets, which keeps the visual shape of the
+    /// series (peaks, dips) for plotting.
+    ///
+    /// Values are folded in as they are decoded and only two buckets are
+    /// held at a time. Series no longer than `target_points` come back
+    /// whole.
+    pub fn decompress_f64_downsampled(
+        &self,
+        blob: &[u8],
+        target_points: usize,
+    ) -> Result<Vec<(usize, f64)>> {
+        if target_points < 3 {
+            return Err(invalid!("LTTB needs at least 3 target points"));
+        }
+        let n = match blob.get(7) {
+            Some(&(4 | BLOCKED_F64)) => Self::open_scaled_f64(blob)?.0,
+            _ => {
+                let values = self.decompress_f64(blob, None)?;
+                if values.len() <= target_points {
+                    return Ok(values.into_iter().enumerate().collect());
+                }
+                let mut lttb = Lttb::new(values.len(), target_points);
+                values
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, &x)| lttb.push(i, x));
+                return Ok(lttb.finish());
+            }
+        };
+        if n <= target_points {
+            let mut out = Vec::with_capacity(reserve_len(n, blob.len()));
+            self.for_each_f64(blob, |i, x| out.push((i, x)))?;
+            return Ok(out);
+        }
+        let mut lttb = Lttb::new(n, target_points);
+        self.for_each_f64(blob, |i, x| lttb.push(i, x))?;
+        Ok(lttb.finish())
+    }
+
+    /// Every `step`-th value, starting with the first.
+    ///
+    /// Blocks of a fixed-scale blob that hold none of the requested
+    /// indices are skipped without decoding.
+    pub fn decompress_f64_every_nth(&self, blob: &[u8], step: usize) -> Result<Vec<f64>> {
+        if step == 0 {
+            return Err(invalid!("step must be positive"));
+        }
+        if !matches!(blob.get(7), Some(&(4 | BLOCKED_F64))) {
+            let values = self.decompress_f64(blob, None)?;
+            return Ok(values.into_iter().step_by(step).collect());
+        }
+        let (n, backend, scale, blocks) = Self::open_scaled_f64(blob)?;
+        let mut out = Vec::with_capacity(n.div_ceil(step));
+        let mut start = 0usize;
+        for block in &blocks {
+            // first wanted index at or after the start of this block
+            let first = start.div_ceil(step) * step;
+            if first < start + block.count {
+                IntegerCodec::decode_i64_payload_each(
+                    block.payload,
+                    block.count,
+                    backend,
+                    |i, x| {
+                        if (start + i).is_multiple_of(step) {
+                            out.push(x as f64 / scale);
+                        }
+                    },
+                )?;
+            }
+            start += block.count;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lttb_keeps_extremes_and_endpoints() -> Result<()> {
+        let c = FloatingCodec::default();
+        let mut v: Vec<f64> = (0..1_000_000).map(|i| (i as f64 / 5_000.0).sin()).collect();
+        v[654_321] = 50.0;
+        let blob = c.compress_f64(&v, None)?;
+        let points = c.decompress_f64_downsampled(&blob, 2_000)?;
+        assert_eq!(points.len(), 2_000);
+        assert_eq!(points[0].0, 0);
+        assert_eq!(points.last().unwrap().0, v.len() - 1);
+        assert!(points.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(points.contains(&(654_321, 50.0)));
+        for &(i, y) in &points {
+            assert!((y - v[i]).abs() < 1e-9);
+        }
+
+        let short = c.compress_f64(&v[..10], None)?;
+        assert_eq!(c.decompress_f64_downsampled(&short, 100)?.len(), 10);
+        assert!(c.decompress_f64_downsampled(&short, 2).is_err());
+        let groomed = c.compress_f64_groomed(&v[..5_000], 30)?;
+        assert_eq!(c.decompress_f64_downsampled(&groomed, 100)?.len(), 100);
+        Ok(())
+    }
+
+    #[test]
+    fn every_nth_skips_blocks() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v: Vec<f64> = (0..300_000).map(|i| i as f64 * 0.5).collect();
+        let blob = c.compress_f64(&v, None)?;
+        for step in [1, 7, 100_000, 400_000] {
+            let expected: Vec<f64> = v.iter().copied().step_by(step).collect();
+            assert_eq!(c.decompress_f64_every_nth(&blob, step)?, expected);
+        }
+        assert!(c.decompress_f64_every_nth(&blob, 0).is_err());
+        assert!(c.decompress_f64_every_nth(&[], 3)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+use crate::error::{Result, invalid};
+use crate::{ColumnEncoding, FloatingCodec, IntegerCodec, StatValue};
+use std::marker::PhantomData;
+
+/// Element types with a compression pipeline, for code generic over the
+/// column type.
+///
+/// Floats use the default scale of their pipeline. Call the concrete
+/// `FloatingCodec` methods to pick a different one.
+pub trait CydecElement: Copy + Sized {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>>;
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>>;
+
+    /// Compress with a frame column encoding; types without alternatives
+    /// only take [`ColumnEncoding::Auto`]
+    fn compress_encoded(data: &[Self], encoding: ColumnEncoding) -> Result<Vec<u8>> {
+        match encoding {
+            ColumnEncoding::Auto => Self::compress_slice(data),
+            other => Err(invalid!(
+                "{other:?} encoding does not apply to {}",
+                std::any::type_name::<Self>()
+            )),
+        }
+    }
+
+    /// Smallest and largest value, skipping nulls; `None` when there are
+    /// none or the type has no ordering worth recording
+    fn value_range(_data: &[Self]) -> Option<(StatValue, StatValue)> {
+        None
+    }
+
+    /// Values that stand for a missing one: NaN for floats
+    fn null_count(_data: &[Self]) -> u64 {
+        0
+    }
+}
+
+macro_rules! integer_range {
+    ($variant:ident, $wide:ty) => {
+        fn value_range(data: &[Self]) -> Option<(StatValue, StatValue)> {
+            let min = *data.iter().min()?;
+            let max = *data.iter().max()?;
+            Some((
+                StatValue::$variant(<$wide>::from(min)),
+                StatValue::$variant(<$wide>::from(max)),
+            ))
+        }
+    };
+}
+
+macro_rules! float_range {
+    () => {
+        fn value_range(data: &[Self]) -> Option<(StatValue, StatValue)> {
+            let mut values = data.iter().filter(|x| !x.is_nan());
+            let first = f64::from(*values.next()?);
+            let (min, max) = values.fold((first, first), |(lo, hi), &x| {
+                (lo.min(f64::from(x)), hi.max(f64::from(x)))
+            });
+            Some((StatValue::Float(min), StatValue::Float(max)))
+        }
+
+        fn null_count(data: &[Self]) -> u64 {
+            data.iter().filter(|x| x.is_nan()).count() as u64
+        }
+    };
+}
+
+macro_rules! integer_element {
+    ($t:ty, $compress:ident, $decompress:ident, $variant:ident, $wide:ty) => {
+        impl CydecElement for $t {
+            fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+                IntegerCodec::default().$compress(data)
+            }
+
+            fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+                IntegerCodec::default().$decompress(blob)
+            }
+
+            integer_range!($variant, $wide);
+        }
+    };
+}
+
+integer_element!(u8, compress_bytes, decompress_bytes, UInt, u128);
+integer_element!(i8, compress_i8, decompress_i8, Int, i128);
+integer_element!(i16, compress_i16, decompress_i16, Int, i128);
+integer_element!(u16, compress_u16, decompress_u16, UInt, u128);
+integer_element!(i32, compress_i32, decompress_i32, Int, i128);
+integer_element!(u32, compress_u32, decompress_u32, UInt, u128);
+
+impl CydecElement for i64 {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+        IntegerCodec::default().compress_i64(data)
+    }
+
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+        IntegerCodec::default().decompress_i64(blob)
+    }
+
+    fn compress_encoded(data: &[Self], encoding: ColumnEncoding) -> Result<Vec<u8>> {
+        match encoding {
+            ColumnEncoding::DeltaOfDelta => Ok(IntegerCodec::compress_i64_dod(data)),
+            ColumnEncoding::Varint => Ok(IntegerCodec::compress_i64_varint(data)),
+            ColumnEncoding::Auto => Self::compress_slice(data),
+            other => Err(invalid!("{other:?} encoding does not apply to i64")),
+        }
+    }
+
+    integer_range!(Int, i128);
+}
+
+impl CydecElement for u64 {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+        IntegerCodec::default().compress_u64(data)
+    }
+
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+        IntegerCodec::default().decompress_u64(blob)
+    }
+
+    fn compress_encoded(data: &[Self], encoding: ColumnEncoding) -> Result<Vec<u8>> {
+        match encoding {
+            ColumnEncoding::Varint => Ok(IntegerCodec::compress_u64_varint(data)),
+            ColumnEncoding::Auto => Self::compress_slice(data),
+            other => Err(invalid!("{other:?} encoding does not apply to u64")),
+        }
+    }
+
+    integer_range!(UInt, u128);
+}
+
+integer_element!(i128, compress_i128, decompress_i128, Int, i128);
+integer_element!(u128, compress_u128, decompress_u128, UInt, u128);
+
+impl CydecElement for f32 {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+        FloatingCodec::default().compress_f32(data, None)
+    }
+
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+        FloatingCodec::default().decompress_f32(blob, None)
+    }
+
+    float_range!();
+}
+
+impl CydecElement for f64 {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+        FloatingCodec::default().compress_f64(data, None)
+    }
+
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+        FloatingCodec::default().decompress_f64(blob, None)
+    }
+
+    fn compress_encoded(data: &[Self], encoding: ColumnEncoding) -> Result<Vec<u8>> {
+        match encoding {
+            // grooming that keeps every mantissa bit is a plain XOR chain
+            ColumnEncoding::Xor => FloatingCodec::default().compress_f64_groomed(data, 52),
+            ColumnEncoding::Auto => Self::compress_slice(data),
+            other => Err(invalid!("{other:?} encoding does not apply to f64")),
+        }
+    }
+
+    float_range!();
+}
+
+#[cfg(feature = "half")]
+impl CydecElement for half::f16 {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+        FloatingCodec::default().compress_f16(data)
+    }
+
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+        FloatingCodec::default().decompress_f16(blob)
+    }
+}
+
+#[cfg(feature = "half")]
+impl CydecElement for half::bf16 {
+    fn compress_slice(data: &[Self]) -> Result<Vec<u8>> {
+        FloatingCodec::default().compress_bf16(data)
+    }
+
+    fn decompress_blob(blob: &[u8]) -> Result<Vec<Self>> {
+        FloatingCodec::default().decompress_bf16(blob)
+    }
+}
+
+/// Compress a slice of any [`CydecElement`]
+pub fn compress<T: CydecElement>(data: &[T]) -> Result<Vec<u8>> {
+    T::compress_slice(data)
+}
+
+/// Decompress a blob written by [`compress`] for the same element type
+pub fn decompress<T: CydecElement>(blob: &[u8]) -> Result<Vec<T>> {
+    T::decompress_blob(blob)
+}
+
+/// Compressed bytes tagged with their element type.
+///
+/// Decompressing as the wrong type is rejected at compile time instead of
+/// failing at runtime:
+///
+/// ```compile_fail
+/// let blob = cydec::compress_typed(&[1i64, 2, 3]).unwrap();
+/// let wrong: Vec<u64> = cydec::decompress_typed(&blob).unwrap();
+/// ```
+///
+/// With the `serde` feature it serializes as a byte string, or as base64
+/// in human-readable formats such as JSON and YAML.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompressedBlob<T> {
+    bytes: Vec<u8>,
+    _element: PhantomData<fn() -> T>,
+}
+
+// manual impl: deriving would require `T: Clone`
+impl<T> Clone for CompressedBlob<T> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            _element: PhantomData,
+        }
+    }
+}
+
+impl<T: CydecElement> CompressedBlob<T> {
+    /// Wrap bytes the caller knows were written for element type `T`
+    pub fn from_bytes_unchecked(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            _element: PhantomData,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn decompress(&self) -> Result<Vec<T>> {
+        T::decompress_blob(&self.bytes)
+    }
+
+    /// The blob as standard, padded base64, for JSON APIs and log lines
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self) -> String {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.encode(&self.bytes)
+    }
+
+    /// Parse standard base64 from [`Self::to_base64`]; the decoded blob
+    /// must pass [`crate::validate`]
+    #[cfg(feature = "base64")]
+    pub fn from_base64(text: &str) -> Result<Self> {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .map_err(|e| invalid!("blob is not valid base64: {e}"))?;
+        Self::validated(bytes)
+    }
+
+    /// The blob as lowercase hex
+    pub fn to_hex(&self) -> String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut hex = String::with_capacity(self.bytes.len() * 2);
+        for &b in &self.bytes {
+            hex.push(DIGITS[usize::from(b >> 4)] as char);
+            hex.push(DIGITS[usize::from(b & 0xf)] as char);
+        }
+        hex
+    }
+
+    /// Parse hex in either case from [`Self::to_hex`]; the decoded blob
+    /// must pass [`crate::validate`]
+    pub fn from_hex(text: &str) -> Result<Self> {
+        if !text.len().is_multiple_of(2) {
+            return Err(invalid!("hex blob has an odd number of digits"));
+        }
+        let digit = |c: u8| match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(invalid!("hex blob has a non-hex character {:?}", c as char)),
+        };
+        let bytes = text
+            .as_bytes()
+            .chunks_exact(2)
+            .map(|pair| Ok(digit(pair[0])? << 4 | digit(pair[1])?))
+            .collect::<Result<Vec<u8>>>()?;
+        Self::validated(bytes)
+    }
+
+    fn validated(bytes: Vec<u8>) -> Result<Self> {
+        crate::validate(&bytes)?;
+        Ok(Self::from_bytes_unchecked(bytes))
+    }
+}
+
+impl<T> AsRef<[u8]> for CompressedBlob<T> {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<T> From<CompressedBlob<T>> for bytes::Bytes {
+    fn from(blob: CompressedBlob<T>) -> Self {
+        blob.bytes.into()
+    }
+}
+
+/// Like [`compress`], but the result remembers its element type
+pub fn compress_typed<T: CydecElement>(data: &[T]) -> Result<CompressedBlob<T>> {
+    Ok(CompressedBlob::from_bytes_unchecked(T::compress_slice(
+        data,
+    )?))
+}
+
+/// Like [`compress`], but the blob comes back as [`bytes::Bytes`], which
+/// takes over the compressed buffer without copying it
+#[cfg(feature = "bytes")]
+pub fn compress_to_bytes<T: CydecElement>(data: &[T]) -> Result<bytes::Bytes> {
+    T::compress_slice(data).map(bytes::Bytes::from)
+}
+
+pub fn decompress_typed<T: CydecElement>(blob: &CompressedBlob<T>) -> Result<Vec<T>> {
+    blob.decompress()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: CydecElement + PartialEq + std::fmt::Debug>(data: &[T]) -> Result<()> {
+        let blob = compress(data)?;
+        assert_eq!(data, decompress::<T>(&blob)?.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn generic_roundtrip_every_type() -> Result<()> {
+        roundtrip(b"generic bytes")?;
+        roundtrip(&[i8::MIN, 0, i8::MAX])?;
+        roundtrip(&[i16::MIN, 0, i16::MAX])?;
+        roundtrip(&[0u16, u16::MAX])?;
+        roundtrip(&[i32::MIN, 0, i32::MAX])?;
+        roundtrip(&[0u32, u32::MAX])?;
+        roundtrip(&[i64::MIN, 0, i64::MAX])?;
+        roundtrip(&[0u64, u64::MAX])?;
+        roundtrip(&[i128::MIN, 0, i128::MAX])?;
+        roundtrip(&[0u128, u128::MAX])?;
+        roundtrip(&[1.5f32, -2.25, 0.0])?;
+        roundtrip(&[1.5f64, -2.25, 0.0])?;
+        Ok(())
+    }
+
+    #[test]
+    fn blobs_roundtrip_through_text() -> Result<()> {
+        let blob = compress_typed(&[1_700_000_000i64, 1_700_000_060, 1_700_000_120])?;
+        let hex = blob.to_hex();
+        assert_eq!(&hex[..10], "4359444543"); // CYDEC
+        assert_eq!(CompressedBlob::<i64>::from_hex(&hex)?, blob);
+        assert_eq!(CompressedBlob::<i64>::from_hex(&hex.to_uppercase())?, blob);
+        assert!(CompressedBlob::<i64>::from_hex(&hex[1..]).is_err());
+        assert!(CompressedBlob::<i64>::from_hex(&format!("{}zz", &hex[2..])).is_err());
+        assert!(CompressedBlob::<i64>::from_hex(&hex[..hex.len() - 4]).is_err());
+        #[cfg(feature = "base64")]
+        {
+            let text = blob.to_base64();
+            assert!(text.starts_with("Q1lERUM"));
+            assert_eq!(CompressedBlob::<i64>::from_base64(&text)?, blob);
+            assert!(CompressedBlob::<i64>::from_base64("not base64!").is_err());
+            assert!(CompressedBlob::<i64>::from_base64("Q1lERUM=").is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_element_type_is_an_error() -> Result<()> {
+        let blob = compress(&[1i64, 2, 3])?;
+        assert!(decompress::<u64>(&blob).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn typed_blob_roundtrip() -> Result<()> {
+        let data: Vec<u64> = (0..1_000).map(|i| i * 7).collect();
+        let blob = compress_typed(&data)?;
+        assert_eq!(blob.as_bytes(), compress(&data)?.as_slice());
+        assert_eq!(data, decompress_typed(&blob)?);
+
+        let reloaded = CompressedBlob::<u64>::from_bytes_unchecked(blob.clone().into_bytes());
+        assert_eq!(data, reloaded.decompress()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use thiserror::Error;
+
+/// Everything that can go wrong while compressing or decompressing.
+///
+/// Header problems get their own variants so callers can tell a blob from
+/// another library apart from one written by a newer cydec or for another
+/// element type. Damage further inside the payload is reported as
+/// [`CydecError::Corrupt`] with a description of what failed to decode.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CydecError {
+    #[error("bad magic")]
+    BadMagic,
+    /// Written by a newer format version; `max` is the newest this build
+    /// reads
+    #[error("unsupported version {found}, this build reads up to {max}")]
+    UnsupportedVersion { found: u8, max: u8 },
+    #[error("unsupported codec {found}")]
+    UnsupportedBackend { found: u8 },
+    #[error("unsupported type {found}, expected {expected}")]
+    TypeMismatch { expected: &'static str, found: u8 },
+    #[error("blob too small: needed {needed} bytes, got {got}")]
+    Truncated { needed: usize, got: usize },
+    /// The payload does not hash to the checksum stored with it
+    #[error("checksum mismatch: stored {stored:#018x}, computed {computed:#018x}")]
+    ChecksumMismatch { stored: u64, computed: u64 },
+    /// The blob's HMAC does not match the key it was checked against
+    #[error("signature does not match")]
+    BadSignature,
+    /// A blob compressed with [`crate::CompressOptions::verify`] did not
+    /// read back as its input
+    #[error("verify after compress: {0}")]
+    VerifyFailed(String),
+    /// The header checked out but the payload did not decode
+    #[error("{0}")]
+    Corrupt(String),
+    /// The caller passed parameters or data the pipeline cannot encode
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// The reader or writer behind a stream failed
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    /// One blob of a batch failed; `index` is its position in the input
+    #[error("blob {index}: {error}")]
+    Batch {
+        index: usize,
+        error: Box<CydecError>,
+    },
+}
+
+impl CydecError {
+    /// Error for a blob whose version byte this build cannot read
+    pub(crate) fn unsupported_version(found: u8) -> Self {
+        Self::UnsupportedVersion {
+            found,
+            max: crate::format::MAX_VERSION,
+        }
+    }
+}
+
+pub type Result<T, E = CydecError> = std::result::Result<T, E>;
+
+/// Return early with a [`CydecError::Corrupt`] built from a format string
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err($crate::CydecError::Corrupt(format!($($arg)*)))
+    };
+}
+
+/// Build a [`CydecError::Corrupt`] from a format string
+macro_rules! corrupt {
+    ($($arg:tt)*) => {
+        $crate::CydecError::Corrupt(format!($($arg)*))
+    };
+}
+
+/// Build a [`CydecError::InvalidArgument`] from a format string
+macro_rules! invalid {
+    ($($arg:tt)*) => {
+        $crate::CydecError::InvalidArgument(format!($($arg)*))
+    };
+}
+
+pub(crate) use {bail, corrupt, invalid};
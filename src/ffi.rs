@@ -0,0 +1,529 @@
+//! C interface to the element pipelines, declared in `include/cydec.h`.
+//!
+//! Every function returns a status code, [`CYDEC_OK`] on success, and
+//! writes its result to a [`CydecBuffer`] owned by the caller from then on.
+//! Hand each filled buffer back to [`cydec_buffer_free`] exactly once; the
+//! memory came from Rust's allocator and must not reach `free`. After a
+//! failure [`cydec_last_error`] describes what went wrong.
+
+use crate::CydecElement;
+use crate::error::{CydecError, Result, invalid};
+use std::cell::RefCell;
+use std::ffi::{CString, c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The call succeeded
+pub const CYDEC_OK: i32 = 0;
+/// A pointer was null or the parameters cannot be encoded
+pub const CYDEC_ERR_INVALID_ARGUMENT: i32 = 1;
+/// The blob is truncated, damaged or not a cydec blob
+pub const CYDEC_ERR_CORRUPT: i32 = 2;
+/// The blob was written by a newer format version
+pub const CYDEC_ERR_UNSUPPORTED_VERSION: i32 = 3;
+/// The blob holds a different element type than the function decodes
+pub const CYDEC_ERR_TYPE_MISMATCH: i32 = 4;
+/// The payload does not match its checksum or signature
+pub const CYDEC_ERR_CHECKSUM: i32 = 5;
+/// Rust code panicked; the panic stopped at the boundary
+pub const CYDEC_ERR_PANIC: i32 = 6;
+
+/// Values or blob bytes returned to C.
+///
+/// `data` holds `len` elements of `elem_size` bytes each. Release it with
+/// [`cydec_buffer_free`]; an empty buffer has a null `data`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CydecBuffer {
+    pub data: *mut c_void,
+    pub len: usize,
+    pub elem_size: usize,
+}
+
+impl CydecBuffer {
+    const EMPTY: Self = Self {
+        data: ptr::null_mut(),
+        len: 0,
+        elem_size: 0,
+    };
+
+    fn new<T>(values: Vec<T>) -> Self {
+        if values.is_empty() {
+            return Self::EMPTY;
+        }
+        let len = values.len();
+        Self {
+            data: Box::into_raw(values.into_boxed_slice()).cast(),
+            len,
+            elem_size: size_of::<T>(),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::default();
+}
+
+/// Release a buffer filled by a cydec function. Empty buffers are ignored.
+///
+/// # Safety
+///
+/// `buffer` must come unchanged from a cydec function and not have been
+/// freed before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_buffer_free(buffer: CydecBuffer) {
+    if buffer.data.is_null() {
+        return;
+    }
+    // SAFETY: a boxed slice of `len` elements of this size, as `new` made
+    // it; the unsigned type of each size has the same layout
+    unsafe {
+        match buffer.elem_size {
+            1 => drop(Box::from_raw(slice_ptr::<u8>(&buffer))),
+            2 => drop(Box::from_raw(slice_ptr::<u16>(&buffer))),
+            4 => drop(Box::from_raw(slice_ptr::<u32>(&buffer))),
+            8 => drop(Box::from_raw(slice_ptr::<u64>(&buffer))),
+            _ => {}
+        }
+    }
+}
+
+/// Message of the last failed call on this thread, empty when none has
+/// failed. The string stays valid until the next failure on the thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn cydec_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+/// Compress `len` timestamps or other i64 values at `data` into `out`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable values, or be null with `len` 0,
+/// and `out` to a writable buffer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_i64(
+    data: *const i64,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode an i64 blob of `len` bytes at `blob` into `out`.
+///
+/// # Safety
+///
+/// `blob` must point to `len` readable bytes, or be null with `len` 0, and
+/// `out` to a writable buffer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_i64(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<i64>(blob, len, out) }
+}
+
+/// Compress `len` u64 values at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_u64(
+    data: *const u64,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode a u64 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_u64(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<u64>(blob, len, out) }
+}
+
+/// Compress `len` i32 values at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_i32(
+    data: *const i32,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode an i32 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_i32(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<i32>(blob, len, out) }
+}
+
+/// Compress `len` u32 values at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_u32(
+    data: *const u32,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode a u32 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_u32(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<u32>(blob, len, out) }
+}
+
+/// Compress `len` i16 values at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_i16(
+    data: *const i16,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode an i16 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_i16(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<i16>(blob, len, out) }
+}
+
+/// Compress `len` u16 values at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_u16(
+    data: *const u16,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode a u16 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_u16(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<u16>(blob, len, out) }
+}
+
+/// Compress `len` i8 values at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_i8(
+    data: *const i8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode an i8 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_i8(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<i8>(blob, len, out) }
+}
+
+/// Compress `len` raw bytes at `data` into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode a byte blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_bytes(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<u8>(blob, len, out) }
+}
+
+/// Compress `len` f64 values at `data` into `out` at the default scale.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_f64(
+    data: *const f64,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode an f64 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_f64(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<f64>(blob, len, out) }
+}
+
+/// Compress `len` f32 values at `data` into `out` at the default scale.
+///
+/// # Safety
+///
+/// As for [`cydec_compress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_compress_f32(
+    data: *const f32,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { compress_into(data, len, out) }
+}
+
+/// Decode an f32 blob into `out`.
+///
+/// # Safety
+///
+/// As for [`cydec_decompress_i64`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cydec_decompress_f32(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    unsafe { decompress_into::<f32>(blob, len, out) }
+}
+
+unsafe fn compress_into<T: CydecElement>(data: *const T, len: usize, out: *mut CydecBuffer) -> i32 {
+    // SAFETY: forwarded from the caller
+    run(out, || T::compress_slice(unsafe { input(data, len)? }))
+}
+
+unsafe fn decompress_into<T: CydecElement>(
+    blob: *const u8,
+    len: usize,
+    out: *mut CydecBuffer,
+) -> i32 {
+    // SAFETY: forwarded from the caller
+    run(out, || T::decompress_blob(unsafe { input(blob, len)? }))
+}
+
+/// The caller's `len` values at `data`
+unsafe fn input<'a, T>(data: *const T, len: usize) -> Result<&'a [T]> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(invalid!("null input of {len} values")),
+        // SAFETY: the caller vouches for `len` values at `data`
+        (false, _) => Ok(unsafe { std::slice::from_raw_parts(data, len) }),
+    }
+}
+
+/// Run `f`, store its values in `out` and turn failures into status codes
+fn run<T>(out: *mut CydecBuffer, f: impl FnOnce() -> Result<Vec<T>>) -> i32 {
+    if out.is_null() {
+        return fail(&invalid!("null output buffer"));
+    }
+    let (buffer, status) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(values)) => (CydecBuffer::new(values), CYDEC_OK),
+        Ok(Err(e)) => (CydecBuffer::EMPTY, fail(&e)),
+        Err(_) => {
+            remember("panic inside cydec");
+            (CydecBuffer::EMPTY, CYDEC_ERR_PANIC)
+        }
+    };
+    // SAFETY: the caller vouches that `out` is writable
+    unsafe { out.write(buffer) };
+    status
+}
+
+fn fail(error: &CydecError) -> i32 {
+    remember(&error.to_string());
+    status(error)
+}
+
+fn status(error: &CydecError) -> i32 {
+    match error {
+        CydecError::InvalidArgument(_) => CYDEC_ERR_INVALID_ARGUMENT,
+        CydecError::UnsupportedVersion { .. } => CYDEC_ERR_UNSUPPORTED_VERSION,
+        CydecError::TypeMismatch { .. } => CYDEC_ERR_TYPE_MISMATCH,
+        CydecError::ChecksumMismatch { .. } | CydecError::BadSignature => CYDEC_ERR_CHECKSUM,
+        CydecError::Batch { error, .. } => status(error),
+        _ => CYDEC_ERR_CORRUPT,
+    }
+}
+
+fn remember(message: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+fn slice_ptr<T>(buffer: &CydecBuffer) -> *mut [T] {
+    ptr::slice_from_raw_parts_mut(buffer.data.cast::<T>(), buffer.len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn take<T: Copy>(buffer: CydecBuffer) -> Vec<T> {
+        assert!(buffer.data.is_null() || buffer.elem_size == size_of::<T>());
+        let values = unsafe { input(buffer.data.cast::<T>(), buffer.len) }
+            .unwrap()
+            .to_vec();
+        unsafe { cydec_buffer_free(buffer) };
+        values
+    }
+
+    #[test]
+    fn c_callers_roundtrip_and_see_errors() -> Result<()> {
+        let ts: Vec<i64> = (0..10_000).map(|i| 1_700_000_000_000 + i * 250).collect();
+        let mut out = CydecBuffer::EMPTY;
+        assert_eq!(
+            unsafe { cydec_compress_i64(ts.as_ptr(), ts.len(), &mut out) },
+            CYDEC_OK
+        );
+        let blob = take::<u8>(out);
+        assert_eq!(blob, crate::compress(&ts)?);
+
+        let mut out = CydecBuffer::EMPTY;
+        assert_eq!(
+            unsafe { cydec_decompress_i64(blob.as_ptr(), blob.len(), &mut out) },
+            CYDEC_OK
+        );
+        assert_eq!(take::<i64>(out), ts);
+
+        let prices = [100.25f32, 100.5, 99.75];
+        let mut out = CydecBuffer::EMPTY;
+        unsafe { cydec_compress_f32(prices.as_ptr(), prices.len(), &mut out) };
+        let blob = take::<u8>(out);
+        let mut out = CydecBuffer::EMPTY;
+        unsafe { cydec_decompress_f32(blob.as_ptr(), blob.len(), &mut out) };
+        assert_eq!(take::<f32>(out), prices);
+
+        let mut out = CydecBuffer::EMPTY;
+        assert_eq!(
+            unsafe { cydec_decompress_u16(blob.as_ptr(), blob.len(), &mut out) },
+            CYDEC_ERR_TYPE_MISMATCH
+        );
+        assert!(out.data.is_null());
+        let message = unsafe { CStr::from_ptr(cydec_last_error()) };
+        assert!(message.to_str().unwrap().contains("unsupported type"));
+        assert_eq!(
+            unsafe { cydec_decompress_i64(blob.as_ptr(), 4, &mut out) },
+            CYDEC_ERR_CORRUPT
+        );
+        assert_eq!(
+            unsafe { cydec_compress_i64(ptr::null(), 3, &mut out) },
+            CYDEC_ERR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            unsafe { cydec_compress_i64(ts.as_ptr(), 1, ptr::null_mut()) },
+            CYDEC_ERR_INVALID_ARGUMENT
+        );
+        Ok(())
+    }
+}
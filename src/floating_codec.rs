@@ -1,21 +1,88 @@
-use anyhow::{Result, anyhow, bail};
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::parallel::*;
 use integer_encoding::{VarIntReader, VarIntWriter};
-use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Cursor;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use crate::blocks::{Block, check_range, decode_blocks_range, push_block, read_blocks_at};
+use crate::format::TypeTag;
+use crate::header;
+use crate::integer_codec::{at_index, reserve_len};
+use crate::options::verify_scaled;
+use crate::{Backend, CompressOptions, CydecError, IntegerCodec, Strided};
+
+#[cfg(feature = "half")]
+use half::{bf16, f16};
+
+/// Type tags of fixed-scale float blobs stored as blocks, like the blocked
+/// i64 layout but with the scale still at 16..
+pub(crate) const BLOCKED_F64: u8 = TypeTag::BlockedF64 as u8;
+pub(crate) const BLOCKED_F32: u8 = TypeTag::BlockedF32 as u8;
+
+/// Smallest exponent in [`POW10`]; 10^-324 rounds to zero
+const MIN_POW10: i32 = -323;
+
+/// Correctly rounded powers of ten from 10^-323 to 10^308. `powi` and
+/// `log10` may round differently from one platform's libm to the next,
+/// which would change the scales, and so the bytes, of float blobs.
+static POW10: LazyLock<Vec<f64>> = LazyLock::new(|| {
+    (MIN_POW10..=308)
+        .map(|e| format!("1e{e}").parse().unwrap())
+        .collect()
+});
+
+/// 10^`exp` for `exp` >= 0, infinite beyond the f64 range, the same on
+/// every platform
+fn pow10(exp: i32) -> f64 {
+    POW10
+        .get((exp - MIN_POW10) as usize)
+        .copied()
+        .unwrap_or(f64::INFINITY)
+}
+
+/// Largest `e` with 10^`e` <= `x` for finite positive `x`, the same on
+/// every platform
+fn floor_log10(x: f64) -> i32 {
+    POW10.partition_point(|&p| p <= x) as i32 + MIN_POW10 - 1
+}
 
-#[derive(Clone, Copy, Debug)]
-pub enum Codec {
-    Lz4,
-} // add Zstd later if you want
+/// Error introduced by quantizing floats to scaled integers
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuantizationReport {
+    /// Scale factor the values were multiplied by before rounding
+    pub scale: f64,
+    /// Number of values quantized
+    pub count: usize,
+    /// Largest absolute difference between an input and its decoded value
+    pub max_abs_error: f64,
+    /// Mean absolute difference between inputs and their decoded values
+    pub mean_abs_error: f64,
+    /// Values that were NaN, infinite, or outside the i64 range once scaled
+    pub clamped: usize,
+}
 
 #[derive(Clone, Debug)]
 pub struct FloatingCodec {
-    pub codec: Codec,
+    /// Backend for i64, u64 and f64 blobs; other types always use LZ4
+    pub codec: Backend,
 }
 
 impl Default for FloatingCodec {
     fn default() -> Self {
-        Self { codec: Codec::Lz4 }
+        Self::new()
+    }
+}
+
+impl FloatingCodec {
+    /// LZ4-backed codec; `const`, so it can live in a `static`
+    pub const fn new() -> Self {
+        Self::with_backend(Backend::Lz4)
+    }
+
+    pub const fn with_backend(codec: Backend) -> Self {
+        Self { codec }
     }
 }
 
@@ -24,6 +91,9 @@ impl FloatingCodec {
     pub const DEFAULT_F64_SCALE: f64 = 1_000_000_000.0; // 9 decimal places
     pub const DEFAULT_F32_SCALE: f32 = 1_000_000.0; // 6 decimal places
 
+    /// Number of values sharing one decimal exponent in block-scaled f64 blobs
+    pub const BLOCK_LEN: usize = 1024;
+
     #[inline]
     fn zigzag_i64(i: i64) -> u64 {
         ((i << 1) ^ (i >> 63)) as u64
@@ -44,196 +114,1132 @@ impl FloatingCodec {
         ((u >> 1) as i32) ^ (-((u & 1) as i32))
     }
 
+    #[cfg(feature = "half")]
+    #[inline]
+    fn zigzag_i16(i: i16) -> u16 {
+        ((i << 1) ^ (i >> 15)) as u16
+    }
+
+    #[cfg(feature = "half")]
+    #[inline]
+    fn unzigzag_i16(u: u16) -> i16 {
+        ((u >> 1) as i16) ^ (-((u & 1) as i16))
+    }
+
     /// Compress f64 vector by converting to scaled i64
     pub fn compress_f64(&self, data: &[f64], scale: Option<f64>) -> Result<Vec<u8>> {
+        let options = CompressOptions {
+            backend: self.codec,
+            scale,
+            ..Default::default()
+        };
+        self.compress_f64_with(data, &options)
+    }
+
+    /// Compress f64 vector with per-call backend, level and scale
+    pub fn compress_f64_with(&self, data: &[f64], options: &CompressOptions) -> Result<Vec<u8>> {
+        let blob = Self::encode_f64_with(data.into(), options)?;
+        if options.verify {
+            let scale_factor = options.scale.unwrap_or(Self::DEFAULT_F64_SCALE);
+            verify_scaled(data, self.decompress_f64(&blob, None), scale_factor)?;
+        }
+        Ok(blob)
+    }
+
+    /// Compress f64 values read through a [`Strided`] view, such as one
+    /// column of a row-major matrix or an `ndarray::ArrayView1`, into the
+    /// bytes [`Self::compress_f64`] writes for the same values
+    pub fn compress_f64_strided<'a>(
+        &self,
+        data: impl Into<Strided<'a, f64>>,
+        scale: Option<f64>,
+    ) -> Result<Vec<u8>> {
+        let options = CompressOptions {
+            backend: self.codec,
+            scale,
+            ..Default::default()
+        };
+        Self::encode_f64_with(data.into(), &options)
+    }
+
+    fn encode_f64_with(data: Strided<'_, f64>, options: &CompressOptions) -> Result<Vec<u8>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
 
-        let scale_factor = scale.unwrap_or(Self::DEFAULT_F64_SCALE);
+        let scale_factor = options.scale.unwrap_or(Self::DEFAULT_F64_SCALE);
         let scaled_data: Vec<i64> = data
             .iter()
-            .map(|&f| (f * scale_factor).round() as i64)
+            .map(|f| (f * scale_factor).round() as i64)
             .collect();
 
+        let blob =
+            Self::encode_scaled_f64(&scaled_data, scale_factor, options.backend, options.level);
+        header::with_options(blob, options)
+    }
+
+    /// Compress f64 vector and report the error introduced by quantization
+    pub fn compress_f64_with_report(
+        &self,
+        data: &[f64],
+        scale: Option<f64>,
+    ) -> Result<(Vec<u8>, QuantizationReport)> {
+        let scale_factor = scale.unwrap_or(Self::DEFAULT_F64_SCALE);
+        let mut report = QuantizationReport {
+            scale: scale_factor,
+            count: data.len(),
+            ..Default::default()
+        };
+        if data.is_empty() {
+            return Ok((Vec::new(), report));
+        }
+
+        // i64::MAX is not representable as f64, so compare against 2^63
+        const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+        let mut error_sum = 0.0;
+        let mut measured = 0usize;
+        let mut scaled_data = Vec::with_capacity(data.len());
+        for &f in data {
+            let scaled = (f * scale_factor).round();
+            let q = scaled as i64;
+            if !(-LIMIT..LIMIT).contains(&scaled) {
+                report.clamped += 1;
+            } else {
+                let err = (f - q as f64 / scale_factor).abs();
+                report.max_abs_error = report.max_abs_error.max(err);
+                error_sum += err;
+                measured += 1;
+            }
+            scaled_data.push(q);
+        }
+        if measured > 0 {
+            report.mean_abs_error = error_sum / measured as f64;
+        }
+
+        Ok((
+            Self::encode_scaled_f64(&scaled_data, scale_factor, self.codec, 0),
+            report,
+        ))
+    }
+
+    fn encode_scaled_f64(
+        scaled_data: &[i64],
+        scale_factor: f64,
+        backend: Backend,
+        level: i32,
+    ) -> Vec<u8> {
         // Compress as i64 but with f64 type identifier
         let mut buf = Vec::with_capacity(scaled_data.len() * 2);
+        let blocked = scaled_data.len() > IntegerCodec::BLOCK_LEN;
         // header: magic + version + len + type
         buf.extend_from_slice(b"CYDEC"); // 0..5
         buf.push(1); // 5: version
-        buf.push(1); // 6: codec LZ4
-        buf.push(4); // 7: type (4 = f64)
-        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+        buf.push(backend.id()); // 6: codec
+        buf.push(if blocked { BLOCKED_F64 } else { 4 }); // 7: type (4 = f64, 33 = blocked f64)
+        buf.extend_from_slice(&(scaled_data.len() as u64).to_le_bytes()); // 8..16
 
         // Add scale factor to header (8 bytes for f64)
         buf.extend_from_slice(&scale_factor.to_le_bytes()); // 16..24
 
-        // stream varints into a temp vec
-        let mut tmp = Vec::with_capacity(scaled_data.len() * 2);
-        let mut prev = 0i64;
-        for &x in &scaled_data {
-            let d = x.wrapping_sub(prev);
-            prev = x;
-            tmp.write_varint(Self::zigzag_i64(d)).unwrap();
+        if !blocked {
+            buf.extend_from_slice(&IntegerCodec::encode_i64_payload_with(
+                scaled_data,
+                backend,
+                level,
+            ));
+            return buf;
+        }
+        for chunk in scaled_data.chunks(IntegerCodec::BLOCK_LEN) {
+            let payload = IntegerCodec::encode_i64_payload_with(chunk, backend, level);
+            push_block(&mut buf, chunk.len(), None, &payload).expect("block length fits in a u32");
         }
+        buf
+    }
 
-        // compress varint bytes
-        let comp = lz4_flex::block::compress_prepend_size(&tmp);
-        buf.extend_from_slice(&comp);
+    /// Value count, backend, stored scale and i64 payload blocks of a
+    /// fixed-scale f64 blob; a plain one is a single block
+    pub(crate) fn open_scaled_f64(blob: &[u8]) -> Result<(usize, Backend, f64, Vec<Block<'_>>)> {
+        let blob = header::strip(blob)?;
+        if blob.len() < 24 {
+            // Minimum header size: 16 (base) + 8 (scale)
+            return Err(CydecError::Truncated {
+                needed: 24,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        let backend = Backend::from_id(blob[6])?;
+        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+        let scale = f64::from_le_bytes(blob[16..24].try_into().unwrap());
+        let (n, blocks) = match blob[7] {
+            4 => {
+                let block = Block {
+                    offset: 24,
+                    count: n as usize,
+                    stats: None,
+                    payload: &blob[24..],
+                };
+                (n as usize, vec![block])
+            }
+            BLOCKED_F64 => read_blocks_at(blob, 24, n)?,
+            found => {
+                return Err(CydecError::TypeMismatch {
+                    expected: "f64",
+                    found,
+                });
+            }
+        };
+        Ok((n, backend, scale, blocks))
+    }
+
+    /// Compress complex samples (re, im) as two separate planes.
+    ///
+    /// Real and imaginary parts are quantized with the same scale and delta
+    /// encoded independently; interleaving them would make every delta jump
+    /// between the two signals and defeat the predictor on FFT/IQ data.
+    pub fn compress_c64(&self, data: &[(f64, f64)], scale: Option<f64>) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let scale_factor = scale.unwrap_or(Self::DEFAULT_F64_SCALE);
+        let quantize = |f: f64| (f * scale_factor).round() as i64;
+        let re: Vec<i64> = data.iter().map(|&(re, _)| quantize(re)).collect();
+        let im: Vec<i64> = data.iter().map(|&(_, im)| quantize(im)).collect();
+
+        let mut buf = Vec::with_capacity(data.len() + 28);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(22); // 7: type (22 = complex f64)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&scale_factor.to_le_bytes()); // 16..24
+
+        Self::write_planes(&mut buf, &re, &im); // 24..: real plane size + planes
         Ok(buf)
     }
 
-    /// Decompress f64 vector from scaled i64 data
-    pub fn decompress_f64(&self, blob: &[u8], scale: Option<f64>) -> Result<Vec<f64>> {
+    /// Metres per degree of latitude (and of longitude at the equator)
+    const METRES_PER_DEGREE: f64 = 111_319.490_793_273_57;
+
+    /// Worst-case positional error, in metres, of a track compressed with
+    /// `decimals` decimal places of a degree.
+    ///
+    /// Each coordinate is off by at most half a unit in the last place, and
+    /// neither a degree of latitude nor of longitude spans more than
+    /// [`Self::METRES_PER_DEGREE`].
+    pub fn track_error_bound_m(decimals: u32) -> f64 {
+        0.5 / pow10(decimals as i32) * Self::METRES_PER_DEGREE * std::f64::consts::SQRT_2
+    }
+
+    /// Compress a GPS track of `(lat, lon)` points in degrees.
+    ///
+    /// Both coordinates are quantized to `decimals` decimal places and delta
+    /// encoded as separate planes. The decoded points lie within
+    /// [`Self::track_error_bound_m`] of the originals.
+    pub fn compress_track(&self, points: &[(f64, f64)], decimals: u32) -> Result<Vec<u8>> {
+        if decimals > 15 {
+            return Err(invalid!(
+                "track precision must be at most 15 decimals, got {decimals}"
+            ));
+        }
+        if points.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some((lat, lon)) = points
+            .iter()
+            .find(|(lat, lon)| !(-90.0..=90.0).contains(lat) || !(-180.0..=180.0).contains(lon))
+        {
+            return Err(invalid!("invalid coordinate ({lat}, {lon})"));
+        }
+
+        let scale_factor = pow10(decimals as i32);
+        let quantize = |f: f64| (f * scale_factor).round() as i64;
+        let lat: Vec<i64> = points.iter().map(|&(lat, _)| quantize(lat)).collect();
+        let lon: Vec<i64> = points.iter().map(|&(_, lon)| quantize(lon)).collect();
+
+        let mut buf = Vec::with_capacity(points.len() + 21);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(23); // 7: type (23 = lat/lon track)
+        buf.extend_from_slice(&(points.len() as u64).to_le_bytes()); // 8..16
+        buf.push(decimals as u8); // 16: decimal places
+
+        Self::write_planes(&mut buf, &lat, &lon); // 17..: latitude plane size + planes
+        Ok(buf)
+    }
+
+    /// Decompress a GPS track written by [`Self::compress_track`]
+    pub fn decompress_track(&self, blob: &[u8]) -> Result<Vec<(f64, f64)>> {
+        let blob = header::strip(blob)?;
         if blob.is_empty() {
             return Ok(Vec::new());
         }
+        if blob.len() < 17 {
+            return Err(CydecError::Truncated {
+                needed: 17,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 23 {
+            return Err(CydecError::TypeMismatch {
+                expected: "lat/lon track",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let decimals = blob[16];
+        if decimals > 15 {
+            bail!("invalid track precision {decimals}");
+        }
+        let scale_factor = pow10(decimals as i32);
+
+        let (lat, lon) = Self::read_planes(&blob[17..], n)?;
+        Ok(lat
+            .into_iter()
+            .zip(lon)
+            .map(|(lat, lon)| (lat as f64 / scale_factor, lon as f64 / scale_factor))
+            .collect())
+    }
 
-        if blob.len() < 24 {
-            // Minimum header size: 15 (base) + 8 (scale)
-            bail!("blob too small");
+    /// Two i64 planes: u32 size of the first payload, then both payloads
+    fn write_planes(buf: &mut Vec<u8>, first: &[i64], second: &[i64]) {
+        let first = IntegerCodec::encode_i64_payload(first);
+        let second = IntegerCodec::encode_i64_payload(second);
+        buf.extend_from_slice(&(first.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&first);
+        buf.extend_from_slice(&second);
+    }
+
+    fn read_planes(bytes: &[u8], n: usize) -> Result<(Vec<i64>, Vec<i64>)> {
+        if bytes.len() < 4 {
+            return Err(CydecError::Truncated {
+                needed: 4,
+                got: bytes.len(),
+            });
+        }
+        let first_len = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        if bytes.len() - 4 < first_len {
+            return Err(CydecError::Truncated {
+                needed: first_len,
+                got: bytes.len() - 4,
+            });
         }
+        let (first, second) = bytes[4..].split_at(first_len);
+        Ok((
+            IntegerCodec::decode_i64_payload(first, n)?,
+            IntegerCodec::decode_i64_payload(second, n)?,
+        ))
+    }
 
+    /// Decompress complex samples written by [`Self::compress_c64`]
+    pub fn decompress_c64(&self, blob: &[u8], scale: Option<f64>) -> Result<Vec<(f64, f64)>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 24 {
+            return Err(CydecError::Truncated {
+                needed: 24,
+                got: blob.len(),
+            });
+        }
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
-
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 22 {
+            return Err(CydecError::TypeMismatch {
+                expected: "complex f64",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let scale_factor =
+            scale.unwrap_or_else(|| f64::from_le_bytes(blob[16..24].try_into().unwrap()));
+
+        let (re, im) = Self::read_planes(&blob[24..], n)?;
+        Ok(re
+            .into_iter()
+            .zip(im)
+            .map(|(re, im)| (re as f64 / scale_factor, im as f64 / scale_factor))
+            .collect())
+    }
+
+    /// Compress a `u64 -> f64` map, e.g. sparse timestamp-keyed measurements.
+    ///
+    /// Keys are stored in ascending order as a delta plane next to the
+    /// quantized values, so dense or regular keys cost about a byte each.
+    pub fn compress_map(&self, map: &BTreeMap<u64, f64>, scale: Option<f64>) -> Result<Vec<u8>> {
+        self.encode_map(map.iter().map(|(&k, &v)| (k, v)), map.len(), scale)
+    }
+
+    /// Same blob as [`Self::compress_map`]; keys are sorted before encoding
+    pub fn compress_hashmap(&self, map: &HashMap<u64, f64>, scale: Option<f64>) -> Result<Vec<u8>> {
+        let mut entries: Vec<(u64, f64)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        entries.sort_unstable_by_key(|&(k, _)| k);
+        self.encode_map(entries.into_iter(), map.len(), scale)
+    }
+
+    fn encode_map(
+        &self,
+        entries: impl Iterator<Item = (u64, f64)>,
+        len: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
         }
 
+        let scale_factor = scale.unwrap_or(Self::DEFAULT_F64_SCALE);
+        // keys are reinterpreted as i64; wrapping deltas round-trip exactly
+        let (keys, values): (Vec<i64>, Vec<i64>) = entries
+            .map(|(k, v)| (k as i64, (v * scale_factor).round() as i64))
+            .unzip();
+
+        let mut buf = Vec::with_capacity(len * 2 + 28);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(29); // 7: type (29 = u64 -> f64 map)
+        buf.extend_from_slice(&(len as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&scale_factor.to_le_bytes()); // 16..24
+
+        Self::write_planes(&mut buf, &keys, &values); // 24..: key plane size + planes
+        Ok(buf)
+    }
+
+    /// Decompress a map written by [`Self::compress_map`] or [`Self::compress_hashmap`]
+    pub fn decompress_map(&self, blob: &[u8], scale: Option<f64>) -> Result<BTreeMap<u64, f64>> {
+        Ok(self.decode_map(blob, scale)?.into_iter().collect())
+    }
+
+    pub fn decompress_hashmap(&self, blob: &[u8], scale: Option<f64>) -> Result<HashMap<u64, f64>> {
+        Ok(self.decode_map(blob, scale)?.into_iter().collect())
+    }
+
+    fn decode_map(&self, blob: &[u8], scale: Option<f64>) -> Result<Vec<(u64, f64)>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 24 {
+            return Err(CydecError::Truncated {
+                needed: 24,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 29 {
+            return Err(CydecError::TypeMismatch {
+                expected: "map",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let scale_factor =
+            scale.unwrap_or_else(|| f64::from_le_bytes(blob[16..24].try_into().unwrap()));
+
+        let (keys, values) = Self::read_planes(&blob[24..], n)?;
+        Ok(keys
+            .into_iter()
+            .zip(values)
+            .map(|(k, v)| (k as u64, v as f64 / scale_factor))
+            .collect())
+    }
+
+    /// Compress f64 vector keeping `digits` significant digits per block.
+    ///
+    /// Every block of [`Self::BLOCK_LEN`] values gets its own decimal exponent,
+    /// chosen from the largest magnitude in the block, so an array mixing
+    /// `0.000123456` and `123456.0` keeps the requested precision for both.
+    pub fn compress_f64_significant(&self, data: &[f64], digits: u32) -> Result<Vec<u8>> {
+        if !(1..=17).contains(&digits) {
+            return Err(invalid!(
+                "significant digits must be between 1 and 17, got {digits}"
+            ));
+        }
+        if data.is_empty() {
+            return Ok(Vec::new());
         }
 
-        if blob[7] != 4 {
-            bail!("unsupported type, expected f64");
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(6); // 7: type (6 = f64 with significant digits)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+        buf.push(digits as u8); // 16: significant digits
+        buf.extend_from_slice(&(Self::BLOCK_LEN as u32).to_le_bytes()); // 17..21
+
+        let tmp = Self::encode_blocks_f64(data, Self::BLOCK_LEN, |block| {
+            let max_abs = block
+                .iter()
+                .filter(|f| f.is_finite())
+                .fold(0.0f64, |m, f| m.max(f.abs()));
+            if max_abs == 0.0 {
+                return 0;
+            }
+            digits as i32 - 1 - floor_log10(max_abs)
+        });
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    /// Compress f64 vector choosing the best decimal scale for every block.
+    ///
+    /// Each block of [`Self::BLOCK_LEN`] values uses the smallest power of ten
+    /// that reproduces all of its values exactly, falling back to the largest
+    /// power that keeps the scaled values within f64's exact integer range.
+    /// Arrays mixing tiny and huge magnitudes no longer share one scale.
+    pub fn compress_f64_adaptive(&self, data: &[f64]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(7); // 7: type (7 = f64 with adaptive block scales)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&(Self::BLOCK_LEN as u32).to_le_bytes()); // 16..20
 
-        // Extract scale factor from blob or use provided
-        let scale_factor = if let Some(s) = scale {
-            s
-        } else {
-            f64::from_le_bytes(blob[16..24].try_into().unwrap())
-        };
+        let tmp = Self::encode_blocks_f64(data, Self::BLOCK_LEN, Self::adaptive_exponent);
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    fn adaptive_exponent(block: &[f64]) -> i32 {
+        // 2^53: beyond this scaled values are no longer exact integers
+        const EXACT_LIMIT: f64 = 9_007_199_254_740_992.0;
+        let finite = || block.iter().copied().filter(|f| f.is_finite());
+        let max_abs = finite().fold(0.0f64, |m, f| m.max(f.abs()));
+        if max_abs == 0.0 {
+            return 0;
+        }
 
-        let packed = lz4_flex::block::decompress_size_prepended(&blob[24..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
+        let lowest = -floor_log10(max_abs);
+        let highest = floor_log10(EXACT_LIMIT / max_abs)
+            .clamp(-Self::MAX_BLOCK_EXPONENT, Self::MAX_BLOCK_EXPONENT);
+        (lowest..highest)
+            .find(|&exp| {
+                finite().all(|f| {
+                    let q = Self::scale_by_pow10(f, exp).round() as i64;
+                    Self::unscale_by_pow10(q, exp) == f
+                })
+            })
+            .unwrap_or(highest)
+    }
+
+    /// Compress f64 vector keeping only the top `mantissa_bits` of each
+    /// mantissa (bit grooming).
+    ///
+    /// Unlike decimal quantization this never overflows: the relative error
+    /// is below `2^-mantissa_bits` for every finite value, and NaN, infinities
+    /// and signed zeros keep their IEEE meaning. Consecutive groomed values
+    /// are XORed and the dropped bits shifted out before LZ4.
+    pub fn compress_f64_groomed(&self, data: &[f64], mantissa_bits: u32) -> Result<Vec<u8>> {
+        if !(1..=52).contains(&mantissa_bits) {
+            return Err(invalid!(
+                "mantissa bits must be between 1 and 52, got {mantissa_bits}"
+            ));
+        }
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(10); // 7: type (10 = f64 with groomed mantissa)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+        buf.push(mantissa_bits as u8); // 16: kept mantissa bits
+
+        let dropped = 52 - mantissa_bits;
+        let mask = u64::MAX << dropped;
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0u64;
+        for &f in data {
+            // a NaN whose payload sits in the dropped bits would turn into infinity
+            let bits = if f.is_nan() {
+                f64::NAN.to_bits()
+            } else {
+                f.to_bits()
+            } & mask;
+            tmp.write_varint((bits ^ prev) >> dropped).unwrap();
+            prev = bits;
+        }
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    fn decompress_groomed_f64(blob: &[u8]) -> Result<Vec<f64>> {
+        if blob.len() < 17 {
+            return Err(CydecError::Truncated {
+                needed: 17,
+                got: blob.len(),
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let mantissa_bits = blob[16] as u32;
+        if !(1..=52).contains(&mantissa_bits) {
+            bail!("invalid mantissa bits {mantissa_bits}");
+        }
+        let dropped = 52 - mantissa_bits;
+
+        let packed = Backend::Lz4.unpack(&blob[17..])?;
 
         let mut cur = Cursor::new(packed.as_slice());
-        let mut out = Vec::with_capacity(n);
-        let mut acc = 0i64;
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut prev = 0u64;
         for _ in 0..n {
             let v: u64 = cur
                 .read_varint()
-                .map_err(|e| anyhow!("varint decode: {e}"))?;
-            let d = Self::unzigzag_i64(v);
-            acc = acc.wrapping_add(d);
-            out.push(acc);
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            prev ^= v << dropped;
+            out.push(f64::from_bits(prev));
+        }
+        Ok(out)
+    }
+
+    /// Largest decimal exponent a block may use; 10^308 is the largest finite power
+    const MAX_BLOCK_EXPONENT: i32 = 308;
+
+    #[inline]
+    fn scale_by_pow10(f: f64, exp: i32) -> f64 {
+        // dividing by an exact power of ten is more accurate than
+        // multiplying by its inexact reciprocal
+        if exp >= 0 {
+            f * pow10(exp)
+        } else {
+            f / pow10(-exp)
+        }
+    }
+
+    #[inline]
+    fn unscale_by_pow10(i: i64, exp: i32) -> f64 {
+        if exp >= 0 {
+            i as f64 / pow10(exp)
+        } else {
+            i as f64 * pow10(-exp)
+        }
+    }
+
+    /// Varint stream of blocks, each a zigzagged decimal exponent followed
+    /// by the block's deltas. Deltas restart at every block.
+    fn encode_blocks_f64(
+        data: &[f64],
+        block_len: usize,
+        exponent_for: impl Fn(&[f64]) -> i32,
+    ) -> Vec<u8> {
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        for block in data.chunks(block_len) {
+            let exp =
+                exponent_for(block).clamp(-Self::MAX_BLOCK_EXPONENT, Self::MAX_BLOCK_EXPONENT);
+            tmp.write_varint(Self::zigzag_i64(exp as i64)).unwrap();
+            let mut prev = 0i64;
+            for &f in block {
+                let x = Self::scale_by_pow10(f, exp).round() as i64;
+                let d = x.wrapping_sub(prev);
+                prev = x;
+                tmp.write_varint(Self::zigzag_i64(d)).unwrap();
+            }
+        }
+        tmp
+    }
+
+    fn decode_blocks_f64(packed: &[u8], n: usize, block_len: usize) -> Result<Vec<f64>> {
+        if block_len == 0 {
+            bail!("invalid block length");
+        }
+        let mut cur = Cursor::new(packed);
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        while out.len() < n {
+            let exp: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let exp = Self::unzigzag_i64(exp);
+            if exp.abs() > Self::MAX_BLOCK_EXPONENT as i64 {
+                bail!("invalid block exponent {exp}");
+            }
+            let exp = exp as i32;
+            let mut acc = 0i64;
+            for _ in 0..block_len.min(n - out.len()) {
+                let v: u64 = cur
+                    .read_varint()
+                    .map_err(|e| corrupt!("varint decode: {e}"))?;
+                acc = acc.wrapping_add(Self::unzigzag_i64(v));
+                out.push(Self::unscale_by_pow10(acc, exp));
+            }
+        }
+        Ok(out)
+    }
+
+    fn decompress_adaptive_f64(blob: &[u8]) -> Result<Vec<f64>> {
+        if blob.len() < 20 {
+            return Err(CydecError::Truncated {
+                needed: 20,
+                got: blob.len(),
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let block_len = u32::from_le_bytes(blob[16..20].try_into().unwrap()) as usize;
+
+        let packed = Backend::Lz4.unpack(&blob[20..])?;
+        Self::decode_blocks_f64(&packed, n, block_len)
+    }
+
+    fn decompress_significant_f64(blob: &[u8]) -> Result<Vec<f64>> {
+        if blob.len() < 21 {
+            return Err(CydecError::Truncated {
+                needed: 21,
+                got: blob.len(),
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let block_len = u32::from_le_bytes(blob[17..21].try_into().unwrap()) as usize;
+
+        let packed = Backend::Lz4.unpack(&blob[21..])?;
+        Self::decode_blocks_f64(&packed, n, block_len)
+    }
+
+    /// Decompress f64 vector from scaled i64 data.
+    ///
+    /// `scale` overrides the stored scale factor of fixed-scale blobs; blobs
+    /// written by [`Self::compress_f64_significant`],
+    /// [`Self::compress_f64_adaptive`] or [`Self::compress_f64_groomed`] do not
+    /// use a single scale and ignore it.
+    pub fn decompress_f64(&self, blob: &[u8], scale: Option<f64>) -> Result<Vec<f64>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
         }
 
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+
+        let backend = Backend::from_id(blob[6])?;
+        if !matches!(blob[7], 4 | BLOCKED_F64) && backend != Backend::Lz4 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+
+        match blob[7] {
+            4 | BLOCKED_F64 => {}
+            6 => return Self::decompress_significant_f64(blob),
+            7 => return Self::decompress_adaptive_f64(blob),
+            10 => return Self::decompress_groomed_f64(blob),
+            found => {
+                return Err(CydecError::TypeMismatch {
+                    expected: "f64",
+                    found,
+                });
+            }
+        }
+
+        let (n, backend, stored, blocks) = Self::open_scaled_f64(blob)?;
+
+        // Extract scale factor from blob or use provided
+        let scale_factor = scale.unwrap_or(stored);
+
         // Convert back to f64 using scale factor
-        let result: Vec<f64> = out.iter().map(|&i| i as f64 / scale_factor).collect();
+        let mut result = Vec::with_capacity(reserve_len(n, blob.len()));
+        for block in &blocks {
+            let out = IntegerCodec::decode_i64_payload_with(block.payload, block.count, backend)?;
+            result.extend(out.iter().map(|&i| i as f64 / scale_factor));
+        }
 
         Ok(result)
     }
 
-    /// Compress f32 vector by converting to scaled i32
+    /// Values at `range` of an f64 blob, decoding only the blocks that
+    /// overlap it.
+    ///
+    /// Fixed-scale blobs longer than [`IntegerCodec::BLOCK_LEN`] values are
+    /// stored in blocks of that size. Significant-digit, adaptive and
+    /// groomed blobs have no independent blocks and are decoded in full.
+    pub fn decompress_f64_range(
+        &self,
+        blob: &[u8],
+        range: Range<usize>,
+        scale: Option<f64>,
+    ) -> Result<Vec<f64>> {
+        self.decompress_f64_window(blob, scale, |_| range)
+    }
+
+    /// The last `n` values of an f64 blob (all of them if it is shorter),
+    /// decoding only the trailing blocks; see [`Self::decompress_f64_range`]
+    pub fn decompress_f64_tail(
+        &self,
+        blob: &[u8],
+        n: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<f64>> {
+        self.decompress_f64_window(blob, scale, |total| total - n.min(total)..total)
+    }
+
+    /// Values from `offset` to the end, skipping whole blocks before it
+    /// without decoding them; see [`Self::decompress_f64_range`]
+    pub fn decompress_f64_from(
+        &self,
+        blob: &[u8],
+        offset: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<f64>> {
+        self.decompress_f64_window(blob, scale, |total| offset..total)
+    }
+
+    /// Values at the range `window` picks given the value count
+    fn decompress_f64_window(
+        &self,
+        blob: &[u8],
+        scale: Option<f64>,
+        window: impl FnOnce(usize) -> Range<usize>,
+    ) -> Result<Vec<f64>> {
+        if !matches!(blob.get(7), Some(&(4 | BLOCKED_F64))) {
+            let all = self.decompress_f64(blob, scale)?;
+            let range = window(all.len());
+            check_range(&range, all.len())?;
+            return Ok(all[range].to_vec());
+        }
+        let (n, backend, stored, blocks) = Self::open_scaled_f64(blob)?;
+        let scale_factor = scale.unwrap_or(stored);
+        let out = decode_blocks_range(&blocks, n, backend, window(n))?;
+        Ok(out.iter().map(|&i| i as f64 / scale_factor).collect())
+    }
+
+    /// Compress f32 vector by converting to scaled i32.
+    ///
+    /// The whole pipeline stays 32-bit: values are quantized to i32 and their
+    /// deltas are zigzagged to u32, never widened to 64-bit lanes.
     pub fn compress_f32(&self, data: &[f32], scale: Option<f32>) -> Result<Vec<u8>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
 
         let scale_factor = scale.unwrap_or(Self::DEFAULT_F32_SCALE);
-        let scaled_data: Vec<i32> = data
-            .iter()
-            .map(|&f| (f * scale_factor).round() as i32)
-            .collect();
+        let blocked = data.len() > IntegerCodec::BLOCK_LEN;
 
         // Compress as i32 but with f32 type identifier
-        let mut buf = Vec::with_capacity(scaled_data.len() * 2);
+        let mut buf = Vec::with_capacity(data.len() + 20);
         // header: magic + version + len + type
         buf.extend_from_slice(b"CYDEC"); // 0..5
         buf.push(1); // 5: version
         buf.push(1); // 6: codec LZ4
-        buf.push(5); // 7: type (5 = f32)
+        buf.push(if blocked { BLOCKED_F32 } else { 5 }); // 7: type (5 = f32, 34 = blocked f32)
         buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
 
         // Add scale factor to header (4 bytes for f32)
         buf.extend_from_slice(&scale_factor.to_le_bytes()); // 16..20
 
-        // stream varints into a temp vec
-        let mut tmp = Vec::with_capacity(scaled_data.len() * 2);
+        if !blocked {
+            buf.extend_from_slice(&Self::encode_f32_payload(data, scale_factor));
+            return Ok(buf);
+        }
+        for chunk in data.chunks(IntegerCodec::BLOCK_LEN) {
+            let payload = Self::encode_f32_payload(chunk, scale_factor);
+            push_block(&mut buf, chunk.len(), None, &payload)?;
+        }
+        Ok(buf)
+    }
+
+    fn encode_f32_payload(data: &[f32], scale_factor: f32) -> Vec<u8> {
+        // quantize and stream varints into a temp vec in one pass
+        let mut tmp = Vec::with_capacity(data.len() * 2);
         let mut prev = 0i32;
-        for &x in &scaled_data {
+        for &f in data {
+            let x = (f * scale_factor).round() as i32;
             let d = x.wrapping_sub(prev);
             prev = x;
             tmp.write_varint(Self::zigzag_i32(d)).unwrap();
         }
 
         // compress varint bytes
-        let comp = lz4_flex::block::compress_prepend_size(&tmp);
-        buf.extend_from_slice(&comp);
-        Ok(buf)
+        lz4_flex::block::compress_prepend_size(&tmp)
     }
 
-    /// Decompress f32 vector from scaled i32 data
-    pub fn decompress_f32(&self, blob: &[u8], scale: Option<f32>) -> Result<Vec<f32>> {
-        if blob.is_empty() {
-            return Ok(Vec::new());
+    /// Decode `n` values of one f32 payload, calling `f` with each index
+    /// and value
+    fn decode_f32_payload_each(
+        payload: &[u8],
+        n: usize,
+        scale_factor: f32,
+        mut f: impl FnMut(usize, f32),
+    ) -> Result<()> {
+        let packed = Backend::Lz4.unpack(payload)?;
+
+        // decode deltas and convert back to f32 in one pass
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut acc = 0i32;
+        for i in 0..n {
+            let v: u32 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let d = Self::unzigzag_i32(v);
+            acc = acc.wrapping_add(d);
+            f(i, acc as f32 / scale_factor);
         }
+        Ok(())
+    }
 
+    /// Value count, stored scale and payload blocks of an f32 blob
+    pub(crate) fn open_f32(blob: &[u8]) -> Result<(usize, f32, Vec<Block<'_>>)> {
+        let blob = header::strip(blob)?;
         if blob.len() < 20 {
-            // Minimum header size: 15 (base) + 4 (scale)
-            bail!("blob too small");
+            // Minimum header size: 16 (base) + 4 (scale)
+            return Err(CydecError::Truncated {
+                needed: 20,
+                got: blob.len(),
+            });
         }
 
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
 
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
         }
 
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
         }
 
-        if blob[7] != 5 {
-            bail!("unsupported type, expected f32");
+        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+        let scale = f32::from_le_bytes(blob[16..20].try_into().unwrap());
+        let (n, blocks) = match blob[7] {
+            5 => {
+                let block = Block {
+                    offset: 20,
+                    count: n as usize,
+                    stats: None,
+                    payload: &blob[20..],
+                };
+                (n as usize, vec![block])
+            }
+            BLOCKED_F32 => read_blocks_at(blob, 20, n)?,
+            found => {
+                return Err(CydecError::TypeMismatch {
+                    expected: "f32",
+                    found,
+                });
+            }
+        };
+        Ok((n, scale, blocks))
+    }
+
+    /// Decompress f32 vector from scaled i32 data
+    pub fn decompress_f32(&self, blob: &[u8], scale: Option<f32>) -> Result<Vec<f32>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
+        let (n, stored, blocks) = Self::open_f32(blob)?;
 
         // Extract scale factor from blob or use provided
-        let scale_factor = if let Some(s) = scale {
-            s
-        } else {
-            f32::from_le_bytes(blob[16..20].try_into().unwrap())
-        };
+        let scale_factor = scale.unwrap_or(stored);
 
-        let packed = lz4_flex::block::decompress_size_prepended(&blob[20..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
+        let mut out = Vec::with_capacity(reserve_len(n, blob.len()));
+        for block in &blocks {
+            Self::decode_f32_payload_each(block.payload, block.count, scale_factor, |_, x| {
+                out.push(x)
+            })?;
+        }
+
+        Ok(out)
+    }
+
+    /// Values at `range` of an f32 blob, decoding only the blocks that
+    /// overlap it; see [`Self::decompress_f64_range`]
+    pub fn decompress_f32_range(
+        &self,
+        blob: &[u8],
+        range: Range<usize>,
+        scale: Option<f32>,
+    ) -> Result<Vec<f32>> {
+        if blob.is_empty() {
+            check_range(&range, 0)?;
+            return Ok(Vec::new());
+        }
+        let (n, stored, blocks) = Self::open_f32(blob)?;
+        check_range(&range, n)?;
+        let scale_factor = scale.unwrap_or(stored);
+
+        let mut out = Vec::with_capacity(range.len());
+        let mut start = 0;
+        for block in &blocks {
+            if start >= range.end {
+                break;
+            }
+            let end = start + block.count;
+            if end > range.start {
+                // deltas are sequential, so values past the range are skipped
+                let wanted =
+                    range.start.saturating_sub(start)..(range.end - start).min(block.count);
+                Self::decode_f32_payload_each(block.payload, wanted.end, scale_factor, |i, x| {
+                    if i >= wanted.start {
+                        out.push(x);
+                    }
+                })?;
+            }
+            start = end;
+        }
+        Ok(out)
+    }
+
+    /// Compress f16 vector losslessly through a 16-bit pipeline.
+    ///
+    /// The raw half-precision bit patterns are delta and zigzag encoded as
+    /// 16-bit lanes, so NaN and infinities round-trip unchanged.
+    #[cfg(feature = "half")]
+    pub fn compress_f16(&self, data: &[f16]) -> Result<Vec<u8>> {
+        Ok(Self::encode_bits_u16(data.iter().map(|f| f.to_bits()), 8))
+    }
+
+    /// Decompress f16 vector
+    #[cfg(feature = "half")]
+    pub fn decompress_f16(&self, blob: &[u8]) -> Result<Vec<f16>> {
+        let bits = Self::decode_bits_u16(blob, 8, "f16")?;
+        Ok(bits.into_iter().map(f16::from_bits).collect())
+    }
+
+    /// Compress bf16 vector losslessly through a 16-bit pipeline
+    #[cfg(feature = "half")]
+    pub fn compress_bf16(&self, data: &[bf16]) -> Result<Vec<u8>> {
+        Ok(Self::encode_bits_u16(data.iter().map(|f| f.to_bits()), 9))
+    }
+
+    /// Decompress bf16 vector
+    #[cfg(feature = "half")]
+    pub fn decompress_bf16(&self, blob: &[u8]) -> Result<Vec<bf16>> {
+        let bits = Self::decode_bits_u16(blob, 9, "bf16")?;
+        Ok(bits.into_iter().map(bf16::from_bits).collect())
+    }
+
+    #[cfg(feature = "half")]
+    fn encode_bits_u16(bits: impl ExactSizeIterator<Item = u16>, type_tag: u8) -> Vec<u8> {
+        if bits.len() == 0 {
+            return Vec::new();
+        }
+
+        let mut buf = Vec::with_capacity(bits.len() + 16);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(type_tag); // 7: type (8 = f16, 9 = bf16)
+        buf.extend_from_slice(&(bits.len() as u64).to_le_bytes()); // 8..16
+
+        // delta of the raw bit patterns as 16-bit lanes
+        let mut tmp = Vec::with_capacity(bits.len() * 2);
+        let mut prev = 0i16;
+        for x in bits {
+            let x = x as i16;
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            tmp.write_varint(Self::zigzag_i16(d)).unwrap();
+        }
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        buf
+    }
+
+    #[cfg(feature = "half")]
+    fn decode_bits_u16(blob: &[u8], type_tag: u8, type_name: &'static str) -> Result<Vec<u16>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != type_tag {
+            return Err(CydecError::TypeMismatch {
+                expected: type_name,
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
 
         let mut cur = Cursor::new(packed.as_slice());
-        let mut out = Vec::with_capacity(n);
-        let mut acc = 0i32;
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0i16;
         for _ in 0..n {
-            let v: u32 = cur
+            let v: u16 = cur
                 .read_varint()
-                .map_err(|e| anyhow!("varint decode: {e}"))?;
-            let d = Self::unzigzag_i32(v);
-            acc = acc.wrapping_add(d);
-            out.push(acc);
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc.wrapping_add(Self::unzigzag_i16(v));
+            out.push(acc as u16);
         }
-
-        // Convert back to f32 using scale factor
-        let result: Vec<f32> = out.iter().map(|&i| i as f32 / scale_factor).collect();
-
-        Ok(result)
+        Ok(out)
     }
 
     /// Compress multiple f64 arrays
@@ -260,20 +1266,28 @@ impl FloatingCodec {
     /// Decompress multiple f64 arrays
     pub fn decompress_many_f64(
         &self,
-        blobs: &[Vec<u8>],
+        blobs: &[impl AsRef<[u8]> + Sync],
         scales: Option<Vec<f64>>,
     ) -> Result<Vec<Vec<f64>>> {
         let default_scale = Self::DEFAULT_F64_SCALE;
         if let Some(scale_vec) = scales {
             blobs
                 .par_iter()
+                .enumerate()
                 .zip(scale_vec.par_iter())
-                .map(|(b, &s)| self.decompress_f64(b, Some(s)))
+                .map(|((i, b), &s)| {
+                    self.decompress_f64(b.as_ref(), Some(s))
+                        .map_err(|e| at_index(i, e))
+                })
                 .collect()
         } else {
             blobs
                 .par_iter()
-                .map(|b| self.decompress_f64(b, Some(default_scale)))
+                .enumerate()
+                .map(|(i, b)| {
+                    self.decompress_f64(b.as_ref(), Some(default_scale))
+                        .map_err(|e| at_index(i, e))
+                })
                 .collect()
         }
     }
@@ -302,20 +1316,28 @@ impl FloatingCodec {
     /// Decompress multiple f32 arrays
     pub fn decompress_many_f32(
         &self,
-        blobs: &[Vec<u8>],
+        blobs: &[impl AsRef<[u8]> + Sync],
         scales: Option<Vec<f32>>,
     ) -> Result<Vec<Vec<f32>>> {
         let default_scale = Self::DEFAULT_F32_SCALE;
         if let Some(scale_vec) = scales {
             blobs
                 .par_iter()
+                .enumerate()
                 .zip(scale_vec.par_iter())
-                .map(|(b, &s)| self.decompress_f32(b, Some(s)))
+                .map(|((i, b), &s)| {
+                    self.decompress_f32(b.as_ref(), Some(s))
+                        .map_err(|e| at_index(i, e))
+                })
                 .collect()
         } else {
             blobs
                 .par_iter()
-                .map(|b| self.decompress_f32(b, Some(default_scale)))
+                .enumerate()
+                .map(|(i, b)| {
+                    self.decompress_f32(b.as_ref(), Some(default_scale))
+                        .map_err(|e| at_index(i, e))
+                })
                 .collect()
         }
     }
@@ -343,6 +1365,219 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn report_tracks_quantization_error() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v = vec![0.1234, 1.0049, -2.5, 3.99951];
+        let (blob, report) = c.compress_f64_with_report(&v, Some(1000.0))?;
+        assert_eq!(report.scale, 1000.0);
+        assert_eq!(report.count, 4);
+        assert_eq!(report.clamped, 0);
+        assert!((report.max_abs_error - 0.00049).abs() < 1e-9);
+        assert!(report.mean_abs_error > 0.0 && report.mean_abs_error <= report.max_abs_error);
+
+        // the blob is identical to the one produced without a report
+        assert_eq!(blob, c.compress_f64(&v, Some(1000.0))?);
+        let back = c.decompress_f64(&blob, None)?;
+        for (original, decompressed) in v.iter().zip(back.iter()) {
+            assert!((original - decompressed).abs() <= report.max_abs_error + 1e-12);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn report_counts_clamped_values() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v = vec![1.0, f64::NAN, 1e300, f64::NEG_INFINITY, 2.0];
+        let (_, report) = c.compress_f64_with_report(&v, None)?;
+        assert_eq!(report.clamped, 3);
+        assert_eq!(report.max_abs_error, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn significant_digits_keep_relative_precision() -> Result<()> {
+        let c = FloatingCodec::default();
+        let block = FloatingCodec::BLOCK_LEN;
+        // one block of tiny values followed by one block of huge values
+        let v: Vec<f64> = (0..block)
+            .map(|i| 0.000123456 + i as f64 * 1e-11)
+            .chain((0..block).map(|i| 123456.0 + i as f64 * 0.5))
+            .chain([42.0])
+            .collect();
+        let blob = c.compress_f64_significant(&v, 6)?;
+        let back = c.decompress_f64(&blob, None)?;
+        assert_eq!(v.len(), back.len());
+        for (original, decompressed) in v.iter().zip(back.iter()) {
+            let rel = ((original - decompressed) / original).abs();
+            assert!(
+                rel <= 5e-6,
+                "Values differ: {} vs {}",
+                original,
+                decompressed
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn significant_digits_rejects_out_of_range() {
+        let c = FloatingCodec::default();
+        assert!(c.compress_f64_significant(&[1.0], 0).is_err());
+        assert!(c.compress_f64_significant(&[1.0], 18).is_err());
+    }
+
+    #[test]
+    fn decimal_exponents_do_not_depend_on_libm() {
+        assert_eq!(pow10(0), 1.0);
+        assert_eq!(pow10(22), 1e22);
+        assert_eq!(pow10(308), 1e308);
+        assert_eq!(pow10(309), f64::INFINITY);
+        for (x, e) in [
+            (1.0, 0),
+            (0.999_999_999_999_999_9, -1),
+            (1e23, 23),
+            (0.001, -3),
+            (f64::MAX, 308),
+            (f64::MIN_POSITIVE, -308),
+            (5e-324, -324),
+        ] {
+            assert_eq!(floor_log10(x), e, "{x:e}");
+        }
+    }
+
+    #[test]
+    fn adaptive_scale_is_lossless_for_mixed_magnitudes() -> Result<()> {
+        let c = FloatingCodec::default();
+        let block = FloatingCodec::BLOCK_LEN;
+        let v: Vec<f64> = (0..block)
+            .map(|i| i as f64 / 1e9)
+            .chain((0..block).map(|i| 117_000.25 + i as f64 * 0.5))
+            .chain((0..block).map(|i| 1e15 + i as f64))
+            .collect();
+        let blob = c.compress_f64_adaptive(&v)?;
+        let back = c.decompress_f64(&blob, None)?;
+        assert_eq!(v, back);
+
+        // a single fixed scale cannot hold both ends of this array
+        let fixed = c.decompress_f64(&c.compress_f64(&v, None)?, None)?;
+        assert_ne!(v, fixed);
+        Ok(())
+    }
+
+    #[test]
+    fn adaptive_scale_bounds_error_for_random_data() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let v: Vec<f64> = (0..5_000).map(|_| rng.r#gen::<f64>() * 1000.0).collect();
+        let c = FloatingCodec::default();
+        let back = c.decompress_f64(&c.compress_f64_adaptive(&v)?, None)?;
+        for (original, decompressed) in v.iter().zip(back.iter()) {
+            assert!(
+                (original - decompressed).abs() < 1e-12,
+                "Values differ: {} vs {}",
+                original,
+                decompressed
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn groomed_mantissa_bounds_relative_error() -> Result<()> {
+        let c = FloatingCodec::default();
+        let mut v: Vec<f64> = (0..10_000)
+            .map(|i| 117_000.0 + (i as f64 / 37.0).sin())
+            .collect();
+        v.extend([1e300, -1e-300, 0.0, -0.0]);
+        let blob = c.compress_f64_groomed(&v, 20)?;
+        let back = c.decompress_f64(&blob, None)?;
+        for (original, decompressed) in v.iter().zip(back.iter()) {
+            assert_eq!(original.is_sign_negative(), decompressed.is_sign_negative());
+            assert!(
+                (original - decompressed).abs() <= original.abs() * 2f64.powi(-20),
+                "Values differ: {} vs {}",
+                original,
+                decompressed
+            );
+        }
+        assert!(blob.len() < c.compress_f64_groomed(&v, 52)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn groomed_mantissa_keeps_special_values() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v = vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5, f64::MAX];
+        let back = c.decompress_f64(&c.compress_f64_groomed(&v, 1)?, None)?;
+        assert!(back[0].is_nan());
+        assert_eq!(back[1], f64::INFINITY);
+        assert_eq!(back[2], f64::NEG_INFINITY);
+        assert_eq!(back[3], 1.5);
+        assert!(back[4].is_finite());
+        assert!(c.compress_f64_groomed(&v, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn complex_planes_beat_interleaving() -> Result<()> {
+        let c = FloatingCodec::default();
+        // IQ samples of a slowly rotating carrier
+        let v: Vec<(f64, f64)> = (0..20_000)
+            .map(|i| {
+                let t = i as f64 * 0.001;
+                (t.cos(), t.sin())
+            })
+            .collect();
+        let blob = c.compress_c64(&v, Some(1e6))?;
+        let back = c.decompress_c64(&blob, None)?;
+        assert_eq!(v.len(), back.len());
+        for (original, decompressed) in v.iter().zip(back.iter()) {
+            assert!((original.0 - decompressed.0).abs() <= 5e-7);
+            assert!((original.1 - decompressed.1).abs() <= 5e-7);
+        }
+
+        let interleaved: Vec<f64> = v.iter().flat_map(|&(re, im)| [re, im]).collect();
+        assert!(blob.len() < c.compress_f64(&interleaved, Some(1e6))?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn track_stays_within_error_bound() -> Result<()> {
+        let c = FloatingCodec::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let (mut lat, mut lon) = (47.376_887_f64, 8.541_694_f64);
+        let track: Vec<(f64, f64)> = (0..10_000)
+            .map(|_| {
+                lat += rng.gen_range(-1e-4..1e-4);
+                lon += rng.gen_range(-1e-4..1e-4);
+                (lat, lon)
+            })
+            .collect();
+
+        let blob = c.compress_track(&track, 5)?;
+        assert!(blob.len() < track.len() * 16 / 4);
+        let back = c.decompress_track(&blob)?;
+        let bound = FloatingCodec::track_error_bound_m(5);
+        assert!(bound < 1.0);
+        for (&(lat0, lon0), &(lat1, lon1)) in track.iter().zip(back.iter()) {
+            let dy = (lat0 - lat1) * FloatingCodec::METRES_PER_DEGREE;
+            let dx = (lon0 - lon1) * FloatingCodec::METRES_PER_DEGREE * lat0.to_radians().cos();
+            assert!(
+                dx.hypot(dy) <= bound,
+                "({lat0}, {lon0}) vs ({lat1}, {lon1})"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn track_rejects_invalid_coordinates() {
+        let c = FloatingCodec::default();
+        assert!(c.compress_track(&[(91.0, 0.0)], 6).is_err());
+        assert!(c.compress_track(&[(0.0, f64::NAN)], 6).is_err());
+        assert!(c.compress_track(&[(0.0, 0.0)], 16).is_err());
+    }
+
     #[test]
     fn roundtrip_f32() -> Result<()> {
         let c = FloatingCodec::default();
@@ -360,6 +1595,87 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn range_decompression_of_long_float_blobs() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v: Vec<f64> = (0..200_000).map(|i| i as f64 * 0.25 - 7.5).collect();
+        let blob = c.compress_f64(&v, None)?;
+        assert_eq!(blob[7], BLOCKED_F64);
+        assert_eq!(v, c.decompress_f64(&blob, None)?);
+        assert_eq!(
+            c.decompress_f64_range(&blob, 199_000..200_000, None)?,
+            v[199_000..]
+        );
+        assert_eq!(
+            c.decompress_f64_range(&blob, 65_000..70_000, None)?,
+            v[65_000..70_000]
+        );
+        assert!(c.decompress_f64_range(&blob, 0..200_001, None).is_err());
+
+        let w: Vec<f32> = (0..150_000).map(|i| (i % 1_000) as f32 * 0.5).collect();
+        let blob = c.compress_f32(&w, None)?;
+        assert_eq!(blob[7], BLOCKED_F32);
+        let all = c.decompress_f32(&blob, None)?;
+        assert!(w.iter().zip(&all).all(|(a, b)| (a - b).abs() < 1e-3));
+        assert_eq!(
+            c.decompress_f32_range(&blob, 140_000..150_000, None)?,
+            all[140_000..]
+        );
+        assert_eq!(
+            c.decompress_f32_range(&blob, 65_530..65_540, None)?,
+            all[65_530..65_540]
+        );
+        let (start, end) = (10, 5);
+        assert!(c.decompress_f32_range(&blob, start..end, None).is_err());
+
+        // layouts without blocks are decoded in full
+        let small = c.compress_f64_significant(&v[..100], 6)?;
+        assert_eq!(c.decompress_f64_range(&small, 10..20, None)?.len(), 10);
+        assert!(c.decompress_f64_range(&[], 0..0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn f32_blob_not_larger_than_f64() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v32: Vec<f32> = (0..10_000)
+            .map(|i| 100.0 + (i % 97) as f32 * 0.25)
+            .collect();
+        let v64: Vec<f64> = v32.iter().map(|&f| f as f64).collect();
+        let blob32 = c.compress_f32(&v32, Some(100.0))?;
+        let blob64 = c.compress_f64(&v64, Some(100.0))?;
+        // 4-byte scale in the header instead of 8, identical payload otherwise
+        assert_eq!(blob32.len() + 4, blob64.len());
+        assert_eq!(v32, c.decompress_f32(&blob32, None)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn roundtrip_f16_and_bf16() -> Result<()> {
+        let c = FloatingCodec::default();
+        let mut v: Vec<f32> = (0..10_000)
+            .map(|i| (i as f32 * 0.01).sin() * 20.0)
+            .collect();
+        v.extend([f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -0.0]);
+
+        let halves: Vec<f16> = v.iter().map(|&f| f16::from_f32(f)).collect();
+        let blob = c.compress_f16(&halves)?;
+        assert!(blob.len() < halves.len() * 2);
+        let back = c.decompress_f16(&blob)?;
+        let bits = |h: &[f16]| h.iter().map(|f| f.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&halves), bits(&back));
+
+        let brains: Vec<bf16> = v.iter().map(|&f| bf16::from_f32(f)).collect();
+        let blob = c.compress_bf16(&brains)?;
+        let back = c.decompress_bf16(&blob)?;
+        let bits = |h: &[bf16]| h.iter().map(|f| f.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&brains), bits(&back));
+
+        assert!(c.decompress_f16(&blob).is_err());
+        Ok(())
+    }
+
     #[test]
     fn roundtrip_parallel_f64() -> Result<()> {
         let c = FloatingCodec::default();
@@ -568,4 +1884,47 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn roundtrip_sparse_map() -> Result<()> {
+        let c = FloatingCodec::default();
+        let map: BTreeMap<u64, f64> = (0..5_000u64)
+            .filter(|i| i % 7 != 3)
+            .map(|i| (1_700_000_000_000 + i * 1_000, i as f64 * 0.25))
+            .chain([(0, -1.5), (u64::MAX, 42.0)])
+            .collect();
+        let blob = c.compress_map(&map, None)?;
+        assert_eq!(map, c.decompress_map(&blob, None)?);
+        assert!(blob.len() < map.len() * 4);
+
+        let hashmap: HashMap<u64, f64> = map.clone().into_iter().collect();
+        let hashed_blob = c.compress_hashmap(&hashmap, None)?;
+        assert_eq!(blob, hashed_blob);
+        assert_eq!(hashmap, c.decompress_hashmap(&hashed_blob, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn per_call_scale_override() -> Result<()> {
+        let c = FloatingCodec::default();
+        let v = vec![1.2345, -6.789, 100.0];
+        let options = CompressOptions {
+            scale: Some(100.0),
+            ..Default::default()
+        };
+        let blob = c.compress_f64_with(&v, &options)?;
+        assert_eq!(blob, c.compress_f64(&v, Some(100.0))?);
+        assert_eq!(vec![1.23, -6.79, 100.0], c.decompress_f64(&blob, None)?);
+
+        #[cfg(feature = "zstd")]
+        {
+            let zstd = CompressOptions {
+                backend: Backend::Zstd,
+                ..options
+            };
+            let blob = c.compress_f64_with(&v, &zstd)?;
+            assert_eq!(vec![1.23, -6.79, 100.0], c.decompress_f64(&blob, None)?);
+        }
+        Ok(())
+    }
 }
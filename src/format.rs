@@ -0,0 +1,283 @@
+//! Constants of the blob format, for storage layers and ports that work
+//! on header bytes directly.
+//!
+//! Every blob starts with [`MAGIC`], a version byte, a [`CodecId`] byte, a
+//! type byte and the element count as a little-endian u64: [`HEADER_LEN`]
+//! bytes in all. The type byte holds a [`TypeTag`], plus layout flags in
+//! its top bits when [`BLOCKED_FLAG`] is set; [`TypeTag::of`] masks them
+//! off. [`crate::Header`] parses the whole header.
+
+use crate::CydecError;
+use crate::error::Result;
+
+/// First bytes of every blob
+pub const MAGIC: [u8; 5] = *b"CYDEC";
+
+/// Magic, version, codec id, type byte and element count
+pub const HEADER_LEN: usize = 16;
+
+/// Version of blobs without extensions, and of the blob a v2 one wraps
+pub const VERSION_1: u8 = 1;
+
+/// Version of blobs carrying an extension area in front of a wrapped v1
+/// blob
+pub const VERSION_2: u8 = 2;
+
+/// Newest version this build reads; decoders read every version up to it
+pub const MAX_VERSION: u8 = VERSION_2;
+
+/// Set on the type byte of blobs stored as a sequence of blocks
+pub const BLOCKED_FLAG: u8 = 0x80;
+
+/// Set along with [`BLOCKED_FLAG`] when every block header is followed by
+/// the block's statistics: i64 min, i64 max, i128 sum
+pub const STATS_FLAG: u8 = 0x40;
+
+/// Set along with [`BLOCKED_FLAG`] when a Bloom filter follows the last
+/// block
+pub const FILTER_FLAG: u8 = 0x20;
+
+/// Bits of the type byte that hold the element type of a blocked blob
+pub const TYPE_MASK: u8 = 0x1f;
+
+/// Id of the final compression stage, stored at byte 6
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum CodecId {
+    /// LZ4 block with the uncompressed size prepended as a u32
+    Lz4 = 1,
+    /// zstd frame with the uncompressed size prepended as a u32
+    Zstd = 2,
+    /// Standard LZ4 frame with its content size set
+    Lz4Frame = 3,
+}
+
+impl TryFrom<u8> for CodecId {
+    type Error = CydecError;
+
+    fn try_from(id: u8) -> Result<Self> {
+        Ok(match id {
+            1 => CodecId::Lz4,
+            2 => CodecId::Zstd,
+            3 => CodecId::Lz4Frame,
+            found => return Err(CydecError::UnsupportedBackend { found }),
+        })
+    }
+}
+
+impl From<CodecId> for u8 {
+    fn from(id: CodecId) -> u8 {
+        id as u8
+    }
+}
+
+/// Element type and layout of a blob, stored at byte 7
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum TypeTag {
+    I64 = 0,
+    U64 = 1,
+    I32 = 2,
+    U32 = 3,
+    /// Fixed-scale f64, or raw bytes from
+    /// [`crate::IntegerCodec::compress_bytes`]; [`crate::Header::scale`]
+    /// is `None` for raw bytes
+    F64OrBytes = 4,
+    F32 = 5,
+    /// f64 rounded to significant digits
+    SignificantF64 = 6,
+    /// f64 with a scale picked per block
+    AdaptiveF64 = 7,
+    F16 = 8,
+    Bf16 = 9,
+    /// f64 with mantissa bits cleared
+    GroomedF64 = 10,
+    I16 = 11,
+    U16 = 12,
+    I8 = 13,
+    I128 = 14,
+    U128 = 15,
+    DateTime = 16,
+    Duration = 17,
+    Uuid = 18,
+    Ipv4 = 19,
+    Ipv6 = 20,
+    Categorical = 21,
+    Complex64 = 22,
+    Track = 23,
+    I64Matrix = 24,
+    F64Matrix = 25,
+    Candles = 26,
+    Series = 27,
+    MultiSeries = 28,
+    Map = 29,
+    Jagged = 30,
+    PcmI16 = 31,
+    PcmI32 = 32,
+    /// Fixed-scale f64 split into blocks
+    BlockedF64 = 33,
+    /// Fixed-scale f32 split into blocks
+    BlockedF32 = 34,
+    /// Named columns, each a blob of its own; see [`crate::Frame`]
+    Frame = 35,
+    /// i64 as bit-packed delta-of-deltas, see
+    /// [`crate::ColumnEncoding::DeltaOfDelta`]
+    DodI64 = 36,
+    /// i64 as zigzag varints without deltas
+    VarintI64 = 37,
+    /// u64 as varints without deltas
+    VarintU64 = 38,
+}
+
+impl TypeTag {
+    /// Element type of a type byte, ignoring the layout flags of blocked
+    /// blobs
+    pub fn of(type_byte: u8) -> Result<Self> {
+        match type_byte & BLOCKED_FLAG {
+            0 => Self::try_from(type_byte),
+            _ => Self::try_from(type_byte & TYPE_MASK).map_err(|_| CydecError::TypeMismatch {
+                expected: "a cydec type",
+                found: type_byte,
+            }),
+        }
+    }
+
+    /// Name of the element type, as used in error messages and by
+    /// [`crate::inspect`]
+    pub fn name(self) -> &'static str {
+        match self {
+            TypeTag::I64 | TypeTag::DodI64 | TypeTag::VarintI64 => "i64",
+            TypeTag::U64 | TypeTag::VarintU64 => "u64",
+            TypeTag::I32 => "i32",
+            TypeTag::U32 => "u32",
+            TypeTag::F64OrBytes
+            | TypeTag::SignificantF64
+            | TypeTag::AdaptiveF64
+            | TypeTag::GroomedF64
+            | TypeTag::BlockedF64 => "f64",
+            TypeTag::F32 | TypeTag::BlockedF32 => "f32",
+            TypeTag::F16 => "f16",
+            TypeTag::Bf16 => "bf16",
+            TypeTag::I16 => "i16",
+            TypeTag::U16 => "u16",
+            TypeTag::I8 => "i8",
+            TypeTag::I128 => "i128",
+            TypeTag::U128 => "u128",
+            TypeTag::DateTime => "datetime",
+            TypeTag::Duration => "duration",
+            TypeTag::Uuid => "uuid",
+            TypeTag::Ipv4 => "ipv4",
+            TypeTag::Ipv6 => "ipv6",
+            TypeTag::Categorical => "categorical",
+            TypeTag::Complex64 => "complex f64",
+            TypeTag::Track => "lat/lon track",
+            TypeTag::I64Matrix => "i64 matrix",
+            TypeTag::F64Matrix => "f64 matrix",
+            TypeTag::Candles => "candles",
+            TypeTag::Series => "series",
+            TypeTag::MultiSeries => "multi-series frame",
+            TypeTag::Map => "map",
+            TypeTag::Jagged => "jagged i64",
+            TypeTag::PcmI16 => "i16 pcm",
+            TypeTag::PcmI32 => "i32 pcm",
+            TypeTag::Frame => "frame",
+        }
+    }
+}
+
+impl TryFrom<u8> for TypeTag {
+    type Error = CydecError;
+
+    /// Exact conversion; type bytes with layout flags go through
+    /// [`TypeTag::of`]
+    fn try_from(tag: u8) -> Result<Self> {
+        use TypeTag::*;
+        const TAGS: [TypeTag; 39] = [
+            I64,
+            U64,
+            I32,
+            U32,
+            F64OrBytes,
+            F32,
+            SignificantF64,
+            AdaptiveF64,
+            F16,
+            Bf16,
+            GroomedF64,
+            I16,
+            U16,
+            I8,
+            I128,
+            U128,
+            DateTime,
+            Duration,
+            Uuid,
+            Ipv4,
+            Ipv6,
+            Categorical,
+            Complex64,
+            Track,
+            I64Matrix,
+            F64Matrix,
+            Candles,
+            Series,
+            MultiSeries,
+            Map,
+            Jagged,
+            PcmI16,
+            PcmI32,
+            BlockedF64,
+            BlockedF32,
+            Frame,
+            DodI64,
+            VarintI64,
+            VarintU64,
+        ];
+        TAGS.get(tag as usize)
+            .copied()
+            .ok_or(CydecError::TypeMismatch {
+                expected: "a cydec type",
+                found: tag,
+            })
+    }
+}
+
+impl From<TypeTag> for u8 {
+    fn from(tag: TypeTag) -> u8 {
+        tag as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatingCodec, IntegerCodec, SeriesCodec};
+
+    #[test]
+    fn tags_match_written_blobs() -> Result<()> {
+        for tag in 0..=38u8 {
+            assert_eq!(u8::from(TypeTag::try_from(tag)?), tag);
+        }
+        assert!(TypeTag::try_from(39).is_err());
+
+        let ints = IntegerCodec::default();
+        let long: Vec<i64> = (0..100_000).collect();
+        let blob = ints.compress_i64(&long)?;
+        assert_eq!(blob[..5], MAGIC);
+        assert_eq!(blob[5], VERSION_1);
+        assert_eq!(CodecId::try_from(blob[6])?, CodecId::Lz4);
+        assert_ne!(blob[7] & STATS_FLAG, 0);
+        assert_eq!(TypeTag::of(blob[7])?, TypeTag::I64);
+
+        let floats = FloatingCodec::default();
+        let blob = floats.compress_f32(&[1.5; 100_000], None)?;
+        assert_eq!(TypeTag::of(blob[7])?, TypeTag::BlockedF32);
+        let blob = SeriesCodec::default().compress(&long, &[0.5; 100_000])?;
+        assert_eq!(TypeTag::of(blob[7])?, TypeTag::Series);
+        assert_eq!(TypeTag::Series.name(), "series");
+        assert!(CodecId::try_from(9).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,699 @@
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::format::{HEADER_LEN, MAGIC, TypeTag, VERSION_1};
+use crate::header;
+use crate::{ColumnEncoding, CydecElement, CydecError};
+use std::collections::{BTreeMap, HashMap};
+
+/// Column count after the fixed header
+const COUNT_LEN: usize = 4;
+
+/// Schema entry bytes besides the name: u16 name len, u8 type, u64 blob len
+const ENTRY_LEN: usize = 11;
+
+/// Footer bytes per column: u8 kind, 16-byte min, 16-byte max, u64 nulls
+const STATS_LEN: usize = 41;
+
+/// Minimum or maximum of a column, widened so every element type fits
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum StatValue {
+    Int(i128),
+    UInt(u128),
+    Float(f64),
+}
+
+impl StatValue {
+    fn kind(self) -> u8 {
+        match self {
+            StatValue::Int(_) => 1,
+            StatValue::UInt(_) => 2,
+            StatValue::Float(_) => 3,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 16] {
+        match self {
+            StatValue::Int(x) => x.to_le_bytes(),
+            StatValue::UInt(x) => x.to_le_bytes(),
+            StatValue::Float(x) => (x.to_bits() as u128).to_le_bytes(),
+        }
+    }
+
+    fn from_bytes(kind: u8, bytes: [u8; 16]) -> Self {
+        match kind {
+            1 => StatValue::Int(i128::from_le_bytes(bytes)),
+            2 => StatValue::UInt(u128::from_le_bytes(bytes)),
+            _ => StatValue::Float(f64::from_bits(u128::from_le_bytes(bytes) as u64)),
+        }
+    }
+}
+
+/// Footer statistics of a frame column, readable without touching the
+/// column's blob
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnStats {
+    /// Smallest and largest value, skipping nulls; `None` when the column
+    /// has no non-null values or its type is not ordered
+    pub min: Option<StatValue>,
+    pub max: Option<StatValue>,
+    /// Nulls of columns from Arrow arrays, plus NaNs in float columns
+    pub null_count: u64,
+    pub compressed_size: u64,
+}
+
+impl ColumnStats {
+    fn of<T: CydecElement>(values: &[T], compressed_size: usize) -> Self {
+        let range = T::value_range(values);
+        Self {
+            min: range.map(|r| r.0),
+            max: range.map(|r| r.1),
+            null_count: T::null_count(values),
+            compressed_size: compressed_size as u64,
+        }
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self.min.zip(self.max) {
+            Some((min, max)) => {
+                buf.push(min.kind());
+                buf.extend_from_slice(&min.to_bytes());
+                buf.extend_from_slice(&max.to_bytes());
+            }
+            None => buf.extend_from_slice(&[0; 33]),
+        }
+        buf.extend_from_slice(&self.null_count.to_le_bytes());
+    }
+
+    fn read(bytes: &[u8], compressed_size: u64) -> Result<Self> {
+        let kind = bytes[0];
+        if kind > 3 {
+            bail!("unknown statistics kind {kind}");
+        }
+        let value = |at: usize| {
+            let raw = bytes[at..at + 16].try_into().unwrap();
+            (kind != 0).then(|| StatValue::from_bytes(kind, raw))
+        };
+        Ok(Self {
+            min: value(1),
+            max: value(17),
+            null_count: u64::from_le_bytes(bytes[33..41].try_into().unwrap()),
+            compressed_size,
+        })
+    }
+}
+
+/// Where one column of a frame lives and what it holds
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameColumn {
+    pub name: String,
+    /// Type byte of the column's blob; 0 for a frame without rows
+    pub type_tag: u8,
+    /// Offset of the column's blob from the start of the frame
+    pub offset: u64,
+    pub len: u64,
+    /// Footer statistics; `None` for frames written before the footer
+    /// existed
+    pub stats: Option<ColumnStats>,
+}
+
+/// Builds a frame: named columns of equal length, each compressed with
+/// the pipeline of its element type unless
+/// [`FrameBuilder::with_encodings`] picks another.
+///
+/// Layout: the usual 16-byte header with type [`TypeTag::Frame`] and the
+/// row count, then the schema `[u32 column count]` followed by
+/// `[u16 name len][name][u8 type][u64 blob len]` per column, then the
+/// column blobs back to back, then a footer with each column's
+/// [`ColumnStats`] as `[u8 kind][min][max][u64 null count]`. Each column
+/// is a complete blob, so reading one never touches the others.
+///
+/// ```
+/// use cydec::{Frame, FrameBuilder};
+///
+/// let mut b = FrameBuilder::new();
+/// b.add("ts", &[1_700_000_000i64, 1_700_000_060])?;
+/// b.add("close", &[101.25f64, 101.5])?;
+/// let frame = Frame::from_bytes(b.finish()?)?;
+/// assert_eq!(frame.column::<f64>("close")?, [101.25, 101.5]);
+/// # Ok::<(), cydec::CydecError>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FrameBuilder {
+    rows: Option<usize>,
+    columns: Vec<(String, Vec<u8>, ColumnStats)>,
+    encodings: BTreeMap<String, ColumnEncoding>,
+}
+
+impl FrameBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encode the columns named in `encodings` their own way, e.g.
+    /// timestamps as [`ColumnEncoding::DeltaOfDelta`] and prices as
+    /// [`ColumnEncoding::Xor`]; other columns stay on
+    /// [`ColumnEncoding::Auto`]. An encoding the column's element type
+    /// lacks fails in [`FrameBuilder::add`].
+    pub fn with_encodings(mut self, encodings: BTreeMap<String, ColumnEncoding>) -> Self {
+        self.encodings = encodings;
+        self
+    }
+
+    /// Compress `values` as column `name`; names must be unique and at
+    /// most 64 KiB, and every column must have as many values as the first
+    pub fn add<T: CydecElement>(&mut self, name: &str, values: &[T]) -> Result<()> {
+        self.check_column(name, values.len())?;
+        let blob = T::compress_encoded(values, self.encoding(name))?;
+        let stats = ColumnStats::of(values, blob.len());
+        self.push_column(name, values.len(), blob, stats)
+    }
+
+    /// Encoding picked for column `name` by [`FrameBuilder::with_encodings`]
+    pub(crate) fn encoding(&self, name: &str) -> ColumnEncoding {
+        self.encodings.get(name).copied().unwrap_or_default()
+    }
+
+    /// Fail if column `name` of `rows` values cannot join the frame
+    pub(crate) fn check_column(&self, name: &str, rows: usize) -> Result<()> {
+        if u16::try_from(name.len()).is_err() {
+            return Err(invalid!("column name of {} bytes is too long", name.len()));
+        }
+        if self.columns.iter().any(|(n, ..)| n == name) {
+            return Err(invalid!("duplicate frame column {name:?}"));
+        }
+        match self.rows {
+            Some(frame_rows) if frame_rows != rows => Err(invalid!(
+                "column {name:?} has {rows} rows, the frame has {frame_rows}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Add a column already compressed to `blob`
+    pub(crate) fn push_column(
+        &mut self,
+        name: &str,
+        rows: usize,
+        blob: Vec<u8>,
+        stats: ColumnStats,
+    ) -> Result<()> {
+        self.check_column(name, rows)?;
+        self.rows = Some(rows);
+        self.columns.push((name.to_owned(), blob, stats));
+        Ok(())
+    }
+
+    /// Write the header, schema, column blobs and footer
+    pub fn finish(self) -> Result<Vec<u8>> {
+        let count =
+            u32::try_from(self.columns.len()).map_err(|_| invalid!("too many frame columns"))?;
+        let body: usize = self
+            .columns
+            .iter()
+            .map(|(n, b, _)| n.len() + ENTRY_LEN + b.len() + STATS_LEN)
+            .sum();
+        let mut buf = Vec::with_capacity(HEADER_LEN + COUNT_LEN + body);
+        buf.extend_from_slice(&MAGIC); // 0..5
+        buf.push(VERSION_1); // 5: version
+        buf.push(1); // 6: codec, unused; columns name their own
+        buf.push(TypeTag::Frame as u8); // 7: type
+        buf.extend_from_slice(&(self.rows.unwrap_or(0) as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&count.to_le_bytes());
+        for (name, blob, _) in &self.columns {
+            buf.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            buf.extend_from_slice(name.as_bytes());
+            buf.push(blob.get(7).copied().unwrap_or(0));
+            buf.extend_from_slice(&(blob.len() as u64).to_le_bytes());
+        }
+        for (_, blob, _) in &self.columns {
+            buf.extend_from_slice(blob);
+        }
+        for (_, _, stats) in &self.columns {
+            stats.write(&mut buf);
+        }
+        Ok(buf)
+    }
+}
+
+/// Read side of [`FrameBuilder`]: parses the schema once, then decodes
+/// columns by name on demand.
+#[derive(Debug)]
+pub struct Frame<B: AsRef<[u8]>> {
+    data: B,
+    /// Start of the wrapped v1 frame within `data`
+    start: usize,
+    rows: usize,
+    columns: Vec<FrameColumn>,
+    names: HashMap<String, usize>,
+}
+
+impl<B: AsRef<[u8]>> Frame<B> {
+    pub fn from_bytes(data: B) -> Result<Self> {
+        let bytes = data.as_ref();
+        let blob = header::strip(bytes)?;
+        let start = bytes.len() - blob.len();
+        if blob.len() < HEADER_LEN + COUNT_LEN {
+            return Err(CydecError::Truncated {
+                needed: HEADER_LEN + COUNT_LEN,
+                got: blob.len(),
+            });
+        }
+        if blob[0..5] != MAGIC {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != VERSION_1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[7] != TypeTag::Frame as u8 {
+            return Err(CydecError::TypeMismatch {
+                expected: "frame",
+                found: blob[7],
+            });
+        }
+        let rows = header::usize_at(blob, 8)?;
+        let count = u32::from_le_bytes(blob[16..20].try_into().unwrap()) as usize;
+
+        let mut schema = &blob[HEADER_LEN + COUNT_LEN..];
+        let mut take = |k: usize| -> Result<&[u8]> {
+            if schema.len() < k {
+                bail!("truncated frame schema");
+            }
+            let (head, rest) = schema.split_at(k);
+            schema = rest;
+            Ok(head)
+        };
+        let mut specs = Vec::with_capacity(count.min(blob.len() / ENTRY_LEN));
+        for _ in 0..count {
+            let name_len = u16::from_le_bytes(take(2)?.try_into().unwrap()) as usize;
+            let name = std::str::from_utf8(take(name_len)?)
+                .map_err(|_| corrupt!("frame column name is not utf-8"))?
+                .to_owned();
+            let type_tag = take(1)?[0];
+            let len = u64::from_le_bytes(take(8)?.try_into().unwrap());
+            specs.push((name, type_tag, len));
+        }
+
+        let mut offset = (blob.len() - schema.len()) as u64;
+        let mut columns = Vec::with_capacity(specs.len());
+        let mut names = HashMap::with_capacity(specs.len());
+        for (name, type_tag, len) in specs {
+            if offset
+                .checked_add(len)
+                .is_none_or(|end| end > blob.len() as u64)
+            {
+                bail!("frame column {name:?} out of bounds");
+            }
+            if names.insert(name.clone(), columns.len()).is_some() {
+                bail!("duplicate frame column {name:?}");
+            }
+            columns.push(FrameColumn {
+                name,
+                type_tag,
+                offset,
+                len,
+                stats: None,
+            });
+            offset += len;
+        }
+        // frames from before the footer end with their last column
+        let footer = &blob[offset as usize..];
+        if !footer.is_empty() {
+            if footer.len() != columns.len() * STATS_LEN {
+                bail!(
+                    "frame footer of {} bytes does not fit {} columns",
+                    footer.len(),
+                    columns.len()
+                );
+            }
+            for (column, bytes) in columns.iter_mut().zip(footer.chunks_exact(STATS_LEN)) {
+                column.stats = Some(ColumnStats::read(bytes, column.len)?);
+            }
+        }
+        Ok(Self {
+            data,
+            start,
+            rows,
+            columns,
+            names,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The whole frame blob, extension area included
+    #[cfg(feature = "arrow")]
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    /// Columns in the order they were added
+    pub fn columns(&self) -> &[FrameColumn] {
+        &self.columns
+    }
+
+    /// Footer statistics of column `name`, for skipping frames that
+    /// cannot match a query before decoding any column
+    pub fn column_stats(&self, name: &str) -> Option<&ColumnStats> {
+        self.columns[*self.names.get(name)?].stats.as_ref()
+    }
+
+    /// The compressed blob of column `name`
+    pub fn column_blob(&self, name: &str) -> Option<&[u8]> {
+        let c = &self.columns[*self.names.get(name)?];
+        let blob = &self.data.as_ref()[self.start..];
+        Some(&blob[c.offset as usize..(c.offset + c.len) as usize])
+    }
+
+    /// Decode column `name` as `T`; a column of another element type is a
+    /// [`CydecError::TypeMismatch`]
+    pub fn column<T: CydecElement>(&self, name: &str) -> Result<Vec<T>> {
+        let blob = self
+            .column_blob(name)
+            .ok_or_else(|| invalid!("no frame column {name:?}"))?;
+        let values = T::decompress_blob(blob)?;
+        if values.len() != self.rows {
+            bail!(
+                "column {name:?} holds {} rows, the frame has {}",
+                values.len(),
+                self.rows
+            );
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Frame<bytes::Bytes> {
+    /// The compressed blob of column `name`, as a view into the frame's
+    /// buffer rather than a copy
+    pub fn column_bytes(&self, name: &str) -> Option<bytes::Bytes> {
+        self.column_blob(name).map(|blob| self.data.slice_ref(blob))
+    }
+}
+
+/// Whether a column the reader asked for was in the frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnPresence {
+    /// In the frame and decoded
+    Present,
+    /// Asked for but not in the frame; filled with nulls or defaults
+    Missing,
+    /// In the frame but never asked for, so skipped
+    Extra,
+}
+
+/// A struct of columns that can be decoded from a frame, possibly one
+/// written with an older or newer schema.
+///
+/// ```
+/// use cydec::{ColumnPresence, Frame, FrameBuilder, FrameReader, FromFrame};
+///
+/// struct Bars {
+///     ts: Vec<i64>,
+///     close: Vec<f64>,
+///     // added after the first frames were written
+///     volume: Option<Vec<u64>>,
+/// }
+///
+/// impl FromFrame for Bars {
+///     fn from_frame<B: AsRef<[u8]>>(r: &mut FrameReader<'_, B>) -> cydec::Result<Self> {
+///         Ok(Bars {
+///             ts: r.column("ts")?,
+///             close: r.column("close")?,
+///             volume: r.optional("volume")?,
+///         })
+///     }
+/// }
+///
+/// let mut old = FrameBuilder::new();
+/// old.add("ts", &[1i64, 2])?;
+/// old.add("close", &[9.5f64, 9.75])?;
+/// let (bars, presence) = Frame::from_bytes(old.finish()?)?.decode::<Bars>()?;
+/// assert_eq!(bars.volume, None);
+/// assert_eq!(presence[2], ("volume".to_string(), ColumnPresence::Missing));
+/// # Ok::<(), cydec::CydecError>(())
+/// ```
+pub trait FromFrame: Sized {
+    fn from_frame<B: AsRef<[u8]>>(columns: &mut FrameReader<'_, B>) -> Result<Self>;
+}
+
+/// Hands columns to [`FromFrame::from_frame`] and notes which ones the
+/// frame had
+#[derive(Debug)]
+pub struct FrameReader<'a, B: AsRef<[u8]>> {
+    frame: &'a Frame<B>,
+    presence: Vec<(String, ColumnPresence)>,
+}
+
+impl<B: AsRef<[u8]>> FrameReader<'_, B> {
+    /// Rows of the frame, the length of every column handed out
+    pub fn rows(&self) -> usize {
+        self.frame.rows
+    }
+
+    /// Decode a column the struct cannot do without; a missing one is an
+    /// error
+    pub fn column<T: CydecElement>(&mut self, name: &str) -> Result<Vec<T>> {
+        self.optional(name)?
+            .ok_or_else(|| invalid!("no frame column {name:?}"))
+    }
+
+    /// Decode a column, or `None` when the frame predates it
+    pub fn optional<T: CydecElement>(&mut self, name: &str) -> Result<Option<Vec<T>>> {
+        let present = self.frame.names.contains_key(name);
+        let presence = match present {
+            true => ColumnPresence::Present,
+            false => ColumnPresence::Missing,
+        };
+        self.presence.push((name.to_owned(), presence));
+        present.then(|| self.frame.column(name)).transpose()
+    }
+
+    /// Decode a column, or one default value per row when the frame
+    /// predates it
+    pub fn column_or_default<T: CydecElement + Default>(&mut self, name: &str) -> Result<Vec<T>> {
+        let rows = self.rows();
+        Ok(self
+            .optional(name)?
+            .unwrap_or_else(|| vec![T::default(); rows]))
+    }
+}
+
+impl<B: AsRef<[u8]>> Frame<B> {
+    /// Decode the frame into `S`, along with the presence of every column
+    /// `S` asked for, in the order it asked, then of each frame column it
+    /// ignored
+    pub fn decode<S: FromFrame>(&self) -> Result<(S, Vec<(String, ColumnPresence)>)> {
+        let mut reader = FrameReader {
+            frame: self,
+            presence: Vec::new(),
+        };
+        let value = S::from_frame(&mut reader)?;
+        let mut presence = reader.presence;
+        for column in &self.columns {
+            if !presence.iter().any(|(name, _)| *name == column.name) {
+                presence.push((column.name.clone(), ColumnPresence::Extra));
+            }
+        }
+        Ok((value, presence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_roundtrip_by_name() -> Result<()> {
+        let ts: Vec<i64> = (0..100_000).map(|i| 1_700_000_000 + i * 60).collect();
+        let close: Vec<f64> = (0..100_000)
+            .map(|i| 100.0 + (i % 400) as f64 * 0.25)
+            .collect();
+        let volume: Vec<u32> = (0..100_000).map(|i| i % 1_000 * 7).collect();
+        let mut b = FrameBuilder::new();
+        b.add("ts", &ts)?;
+        b.add("close", &close)?;
+        b.add("volume", &volume)?;
+        assert!(b.add("close", &close).is_err());
+        assert!(b.add("short", &[1i64]).is_err());
+        let blob = b.finish()?;
+
+        let frame = Frame::from_bytes(blob.as_slice())?;
+        assert_eq!(frame.rows(), 100_000);
+        let names: Vec<&str> = frame.columns().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["ts", "close", "volume"]);
+        assert_eq!(frame.column::<f64>("close")?, close);
+        assert_eq!(frame.column::<i64>("ts")?, ts);
+        assert_eq!(frame.column::<u32>("volume")?, volume);
+        assert!(matches!(
+            frame.column::<u64>("ts"),
+            Err(CydecError::TypeMismatch { .. })
+        ));
+        assert!(frame.column::<i64>("missing").is_err());
+        crate::validate(&blob)?;
+        let info = crate::inspect(&blob)?;
+        assert_eq!((info.element_type, info.len), ("frame", 100_000));
+        assert_eq!(info.uncompressed_size, 100_000 * 20);
+
+        // metadata wraps frames like any other blob
+        let meta = [("symbol".to_string(), "ABC".to_string())].into();
+        let tagged = crate::with_metadata(blob.clone(), &meta)?;
+        assert_eq!(Frame::from_bytes(tagged)?.column::<i64>("ts")?, ts);
+
+        assert!(Frame::from_bytes(&blob[..blob.len() - 1]).is_err());
+        let empty = Frame::from_bytes(FrameBuilder::new().finish()?)?;
+        assert_eq!((empty.rows(), empty.columns().len()), (0, 0));
+        Ok(())
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn columns_share_the_frame_buffer() -> Result<()> {
+        let mut b = FrameBuilder::new();
+        b.add("ts", &[1_700_000_000i64, 1_700_000_060, 1_700_000_120])?;
+        b.add("close", &[101.25f64, 101.5, 99.0])?;
+        let data = bytes::Bytes::from(b.finish()?);
+        let frame = Frame::from_bytes(data.clone())?;
+        let close = frame.column_bytes("close").unwrap();
+        assert!(data.as_ptr_range().contains(&close.as_ptr()));
+        assert_eq!(f64::decompress_blob(&close)?, [101.25, 101.5, 99.0]);
+        assert!(frame.column_bytes("open").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn footer_stats_describe_columns() -> Result<()> {
+        let ts: Vec<i64> = (0..1_000).map(|i| 5_000 - i).collect();
+        let mut price: Vec<f64> = (0..1_000).map(|i| i as f64 * 0.5).collect();
+        price[10] = f64::NAN;
+        price[20] = f64::NAN;
+        let mut b = FrameBuilder::new();
+        b.add("ts", &ts)?;
+        b.add("price", &price)?;
+        b.add("qty", &vec![7u32; 1_000])?;
+        let blob = b.finish()?;
+        let frame = Frame::from_bytes(blob.as_slice())?;
+
+        let stats = frame.column_stats("ts").unwrap();
+        assert_eq!(stats.min, Some(StatValue::Int(4_001)));
+        assert_eq!(stats.max, Some(StatValue::Int(5_000)));
+        assert_eq!(stats.null_count, 0);
+        assert_eq!(
+            stats.compressed_size,
+            frame.column_blob("ts").unwrap().len() as u64
+        );
+        let stats = frame.column_stats("price").unwrap();
+        assert_eq!(stats.max, Some(StatValue::Float(499.5)));
+        assert_eq!(stats.null_count, 2);
+        assert_eq!(
+            frame.column_stats("qty").unwrap().min,
+            Some(StatValue::UInt(7))
+        );
+        // a planner looking for ts < 4_000 can skip this frame
+        assert!(frame.column_stats("ts").unwrap().min > Some(StatValue::Int(3_999)));
+
+        // frames written without a footer still open, without stats
+        let bare = &blob[..blob.len() - 3 * STATS_LEN];
+        let old = Frame::from_bytes(bare)?;
+        assert_eq!(old.column_stats("ts"), None);
+        assert_eq!(old.column::<i64>("ts")?, ts);
+        assert!(Frame::from_bytes(&blob[..blob.len() - 1]).is_err());
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct Quotes {
+        ts: Vec<i64>,
+        bid: Vec<f64>,
+        size: Vec<u32>,
+        venue: Option<Vec<u16>>,
+    }
+
+    impl FromFrame for Quotes {
+        fn from_frame<B: AsRef<[u8]>>(r: &mut FrameReader<'_, B>) -> Result<Self> {
+            Ok(Quotes {
+                ts: r.column("ts")?,
+                bid: r.column("bid")?,
+                size: r.column_or_default("size")?,
+                venue: r.optional("venue")?,
+            })
+        }
+    }
+
+    #[test]
+    fn older_schemas_decode_into_newer_structs() -> Result<()> {
+        let mut b = FrameBuilder::new();
+        b.add("ts", &[10i64, 20, 30])?;
+        b.add("legacy_flag", &[1u8, 0, 1])?;
+        b.add("bid", &[1.5f64, 1.75, 2.0])?;
+        b.add("venue", &[3u16, 3, 4])?;
+        let frame = Frame::from_bytes(b.finish()?)?;
+        let (quotes, presence) = frame.decode::<Quotes>()?;
+        assert_eq!(quotes.ts, [10, 20, 30]);
+        assert_eq!(quotes.bid, [1.5, 1.75, 2.0]);
+        assert_eq!(quotes.size, [0, 0, 0]);
+        assert_eq!(quotes.venue, Some(vec![3, 3, 4]));
+        let expected = [
+            ("ts", ColumnPresence::Present),
+            ("bid", ColumnPresence::Present),
+            ("size", ColumnPresence::Missing),
+            ("venue", ColumnPresence::Present),
+            ("legacy_flag", ColumnPresence::Extra),
+        ];
+        let got: Vec<(&str, ColumnPresence)> =
+            presence.iter().map(|(n, p)| (n.as_str(), *p)).collect();
+        assert_eq!(got, expected);
+
+        // required columns stay required, and types must still match
+        let mut b = FrameBuilder::new();
+        b.add("ts", &[10i64])?;
+        assert!(Frame::from_bytes(b.finish()?)?.decode::<Quotes>().is_err());
+        let mut b = FrameBuilder::new();
+        b.add("ts", &[10i64])?;
+        b.add("bid", &[1i64])?;
+        assert!(matches!(
+            Frame::from_bytes(b.finish()?)?.decode::<Quotes>(),
+            Err(CydecError::TypeMismatch { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn columns_pick_their_encoding() -> Result<()> {
+        let ts: Vec<i64> = (0..50_000).map(|i| 1_700_000_000_000 + i * 250).collect();
+        let close: Vec<f64> = (0..50_000)
+            .map(|i| 100.0 + ((i * 37) % 1_000) as f64 / 3.0)
+            .collect();
+        let volume: Vec<u64> = (0..50_000).map(|i| (i * 7_919) % 100_000).collect();
+        let build = |encodings: BTreeMap<String, ColumnEncoding>| -> Result<Vec<u8>> {
+            let mut b = FrameBuilder::new().with_encodings(encodings);
+            b.add("ts", &ts)?;
+            b.add("close", &close)?;
+            b.add("volume", &volume)?;
+            b.finish()
+        };
+        let encodings = BTreeMap::from([
+            ("ts".to_owned(), ColumnEncoding::DeltaOfDelta),
+            ("close".to_owned(), ColumnEncoding::Xor),
+            ("volume".to_owned(), ColumnEncoding::Varint),
+        ]);
+        let blob = build(encodings)?;
+        let frame = Frame::from_bytes(blob.as_slice())?;
+        assert_eq!(frame.column::<i64>("ts")?, ts);
+        assert_eq!(frame.column::<f64>("close")?, close);
+        assert_eq!(frame.column::<u64>("volume")?, volume);
+        let plain = build(BTreeMap::new())?;
+        let ts_len = |b: &[u8]| Frame::from_bytes(b).map(|f| f.column_blob("ts").unwrap().len());
+        assert!(ts_len(&blob)? < ts_len(&plain)?);
+        crate::validate(&blob)?;
+
+        let wrong = BTreeMap::from([("volume".to_owned(), ColumnEncoding::Xor)]);
+        let mut b = FrameBuilder::new().with_encodings(wrong);
+        assert!(matches!(
+            b.add("volume", &volume),
+            Err(CydecError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use crate::CydecError;
+use crate::error::{Result, corrupt, invalid};
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Frames whole blobs over a byte stream for `tokio_util::codec::Framed`.
+///
+/// Each frame is a u32 little-endian length followed by the blob, so agents
+/// can push compressed batches over TCP or QUIC without their own framing.
+/// Frames above the configured limit are rejected on both ends, before the
+/// decoder buffers them. Decoded frames must carry the CYDEC magic; a
+/// zero-length frame is the empty blob.
+#[derive(Clone, Copy, Debug)]
+pub struct CydecFrameCodec {
+    max_frame_len: usize,
+}
+
+impl Default for CydecFrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CydecFrameCodec {
+    pub const DEFAULT_MAX_FRAME_LEN: usize = 64 << 20;
+
+    pub const fn new() -> Self {
+        Self::with_max_frame_len(Self::DEFAULT_MAX_FRAME_LEN)
+    }
+
+    pub const fn with_max_frame_len(max_frame_len: usize) -> Self {
+        Self { max_frame_len }
+    }
+
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+}
+
+impl Decoder for CydecFrameCodec {
+    type Item = Vec<u8>;
+    type Error = CydecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>> {
+        if src.len() < 4 {
+            return Ok(None);
+        }
+        let len = u32::from_le_bytes(src[..4].try_into().unwrap()) as usize;
+        if len > self.max_frame_len {
+            return Err(corrupt!(
+                "frame of {len} bytes exceeds the {} byte limit",
+                self.max_frame_len
+            ));
+        }
+        if src.len() - 4 < len {
+            src.reserve(len + 4 - src.len());
+            return Ok(None);
+        }
+        src.advance(4);
+        let blob = src.split_to(len).to_vec();
+        if !blob.is_empty() && &blob[..blob.len().min(5)] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        Ok(Some(blob))
+    }
+}
+
+impl<B: AsRef<[u8]>> Encoder<B> for CydecFrameCodec {
+    type Error = CydecError;
+
+    fn encode(&mut self, blob: B, dst: &mut BytesMut) -> Result<()> {
+        let blob = blob.as_ref();
+        if blob.len() > self.max_frame_len || u32::try_from(blob.len()).is_err() {
+            return Err(invalid!(
+                "blob of {} bytes exceeds the {} byte frame limit",
+                blob.len(),
+                self.max_frame_len
+            ));
+        }
+        dst.reserve(blob.len() + 4);
+        dst.put_u32_le(blob.len() as u32);
+        dst.extend_from_slice(blob);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntegerCodec;
+
+    #[test]
+    fn frames_survive_arbitrary_splits() -> Result<()> {
+        let c = IntegerCodec::default();
+        let batches: Vec<Vec<i64>> = (0..5).map(|b| (0..1_000 * b).collect()).collect();
+        let mut wire = BytesMut::new();
+        let mut framer = CydecFrameCodec::new();
+        for batch in &batches {
+            framer.encode(c.compress_i64(batch)?, &mut wire)?;
+        }
+
+        // deliver the stream in odd-sized pieces
+        let mut buf = BytesMut::new();
+        let mut out = Vec::new();
+        for piece in wire.chunks(97) {
+            buf.extend_from_slice(piece);
+            while let Some(blob) = framer.decode(&mut buf)? {
+                out.push(c.decompress_i64(&blob)?);
+            }
+        }
+        assert_eq!(batches, out);
+        assert!(buf.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_and_foreign_frames_are_rejected() -> Result<()> {
+        let mut framer = CydecFrameCodec::with_max_frame_len(64);
+        let mut wire = BytesMut::new();
+        assert!(framer.encode(vec![0u8; 65], &mut wire).is_err());
+
+        wire.put_u32_le(1_000);
+        assert!(framer.decode(&mut wire).is_err());
+
+        let mut wire = BytesMut::new();
+        framer.encode(b"not a cydec blob".as_slice(), &mut wire)?;
+        assert!(matches!(
+            framer.decode(&mut wire),
+            Err(CydecError::BadMagic)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+// TSM blocks are only read
+#![cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+
+use crate::error::{Result, bail, corrupt};
+
+/// Leading-zero count before the first value that changed, the marker for
+/// "no window yet"
+const NO_LEADING: u8 = 0xff;
+
+/// Bits written most significant first, the order of the Gorilla streams in
+/// Prometheus chunks and InfluxDB TSM blocks
+pub(crate) struct BitWriter {
+    pub(crate) out: Vec<u8>,
+    /// Bits taken in the last byte of `out`
+    used: u32,
+}
+
+impl BitWriter {
+    /// Append bits to `out`, from the start of a fresh byte
+    pub(crate) fn new(out: Vec<u8>) -> Self {
+        Self { out, used: 8 }
+    }
+
+    pub(crate) fn write_bit(&mut self, bit: bool) {
+        if self.used == 8 {
+            self.out.push(0);
+            self.used = 0;
+        }
+        if bit {
+            *self.out.last_mut().unwrap() |= 0x80 >> self.used;
+        }
+        self.used += 1;
+    }
+
+    /// The low `n` bits of `value`
+    pub(crate) fn write_bits(&mut self, value: u64, n: u32) {
+        for i in (0..n).rev() {
+            self.write_bit(value >> i & 1 == 1);
+        }
+    }
+}
+
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    /// Next bit to read, counted from the start of `data`
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Read `data` from byte `start` on
+    pub(crate) fn new(data: &'a [u8], start: usize) -> Self {
+        Self {
+            data,
+            pos: start * 8,
+        }
+    }
+
+    pub(crate) fn read_bit(&mut self) -> Result<bool> {
+        let byte = self
+            .data
+            .get(self.pos / 8)
+            .ok_or_else(|| corrupt!("gorilla stream ends mid-value"))?;
+        let bit = byte << (self.pos % 8) & 0x80 != 0;
+        self.pos += 1;
+        Ok(bit)
+    }
+
+    pub(crate) fn read_bits(&mut self, n: u32) -> Result<u64> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = value << 1 | u64::from(self.read_bit()?);
+        }
+        Ok(value)
+    }
+}
+
+/// The XOR coding of float values: each value's bits XORed with the
+/// previous value's, with only the window between the leading and trailing
+/// zeros written
+pub(crate) struct XorWindow {
+    leading: u8,
+    trailing: u8,
+}
+
+impl XorWindow {
+    pub(crate) fn new() -> Self {
+        Self {
+            leading: NO_LEADING,
+            trailing: 0,
+        }
+    }
+
+    /// Write the bits of a value following one with bits `prev`
+    pub(crate) fn write(&mut self, w: &mut BitWriter, prev: u64, bits: u64) {
+        let xor = bits ^ prev;
+        if xor == 0 {
+            w.write_bit(false);
+            return;
+        }
+        w.write_bit(true);
+        let leading = (xor.leading_zeros() as u8).min(31);
+        let trailing = xor.trailing_zeros() as u8;
+        if self.leading != NO_LEADING && leading >= self.leading && trailing >= self.trailing {
+            // the previous window still covers every changed bit
+            w.write_bit(false);
+            w.write_bits(xor >> self.trailing, self.width());
+        } else {
+            (self.leading, self.trailing) = (leading, trailing);
+            w.write_bit(true);
+            w.write_bits(u64::from(leading), 5);
+            w.write_bits(u64::from(self.width()), 6); // 64 wraps to 0
+            w.write_bits(xor >> trailing, self.width());
+        }
+    }
+
+    /// Read the bits of a value following one with bits `prev`
+    pub(crate) fn read(&mut self, r: &mut BitReader, prev: u64) -> Result<u64> {
+        if !r.read_bit()? {
+            return Ok(prev);
+        }
+        if r.read_bit()? {
+            let leading = r.read_bits(5)? as u8;
+            let width = match r.read_bits(6)? as u8 {
+                0 => 64,
+                width => width,
+            };
+            if leading + width > 64 {
+                bail!("gorilla value with {leading} leading and {width} significant bits");
+            }
+            (self.leading, self.trailing) = (leading, 64 - leading - width);
+        } else if self.leading == NO_LEADING {
+            bail!("gorilla value reuses a bit window before any was set");
+        }
+        Ok(prev ^ r.read_bits(self.width())? << self.trailing)
+    }
+
+    fn width(&self) -> u32 {
+        64 - u32::from(self.leading + self.trailing)
+    }
+}
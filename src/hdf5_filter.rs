@@ -0,0 +1,231 @@
+//! HDF5 filter plugin, so datasets can be stored cydec-compressed inside
+//! HDF5 files.
+//!
+//! Build the crate as a `cdylib` with the `hdf5` feature and put the
+//! library in a directory listed in `HDF5_PLUGIN_PATH`; HDF5 finds the
+//! filter through [`H5PLget_plugin_info`]. From h5py:
+//!
+//! ```python
+//! f.create_dataset("close", data=close, chunks=True,
+//!                  compression=400, compression_opts=(10,))
+//! ```
+//!
+//! The first client value names the element type of the little-endian
+//! dataset, one of the `H5Z_CYDEC_*` constants; without it chunks compress
+//! as raw bytes. Every type is stored losslessly: f64 with the XOR
+//! encoding, f32 as the integers of its bit patterns. Chunks decode by
+//! their own header, so reading needs no client values.
+
+use crate::error::{Result, invalid};
+use crate::raw_chunk::{self, RawType};
+use std::ffi::{c_char, c_int, c_uint, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Filter id of cydec, in the range 256-511 HDF5 leaves to filters that
+/// have not been registered with The HDF Group
+pub const H5Z_FILTER_CYDEC: c_int = 400;
+
+pub const H5Z_CYDEC_BYTES: c_uint = 0;
+pub const H5Z_CYDEC_I8: c_uint = 1;
+pub const H5Z_CYDEC_U8: c_uint = 2;
+pub const H5Z_CYDEC_I16: c_uint = 3;
+pub const H5Z_CYDEC_U16: c_uint = 4;
+pub const H5Z_CYDEC_I32: c_uint = 5;
+pub const H5Z_CYDEC_U32: c_uint = 6;
+pub const H5Z_CYDEC_I64: c_uint = 7;
+pub const H5Z_CYDEC_U64: c_uint = 8;
+pub const H5Z_CYDEC_F32: c_uint = 9;
+pub const H5Z_CYDEC_F64: c_uint = 10;
+
+/// `H5Z_FLAG_REVERSE`: the filter is asked to decode
+const FLAG_REVERSE: c_uint = 0x0100;
+/// `H5Z_CLASS_T_VERS`
+const CLASS_VERSION: c_int = 1;
+/// `H5PL_TYPE_FILTER`
+const PLUGIN_TYPE_FILTER: c_int = 0;
+
+type FilterFn = unsafe extern "C" fn(
+    flags: c_uint,
+    cd_nelmts: usize,
+    cd_values: *const c_uint,
+    nbytes: usize,
+    buf_size: *mut usize,
+    buf: *mut *mut c_void,
+) -> usize;
+
+/// `H5Z_class2_t`
+#[repr(C)]
+struct FilterClass {
+    version: c_int,
+    id: c_int,
+    encoder_present: c_uint,
+    decoder_present: c_uint,
+    name: *const c_char,
+    can_apply: Option<unsafe extern "C" fn()>,
+    set_local: Option<unsafe extern "C" fn()>,
+    filter: FilterFn,
+}
+
+// SAFETY: immutable, and `name` points to a static string
+unsafe impl Sync for FilterClass {}
+
+static FILTER_CLASS: FilterClass = FilterClass {
+    version: CLASS_VERSION,
+    id: H5Z_FILTER_CYDEC,
+    encoder_present: 1,
+    decoder_present: 1,
+    name: c"cydec: delta, zigzag and LZ4 for numeric data".as_ptr(),
+    can_apply: None,
+    set_local: None,
+    filter: h5z_filter_cydec,
+};
+
+unsafe extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+/// Plugin kind, read by HDF5 when it loads the library
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "C" fn H5PLget_plugin_type() -> c_int {
+    PLUGIN_TYPE_FILTER
+}
+
+/// The `H5Z_class2_t` describing the filter, read by HDF5 when it loads the
+/// library
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "C" fn H5PLget_plugin_info() -> *const c_void {
+    ptr::from_ref(&FILTER_CLASS).cast()
+}
+
+/// The filter callback, an `H5Z_func_t`. Replaces `*buf`, which HDF5
+/// allocated with `malloc`, by the encoded or decoded chunk and returns its
+/// length, or 0 on failure.
+///
+/// # Safety
+///
+/// The pointers must be valid as HDF5 passes them: `cd_values` holding
+/// `cd_nelmts` values and `*buf` a `malloc` block of `*buf_size` bytes whose
+/// first `nbytes` hold the chunk.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn h5z_filter_cydec(
+    flags: c_uint,
+    cd_nelmts: usize,
+    cd_values: *const c_uint,
+    nbytes: usize,
+    buf_size: *mut usize,
+    buf: *mut *mut c_void,
+) -> usize {
+    let run = || {
+        // SAFETY: as the caller guarantees
+        let (cd_values, chunk) = unsafe {
+            (
+                slice_or_empty(cd_values, cd_nelmts),
+                slice_or_empty((*buf).cast::<u8>(), nbytes),
+            )
+        };
+        if flags & FLAG_REVERSE != 0 {
+            raw_chunk::decode_bytes(chunk, None)
+        } else {
+            encode_chunk(cd_values.first().copied().unwrap_or(H5Z_CYDEC_BYTES), chunk)
+        }
+    };
+    let Ok(Ok(out)) = panic::catch_unwind(AssertUnwindSafe(run)) else {
+        return 0;
+    };
+    // SAFETY: a fresh block replaces the caller's, which came from malloc
+    unsafe {
+        let block = malloc(out.len().max(1));
+        if block.is_null() {
+            return 0;
+        }
+        ptr::copy_nonoverlapping(out.as_ptr(), block.cast::<u8>(), out.len());
+        free(*buf);
+        *buf = block;
+        *buf_size = out.len().max(1);
+    }
+    out.len()
+}
+
+/// Compress a chunk of little-endian values of type `code`
+fn encode_chunk(code: c_uint, chunk: &[u8]) -> Result<Vec<u8>> {
+    let ty = match code {
+        H5Z_CYDEC_BYTES | H5Z_CYDEC_U8 => RawType::U8,
+        H5Z_CYDEC_I8 => RawType::I8,
+        H5Z_CYDEC_I16 => RawType::I16,
+        H5Z_CYDEC_U16 => RawType::U16,
+        H5Z_CYDEC_I32 => RawType::I32,
+        H5Z_CYDEC_U32 => RawType::U32,
+        H5Z_CYDEC_I64 => RawType::I64,
+        H5Z_CYDEC_U64 => RawType::U64,
+        H5Z_CYDEC_F32 => RawType::F32,
+        H5Z_CYDEC_F64 => RawType::F64,
+        other => return Err(invalid!("hdf5 filter: unknown element type {other}")),
+    };
+    raw_chunk::encode(ty, chunk, None)
+}
+
+unsafe fn slice_or_empty<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    if data.is_null() || len == 0 {
+        &[]
+    } else {
+        // SAFETY: the caller vouches for `len` values at `data`
+        unsafe { std::slice::from_raw_parts(data, len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the filter as HDF5 would, on a malloc'd copy of `chunk`
+    fn filter(flags: c_uint, cd_values: &[c_uint], chunk: &[u8]) -> Option<Vec<u8>> {
+        unsafe {
+            let mut buf = malloc(chunk.len().max(1));
+            ptr::copy_nonoverlapping(chunk.as_ptr(), buf.cast::<u8>(), chunk.len());
+            let mut size = chunk.len();
+            let n = h5z_filter_cydec(
+                flags,
+                cd_values.len(),
+                cd_values.as_ptr(),
+                chunk.len(),
+                &mut size,
+                &mut buf,
+            );
+            let out = (n > 0).then(|| slice_or_empty(buf.cast::<u8>(), n).to_vec());
+            free(buf);
+            out
+        }
+    }
+
+    #[test]
+    fn chunks_roundtrip_through_the_plugin() {
+        let info = unsafe { &*H5PLget_plugin_info().cast::<FilterClass>() };
+        assert_eq!(info.id, H5Z_FILTER_CYDEC);
+        assert_eq!(H5PLget_plugin_type(), PLUGIN_TYPE_FILTER);
+
+        let ts: Vec<u8> = (0..4_096i64)
+            .flat_map(|i| (1_700_000_000_000 + i * 1_000).to_le_bytes())
+            .collect();
+        let packed = filter(0, &[H5Z_CYDEC_I64], &ts).unwrap();
+        assert!(packed.len() < ts.len() / 20);
+        assert_eq!(filter(FLAG_REVERSE, &[], &packed).unwrap(), ts);
+
+        let readings: Vec<u8> = (0..2_000)
+            .flat_map(|i| (20.0 + (i as f64 / 7.0).sin()).to_le_bytes())
+            .collect();
+        let packed = filter(0, &[H5Z_CYDEC_F64], &readings).unwrap();
+        assert_eq!(filter(FLAG_REVERSE, &[], &packed).unwrap(), readings);
+        let packed = filter(0, &[H5Z_CYDEC_F32], &readings).unwrap();
+        assert_eq!(filter(FLAG_REVERSE, &[], &packed).unwrap(), readings);
+        let packed = filter(0, &[], &readings).unwrap();
+        assert_eq!(filter(FLAG_REVERSE, &[], &packed).unwrap(), readings);
+
+        assert!(filter(0, &[H5Z_CYDEC_I64], &readings[..12]).is_none());
+        assert!(filter(0, &[99], &readings).is_none());
+        assert!(filter(FLAG_REVERSE, &[], &readings).is_none());
+    }
+}
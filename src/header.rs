@@ -0,0 +1,740 @@
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::floating_codec::{BLOCKED_F32, BLOCKED_F64};
+use crate::series_codec::BLOCKED_SERIES;
+use crate::{CompressOptions, CydecError};
+use std::collections::BTreeMap;
+use std::io::Write;
+use xxhash_rust::xxh3::xxh3_64;
+use xxhash_rust::xxh64::xxh64;
+
+// v2 blobs are laid out as `[fixed header][u32 area len][entries][v1 blob]`.
+// The fixed header repeats the codec, type and length of the wrapped blob,
+// so the same 16 bytes describe a blob of either version and decoders only
+// have to skip to the wrapped one.
+pub(crate) use crate::format::{HEADER_LEN, VERSION_1, VERSION_2};
+
+/// Extension kind of the user metadata map
+pub(crate) const METADATA: u16 = 1;
+
+/// Extension kind of the checksum of the wrapped blob: the
+/// [`Checksum`] id, then the digest
+pub(crate) const CHECKSUM: u16 = 2;
+
+/// Extension kind of an HMAC-SHA256 tag over the other entries and the
+/// wrapped blob; see [`crate::with_signature`]
+pub(crate) const SIGNATURE: u16 = 3;
+
+/// Extension kind of a validity bitmap from an Arrow array: the bit count
+/// as a u64, then the bitmap packed with LZ4
+#[cfg(feature = "arrow")]
+pub(crate) const NULLS: u16 = 4;
+
+/// Extension kind of the Arrow schema of a frame built from a record
+/// batch; see [`crate::Frame::from_record_batch`]
+#[cfg(feature = "arrow")]
+pub(crate) const ARROW_SCHEMA: u16 = 5;
+
+/// Checksum stored with a blob by [`with_checksum`] or
+/// [`crate::CompressOptions::checksum`], and verified before decoding.
+///
+/// The algorithm is recorded in the blob, so readers need no
+/// configuration. CRC32C is fastest on CPUs with hardware support,
+/// xxHash64 and XXH3 give 64-bit digests for archives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Checksum {
+    /// No checksum, and no extension area for one
+    #[default]
+    None,
+    Crc32c,
+    XxHash64,
+    Xxh3,
+}
+
+impl Checksum {
+    /// Id stored in front of the digest
+    pub fn id(self) -> u8 {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32c => 1,
+            Checksum::XxHash64 => 2,
+            Checksum::Xxh3 => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        Ok(match id {
+            0 => Checksum::None,
+            1 => Checksum::Crc32c,
+            2 => Checksum::XxHash64,
+            3 => Checksum::Xxh3,
+            _ => bail!("unknown checksum algorithm {id}"),
+        })
+    }
+
+    /// Digest of `bytes`, widened to a u64
+    fn digest(self, bytes: &[u8]) -> u64 {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32c => crc32c::crc32c(bytes) as u64,
+            Checksum::XxHash64 => xxh64(bytes, 0),
+            Checksum::Xxh3 => xxh3_64(bytes),
+        }
+    }
+
+    /// Bytes the digest takes in the extension entry
+    fn width(self) -> usize {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32c => 4,
+            Checksum::XxHash64 | Checksum::Xxh3 => 8,
+        }
+    }
+
+    /// Extension value for `bytes`: id, then the digest's low bytes
+    fn entry(self, bytes: &[u8]) -> Vec<u8> {
+        let mut value = vec![self.id()];
+        value.extend_from_slice(&self.digest(bytes).to_le_bytes()[..self.width()]);
+        value
+    }
+
+    /// Check `bytes` against a stored extension value
+    fn verify(value: &[u8], bytes: &[u8]) -> Result<()> {
+        let Some((&id, digest)) = value.split_first() else {
+            bail!("empty checksum entry");
+        };
+        let algorithm = Self::from_id(id)?;
+        if digest.len() != algorithm.width() {
+            bail!(
+                "{algorithm:?} checksum of {} bytes, expected {}",
+                digest.len(),
+                algorithm.width()
+            );
+        }
+        let mut stored = [0u8; 8];
+        stored[..digest.len()].copy_from_slice(digest);
+        let stored = u64::from_le_bytes(stored);
+        let computed = algorithm.digest(bytes);
+        if stored != computed {
+            return Err(CydecError::ChecksumMismatch { stored, computed });
+        }
+        Ok(())
+    }
+}
+
+/// Fixed header + u32 extension area length
+const PREAMBLE_LEN: usize = HEADER_LEN + 4;
+
+/// Per-entry header: u16 kind + u32 value length
+const ENTRY_HEADER_LEN: usize = 6;
+
+/// One type-length-value entry of a v2 extension area
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Extension<'a> {
+    pub kind: u16,
+    pub value: &'a [u8],
+}
+
+/// Header of a blob, as laid out in front of its payload.
+///
+/// Every blob starts with the same 16 fixed bytes: magic, version, codec
+/// id, type tag and element count. Float layouts with a single scale store
+/// it right after them; v2 blobs add an extension area of
+/// `(kind, value)` entries. Storage layers can read these without knowing
+/// the payload layout, and [`Header::write_to`] writes back exactly the
+/// bytes [`Header::parse`] read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    pub magic: [u8; 5],
+    /// 1, or 2 when the blob has an extension area
+    pub version: u8,
+    /// Id of the final compression stage: 1 = LZ4, 2 = zstd, 3 = LZ4 frame
+    pub codec: u8,
+    /// Element type, with the layout flags of blocked blobs in its top bits
+    pub type_tag: u8,
+    /// Number of elements
+    pub len: u64,
+    /// Scale factor of fixed-scale f64 and f32 blobs, complex, map and
+    /// series blobs; f32 scales are widened
+    pub scale: Option<f64>,
+    /// Extension entries of a v2 blob as `(kind, value)`, in stored order
+    pub extensions: Vec<(u16, Vec<u8>)>,
+}
+
+/// Width of the scale stored after the fixed fields of `blob`, if any
+fn scale_width(blob: &[u8]) -> usize {
+    match blob[7] {
+        5 | BLOCKED_F32 => 4,
+        22 | 27 | 28 | 29 | BLOCKED_F64 | BLOCKED_SERIES => 8,
+        // raw bytes share tag 4 with f64; their LZ4 payload starts with
+        // the byte count where an f64 blob has its scale
+        4 => match blob.get(16..20) {
+            Some(size) if u32::from_le_bytes(size.try_into().unwrap()) as u64 == read_len(blob) => {
+                0
+            }
+            _ => 8,
+        },
+        _ => 0,
+    }
+}
+
+fn read_len(blob: &[u8]) -> u64 {
+    u64::from_le_bytes(blob[8..16].try_into().unwrap())
+}
+
+/// The u64 length at `at` in `bytes` as a usize, which the caller has
+/// checked are there. On 32-bit targets a length past `usize::MAX` is an
+/// error instead of wrapping to a smaller, valid-looking one.
+pub(crate) fn usize_at(bytes: &[u8], at: usize) -> Result<usize> {
+    let len = u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+    usize::try_from(len).map_err(|_| corrupt!("length {len} does not fit in memory"))
+}
+
+impl Header {
+    /// Parse the header at the start of `blob`; only the header bytes
+    /// need to be present
+    pub fn parse(blob: &[u8]) -> Result<Self> {
+        if blob.len() < HEADER_LEN {
+            return Err(CydecError::Truncated {
+                needed: HEADER_LEN,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != VERSION_1 && blob[5] != VERSION_2 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        let extensions = extensions(blob)?
+            .iter()
+            .map(|e| (e.kind, e.value.to_vec()))
+            .collect();
+        let inner = strip(blob)?;
+        let scale = match scale_width(inner) {
+            0 => None,
+            width if inner.len() < HEADER_LEN + width => {
+                return Err(CydecError::Truncated {
+                    needed: HEADER_LEN + width,
+                    got: inner.len(),
+                });
+            }
+            4 => Some(f32::from_le_bytes(inner[16..20].try_into().unwrap()) as f64),
+            _ => Some(f64::from_le_bytes(inner[16..24].try_into().unwrap())),
+        };
+        Ok(Self {
+            magic: blob[0..5].try_into().unwrap(),
+            version: blob[5],
+            codec: blob[6],
+            type_tag: blob[7],
+            len: read_len(blob),
+            scale,
+            extensions,
+        })
+    }
+
+    /// Bytes [`Self::write_to`] writes, which is also where the payload
+    /// starts
+    pub fn encoded_len(&self) -> usize {
+        let area: usize = self
+            .extensions
+            .iter()
+            .map(|(_, value)| ENTRY_HEADER_LEN + value.len())
+            .sum();
+        let preamble = match self.version {
+            VERSION_2 => PREAMBLE_LEN + area,
+            _ => 0,
+        };
+        let scale = match self.scale {
+            Some(_) if matches!(self.type_tag, 5 | BLOCKED_F32) => 4,
+            Some(_) => 8,
+            None => 0,
+        };
+        preamble + HEADER_LEN + scale
+    }
+
+    /// Write the header; appending the payload makes a complete blob
+    pub fn write_to(&self, out: &mut impl Write) -> Result<()> {
+        let fixed = |version: u8| {
+            let mut buf = [0u8; HEADER_LEN];
+            buf[0..5].copy_from_slice(&self.magic);
+            buf[5] = version;
+            buf[6] = self.codec;
+            buf[7] = self.type_tag;
+            buf[8..16].copy_from_slice(&self.len.to_le_bytes());
+            buf
+        };
+        match self.version {
+            VERSION_1 if !self.extensions.is_empty() => {
+                return Err(invalid!("version 1 headers have no extension area"));
+            }
+            VERSION_1 => {}
+            VERSION_2 => {
+                let entries: Vec<Extension<'_>> = self
+                    .extensions
+                    .iter()
+                    .map(|(kind, value)| Extension { kind: *kind, value })
+                    .collect();
+                let mut buf = fixed(VERSION_2).to_vec();
+                write_area(&mut buf, &entries)?;
+                out.write_all(&buf)?;
+            }
+            found => return Err(invalid!("cannot write a version {found} header")),
+        }
+        out.write_all(&fixed(VERSION_1))?;
+        match self.scale {
+            Some(scale) if matches!(self.type_tag, 5 | BLOCKED_F32) => {
+                out.write_all(&(scale as f32).to_le_bytes())?
+            }
+            Some(scale) => out.write_all(&scale.to_le_bytes())?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Whether `header` starts a v2 blob
+pub(crate) fn is_v2(header: &[u8]) -> bool {
+    header.len() > 5 && &header[0..5] == b"CYDEC" && header[5] == VERSION_2
+}
+
+/// Extension area and wrapped v1 blob of a v2 blob
+fn open_v2(blob: &[u8]) -> Result<(&[u8], &[u8])> {
+    if blob.len() < PREAMBLE_LEN {
+        return Err(CydecError::Truncated {
+            needed: PREAMBLE_LEN,
+            got: blob.len(),
+        });
+    }
+    let area_len = u32::from_le_bytes(blob[16..20].try_into().unwrap()) as usize;
+    if blob.len() - PREAMBLE_LEN < area_len {
+        return Err(CydecError::Truncated {
+            needed: area_len,
+            got: blob.len() - PREAMBLE_LEN,
+        });
+    }
+    let (area, inner) = blob[PREAMBLE_LEN..].split_at(area_len);
+    if inner.len() < HEADER_LEN
+        || &inner[0..5] != b"CYDEC"
+        || inner[5] != VERSION_1
+        || inner[6..16] != blob[6..16]
+    {
+        bail!("v2 header does not match the blob it wraps");
+    }
+    Ok((area, inner))
+}
+
+/// Version dispatch in front of every decoder: the v1 blob a blob of any
+/// readable version holds, or [`CydecError::UnsupportedVersion`] for a
+/// newer one. Input without the magic is returned as is, so decoders can
+/// call this first and keep their own header checks.
+///
+/// A stored checksum is verified here, so no decoder reads a damaged blob.
+pub(crate) fn strip(blob: &[u8]) -> Result<&[u8]> {
+    if blob.len() <= 5 || &blob[0..5] != b"CYDEC" {
+        return Ok(blob);
+    }
+    match blob[5] {
+        VERSION_1 => Ok(blob),
+        VERSION_2 => {
+            let (area, inner) = open_v2(blob)?;
+            if let Some(entry) = parse_area(area)?.iter().find(|e| e.kind == CHECKSUM) {
+                Checksum::verify(entry.value, inner)?;
+            }
+            Ok(inner)
+        }
+        found => Err(CydecError::unsupported_version(found)),
+    }
+}
+
+/// Entries of the extension area, empty for v1 blobs
+pub(crate) fn extensions(blob: &[u8]) -> Result<Vec<Extension<'_>>> {
+    if !is_v2(blob) {
+        return Ok(Vec::new());
+    }
+    parse_area(open_v2(blob)?.0)
+}
+
+/// Split an extension area into its entries
+fn parse_area(mut area: &[u8]) -> Result<Vec<Extension<'_>>> {
+    let mut entries = Vec::new();
+    while !area.is_empty() {
+        if area.len() < ENTRY_HEADER_LEN {
+            bail!("truncated extension entry");
+        }
+        let kind = u16::from_le_bytes(area[0..2].try_into().unwrap());
+        let len = u32::from_le_bytes(area[2..6].try_into().unwrap()) as usize;
+        if area.len() - ENTRY_HEADER_LEN < len {
+            bail!("extension {kind} overruns the extension area");
+        }
+        let (value, rest) = area[ENTRY_HEADER_LEN..].split_at(len);
+        entries.push(Extension { kind, value });
+        area = rest;
+    }
+    Ok(entries)
+}
+
+/// Attach `added` to `blob`, replacing entries of the same kind it already
+/// has. Without any entry left the blob stays v1; empty blobs stay empty.
+/// A checksum entry is recomputed for the wrapped blob, so callers that
+/// rewrite the payload and wrap it again keep it current. A signature is
+/// dropped unless `added` holds a new one, as only the key holder can
+/// renew it.
+pub(crate) fn with_extensions(blob: Vec<u8>, added: &[Extension<'_>]) -> Result<Vec<u8>> {
+    if blob.is_empty() || (added.is_empty() && !is_v2(&blob)) {
+        return Ok(blob);
+    }
+    let inner = strip(&blob)?;
+    let kept = extensions(&blob)?;
+    let mut entries: Vec<Extension<'_>> = kept
+        .into_iter()
+        .filter(|e| e.kind != SIGNATURE && added.iter().all(|a| a.kind != e.kind))
+        .chain(added.iter().copied())
+        .collect();
+    let checksum = match entries.iter().find(|e| e.kind == CHECKSUM) {
+        Some(e) => Checksum::from_id(e.value.first().copied().unwrap_or(0))?,
+        None => Checksum::None,
+    };
+    let sealed = checksum.entry(inner);
+    entries.retain(|e| e.kind != CHECKSUM);
+    if checksum != Checksum::None {
+        entries.push(Extension {
+            kind: CHECKSUM,
+            value: &sealed,
+        });
+    }
+    if entries.is_empty() {
+        return Ok(inner.to_vec());
+    }
+
+    let mut out = Vec::with_capacity(PREAMBLE_LEN + inner.len());
+    out.extend_from_slice(b"CYDEC"); // 0..5
+    out.push(VERSION_2); // 5: version
+    out.extend_from_slice(&inner[6..16]); // 6..16: codec, type, len of the wrapped blob
+    write_area(&mut out, &entries)?;
+    out.extend_from_slice(inner);
+    Ok(out)
+}
+
+/// Store `metadata` in the extension area of `blob`, replacing any map it
+/// already has; compression calls do this for
+/// [`crate::CompressOptions::metadata`].
+///
+/// The map is written as `[u16 key len][key][u16 value len][value]` per
+/// pair and read back by [`crate::inspect`] without touching the payload.
+/// An empty map leaves the blob as it is; empty blobs stay empty.
+pub fn with_metadata(blob: Vec<u8>, metadata: &BTreeMap<String, String>) -> Result<Vec<u8>> {
+    if metadata.is_empty() {
+        return Ok(blob);
+    }
+    let mut value = Vec::new();
+    for (k, v) in metadata {
+        for s in [k, v] {
+            let Ok(len) = u16::try_from(s.len()) else {
+                return Err(invalid!("metadata entry {k:?} too long"));
+            };
+            value.extend_from_slice(&len.to_le_bytes());
+            value.extend_from_slice(s.as_bytes());
+        }
+    }
+    with_extensions(
+        blob,
+        &[Extension {
+            kind: METADATA,
+            value: &value,
+        }],
+    )
+}
+
+/// Store a checksum of `blob`'s payload in its extension area, replacing
+/// any it has; [`Checksum::None`] removes it. Decoders given the whole
+/// blob and [`crate::validate`] verify it first and report
+/// [`CydecError::ChecksumMismatch`] for a blob damaged in storage, instead
+/// of decoding it to wrong values. Compression calls do this for
+/// [`crate::CompressOptions::checksum`]; empty blobs stay empty.
+///
+/// The checksum covers the wrapped v1 blob, whose decoding is
+/// deterministic, so it also vouches for the decoded values.
+pub fn with_checksum(blob: Vec<u8>, checksum: Checksum) -> Result<Vec<u8>> {
+    if blob.is_empty() || (checksum == Checksum::None && !is_v2(&blob)) {
+        return Ok(blob);
+    }
+    // the digest is filled in by `with_extensions`
+    with_extensions(
+        blob,
+        &[Extension {
+            kind: CHECKSUM,
+            value: &[checksum.id()],
+        }],
+    )
+}
+
+/// Apply the header extensions `options` ask for to a freshly written blob
+pub(crate) fn with_options(blob: Vec<u8>, options: &CompressOptions) -> Result<Vec<u8>> {
+    let blob = with_metadata(blob, &options.metadata)?;
+    match options.checksum {
+        Checksum::None => Ok(blob),
+        checksum => with_checksum(blob, checksum),
+    }
+}
+
+/// The metadata map stored in `blob`, empty if it has none
+pub(crate) fn metadata(blob: &[u8]) -> Result<BTreeMap<String, String>> {
+    let mut map = BTreeMap::new();
+    let Some(entry) = extensions(blob)?.into_iter().find(|e| e.kind == METADATA) else {
+        return Ok(map);
+    };
+    let mut rest = entry.value;
+    while !rest.is_empty() {
+        let key = take_str(&mut rest)?;
+        map.insert(key, take_str(&mut rest)?);
+    }
+    Ok(map)
+}
+
+/// Split one u16-length-prefixed string off the front of `rest`
+fn take_str(rest: &mut &[u8]) -> Result<String> {
+    if rest.len() < 2 {
+        bail!("truncated metadata");
+    }
+    let len = u16::from_le_bytes(rest[0..2].try_into().unwrap()) as usize;
+    if rest.len() - 2 < len {
+        bail!("truncated metadata");
+    }
+    let (s, tail) = rest[2..].split_at(len);
+    *rest = tail;
+    String::from_utf8(s.to_vec()).map_err(|_| corrupt!("metadata is not utf-8"))
+}
+
+/// Append the u32 area length and the entries
+fn write_area(out: &mut Vec<u8>, entries: &[Extension<'_>]) -> Result<()> {
+    let area_len: usize = entries
+        .iter()
+        .map(|e| ENTRY_HEADER_LEN + e.value.len())
+        .sum();
+    let Ok(area_len) = u32::try_from(area_len) else {
+        return Err(invalid!("extension area too large"));
+    };
+    out.extend_from_slice(&area_len.to_le_bytes()); // 16..20
+    for entry in entries {
+        out.extend_from_slice(&entry.kind.to_le_bytes());
+        out.extend_from_slice(&(entry.value.len() as u32).to_le_bytes());
+        out.extend_from_slice(entry.value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatingCodec, IntegerCodec};
+
+    #[test]
+    fn checksums_catch_damaged_payloads() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..50_000).map(|i| i * 31 % 1_009).collect();
+        let options = CompressOptions {
+            checksum: Checksum::XxHash64,
+            metadata: [("unit".to_string(), "ms".to_string())].into(),
+            ..CompressOptions::new()
+        };
+        let blob = c.compress_i64_with(&data, &options)?;
+        assert_eq!(c.decompress_i64(&blob)?, data);
+        crate::validate(&blob)?;
+        assert_eq!(metadata(&blob)?["unit"], "ms");
+
+        // one flipped bit anywhere in the payload is caught before decoding
+        let mut damaged = blob.clone();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 0x10;
+        assert!(matches!(
+            c.decompress_i64(&damaged),
+            Err(CydecError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            crate::validate(&damaged),
+            Err(CydecError::ChecksumMismatch { .. })
+        ));
+
+        // rewriting the payload refreshes the checksum
+        let mut appended = blob.clone();
+        c.append_i64(&mut appended, &[5, 6])?;
+        assert_eq!(c.decompress_i64(&appended)?[data.len()..], [5, 6]);
+        let floats = FloatingCodec::default();
+        let plain = floats.compress_f64(&[1.5, 2.25], None)?;
+        for checksum in [Checksum::Crc32c, Checksum::XxHash64, Checksum::Xxh3] {
+            let prices = with_checksum(plain.clone(), checksum)?;
+            assert_eq!(extensions(&prices)?[0].value[0], checksum.id());
+            assert_eq!(floats.decompress_f64(&prices, None)?, [1.5, 2.25]);
+            let mut damaged = prices.clone();
+            damaged[PREAMBLE_LEN + 10] ^= 1;
+            assert!(crate::validate(&damaged).is_err());
+        }
+        // opting out drops the entry, and the blob goes back to v1
+        let stripped = with_checksum(
+            with_checksum(plain.clone(), Checksum::Xxh3)?,
+            Checksum::None,
+        )?;
+        assert_eq!(stripped, plain);
+        assert_eq!(
+            with_checksum(Vec::new(), Checksum::Crc32c)?,
+            Vec::<u8>::new()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v2_blobs_wrap_v1_blobs() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..100_000).map(|i| i * i % 977).collect();
+        let v1 = c.compress_i64(&data)?;
+        let v2 = with_extensions(
+            v1.clone(),
+            &[
+                Extension {
+                    kind: 7,
+                    value: b"first",
+                },
+                Extension {
+                    kind: 9,
+                    value: b"",
+                },
+            ],
+        )?;
+        assert_eq!(v2[5], VERSION_2);
+        assert_eq!(v2[6..16], v1[6..16]);
+        assert_eq!(strip(&v2)?, v1.as_slice());
+        assert_eq!(c.decompress_i64(&v2)?, data);
+        assert_eq!(c.get_i64(&v2, 77_777)?, data[77_777]);
+        assert_eq!(c.aggregate_i64(&v2)?, c.aggregate_i64(&v1)?);
+
+        // replacing an entry keeps the others
+        let v2 = with_extensions(
+            v2,
+            &[Extension {
+                kind: 7,
+                value: b"second",
+            }],
+        )?;
+        let entries = extensions(&v2)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind, 9);
+        assert_eq!(entries[1].value, b"second");
+
+        // appends and streaming readers see through the extension area
+        let mut appended = v2.clone();
+        c.append_i64(&mut appended, &[1, 2, 3])?;
+        assert_eq!(extensions(&appended)?, entries);
+        assert_eq!(c.decompress_i64(&appended)?[data.len()..], [1, 2, 3]);
+        let streamed: Vec<i64> = crate::CydecReader::new(v2.as_slice()).collect::<Result<_>>()?;
+        assert_eq!(streamed, data);
+
+        let f = FloatingCodec::default();
+        let floats = with_extensions(
+            f.compress_f64(&[1.5, 2.25], None)?,
+            &[Extension {
+                kind: 1,
+                value: b"x",
+            }],
+        )?;
+        assert_eq!(f.decompress_f64(&floats, None)?, [1.5, 2.25]);
+        Ok(())
+    }
+
+    #[test]
+    fn headers_parse_and_write_back() -> Result<()> {
+        let i = IntegerCodec::default();
+        let f = FloatingCodec::default();
+        let blobs = [
+            i.compress_i64(&[1, 2, 3])?,
+            i.compress_i64(&(0..70_000).collect::<Vec<_>>())?,
+            i.compress_bytes(b"raw bytes share a tag with f64")?,
+            f.compress_f64(&[1.5, -2.25], Some(100.0))?,
+            f.compress_f32(&[0.5, 4.0], Some(8.0))?,
+            crate::SeriesCodec::default().compress(&[1, 2], &[3.0, 4.0])?,
+            with_extensions(
+                f.compress_f64(&[7.0], None)?,
+                &[Extension {
+                    kind: 3,
+                    value: b"units=ms",
+                }],
+            )?,
+        ];
+        let scales = [
+            None,
+            None,
+            None,
+            Some(100.0),
+            Some(8.0),
+            Some(FloatingCodec::DEFAULT_F64_SCALE),
+            Some(FloatingCodec::DEFAULT_F64_SCALE),
+        ];
+        for (blob, scale) in blobs.iter().zip(scales) {
+            let header = Header::parse(blob)?;
+            assert_eq!(header.scale, scale);
+            assert_eq!(header.len, read_len(blob));
+            let mut written = Vec::new();
+            header.write_to(&mut written)?;
+            assert_eq!(written.len(), header.encoded_len());
+            assert_eq!(written, blob[..header.encoded_len()]);
+        }
+
+        let header = Header::parse(&blobs[6])?;
+        assert_eq!(header.version, VERSION_2);
+        assert_eq!(header.extensions, [(3, b"units=ms".to_vec())]);
+        // only the header bytes are needed
+        assert_eq!(Header::parse(&blobs[6][..header.encoded_len()])?, header);
+
+        // a header plus a payload is a blob
+        let mut blob = Vec::new();
+        Header {
+            len: 2,
+            scale: Some(10.0),
+            ..Header::parse(&blobs[3])?
+        }
+        .write_to(&mut blob)?;
+        blob.extend_from_slice(&blobs[3][24..]);
+        assert_eq!(f.decompress_f64(&blob, None)?, [15.0, -22.5]);
+
+        let v1 = Header {
+            version: VERSION_1,
+            ..header
+        };
+        assert!(v1.write_to(&mut Vec::new()).is_err());
+        assert!(matches!(
+            Header::parse(b"CYDEC"),
+            Err(CydecError::Truncated { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn damaged_v2_headers_are_rejected() -> Result<()> {
+        let c = IntegerCodec::default();
+        let ext = [Extension {
+            kind: 1,
+            value: b"abc",
+        }];
+        let blob = with_extensions(c.compress_i64(&[1, 2, 3])?, &ext)?;
+        assert!(c.decompress_i64(&blob[..18]).is_err());
+
+        let mut mismatched = blob.clone();
+        mismatched[8] ^= 1;
+        assert!(matches!(
+            c.decompress_i64(&mismatched),
+            Err(CydecError::Corrupt(_))
+        ));
+        let mut overrun = blob.clone();
+        overrun[22] = 200;
+        assert!(extensions(&overrun).is_err());
+
+        let mut newer = blob;
+        newer[5] = 3;
+        assert!(matches!(
+            c.decompress_i64(&newer),
+            Err(CydecError::UnsupportedVersion { found: 3, max: 2 })
+        ));
+        assert!(with_extensions(Vec::new(), &ext)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use crate::blocks::{write_block, write_blocked_header};
+use crate::{Backend, IntegerCodec};
+
+/// Compresses i64 points one at a time as they arrive.
+///
+/// Points are buffered until a block fills up, which is then compressed
+/// and kept; only the open block stays uncompressed. [`Self::finalize`]
+/// produces an ordinary blocked blob for [`IntegerCodec::decompress_i64`],
+/// and [`Self::flush_segment`] cuts one off mid-stream.
+///
+/// ```
+/// use cydec::{IncrementalCompressor, IntegerCodec};
+///
+/// let mut c = IncrementalCompressor::new();
+/// for t in 0..10_000i64 {
+///     c.push(1_700_000_000 + t * 60);
+/// }
+/// let blob = c.finalize();
+/// assert_eq!(IntegerCodec::default().decompress_i64(&blob).unwrap().len(), 10_000);
+/// ```
+#[derive(Clone, Debug)]
+pub struct IncrementalCompressor {
+    backend: Backend,
+    block_len: usize,
+    /// Sealed blocks, each with its block header
+    body: Vec<u8>,
+    sealed: usize,
+    pending: Vec<i64>,
+}
+
+impl Default for IncrementalCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IncrementalCompressor {
+    pub const DEFAULT_BLOCK_LEN: usize = IntegerCodec::APPEND_BLOCK_LEN;
+
+    pub fn new() -> Self {
+        Self::with_backend(Backend::Lz4)
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        Self {
+            backend,
+            block_len: Self::DEFAULT_BLOCK_LEN,
+            body: Vec::new(),
+            sealed: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Points per block; larger blocks compress better, smaller ones keep
+    /// less uncompressed
+    pub fn with_block_len(mut self, block_len: usize) -> Self {
+        assert!(
+            block_len > 0 && block_len <= u32::MAX as usize,
+            "block length must fit in a block header"
+        );
+        self.block_len = block_len;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.sealed + self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes held by sealed blocks, excluding the open block
+    pub fn compressed_bytes(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn push(&mut self, value: i64) {
+        self.pending.push(value);
+        if self.pending.len() == self.block_len {
+            self.seal();
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[i64]) {
+        for &v in values {
+            self.push(v);
+        }
+    }
+
+    /// Seal the open block and return the finished blob
+    pub fn finalize(mut self) -> Vec<u8> {
+        self.flush_segment()
+    }
+
+    /// Emit everything pushed since the last flush as a self-contained
+    /// blob and start a new segment.
+    ///
+    /// Replicas and readers get the points so far without waiting for the
+    /// series to end, while pushing carries on. Segments decode on their
+    /// own, and [`IntegerCodec::concat_i64`] joins them back into one blob.
+    pub fn flush_segment(&mut self) -> Vec<u8> {
+        if !self.pending.is_empty() {
+            self.seal();
+        }
+        if self.sealed == 0 {
+            return Vec::new();
+        }
+        let mut blob = Vec::with_capacity(self.body.len() + 16);
+        write_blocked_header(&mut blob, self.backend, self.sealed, true);
+        blob.append(&mut self.body);
+        self.sealed = 0;
+        blob
+    }
+
+    fn seal(&mut self) {
+        write_block(&mut self.body, &self.pending, self.backend, true);
+        self.sealed += self.pending.len();
+        self.pending.clear();
+    }
+}
+
+impl Extend<i64> for IncrementalCompressor {
+    fn extend<I: IntoIterator<Item = i64>>(&mut self, iter: I) {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+
+    #[test]
+    fn pushed_points_roundtrip() {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..25_000).map(|i| i * 250 + (i * 7) % 31).collect();
+        let mut inc = IncrementalCompressor::new().with_block_len(1_000);
+        inc.extend(data[..10_500].iter().copied());
+        assert_eq!(inc.len(), 10_500);
+        assert!(inc.compressed_bytes() > 0);
+        inc.extend_from_slice(&data[10_500..]);
+        let blob = inc.finalize();
+        assert_eq!(data, c.decompress_i64(&blob).unwrap());
+        assert!(IncrementalCompressor::new().finalize().is_empty());
+    }
+
+    #[test]
+    fn flushed_segments_join_back_up() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut inc = IncrementalCompressor::new().with_block_len(500);
+        let mut segments = Vec::new();
+        for hour in 0..5i64 {
+            inc.extend((0..1_234).map(|m| hour * 10_000 + m));
+            let segment = inc.flush_segment();
+            assert_eq!(c.decompress_i64(&segment)?.len(), 1_234);
+            segments.push(segment);
+        }
+        assert!(inc.is_empty());
+        assert!(inc.flush_segment().is_empty());
+
+        inc.push(99);
+        segments.push(inc.finalize());
+        let refs: Vec<&[u8]> = segments.iter().map(Vec::as_slice).collect();
+        let joined = c.decompress_i64(&c.concat_i64(&refs)?)?;
+        assert_eq!(joined.len(), 5 * 1_234 + 1);
+        assert_eq!(joined[1_234], 10_000);
+        assert_eq!(joined.last(), Some(&99));
+        Ok(())
+    }
+}
@@ -0,0 +1,370 @@
+use crate::blocks::{BLOCKED_FLAG, BlockStats, STREAMED_LEN, read_blocks};
+use crate::error::{Result, bail};
+use crate::floating_codec::{BLOCKED_F32, BLOCKED_F64};
+use crate::format::{TYPE_MASK, TypeTag};
+use crate::header::{self, Header};
+use crate::series_codec::{BLOCKED_SERIES, open_series};
+use crate::{Backend, CandleCodec, CydecError, FloatingCodec, Frame, IntegerCodec, SeriesCodec};
+use std::collections::BTreeMap;
+
+/// What [`inspect`] can tell about a blob from its headers alone
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlobInfo {
+    /// Element type, named as in [`crate::CydecError::TypeMismatch`]
+    pub element_type: &'static str,
+    /// Number of elements
+    pub len: usize,
+    /// Blob format version
+    pub version: u8,
+    /// Id of the final compression stage: 1 = LZ4, 2 = zstd, 3 = LZ4 frame
+    pub codec: u8,
+    /// Stored scale factor, for layouts that have a single one
+    pub scale: Option<f64>,
+    /// Whether the payload is split into independent blocks
+    pub blocked: bool,
+    pub compressed_size: usize,
+    /// Size of the decoded elements in memory
+    pub uncompressed_size: usize,
+    /// User metadata from [`crate::CompressOptions::metadata`]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl BlobInfo {
+    /// Uncompressed over compressed size, `None` for an empty blob
+    pub fn ratio(&self) -> Option<f64> {
+        (self.compressed_size > 0)
+            .then(|| self.uncompressed_size as f64 / self.compressed_size as f64)
+    }
+}
+
+/// Bytes one decoded element takes in memory
+fn element_width(tag: TypeTag) -> usize {
+    match tag {
+        TypeTag::I8 => 1,
+        TypeTag::F16 | TypeTag::Bf16 | TypeTag::I16 | TypeTag::U16 | TypeTag::PcmI16 => 2,
+        TypeTag::I32
+        | TypeTag::U32
+        | TypeTag::F32
+        | TypeTag::BlockedF32
+        | TypeTag::Ipv4
+        | TypeTag::Categorical
+        | TypeTag::PcmI32 => 4,
+        TypeTag::I128
+        | TypeTag::U128
+        | TypeTag::Uuid
+        | TypeTag::Ipv6
+        | TypeTag::Complex64
+        | TypeTag::Track
+        | TypeTag::Series
+        | TypeTag::Map => 16,
+        TypeTag::Candles => 48,
+        _ => 8,
+    }
+}
+
+/// Describe a blob of any type without decompressing it.
+///
+/// Everything comes from the header, except the element count of streamed
+/// blobs, which is added up from their block headers.
+pub fn inspect(blob: &[u8]) -> Result<BlobInfo> {
+    if blob.is_empty() {
+        return Ok(BlobInfo {
+            element_type: "empty",
+            len: 0,
+            version: header::VERSION_1,
+            codec: 1,
+            scale: None,
+            blocked: false,
+            compressed_size: 0,
+            uncompressed_size: 0,
+            metadata: BTreeMap::new(),
+        });
+    }
+    let header = Header::parse(blob)?;
+    let inner = header::strip(blob)?;
+    let blocked = header.type_tag & BLOCKED_FLAG != 0;
+    let tag = match blocked {
+        // the low bits hold the element type, the rest are layout flags
+        true => header.type_tag & TYPE_MASK,
+        false => header.type_tag,
+    };
+    let len = match header.len {
+        STREAMED_LEN if blocked => read_blocks(inner, STREAMED_LEN)?.0,
+        n => n as usize,
+    };
+
+    let (element_type, width) = match TypeTag::try_from(tag) {
+        Ok(TypeTag::F64OrBytes) if header.scale.is_none() => ("raw bytes", 1),
+        Ok(tag) => (tag.name(), element_width(tag)),
+        Err(_) => ("unknown", 0),
+    };
+    let uncompressed_size = match tag {
+        // timestamps plus one value per column
+        28 if inner.len() >= 28 => {
+            let columns = u32::from_le_bytes(inner[24..28].try_into().unwrap()) as usize;
+            len.saturating_mul(width)
+                .saturating_mul(columns.saturating_add(1))
+        }
+        // every array's values, counted at 16..24
+        30 if inner.len() >= 24 => header::usize_at(inner, 16)?.saturating_mul(width),
+        // what each column decodes to
+        35 => {
+            let frame = Frame::from_bytes(inner)?;
+            let mut total = 0;
+            for column in frame.columns() {
+                let column = inspect(frame.column_blob(&column.name).unwrap())?;
+                total = column.uncompressed_size.saturating_add(total);
+            }
+            total
+        }
+        _ => len.saturating_mul(width),
+    };
+    Ok(BlobInfo {
+        element_type,
+        len,
+        version: header.version,
+        codec: header.codec,
+        scale: header.scale,
+        blocked,
+        compressed_size: blob.len(),
+        uncompressed_size,
+        metadata: header::metadata(blob)?,
+    })
+}
+
+/// Check that an unpacked varint stream holds exactly `n` values
+fn check_varints(packed: &[u8], n: usize) -> Result<()> {
+    let count = packed.iter().filter(|&&b| b & 0x80 == 0).count();
+    if count != n || packed.last().is_some_and(|&b| b & 0x80 != 0) {
+        bail!("payload holds {count} values, header says {n}");
+    }
+    Ok(())
+}
+
+/// Check a blob end to end without materializing its values.
+///
+/// Verifies the header, that every payload is in bounds and decompresses,
+/// and that it holds exactly the element count the header declares.
+/// Blocked blobs are checked one block at a time, and stored block
+/// statistics are compared with the values they describe. Layouts whose
+/// payload is a single varint stream only have that stream unpacked; the
+/// remaining composite layouts are run through their decompressor and the
+/// output dropped.
+pub fn validate(blob: &[u8]) -> Result<()> {
+    if blob.is_empty() {
+        return Ok(());
+    }
+    let header = Header::parse(blob)?;
+    let inner = header::strip(blob)?;
+    let n = header.len as usize;
+    let i = IntegerCodec::default();
+    let f = FloatingCodec::default();
+    match header.type_tag {
+        4 if header.scale.is_none() => drop(i.decompress_bytes(inner)?),
+        t if t & BLOCKED_FLAG != 0 && t & TYPE_MASK == 4 => drop(i.decompress_bytes(inner)?),
+        // i64 in any layout; the flags were checked while opening
+        t if t & BLOCKED_FLAG != 0 && t != BLOCKED_SERIES || t == 0 => {
+            let (_, backend, blocks) = IntegerCodec::open_i64_blob(inner)?;
+            for block in &blocks {
+                check_varints(&backend.unpack(block.payload)?, block.count)?;
+                let Some(stored) = block.stats.filter(|s| s.count > 0) else {
+                    continue;
+                };
+                let values =
+                    IntegerCodec::decode_i64_payload_with(block.payload, block.count, backend)?;
+                if BlockStats::of(&values) != stored {
+                    bail!("block at {} does not match its statistics", block.offset);
+                }
+            }
+        }
+        4 | BLOCKED_F64 => {
+            let (_, backend, _, blocks) = FloatingCodec::open_scaled_f64(inner)?;
+            for block in &blocks {
+                check_varints(&backend.unpack(block.payload)?, block.count)?;
+            }
+        }
+        5 | BLOCKED_F32 => {
+            let (_, _, blocks) = FloatingCodec::open_f32(inner)?;
+            for block in &blocks {
+                check_varints(&Backend::Lz4.unpack(block.payload)?, block.count)?;
+            }
+        }
+        27 | BLOCKED_SERIES => {
+            for chunk in open_series(inner)?.2 {
+                check_varints(&Backend::Lz4.unpack(chunk.ts_payload)?, chunk.count)?;
+                check_varints(&Backend::Lz4.unpack(chunk.value_payload)?, chunk.count)?;
+            }
+        }
+        // a single varint stream after the fixed fields
+        1 | 2 | 3 | 8 | 9 | 11 | 12 | 13 | 14 | 15 | 37 | 38 => {
+            let backend = Backend::from_id(header.codec)?;
+            if header.codec != 1 && header.type_tag != 1 {
+                return Err(CydecError::UnsupportedBackend {
+                    found: header.codec,
+                });
+            }
+            check_varints(&backend.unpack(&inner[16..])?, n)?;
+        }
+        // time unit, then the i64 payload
+        16 | 17 => {
+            if inner.len() < 17 {
+                return Err(CydecError::Truncated {
+                    needed: 17,
+                    got: inner.len(),
+                });
+            }
+            check_varints(&Backend::Lz4.unpack(&inner[17..])?, n)?;
+        }
+        6 | 7 | 10 => drop(f.decompress_f64(inner, None)?),
+        18 => drop(i.decompress_uuid_bytes(inner)?),
+        19 => drop(i.decompress_ipv4(inner)?),
+        20 => drop(i.decompress_ipv6(inner)?),
+        21 => drop(i.decompress_categorical::<u32>(inner)?),
+        22 => drop(f.decompress_c64(inner, None)?),
+        23 => drop(f.decompress_track(inner)?),
+        24 => drop(i.decompress_matrix_i64(inner)?),
+        25 => drop(f.decompress_matrix_f64(inner, None)?),
+        26 => drop(CandleCodec::default().decompress(inner)?),
+        28 => drop(SeriesCodec::default().decompress_multi(inner)?),
+        29 => drop(f.decompress_map(inner, None)?),
+        30 => drop(i.decompress_jagged_i64(inner)?),
+        31 => drop(i.decompress_pcm_i16(inner)?),
+        32 => drop(i.decompress_pcm_i32(inner)?),
+        36 => drop(i.decompress_i64(inner)?),
+        35 => {
+            let frame = Frame::from_bytes(inner)?;
+            for column in frame.columns() {
+                let blob = frame.column_blob(&column.name).unwrap();
+                validate(blob)?;
+                let rows = inspect(blob)?.len;
+                if rows != n {
+                    bail!(
+                        "column {:?} holds {rows} rows, header says {n}",
+                        column.name
+                    );
+                }
+            }
+        }
+        found => {
+            return Err(CydecError::TypeMismatch {
+                expected: "a cydec type",
+                found,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CydecWriter, FloatingCodec, IntegerCodec, SeriesCodec};
+
+    #[test]
+    fn inspect_reads_headers_only() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..100_000).collect();
+        let blob = c.compress_i64(&data)?;
+        let info = inspect(&blob)?;
+        assert_eq!((info.element_type, info.len), ("i64", data.len()));
+        assert!(info.blocked);
+        assert_eq!(info.compressed_size, blob.len());
+        assert_eq!(info.uncompressed_size, data.len() * 8);
+        assert!(info.ratio().unwrap() > 10.0);
+
+        let f = FloatingCodec::default();
+        let info = inspect(&f.compress_f64(&[1.5, 2.5, 3.5], Some(10.0))?)?;
+        assert_eq!((info.element_type, info.scale), ("f64", Some(10.0)));
+        let info = inspect(&c.compress_bytes(b"hello")?)?;
+        assert_eq!(
+            (info.element_type, info.uncompressed_size),
+            ("raw bytes", 5)
+        );
+        let info = inspect(&SeriesCodec::default().compress(&[1, 2], &[3.0, 4.0])?)?;
+        assert_eq!((info.element_type, info.uncompressed_size), ("series", 32));
+        let info = inspect(&c.compress_jagged_i64(&[vec![1, 2, 3], vec![4]])?)?;
+        assert_eq!((info.len, info.uncompressed_size), (2, 32));
+
+        // streamed blobs only know their length once the blocks are counted
+        let mut writer = CydecWriter::new(Vec::new());
+        writer.write_values(&data[..5_000])?;
+        let info = inspect(&writer.finish()?)?;
+        assert_eq!(info.len, 5_000);
+
+        let empty = inspect(&[])?;
+        assert!(empty.metadata.is_empty());
+        assert_eq!((empty.len, empty.ratio()), (0, None));
+        assert!(inspect(b"not a blob at all").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_is_read_back_by_inspect() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut options = crate::CompressOptions::default();
+        options.metadata.insert("series".into(), "cpu.user".into());
+        options.metadata.insert("units".into(), "%".into());
+        let data: Vec<i64> = (0..1_000).collect();
+        let blob = c.compress_i64_with(&data, &options)?;
+        let info = inspect(&blob)?;
+        assert_eq!(info.metadata, options.metadata);
+        assert_eq!(info.version, header::VERSION_2);
+        assert_eq!(c.decompress_i64(&blob)?, data);
+        validate(&blob)?;
+
+        let f = FloatingCodec::default();
+        let floats = f.compress_f64_with(&[1.5, 2.5], &options)?;
+        assert_eq!(inspect(&floats)?.metadata["units"], "%");
+        assert_eq!(f.decompress_f64(&floats, None)?, [1.5, 2.5]);
+
+        // any blob can be tagged after the fact; the map is replaced whole
+        let mut map = BTreeMap::new();
+        map.insert("source".to_string(), "host-7".to_string());
+        let retagged = crate::with_metadata(blob, &map)?;
+        assert_eq!(inspect(&retagged)?.metadata, map);
+        let plain = c.compress_u32(&[1, 2, 3])?;
+        assert_eq!(inspect(&crate::with_metadata(plain, &map)?)?.metadata, map);
+
+        let too_long = BTreeMap::from([("k".to_string(), "v".repeat(70_000))]);
+        assert!(crate::with_metadata(c.compress_i64(&data)?, &too_long).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_catches_damage() -> Result<()> {
+        let c = IntegerCodec::default();
+        let f = FloatingCodec::default();
+        let data: Vec<i64> = (0..150_000).map(|i| i * 3 % 1_001).collect();
+        let floats: Vec<f64> = data.iter().map(|&x| x as f64 * 0.5).collect();
+        let blobs = [
+            c.compress_i64(&data)?,
+            c.compress_i64(&data[..100])?,
+            c.compress_u32(&[1, 2, 3])?,
+            c.compress_bytes(b"bytes")?,
+            f.compress_f64(&floats, None)?,
+            f.compress_f32(&[1.0, 2.0], None)?,
+            f.compress_f64_groomed(&floats[..1_000], 20)?,
+            SeriesCodec::default().compress(&data, &floats)?,
+            c.compress_jagged_i64(&[vec![1], vec![2, 3]])?,
+        ];
+        for blob in &blobs {
+            validate(blob)?;
+        }
+        validate(&[])?;
+
+        // the declared count must match the payload
+        let mut short = blobs[1].clone();
+        short[8..16].copy_from_slice(&99u64.to_le_bytes());
+        assert!(validate(&short).is_err());
+        // a flipped stats byte is caught even though the values decode
+        let mut stats = blobs[0].clone();
+        stats[16 + 8] ^= 1;
+        assert!(c.decompress_i64(&stats).is_ok());
+        assert!(validate(&stats).is_err());
+        // a cut-off block
+        let cut = &blobs[4][..blobs[4].len() - 10];
+        assert!(validate(cut).is_err());
+        assert!(validate(b"CYDEC\x01\x01\xfe........").is_err());
+        Ok(())
+    }
+}
@@ -1,35 +1,330 @@
-use anyhow::{Result, anyhow, bail};
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::parallel::*;
 use integer_encoding::{VarIntReader, VarIntWriter};
-use rayon::prelude::*;
-use std::io::Cursor;
-
-#[derive(Clone, Copy, Debug)]
-pub enum Codec {
+use lz4_flex::frame::{FrameDecoder, FrameEncoder, FrameInfo};
+use std::io::{Cursor, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::blocks::{
+    BLOCKED_FLAG, Block, FILTER_FLAG, STATS_FLAG, decode_blocked_bytes, encode_blocked_i64,
+    read_blocks,
+};
+use crate::bloom::with_filter;
+use crate::column_encoding::{DOD_I64, VARINT_I64, VARINT_U64};
+use crate::header;
+use crate::options::verify_exact;
+use crate::{CompressOptions, CydecError, Strided};
+
+/// Block compressor applied after delta/zigzag/varint encoding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
     Lz4,
-} // add Zstd later if you want
+    /// LZ4 in the standard frame format instead of a raw block with a
+    /// size prefix, so a payload cut out of a blob decompresses with stock
+    /// `lz4` tools and bindings. A few bytes larger per payload.
+    Lz4Frame,
+    /// Denser but slower than LZ4; needs the `zstd` feature
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Backend {
+    /// Codec byte stored at offset 6 of the header
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Backend::Lz4 => 1,
+            #[cfg(feature = "zstd")]
+            Backend::Zstd => 2,
+            Backend::Lz4Frame => 3,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Result<Self> {
+        Ok(match id {
+            1 => Backend::Lz4,
+            #[cfg(feature = "zstd")]
+            2 => Backend::Zstd,
+            3 => Backend::Lz4Frame,
+            found => return Err(CydecError::UnsupportedBackend { found }),
+        })
+    }
+
+    /// Compress `raw` with its uncompressed size prepended, or recorded in
+    /// the frame header for [`Backend::Lz4Frame`]; `level` only applies to
+    /// zstd and is clamped to the supported range
+    #[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+    pub(crate) fn pack(self, raw: &[u8], level: i32) -> Vec<u8> {
+        match self {
+            Backend::Lz4 => lz4_flex::block::compress_prepend_size(raw),
+            Backend::Lz4Frame => {
+                let info = FrameInfo::new().content_size(Some(raw.len() as u64));
+                let mut encoder =
+                    FrameEncoder::with_frame_info(info, Vec::with_capacity(raw.len()));
+                encoder
+                    .write_all(raw)
+                    .expect("lz4 frames compress in-memory buffers");
+                encoder
+                    .finish()
+                    .expect("lz4 frames compress in-memory buffers")
+            }
+            #[cfg(feature = "zstd")]
+            Backend::Zstd => {
+                let range = zstd::compression_level_range();
+                let level = level.clamp(*range.start(), *range.end());
+                let mut out = (raw.len() as u32).to_le_bytes().to_vec();
+                out.extend(
+                    zstd::bulk::compress(raw, level).expect("zstd compresses in-memory buffers"),
+                );
+                out
+            }
+        }
+    }
+
+    /// Bytes taken by the packed payload at the start of `bytes`, found
+    /// from the compressed stream itself so whatever follows is ignored
+    pub(crate) fn payload_len(self, bytes: &[u8]) -> Result<usize> {
+        let byte = |pos: usize| {
+            bytes.get(pos).copied().ok_or(CydecError::Truncated {
+                needed: pos + 1,
+                got: bytes.len(),
+            })
+        };
+        let u32_at = |pos: usize| -> Result<usize> {
+            let mut x = 0;
+            for i in (0..4).rev() {
+                x = x << 8 | byte(pos + i)? as usize;
+            }
+            Ok(x)
+        };
+        let end = match self {
+            // walk the sequences until the prepended size is produced; the
+            // last one holds only literals
+            Backend::Lz4 => {
+                let size = u32_at(0)?;
+                let (mut pos, mut out) = (4, 0);
+                loop {
+                    let token = byte(pos)?;
+                    pos += 1;
+                    let mut literals = (token >> 4) as usize;
+                    if literals == 15 {
+                        loop {
+                            let b = byte(pos)?;
+                            pos += 1;
+                            literals += b as usize;
+                            if b != 255 {
+                                break;
+                            }
+                        }
+                    }
+                    pos += literals;
+                    out += literals;
+                    if out >= size {
+                        break pos;
+                    }
+                    // match offset
+                    pos += 2;
+                    let mut len = (token & 15) as usize;
+                    if len == 15 {
+                        loop {
+                            let b = byte(pos)?;
+                            pos += 1;
+                            len += b as usize;
+                            if b != 255 {
+                                break;
+                            }
+                        }
+                    }
+                    out += len + 4;
+                }
+            }
+            Backend::Lz4Frame => {
+                let flags = byte(4)?;
+                // magic, FLG, BD, optional content size and dictionary id,
+                // header checksum
+                let mut pos = 7;
+                if flags & 0x08 != 0 {
+                    pos += 8;
+                }
+                if flags & 0x01 != 0 {
+                    pos += 4;
+                }
+                loop {
+                    let block = u32_at(pos)?;
+                    pos += 4;
+                    if block == 0 {
+                        break;
+                    }
+                    pos += block & 0x7fff_ffff;
+                    if flags & 0x10 != 0 {
+                        pos += 4;
+                    }
+                }
+                if flags & 0x04 != 0 {
+                    pos += 4;
+                }
+                pos
+            }
+            #[cfg(feature = "zstd")]
+            Backend::Zstd => {
+                let frame = bytes.get(4..).unwrap_or_default();
+                4 + zstd::zstd_safe::find_frame_compressed_size(frame)
+                    .map_err(|_| corrupt!("zstd frame has no end"))?
+            }
+        };
+        if end > bytes.len() {
+            return Err(CydecError::Truncated {
+                needed: end,
+                got: bytes.len(),
+            });
+        }
+        Ok(end)
+    }
+
+    /// The u32 size prepended to `payload`, refused before anything is
+    /// allocated for it when the rest could not expand to that much
+    fn prepended_size(payload: &[u8], max_expansion: usize) -> Result<usize> {
+        let Some(prefix) = payload.get(..4) else {
+            return Err(CydecError::Truncated {
+                needed: 4,
+                got: payload.len(),
+            });
+        };
+        let size = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+        let limit = (payload.len() - 4).saturating_mul(max_expansion);
+        if size > limit {
+            bail!(
+                "payload of {} bytes claims to expand to {size}",
+                payload.len() - 4
+            );
+        }
+        Ok(size)
+    }
+
+    /// Content size from the descriptor of an LZ4 frame, if it has one;
+    /// the decoder checks it against the output
+    fn frame_content_size(payload: &[u8]) -> Option<usize> {
+        let flags = *payload.get(4)?;
+        let size = payload.get(6..14).filter(|_| flags & 0x08 != 0)?;
+        usize::try_from(u64::from_le_bytes(size.try_into().unwrap())).ok()
+    }
+
+    /// Decompress a payload written by [`Backend::pack`] into a buffer of
+    /// exactly its recorded size; output of any other length is an error
+    pub(crate) fn unpack(self, payload: &[u8]) -> Result<Vec<u8>> {
+        let (size, raw) = match self {
+            Backend::Lz4 => {
+                let size = Self::prepended_size(payload, LZ4_MAX_EXPANSION)?;
+                let raw = lz4_flex::block::decompress(&payload[4..], size)
+                    .map_err(|e| corrupt!("lz4 decompress failed: {e}"))?;
+                (size, raw)
+            }
+            Backend::Lz4Frame => {
+                let size = Self::frame_content_size(payload);
+                let limit = payload.len().saturating_mul(LZ4_MAX_EXPANSION);
+                let mut raw = Vec::with_capacity(size.unwrap_or(0).min(limit));
+                FrameDecoder::new(payload)
+                    .read_to_end(&mut raw)
+                    .map_err(|e| corrupt!("lz4 frame decompress failed: {e}"))?;
+                (size.unwrap_or(raw.len()), raw)
+            }
+            #[cfg(feature = "zstd")]
+            Backend::Zstd => {
+                let size = Self::prepended_size(payload, ZSTD_MAX_EXPANSION)?;
+                let raw = zstd::bulk::decompress(&payload[4..], size)
+                    .map_err(|e| corrupt!("zstd decompress failed: {e}"))?;
+                (size, raw)
+            }
+        };
+        if raw.len() != size {
+            bail!(
+                "payload decompressed to {} bytes, its header records {size}",
+                raw.len()
+            );
+        }
+        Ok(raw)
+    }
+}
+
+/// Largest ratio an LZ4 block reaches: a match grows by 255 bytes per
+/// length byte
+pub(crate) const LZ4_MAX_EXPANSION: usize = 255;
+
+/// Largest ratio a zstd frame reaches: a 128 KiB RLE block in 4 bytes
+#[cfg(feature = "zstd")]
+const ZSTD_MAX_EXPANSION: usize = 1 << 15;
+
+/// Values to reserve for `n` declared by a header before `input`
+/// compressed bytes are decoded: no more than those bytes could expand to,
+/// so a damaged count fails while decoding instead of allocating first
+pub(crate) fn reserve_len(n: usize, input: usize) -> usize {
+    n.min(input.saturating_mul(LZ4_MAX_EXPANSION))
+}
 
 #[derive(Clone, Debug)]
 pub struct IntegerCodec {
-    pub codec: Codec,
+    /// Backend for i64, u64 and f64 blobs; other types always use LZ4
+    pub codec: Backend,
 }
 
 impl Default for IntegerCodec {
     fn default() -> Self {
-        Self { codec: Codec::Lz4 }
+        Self::new()
+    }
+}
+
+impl IntegerCodec {
+    /// LZ4-backed codec; `const`, so it can live in a `static`
+    pub const fn new() -> Self {
+        Self::with_backend(Backend::Lz4)
+    }
+
+    pub const fn with_backend(codec: Backend) -> Self {
+        Self { codec }
     }
 }
 
 impl IntegerCodec {
     #[inline]
-    fn zigzag_i64(i: i64) -> u64 {
+    pub(crate) fn zigzag_i64(i: i64) -> u64 {
         ((i << 1) ^ (i >> 63)) as u64
     }
 
     #[inline]
-    fn unzigzag_i64(u: u64) -> i64 {
+    pub(crate) fn unzigzag_i64(u: u64) -> i64 {
         ((u >> 1) as i64) ^ (-((u & 1) as i64))
     }
 
+    #[inline]
+    fn zigzag_i8(i: i8) -> u8 {
+        ((i << 1) ^ (i >> 7)) as u8
+    }
+
+    #[inline]
+    fn unzigzag_i8(u: u8) -> i8 {
+        ((u >> 1) as i8) ^ (-((u & 1) as i8))
+    }
+
+    #[inline]
+    fn zigzag_i16(i: i16) -> u16 {
+        ((i << 1) ^ (i >> 15)) as u16
+    }
+
+    #[inline]
+    fn unzigzag_i16(u: u16) -> i16 {
+        ((u >> 1) as i16) ^ (-((u & 1) as i16))
+    }
+
+    #[inline]
+    fn zigzag_i128(i: i128) -> u128 {
+        ((i << 1) ^ (i >> 127)) as u128
+    }
+
+    #[inline]
+    fn unzigzag_i128(u: u128) -> i128 {
+        ((u >> 1) as i128) ^ (-((u & 1) as i128))
+    }
+
     #[inline]
     fn zigzag_i32(i: i32) -> u32 {
         ((i << 1) ^ (i >> 31)) as u32
@@ -63,28 +358,37 @@ impl IntegerCodec {
 
     // Add general decompression for any binary data
     pub fn decompress_bytes(&self, blob: &[u8]) -> Result<Vec<u8>> {
+        let blob = header::strip(blob)?;
         if blob.is_empty() {
             return Ok(Vec::new());
         }
         if blob.len() < 16 {
-            bail!("blob too small");
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
         }
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[7] == 4 | BLOCKED_FLAG {
+            return decode_blocked_bytes(blob);
         }
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
         }
         if blob[7] != 4 {
-            bail!("unsupported type, expected raw bytes");
+            return Err(CydecError::TypeMismatch {
+                expected: "raw bytes",
+                found: blob[7],
+            });
         }
-        let original_len = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
+        let original_len = header::usize_at(blob, 8)?;
 
-        let decompressed = lz4_flex::block::decompress_size_prepended(&blob[16..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
+        let decompressed = Backend::Lz4.unpack(&blob[16..])?;
 
         if decompressed.len() != original_len {
             bail!("decompressed length mismatch");
@@ -93,74 +397,451 @@ impl IntegerCodec {
         Ok(decompressed)
     }
 
-    pub fn compress_i64(&self, data: &Vec<i64>) -> Result<Vec<u8>> {
+    pub fn compress_i64(&self, data: &[i64]) -> Result<Vec<u8>> {
+        self.compress_i64_with(data, &self.options())
+    }
+
+    /// Compress i64 vector with per-call backend and level
+    pub fn compress_i64_with(&self, data: &[i64], options: &CompressOptions) -> Result<Vec<u8>> {
+        let blob = Self::encode_i64_with(data.into(), options)?;
+        if options.verify {
+            verify_exact(data, self.decompress_i64(&blob))?;
+        }
+        Ok(blob)
+    }
+
+    /// Compress i64 values read through a [`Strided`] view, such as one
+    /// column of a row-major matrix or an `ndarray::ArrayView1`, into the
+    /// bytes [`Self::compress_i64`] writes for the same values
+    pub fn compress_i64_strided<'a>(&self, data: impl Into<Strided<'a, i64>>) -> Result<Vec<u8>> {
+        Self::encode_i64_with(data.into(), &self.options())
+    }
+
+    fn encode_i64_with(data: Strided<'_, i64>, options: &CompressOptions) -> Result<Vec<u8>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
+        if data.len() > Self::BLOCK_LEN || options.filter_bits > 0 {
+            // blocks keep point and range lookups from decoding everything
+            let blob = encode_blocked_i64(data, Self::BLOCK_LEN, options.backend, options.level);
+            let blob = match options.filter_bits {
+                0 => blob,
+                bits => with_filter(blob, data.iter(), bits),
+            };
+            return header::with_options(blob, options);
+        }
 
         // delta + zigzag → varint
         let mut buf = Vec::with_capacity(data.len() * 2);
         // header: magic + version + len + type
         buf.extend_from_slice(b"CYDEC"); // 0..5
         buf.push(1); // 5: version
-        buf.push(1); // 6: codec LZ4
+        buf.push(options.backend.id()); // 6: codec
         buf.push(0); // 7: type (0 = i64)
         buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
 
+        buf.extend_from_slice(&Self::encode_i64_values(
+            data.iter(),
+            options.backend,
+            options.level,
+        ));
+        header::with_options(buf, options)
+    }
+
+    /// Options equivalent to this codec's own configuration
+    pub(crate) fn options(&self) -> CompressOptions {
+        CompressOptions {
+            backend: self.codec,
+            ..Default::default()
+        }
+    }
+
+    /// Delta + zigzag + varint + LZ4 payload shared by every i64-backed type
+    pub(crate) fn encode_i64_payload(data: &[i64]) -> Vec<u8> {
+        Self::encode_i64_payload_with(data, Backend::Lz4, 0)
+    }
+
+    pub(crate) fn encode_i64_payload_with(data: &[i64], backend: Backend, level: i32) -> Vec<u8> {
+        Self::encode_i64_values(data.iter().copied(), backend, level)
+    }
+
+    pub(crate) fn encode_i64_values(
+        data: impl ExactSizeIterator<Item = i64>,
+        backend: Backend,
+        level: i32,
+    ) -> Vec<u8> {
         // stream varints into a temp vec
         let mut tmp = Vec::with_capacity(data.len() * 2);
         let mut prev = 0i64;
-        for &x in data {
+        for x in data {
             let d = x.wrapping_sub(prev);
             prev = x;
             tmp.write_varint(Self::zigzag_i64(d)).unwrap();
         }
 
+        // compress varint bytes
+        backend.pack(&tmp, level)
+    }
+
+    /// Delta-of-delta + zigzag + varint + LZ4 payload for timestamp columns;
+    /// a regular sampling interval encodes to a run of zero bytes
+    pub(crate) fn encode_dod_payload(data: &[i64]) -> Vec<u8> {
+        let mut tmp = Vec::with_capacity(data.len() + 16);
+        let (mut prev, mut prev_delta) = (0i64, 0i64);
+        for &x in data {
+            let delta = x.wrapping_sub(prev);
+            tmp.write_varint(Self::zigzag_i64(delta.wrapping_sub(prev_delta)))
+                .unwrap();
+            (prev, prev_delta) = (x, delta);
+        }
+        lz4_flex::block::compress_prepend_size(&tmp)
+    }
+
+    /// Inverse of [`Self::encode_dod_payload`] for `n` values
+    pub(crate) fn decode_dod_payload(payload: &[u8], n: usize) -> Result<Vec<i64>> {
+        let packed = Backend::Lz4.unpack(payload)?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let (mut acc, mut delta) = (0i64, 0i64);
+        for _ in 0..n {
+            let v: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            delta = delta.wrapping_add(Self::unzigzag_i64(v));
+            acc = acc.wrapping_add(delta);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    /// Inverse of [`Self::encode_i64_payload`] for `n` values
+    pub(crate) fn decode_i64_payload(payload: &[u8], n: usize) -> Result<Vec<i64>> {
+        Self::decode_i64_payload_with(payload, n, Backend::Lz4)
+    }
+
+    pub(crate) fn decode_i64_payload_with(
+        payload: &[u8],
+        n: usize,
+        backend: Backend,
+    ) -> Result<Vec<i64>> {
+        let mut out = Vec::with_capacity(reserve_len(n, payload.len()));
+        Self::decode_i64_payload_each(payload, n, backend, |_, x| out.push(x))?;
+        Ok(out)
+    }
+
+    /// Decode `n` values, handing each one to `sink` with its index
+    pub(crate) fn decode_i64_payload_each(
+        payload: &[u8],
+        n: usize,
+        backend: Backend,
+        mut sink: impl FnMut(usize, i64),
+    ) -> Result<()> {
+        let packed = backend.unpack(payload)?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut acc = 0i64;
+        for i in 0..n {
+            let v: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let d = Self::unzigzag_i64(v);
+            acc = acc.wrapping_add(d);
+            sink(i, acc);
+        }
+        Ok(())
+    }
+
+    pub fn decompress_i64(&self, blob: &[u8]) -> Result<Vec<i64>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let inner = header::strip(blob)?;
+        if inner.len() >= 16 && matches!(inner[7], DOD_I64 | VARINT_I64) {
+            return Self::decode_column_i64(inner);
+        }
+        let (n, backend, blocks) = Self::open_i64_blob(blob)?;
+        let mut out = Vec::with_capacity(reserve_len(n, blob.len()));
+        Self::decode_blocks_each(&blocks, backend, |_, x| out.push(x))?;
+        Ok(out)
+    }
+
+    /// Decompress into `out`, reusing its allocation; previous contents are
+    /// discarded
+    pub fn decompress_i64_into(&self, blob: &[u8], out: &mut Vec<i64>) -> Result<()> {
+        out.clear();
+        if blob.is_empty() {
+            return Ok(());
+        }
+        let (n, backend, blocks) = Self::open_i64_blob(blob)?;
+        out.reserve(reserve_len(n, blob.len()));
+        Self::decode_blocks_each(&blocks, backend, |_, x| out.push(x))
+    }
+
+    /// Decompress into the front of `out` and return the number of values
+    /// written; fails without writing if `out` is too short
+    pub fn decompress_i64_into_slice(&self, blob: &[u8], out: &mut [i64]) -> Result<usize> {
+        if blob.is_empty() {
+            return Ok(0);
+        }
+        let (n, backend, blocks) = Self::open_i64_blob(blob)?;
+        if out.len() < n {
+            return Err(invalid!(
+                "output buffer holds {} values, blob has {n}",
+                out.len()
+            ));
+        }
+        Self::decode_blocks_each(&blocks, backend, |i, x| out[i] = x)?;
+        Ok(n)
+    }
+
+    /// Decode values lazily while iterating.
+    ///
+    /// Blobs are decoded one block at a time and only the current block's
+    /// varint stream is held in memory, so a consumer that scans once or
+    /// stops early never materializes the full `Vec<i64>`. Header errors and
+    /// blocks holding fewer values than declared are reported before the
+    /// first value is yielded.
+    pub fn decompress_i64_iter(&self, blob: &[u8]) -> Result<impl Iterator<Item = i64>> {
+        let (backend, blocks) = if blob.is_empty() {
+            (Backend::Lz4, Vec::new())
+        } else {
+            let (_, backend, blocks) = Self::open_i64_blob(blob)?;
+            (backend, blocks)
+        };
+        for block in &blocks {
+            // every varint ends on a byte without the continuation bit
+            let packed = backend.unpack(block.payload)?;
+            if packed.iter().filter(|&&b| b & 0x80 == 0).count() < block.count {
+                bail!("varint decode: unexpected end of input");
+            }
+        }
+        Ok(I64Iter {
+            backend,
+            blocks: blocks.into_iter(),
+            packed: Vec::new(),
+            pos: 0,
+            remaining: 0,
+            acc: 0,
+        })
+    }
+
+    /// Decode every block in order, handing each value to `sink` with its
+    /// index in the whole blob
+    fn decode_blocks_each(
+        blocks: &[Block],
+        backend: Backend,
+        mut sink: impl FnMut(usize, i64),
+    ) -> Result<()> {
+        let mut base = 0;
+        for block in blocks {
+            Self::decode_i64_payload_each(block.payload, block.count, backend, |i, x| {
+                sink(base + i, x)
+            })?;
+            base += block.count;
+        }
+        Ok(())
+    }
+
+    /// Validate an i64 header, returning the element count, backend and
+    /// blocks; a plain blob is a single block
+    pub(crate) fn open_i64_blob(blob: &[u8]) -> Result<(usize, Backend, Vec<Block<'_>>)> {
+        let blob = header::strip(blob)?;
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        let backend = Backend::from_id(blob[6])?;
+        let flags = blob[7] & (BLOCKED_FLAG | STATS_FLAG | FILTER_FLAG);
+        if blob[7] != flags || (flags != 0 && flags & BLOCKED_FLAG == 0) {
+            return Err(CydecError::TypeMismatch {
+                expected: "i64",
+                found: blob[7],
+            });
+        }
+        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+        let (n, blocks) = if blob[7] & BLOCKED_FLAG != 0 {
+            read_blocks(blob, n)?
+        } else {
+            let block = Block {
+                offset: 16,
+                count: n as usize,
+                stats: None,
+                payload: &blob[16..],
+            };
+            (n as usize, vec![block])
+        };
+        Ok((n, backend, blocks))
+    }
+
+    pub fn compress_u64(&self, data: &[u64]) -> Result<Vec<u8>> {
+        self.compress_u64_with(data, &self.options())
+    }
+
+    /// Compress u64 vector with per-call backend and level
+    pub fn compress_u64_with(&self, data: &[u64], options: &CompressOptions) -> Result<Vec<u8>> {
+        let blob = Self::encode_u64_with(data, options)?;
+        if options.verify {
+            verify_exact(data, self.decompress_u64(&blob))?;
+        }
+        Ok(blob)
+    }
+
+    fn encode_u64_with(data: &[u64], options: &CompressOptions) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + varint (no zigzag needed for unsigned)
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(options.backend.id()); // 6: codec
+        buf.push(1); // 7: type (1 = u64)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0u64;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            tmp.write_varint(d).unwrap();
+        }
+
+        // compress varint bytes
+        let comp = options.backend.pack(&tmp, options.level);
+        buf.extend_from_slice(&comp);
+        header::with_options(buf, options)
+    }
+
+    pub fn decompress_u64(&self, blob: &[u8]) -> Result<Vec<u64>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        let backend = Backend::from_id(blob[6])?;
+        if blob[7] == VARINT_U64 {
+            return Self::decode_column_u64(blob);
+        }
+        if blob[7] != 1 {
+            return Err(CydecError::TypeMismatch {
+                expected: "u64",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = backend.unpack(&blob[16..])?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u64;
+        for _ in 0..n {
+            let v: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc.wrapping_add(v);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    pub fn compress_i32(&self, data: &[i32]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + zigzag → varint (similar to i64 but with i32)
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(2); // 7: type (2 = i32)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0i32;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            tmp.write_varint(Self::zigzag_i32(d)).unwrap();
+        }
+
         // compress varint bytes
         let comp = lz4_flex::block::compress_prepend_size(&tmp);
         buf.extend_from_slice(&comp);
         Ok(buf)
     }
 
-    pub fn decompress_i64(&self, blob: &[u8]) -> Result<Vec<i64>> {
+    pub fn decompress_i32(&self, blob: &[u8]) -> Result<Vec<i32>> {
+        let blob = header::strip(blob)?;
         if blob.is_empty() {
             return Ok(Vec::new());
         }
         if blob.len() < 16 {
-            bail!("blob too small");
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
         }
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
         }
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
         }
-        if blob[7] != 0 {
-            bail!("unsupported type, expected i64");
+        if blob[7] != 2 {
+            return Err(CydecError::TypeMismatch {
+                expected: "i32",
+                found: blob[7],
+            });
         }
-        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
+        let n = header::usize_at(blob, 8)?;
 
-        let packed = lz4_flex::block::decompress_size_prepended(&blob[16..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
 
         let mut cur = Cursor::new(packed.as_slice());
-        let mut out = Vec::with_capacity(n);
-        let mut acc = 0i64;
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0i32;
         for _ in 0..n {
-            let v: u64 = cur
+            let v: u32 = cur
                 .read_varint()
-                .map_err(|e| anyhow!("varint decode: {e}"))?;
-            let d = Self::unzigzag_i64(v);
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let d = Self::unzigzag_i32(v);
             acc = acc.wrapping_add(d);
             out.push(acc);
         }
         Ok(out)
     }
 
-    pub fn compress_u64(&self, data: &Vec<u64>) -> Result<Vec<u8>> {
+    pub fn compress_u32(&self, data: &[u32]) -> Result<Vec<u8>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
@@ -171,190 +852,887 @@ impl IntegerCodec {
         buf.extend_from_slice(b"CYDEC"); // 0..5
         buf.push(1); // 5: version
         buf.push(1); // 6: codec LZ4
-        buf.push(1); // 7: type (1 = u64)
+        buf.push(3); // 7: type (3 = u32)
         buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
 
         // stream varints into a temp vec
         let mut tmp = Vec::with_capacity(data.len() * 2);
-        let mut prev = 0u64;
+        let mut prev = 0u32;
         for &x in data {
             let d = x.wrapping_sub(prev);
             prev = x;
-            tmp.write_varint(d).unwrap();
+            tmp.write_varint(d).unwrap();
+        }
+
+        // compress varint bytes
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_u32(&self, blob: &[u8]) -> Result<Vec<u32>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 3 {
+            return Err(CydecError::TypeMismatch {
+                expected: "u32",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u32;
+        for _ in 0..n {
+            let v: u32 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc.wrapping_add(v);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    pub fn compress_i16(&self, data: &[i16]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + zigzag → varint (16-bit lanes throughout)
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(11); // 7: type (11 = i16)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0i16;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            tmp.write_varint(Self::zigzag_i16(d)).unwrap();
+        }
+
+        // compress varint bytes
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_i16(&self, blob: &[u8]) -> Result<Vec<i16>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 11 {
+            return Err(CydecError::TypeMismatch {
+                expected: "i16",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0i16;
+        for _ in 0..n {
+            let v: u16 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let d = Self::unzigzag_i16(v);
+            acc = acc.wrapping_add(d);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    pub fn compress_u16(&self, data: &[u16]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + varint (no zigzag needed for unsigned)
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(12); // 7: type (12 = u16)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0u16;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            tmp.write_varint(d).unwrap();
+        }
+
+        // compress varint bytes
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_u16(&self, blob: &[u8]) -> Result<Vec<u16>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 12 {
+            return Err(CydecError::TypeMismatch {
+                expected: "u16",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u16;
+        for _ in 0..n {
+            let v: u16 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc.wrapping_add(v);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    pub fn compress_i8(&self, data: &[i8]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + zigzag → varint (8-bit lanes throughout)
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(13); // 7: type (13 = i8)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0i8;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            tmp.write_varint(Self::zigzag_i8(d)).unwrap();
+        }
+
+        // compress varint bytes
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_i8(&self, blob: &[u8]) -> Result<Vec<i8>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 13 {
+            return Err(CydecError::TypeMismatch {
+                expected: "i8",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0i8;
+        for _ in 0..n {
+            let v: u8 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let d = Self::unzigzag_i8(v);
+            acc = acc.wrapping_add(d);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    pub fn compress_i128(&self, data: &[i128]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + zigzag → varint
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(14); // 7: type (14 = i128)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0i128;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            Self::write_varint_u128(&mut tmp, Self::zigzag_i128(d));
+        }
+
+        // compress varint bytes
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_i128(&self, blob: &[u8]) -> Result<Vec<i128>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 14 {
+            return Err(CydecError::TypeMismatch {
+                expected: "i128",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = packed.as_slice();
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0i128;
+        for _ in 0..n {
+            let v = Self::read_varint_u128(&mut cur)?;
+            let d = Self::unzigzag_i128(v);
+            acc = acc.wrapping_add(d);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    pub fn compress_u128(&self, data: &[u128]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // delta + varint (no zigzag needed for unsigned)
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(15); // 7: type (15 = u128)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // stream varints into a temp vec
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0u128;
+        for &x in data {
+            let d = x.wrapping_sub(prev);
+            prev = x;
+            Self::write_varint_u128(&mut tmp, d);
+        }
+
+        // compress varint bytes
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_u128(&self, blob: &[u8]) -> Result<Vec<u128>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 15 {
+            return Err(CydecError::TypeMismatch {
+                expected: "u128",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = packed.as_slice();
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u128;
+        for _ in 0..n {
+            let v = Self::read_varint_u128(&mut cur)?;
+            acc = acc.wrapping_add(v);
+            out.push(acc);
+        }
+        Ok(out)
+    }
+
+    /// Compress 16-byte UUIDs, exploiting timestamp-prefixed (v7 / sequential)
+    /// layouts.
+    ///
+    /// The high 8 bytes (timestamp, version and counter bits) are read as a
+    /// big-endian integer and delta encoded; the low 8 bytes are mostly random
+    /// and are stored verbatim after the deltas.
+    pub fn compress_uuid_bytes(&self, data: &[[u8; 16]]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::with_capacity(data.len() * 10);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(18); // 7: type (18 = uuid)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        // high halves as varint deltas, then the low halves as raw bytes
+        let mut tmp = Vec::with_capacity(data.len() * 12);
+        let mut prev = 0u64;
+        for id in data {
+            let hi = u64::from_be_bytes(id[0..8].try_into().unwrap());
+            tmp.write_varint(Self::zigzag_i64(hi.wrapping_sub(prev) as i64))
+                .unwrap();
+            prev = hi;
+        }
+        for id in data {
+            tmp.extend_from_slice(&id[8..16]);
+        }
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_uuid_bytes(&self, blob: &[u8]) -> Result<Vec<[u8; 16]>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        if blob.len() < 16 {
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != 18 {
+            return Err(CydecError::TypeMismatch {
+                expected: "uuid",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u64;
+        for _ in 0..n {
+            let v: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc.wrapping_add(Self::unzigzag_i64(v) as u64);
+            let mut id = [0u8; 16];
+            id[0..8].copy_from_slice(&acc.to_be_bytes());
+            out.push(id);
+        }
+
+        let lows = &packed[cur.position() as usize..];
+        if lows.len() != n * 8 {
+            bail!("uuid payload length mismatch");
+        }
+        for (id, low) in out.iter_mut().zip(lows.chunks_exact(8)) {
+            id[8..16].copy_from_slice(low);
+        }
+        Ok(out)
+    }
+
+    /// Compress a UUID column, see [`Self::compress_uuid_bytes`]
+    #[cfg(feature = "uuid")]
+    pub fn compress_uuids(&self, data: &[uuid::Uuid]) -> Result<Vec<u8>> {
+        let bytes: Vec<[u8; 16]> = data.iter().map(|id| *id.as_bytes()).collect();
+        self.compress_uuid_bytes(&bytes)
+    }
+
+    #[cfg(feature = "uuid")]
+    pub fn decompress_uuids(&self, blob: &[u8]) -> Result<Vec<uuid::Uuid>> {
+        let bytes = self.decompress_uuid_bytes(blob)?;
+        Ok(bytes.into_iter().map(uuid::Uuid::from_bytes).collect())
+    }
+
+    /// Compress IPv4 addresses as u32 values.
+    ///
+    /// Deltas are zigzag encoded, so sorted columns shrink to a byte or two
+    /// per address while unsorted netflow-style columns don't pay for
+    /// wrapped-around negative deltas.
+    pub fn compress_ipv4(&self, data: &[Ipv4Addr]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::with_capacity(data.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(19); // 7: type (19 = ipv4)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        let mut tmp = Vec::with_capacity(data.len() * 2);
+        let mut prev = 0u32;
+        for ip in data {
+            let x = ip.to_bits();
+            tmp.write_varint(Self::zigzag_i32(x.wrapping_sub(prev) as i32))
+                .unwrap();
+            prev = x;
+        }
+
+        let comp = lz4_flex::block::compress_prepend_size(&tmp);
+        buf.extend_from_slice(&comp);
+        Ok(buf)
+    }
+
+    pub fn decompress_ipv4(&self, blob: &[u8]) -> Result<Vec<Ipv4Addr>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (n, packed) = Self::open_ip_blob(blob, 19, "ipv4")?;
+
+        let mut cur = Cursor::new(packed.as_slice());
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u32;
+        for _ in 0..n {
+            let v: u32 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc.wrapping_add(Self::unzigzag_i32(v) as u32);
+            out.push(Ipv4Addr::from_bits(acc));
+        }
+        Ok(out)
+    }
+
+    /// Compress IPv6 addresses as u128 values, see [`Self::compress_ipv4`]
+    pub fn compress_ipv6(&self, data: &[Ipv6Addr]) -> Result<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::with_capacity(data.len() * 4);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(20); // 7: type (20 = ipv6)
+        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
+
+        let mut tmp = Vec::with_capacity(data.len() * 4);
+        let mut prev = 0u128;
+        for ip in data {
+            let x = ip.to_bits();
+            Self::write_varint_u128(&mut tmp, Self::zigzag_i128(x.wrapping_sub(prev) as i128));
+            prev = x;
         }
 
-        // compress varint bytes
         let comp = lz4_flex::block::compress_prepend_size(&tmp);
         buf.extend_from_slice(&comp);
         Ok(buf)
     }
 
-    pub fn decompress_u64(&self, blob: &[u8]) -> Result<Vec<u64>> {
+    pub fn decompress_ipv6(&self, blob: &[u8]) -> Result<Vec<Ipv6Addr>> {
         if blob.is_empty() {
             return Ok(Vec::new());
         }
+        let (n, packed) = Self::open_ip_blob(blob, 20, "ipv6")?;
+
+        let mut cur = packed.as_slice();
+        let mut out = Vec::with_capacity(n.min(packed.len()));
+        let mut acc = 0u128;
+        for _ in 0..n {
+            let v = Self::read_varint_u128(&mut cur)?;
+            acc = acc.wrapping_add(Self::unzigzag_i128(v) as u128);
+            out.push(Ipv6Addr::from_bits(acc));
+        }
+        Ok(out)
+    }
+
+    fn open_ip_blob(
+        blob: &[u8],
+        type_tag: u8,
+        type_name: &'static str,
+    ) -> Result<(usize, Vec<u8>)> {
+        let blob = header::strip(blob)?;
         if blob.len() < 16 {
-            bail!("blob too small");
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
         }
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
         }
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
         }
-        if blob[7] != 1 {
-            bail!("unsupported type, expected u64");
+        if blob[7] != type_tag {
+            return Err(CydecError::TypeMismatch {
+                expected: type_name,
+                found: blob[7],
+            });
         }
-        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
+        let n = header::usize_at(blob, 8)?;
 
-        let packed = lz4_flex::block::decompress_size_prepended(&blob[16..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
-
-        let mut cur = Cursor::new(packed.as_slice());
-        let mut out = Vec::with_capacity(n);
-        let mut acc = 0u64;
-        for _ in 0..n {
-            let v: u64 = cur
-                .read_varint()
-                .map_err(|e| anyhow!("varint decode: {e}"))?;
-            acc = acc.wrapping_add(v);
-            out.push(acc);
-        }
-        Ok(out)
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
+        Ok((n, packed))
     }
 
-    pub fn compress_i32(&self, data: &Vec<i32>) -> Result<Vec<u8>> {
+    /// Compress a categorical column (enum-like values with few distinct
+    /// variants).
+    ///
+    /// The distinct values are stored once as a sorted dictionary and the
+    /// column itself as run-length encoded dictionary codes, so long regime or
+    /// state runs cost a couple of bytes each.
+    pub fn compress_categorical<T: Copy + Into<u32>>(&self, data: &[T]) -> Result<Vec<u8>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
 
-        // delta + zigzag → varint (similar to i64 but with i32)
-        let mut buf = Vec::with_capacity(data.len() * 2);
+        let values: Vec<u32> = data.iter().map(|&v| v.into()).collect();
+        let mut dict = values.clone();
+        dict.sort_unstable();
+        dict.dedup();
+
+        let mut buf = Vec::with_capacity(16 + dict.len() * 2);
         // header: magic + version + len + type
         buf.extend_from_slice(b"CYDEC"); // 0..5
         buf.push(1); // 5: version
         buf.push(1); // 6: codec LZ4
-        buf.push(2); // 7: type (2 = i32)
+        buf.push(21); // 7: type (21 = categorical)
         buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
 
-        // stream varints into a temp vec
-        let mut tmp = Vec::with_capacity(data.len() * 2);
-        let mut prev = 0i32;
-        for &x in data {
-            let d = x.wrapping_sub(prev);
+        // dictionary: count + sorted values as deltas
+        let mut tmp = Vec::new();
+        tmp.write_varint(dict.len() as u64).unwrap();
+        let mut prev = 0u32;
+        for &x in &dict {
+            tmp.write_varint(x - prev).unwrap();
             prev = x;
-            tmp.write_varint(Self::zigzag_i32(d)).unwrap();
         }
 
-        // compress varint bytes
+        // runs of (code, length)
+        let mut i = 0;
+        while i < values.len() {
+            let run = values[i..].iter().take_while(|&&v| v == values[i]).count();
+            let code = dict.binary_search(&values[i]).unwrap();
+            tmp.write_varint(code as u64).unwrap();
+            tmp.write_varint(run as u64).unwrap();
+            i += run;
+        }
+
         let comp = lz4_flex::block::compress_prepend_size(&tmp);
         buf.extend_from_slice(&comp);
         Ok(buf)
     }
 
-    pub fn decompress_i32(&self, blob: &[u8]) -> Result<Vec<i32>> {
+    pub fn decompress_categorical<T: TryFrom<u32>>(&self, blob: &[u8]) -> Result<Vec<T>> {
+        let blob = header::strip(blob)?;
         if blob.is_empty() {
             return Ok(Vec::new());
         }
         if blob.len() < 16 {
-            bail!("blob too small");
+            return Err(CydecError::Truncated {
+                needed: 16,
+                got: blob.len(),
+            });
         }
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
         }
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
         }
-        if blob[7] != 2 {
-            bail!("unsupported type, expected i32");
+        if blob[7] != 21 {
+            return Err(CydecError::TypeMismatch {
+                expected: "categorical",
+                found: blob[7],
+            });
         }
-        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
+        let n = header::usize_at(blob, 8)?;
 
-        let packed = lz4_flex::block::decompress_size_prepended(&blob[16..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
+        let packed = Backend::Lz4.unpack(&blob[16..])?;
 
         let mut cur = Cursor::new(packed.as_slice());
-        let mut out = Vec::with_capacity(n);
-        let mut acc = 0i32;
-        for _ in 0..n {
-            let v: u32 = cur
+        let dict_len: u64 = cur
+            .read_varint()
+            .map_err(|e| corrupt!("varint decode: {e}"))?;
+        let mut dict = Vec::new();
+        let mut acc = 0u32;
+        for _ in 0..dict_len {
+            let d: u32 = cur
                 .read_varint()
-                .map_err(|e| anyhow!("varint decode: {e}"))?;
-            let d = Self::unzigzag_i32(v);
-            acc = acc.wrapping_add(d);
-            out.push(acc);
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            acc = acc
+                .checked_add(d)
+                .ok_or_else(|| corrupt!("corrupt categorical dictionary"))?;
+            dict.push(acc);
+        }
+
+        let mut out = Vec::with_capacity(reserve_len(n, blob.len()));
+        while out.len() < n {
+            let code: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let run: u64 = cur
+                .read_varint()
+                .map_err(|e| corrupt!("varint decode: {e}"))?;
+            let &raw = dict
+                .get(code as usize)
+                .ok_or_else(|| corrupt!("category code {code} outside dictionary"))?;
+            if run == 0 || run as usize > n - out.len() {
+                bail!("corrupt categorical run length");
+            }
+            for _ in 0..run {
+                let v = T::try_from(raw).map_err(|_| corrupt!("invalid category value {raw}"))?;
+                out.push(v);
+            }
         }
         Ok(out)
     }
 
-    pub fn compress_u32(&self, data: &Vec<u32>) -> Result<Vec<u8>> {
-        if data.is_empty() {
+    // integer-encoding stops at 64 bits, so 128-bit lanes use their own LEB128
+    fn write_varint_u128(out: &mut Vec<u8>, mut v: u128) {
+        while v >= 0x80 {
+            out.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn read_varint_u128(cur: &mut &[u8]) -> Result<u128> {
+        let mut v = 0u128;
+        for shift in (0..128).step_by(7) {
+            let (&byte, rest) = cur
+                .split_first()
+                .ok_or_else(|| corrupt!("varint decode: unexpected end of input"))?;
+            *cur = rest;
+            v |= ((byte & 0x7f) as u128) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        bail!("varint decode: value exceeds 128 bits")
+    }
+
+    /// Compress a jagged list of i64 arrays into a single blob.
+    ///
+    /// Stores a column of end offsets followed by all values concatenated, so
+    /// deltas also run across array boundaries.
+    pub fn compress_jagged_i64(&self, arrays: &[Vec<i64>]) -> Result<Vec<u8>> {
+        if arrays.is_empty() {
             return Ok(Vec::new());
         }
 
-        // delta + varint (no zigzag needed for unsigned)
-        let mut buf = Vec::with_capacity(data.len() * 2);
+        let offsets: Vec<i64> = arrays
+            .iter()
+            .scan(0i64, |end, a| {
+                *end += a.len() as i64;
+                Some(*end)
+            })
+            .collect();
+        let values = arrays.concat();
+        let offsets_payload = Self::encode_i64_payload(&offsets);
+
+        let mut buf = Vec::with_capacity(offsets_payload.len() + values.len() + 28);
         // header: magic + version + len + type
         buf.extend_from_slice(b"CYDEC"); // 0..5
         buf.push(1); // 5: version
         buf.push(1); // 6: codec LZ4
-        buf.push(3); // 7: type (3 = u32)
-        buf.extend_from_slice(&(data.len() as u64).to_le_bytes()); // 8..16
-
-        // stream varints into a temp vec
-        let mut tmp = Vec::with_capacity(data.len() * 2);
-        let mut prev = 0u32;
-        for &x in data {
-            let d = x.wrapping_sub(prev);
-            prev = x;
-            tmp.write_varint(d).unwrap();
-        }
+        buf.push(30); // 7: type (30 = jagged i64)
+        buf.extend_from_slice(&(arrays.len() as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&(values.len() as u64).to_le_bytes()); // 16..24
+        buf.extend_from_slice(&(offsets_payload.len() as u32).to_le_bytes()); // 24..28
 
-        // compress varint bytes
-        let comp = lz4_flex::block::compress_prepend_size(&tmp);
-        buf.extend_from_slice(&comp);
+        buf.extend_from_slice(&offsets_payload);
+        buf.extend_from_slice(&Self::encode_i64_payload(&values));
         Ok(buf)
     }
 
-    pub fn decompress_u32(&self, blob: &[u8]) -> Result<Vec<u32>> {
+    pub fn decompress_jagged_i64(&self, blob: &[u8]) -> Result<Vec<Vec<i64>>> {
+        let blob = header::strip(blob)?;
         if blob.is_empty() {
             return Ok(Vec::new());
         }
-        if blob.len() < 16 {
-            bail!("blob too small");
+        if blob.len() < 28 {
+            return Err(CydecError::Truncated {
+                needed: 28,
+                got: blob.len(),
+            });
         }
         if &blob[0..5] != b"CYDEC" {
-            bail!("bad magic");
+            return Err(CydecError::BadMagic);
         }
         if blob[5] != 1 {
-            bail!("bad version");
+            return Err(CydecError::unsupported_version(blob[5]));
         }
         if blob[6] != 1 {
-            bail!("unsupported codec");
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
         }
-        if blob[7] != 3 {
-            bail!("unsupported type, expected u32");
+        if blob[7] != 30 {
+            return Err(CydecError::TypeMismatch {
+                expected: "jagged i64",
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let total = header::usize_at(blob, 16)?;
+        let offsets_len = u32::from_le_bytes(blob[24..28].try_into().unwrap()) as usize;
+        if blob.len() - 28 < offsets_len {
+            return Err(CydecError::Truncated {
+                needed: offsets_len,
+                got: blob.len() - 28,
+            });
         }
-        let n = u64::from_le_bytes(blob[8..16].try_into().unwrap()) as usize;
-
-        let packed = lz4_flex::block::decompress_size_prepended(&blob[16..])
-            .map_err(|e| anyhow!("lz4 decompress failed: {e}"))?;
 
-        let mut cur = Cursor::new(packed.as_slice());
-        let mut out = Vec::with_capacity(n);
-        let mut acc = 0u32;
-        for _ in 0..n {
-            let v: u32 = cur
-                .read_varint()
-                .map_err(|e| anyhow!("varint decode: {e}"))?;
-            acc = acc.wrapping_add(v);
-            out.push(acc);
+        let (offsets_payload, values_payload) = blob[28..].split_at(offsets_len);
+        let offsets = Self::decode_i64_payload(offsets_payload, n)?;
+        let values = if total == 0 {
+            Vec::new()
+        } else {
+            Self::decode_i64_payload(values_payload, total)?
+        };
+
+        let mut out = Vec::with_capacity(n.min(offsets.len()));
+        let mut start = 0usize;
+        for end in offsets {
+            let end = usize::try_from(end).map_err(|_| corrupt!("corrupt jagged offsets"))?;
+            if end < start || end > total {
+                bail!("corrupt jagged offsets");
+            }
+            out.push(values[start..end].to_vec());
+            start = end;
+        }
+        if start != total {
+            bail!("corrupt jagged offsets");
         }
         Ok(out)
     }
@@ -363,16 +1741,127 @@ impl IntegerCodec {
         arrays.par_iter().map(|a| self.compress_i64(a)).collect()
     }
 
-    pub fn decompress_many_i64(&self, blobs: &[Vec<u8>]) -> Result<Vec<Vec<i64>>> {
-        blobs.par_iter().map(|b| self.decompress_i64(b)).collect()
+    pub fn decompress_many_i64(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<i64>>> {
+        par_decode(blobs, |b| self.decompress_i64(b))
+    }
+
+    /// Decompress every blob independently, keeping the good results when
+    /// some blobs are corrupt; errors name the failing blob's index
+    pub fn decompress_many_i64_partial(
+        &self,
+        blobs: &[impl AsRef<[u8]> + Sync],
+    ) -> Vec<Result<Vec<i64>>> {
+        blobs
+            .par_iter()
+            .enumerate()
+            .map(|(i, b)| self.decompress_i64(b.as_ref()).map_err(|e| at_index(i, e)))
+            .collect()
     }
 
     pub fn compress_many_u64(&self, arrays: &[Vec<u64>]) -> Result<Vec<Vec<u8>>> {
         arrays.par_iter().map(|a| self.compress_u64(a)).collect()
     }
 
-    pub fn decompress_many_u64(&self, blobs: &[Vec<u8>]) -> Result<Vec<Vec<u64>>> {
-        blobs.par_iter().map(|b| self.decompress_u64(b)).collect()
+    pub fn decompress_many_u64(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<u64>>> {
+        par_decode(blobs, |b| self.decompress_u64(b))
+    }
+
+    pub fn compress_many_bytes(&self, arrays: &[Vec<u8>]) -> Result<Vec<Vec<u8>>> {
+        arrays.par_iter().map(|a| self.compress_bytes(a)).collect()
+    }
+
+    pub fn decompress_many_bytes(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<u8>>> {
+        par_decode(blobs, |b| self.decompress_bytes(b))
+    }
+
+    pub fn compress_many_i32(&self, arrays: &[Vec<i32>]) -> Result<Vec<Vec<u8>>> {
+        arrays.par_iter().map(|a| self.compress_i32(a)).collect()
+    }
+
+    pub fn decompress_many_i32(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<i32>>> {
+        par_decode(blobs, |b| self.decompress_i32(b))
+    }
+
+    pub fn compress_many_u32(&self, arrays: &[Vec<u32>]) -> Result<Vec<Vec<u8>>> {
+        arrays.par_iter().map(|a| self.compress_u32(a)).collect()
+    }
+
+    pub fn decompress_many_u32(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<u32>>> {
+        par_decode(blobs, |b| self.decompress_u32(b))
+    }
+
+    pub fn compress_many_i16(&self, arrays: &[Vec<i16>]) -> Result<Vec<Vec<u8>>> {
+        arrays.par_iter().map(|a| self.compress_i16(a)).collect()
+    }
+
+    pub fn decompress_many_i16(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<i16>>> {
+        par_decode(blobs, |b| self.decompress_i16(b))
+    }
+
+    pub fn compress_many_u16(&self, arrays: &[Vec<u16>]) -> Result<Vec<Vec<u8>>> {
+        arrays.par_iter().map(|a| self.compress_u16(a)).collect()
+    }
+
+    pub fn decompress_many_u16(&self, blobs: &[impl AsRef<[u8]> + Sync]) -> Result<Vec<Vec<u16>>> {
+        par_decode(blobs, |b| self.decompress_u16(b))
+    }
+}
+
+/// Prefix a batch error with the index of the blob that caused it
+pub(crate) fn at_index(index: usize, error: CydecError) -> CydecError {
+    CydecError::Batch {
+        index,
+        error: Box::new(error),
+    }
+}
+
+/// Decode a batch in parallel, failing with the index of the first bad blob
+fn par_decode<R: Send>(
+    blobs: &[impl AsRef<[u8]> + Sync],
+    decode: impl Fn(&[u8]) -> Result<R> + Sync,
+) -> Result<Vec<R>> {
+    blobs
+        .par_iter()
+        .enumerate()
+        .map(|(i, b)| decode(b.as_ref()).map_err(|e| at_index(i, e)))
+        .collect()
+}
+
+/// Lazy delta decoder behind [`IntegerCodec::decompress_i64_iter`]
+struct I64Iter<'a> {
+    backend: Backend,
+    blocks: std::vec::IntoIter<Block<'a>>,
+    packed: Vec<u8>,
+    pos: usize,
+    /// Values left in the current block
+    remaining: usize,
+    acc: i64,
+}
+
+impl Iterator for I64Iter<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        while self.remaining == 0 {
+            let block = self.blocks.next()?;
+            // blocks were unpacked and checked up front
+            self.packed = self.backend.unpack(block.payload).ok()?;
+            (self.pos, self.remaining, self.acc) = (0, block.count, 0);
+        }
+        // terminators were counted up front, so this stays in bounds
+        let (mut v, mut shift) = (0u64, 0u32);
+        loop {
+            let byte = self.packed[self.pos];
+            self.pos += 1;
+            v |= ((byte & 0x7f) as u64).checked_shl(shift).unwrap_or(0);
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        self.remaining -= 1;
+        self.acc = self.acc.wrapping_add(IntegerCodec::unzigzag_i64(v));
+        Some(self.acc)
     }
 }
 
@@ -431,6 +1920,199 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn roundtrip_i16() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v: Vec<i16> = (0..10_000)
+            .map(|i| ((i as f64 / 50.0).sin() * 2000.0) as i16)
+            .collect();
+        let blob = c.compress_i16(&v)?;
+        let back = c.decompress_i16(&blob)?;
+        assert_eq!(v, back);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_u16() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v: Vec<u16> = (0..10_000).map(|i| (i * 7) as u16).collect();
+        let blob = c.compress_u16(&v)?;
+        let back = c.decompress_u16(&blob)?;
+        assert_eq!(v, back);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_i8_regime_labels() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v: Vec<i8> = (0..10_000).map(|i| [-1, 0, 1][(i / 250) % 3]).collect();
+        let blob = c.compress_i8(&v)?;
+        assert!(blob.len() < 200);
+        let back = c.decompress_i8(&blob)?;
+        assert_eq!(v, back);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_i128() -> Result<()> {
+        let c = IntegerCodec::default();
+        // nanosecond timestamps shifted into the high half of compound keys
+        let v: Vec<i128> = (0..10_000)
+            .map(|i| ((1_700_000_000_000_000_000i128 + i * 1_000) << 64) - i)
+            .chain([i128::MIN, i128::MAX, 0])
+            .collect();
+        let blob = c.compress_i128(&v)?;
+        let back = c.decompress_i128(&blob)?;
+        assert_eq!(v, back);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_u128() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v: Vec<u128> = (0..10_000u128)
+            .map(|i| u128::MAX / 3 + i * 17)
+            .chain([0, u128::MAX])
+            .collect();
+        let blob = c.compress_u128(&v)?;
+        assert!(blob.len() < v.len() * 16 / 4);
+        let back = c.decompress_u128(&blob)?;
+        assert_eq!(v, back);
+        Ok(())
+    }
+
+    // v7 layout: 48-bit millisecond timestamp, version, counter, random tail
+    fn uuid_v7_like(n: usize) -> Vec<[u8; 16]> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..n as u64)
+            .map(|i| {
+                let ms = 1_700_000_000_000u64 + i / 4;
+                let hi = (ms << 16) | 0x7000 | (i % 4);
+                let mut id = [0u8; 16];
+                id[0..8].copy_from_slice(&hi.to_be_bytes());
+                id[8..16].copy_from_slice(&rng.r#gen::<u64>().to_le_bytes());
+                id[8] = (id[8] & 0x3f) | 0x80;
+                id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip_uuid_bytes() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v = uuid_v7_like(10_000);
+        let blob = c.compress_uuid_bytes(&v)?;
+        // the random tail dominates; the timestamp half shrinks to ~1 byte
+        assert!(blob.len() < v.len() * 10);
+        assert_eq!(v, c.decompress_uuid_bytes(&blob)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn roundtrip_uuids() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v: Vec<uuid::Uuid> = uuid_v7_like(1_000)
+            .into_iter()
+            .map(uuid::Uuid::from_bytes)
+            .collect();
+        assert!(v.iter().all(|id| id.get_version_num() == 7));
+        let blob = c.compress_uuids(&v)?;
+        assert_eq!(v, c.decompress_uuids(&blob)?);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_ip_addresses() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let mut v4: Vec<Ipv4Addr> = (0..5_000)
+            .map(|_| Ipv4Addr::new(10, rng.gen_range(0..4), rng.r#gen(), rng.r#gen()))
+            .collect();
+        let blob = c.compress_ipv4(&v4)?;
+        assert_eq!(v4, c.decompress_ipv4(&blob)?);
+        v4.sort();
+        let sorted = c.compress_ipv4(&v4)?;
+        assert!(sorted.len() < blob.len());
+        assert_eq!(v4, c.decompress_ipv4(&sorted)?);
+
+        let v6: Vec<Ipv6Addr> = (0..5_000u128)
+            .map(|i| Ipv6Addr::from_bits((0x2001_0db8u128 << 96) + i * 3))
+            .chain([Ipv6Addr::UNSPECIFIED, Ipv6Addr::from_bits(u128::MAX)])
+            .collect();
+        let blob = c.compress_ipv6(&v6)?;
+        assert!(blob.len() < v6.len() * 2);
+        assert_eq!(v6, c.decompress_ipv6(&blob)?);
+        assert!(c.decompress_ipv4(&blob).is_err());
+        Ok(())
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Regime {
+        Bear,
+        Flat,
+        Bull,
+    }
+
+    impl From<Regime> for u32 {
+        fn from(r: Regime) -> u32 {
+            match r {
+                Regime::Bear => 10,
+                Regime::Flat => 20,
+                Regime::Bull => 30,
+            }
+        }
+    }
+
+    impl TryFrom<u32> for Regime {
+        type Error = ();
+        fn try_from(v: u32) -> std::result::Result<Self, ()> {
+            match v {
+                10 => Ok(Regime::Bear),
+                20 => Ok(Regime::Flat),
+                30 => Ok(Regime::Bull),
+                _ => Err(()),
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_categorical_enum() -> Result<()> {
+        let c = IntegerCodec::default();
+        let v: Vec<Regime> = (0..100_000)
+            .map(|i| [Regime::Flat, Regime::Bull, Regime::Flat, Regime::Bear][(i / 700) % 4])
+            .collect();
+        let blob = c.compress_categorical(&v)?;
+        assert!(blob.len() < 1_000);
+        let back: Vec<Regime> = c.decompress_categorical(&blob)?;
+        assert_eq!(v, back);
+
+        // decoding into a type that rejects the stored values fails cleanly
+        let raw: Vec<u32> = c.decompress_categorical(&blob)?;
+        assert_eq!(raw[0], 20);
+        let narrow: Result<Vec<bool>> = c.decompress_categorical(&c.compress_categorical(&[7u8])?);
+        assert!(narrow.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_parallel_i16_u16() -> Result<()> {
+        let c = IntegerCodec::default();
+        let signed: Vec<Vec<i16>> = (0..16)
+            .map(|k| (0..4096).map(|i| (i as i16).wrapping_mul(k)).collect())
+            .collect();
+        let blobs = c.compress_many_i16(&signed)?;
+        assert_eq!(signed, c.decompress_many_i16(&blobs)?);
+
+        let unsigned: Vec<Vec<u16>> = (0..16)
+            .map(|k| (0..4096).map(|i| (i as u16).wrapping_add(k)).collect())
+            .collect();
+        let blobs = c.compress_many_u16(&unsigned)?;
+        assert_eq!(unsigned, c.decompress_many_u16(&blobs)?);
+        Ok(())
+    }
+
     #[test]
     fn roundtrip_parallel_i64() -> Result<()> {
         let c = IntegerCodec::default();
@@ -695,4 +2377,187 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn roundtrip_jagged_i64() -> Result<()> {
+        let c = IntegerCodec::default();
+        let arrays: Vec<Vec<i64>> = (0..500)
+            .map(|i| (0..i % 17).map(|j| i * 1_000 + j).collect())
+            .chain([vec![], vec![i64::MIN, i64::MAX]])
+            .collect();
+        let blob = c.compress_jagged_i64(&arrays)?;
+        assert_eq!(arrays, c.decompress_jagged_i64(&blob)?);
+
+        let separate: usize = c.compress_many_i64(&arrays)?.iter().map(Vec::len).sum();
+        assert!(blob.len() < separate);
+        Ok(())
+    }
+
+    #[test]
+    fn jagged_of_empty_arrays() -> Result<()> {
+        let c = IntegerCodec::default();
+        let arrays = vec![Vec::new(); 3];
+        assert_eq!(
+            arrays,
+            c.decompress_jagged_i64(&c.compress_jagged_i64(&arrays)?)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn per_call_options_override_the_codec() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..10_000).map(|i| i * i % 977).collect();
+        let options = CompressOptions::default();
+        assert_eq!(
+            c.compress_i64(&data)?,
+            c.compress_i64_with(&data, &options)?
+        );
+
+        #[cfg(feature = "zstd")]
+        {
+            let zstd = CompressOptions {
+                backend: Backend::Zstd,
+                level: 19,
+                ..Default::default()
+            };
+            let blob = c.compress_i64_with(&data, &zstd)?;
+            assert_eq!(blob[6], 2);
+            assert_eq!(data, c.decompress_i64(&blob)?);
+
+            let unsigned: Vec<u64> = data.iter().map(|&x| x as u64).collect();
+            let blob = c.compress_u64_with(&unsigned, &zstd)?;
+            assert_eq!(unsigned, c.decompress_u64(&blob)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn lz4_frame_payloads_open_with_stock_decoders() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..10_000).map(|i| i * 7 - 3_000).collect();
+        let frame = CompressOptions {
+            backend: Backend::Lz4Frame,
+            ..Default::default()
+        };
+        let blob = c.compress_i64_with(&data, &frame)?;
+        assert_eq!(blob[6], 3);
+        assert_eq!(blob[16..20], [0x04, 0x22, 0x4d, 0x18]);
+        let mut raw = Vec::new();
+        FrameDecoder::new(&blob[16..])
+            .read_to_end(&mut raw)
+            .unwrap();
+        assert!(!raw.is_empty());
+        assert_eq!(data, c.decompress_i64(&blob)?);
+
+        let unsigned: Vec<u64> = data.iter().map(|&x| x as u64).collect();
+        let blob = c.compress_u64_with(&unsigned, &frame)?;
+        assert_eq!(unsigned, c.decompress_u64(&blob)?);
+
+        let floats: Vec<f64> = (0..1_000).map(|i| i as f64 * 0.25).collect();
+        let blob = crate::FloatingCodec::default().compress_f64_with(&floats, &frame)?;
+        assert_eq!(blob[24..28], [0x04, 0x22, 0x4d, 0x18]);
+        assert_eq!(
+            floats,
+            crate::FloatingCodec::default().decompress_f64(&blob, None)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn payloads_decompress_to_their_recorded_size() -> Result<()> {
+        let raw: Vec<u8> = (0..5_000u32).map(|i| (i * i % 251) as u8).collect();
+        for backend in [Backend::Lz4, Backend::Lz4Frame] {
+            let payload = backend.pack(&raw, 0);
+            let out = backend.unpack(&payload)?;
+            assert_eq!(out, raw);
+            assert_eq!(out.capacity(), raw.len());
+        }
+        assert_eq!(
+            Backend::frame_content_size(&Backend::Lz4Frame.pack(&raw, 0)),
+            Some(raw.len())
+        );
+
+        // a size prefix one byte longer than the stream used to decode
+        // silently to the shorter output
+        let mut payload = Backend::Lz4.pack(&raw, 0);
+        payload[..4].copy_from_slice(&(raw.len() as u32 + 1).to_le_bytes());
+        let err = Backend::Lz4.unpack(&payload).unwrap_err().to_string();
+        assert!(err.contains("header records 5001"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_into_reuses_buffers() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..1_000).map(|i| i * 3 - 500).collect();
+        let blob = c.compress_i64(&data)?;
+
+        let mut out = vec![42; 7];
+        c.decompress_i64_into(&blob, &mut out)?;
+        assert_eq!(data, out);
+        let capacity = out.capacity();
+        c.decompress_i64_into(&blob[..0], &mut out)?;
+        assert!(out.is_empty());
+        c.decompress_i64_into(&blob, &mut out)?;
+        assert_eq!(
+            (data.as_slice(), capacity),
+            (out.as_slice(), out.capacity())
+        );
+
+        let mut slice = [0i64; 1_200];
+        assert_eq!(1_000, c.decompress_i64_into_slice(&blob, &mut slice)?);
+        assert_eq!(data, slice[..1_000]);
+        assert!(c.decompress_i64_into_slice(&blob, &mut [0i64; 10]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn lazy_iterator_matches_decompress() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..10_000).map(|i| (i * 7919) % 10_007 - 5_000).collect();
+        let blob = c.compress_i64(&data)?;
+        assert_eq!(data, c.decompress_i64_iter(&blob)?.collect::<Vec<_>>());
+
+        let first_big = c.decompress_i64_iter(&blob)?.find(|&x| x > 4_990);
+        assert_eq!(first_big, data.iter().copied().find(|&x| x > 4_990));
+        assert_eq!(c.decompress_i64_iter(&[])?.count(), 0);
+
+        let extremes = [i64::MIN, i64::MAX, 0, -1];
+        let blob = c.compress_i64(&extremes)?;
+        assert_eq!(
+            extremes.to_vec(),
+            c.decompress_i64_iter(&blob)?.collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lazy_iterator_rejects_short_streams() -> Result<()> {
+        let c = IntegerCodec::default();
+        let mut blob = c.compress_i64(&[1, 2, 3])?;
+        blob[8..16].copy_from_slice(&4u64.to_le_bytes());
+        assert!(c.decompress_i64_iter(&blob).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn batch_errors_name_the_bad_blob() -> Result<()> {
+        let c = IntegerCodec::default();
+        let arrays: Vec<Vec<i64>> = (0..5).map(|i| vec![i; 10]).collect();
+        let mut blobs = c.compress_many_i64(&arrays)?;
+        blobs[3][0] = b'X';
+
+        let err = c.decompress_many_i64(&blobs).unwrap_err();
+        assert_eq!(err.to_string(), "blob 3: bad magic");
+        assert!(matches!(err, CydecError::Batch { index: 3, .. }));
+
+        let partial = c.decompress_many_i64_partial(&blobs);
+        assert_eq!(partial.len(), 5);
+        assert!(partial[3].is_err());
+        for i in [0, 1, 2, 4] {
+            assert_eq!(partial[i].as_ref().unwrap(), &arrays[i]);
+        }
+        Ok(())
+    }
 }
@@ -10,7 +10,7 @@
 //! Achieves 2-4x compression ratios on typical time series data with:
 //! - Sub-millisecond compression/decompression for 100K elements
 //! - Zero-copy operations where possible
-//! - Parallel processing support via Rayon
+//! - Parallel processing support via Rayon (the default `parallel` feature)
 //!
 //! # Example
 //!
@@ -30,12 +30,160 @@
 //!
 //! # Supported Types
 //!
-//! - **Integers**: `i32`, `i64`, `u32`, `u64`
+//! - **Integers**: `i8`, `i16`, `i32`, `i64`, `i128`, `u16`, `u32`, `u64`, `u128`
 //! - **Floats**: `f32`, `f64` (with configurable precision)
+//! - **Audio**: `i16`/`i32` PCM with FLAC-style linear prediction
+//! - **Matrices**: row-major 2-D `i64`/`f64` arrays, or `ndarray::Array2` behind the
+//!   `ndarray` feature
+//! - **IP addresses**: `Ipv4Addr`, `Ipv6Addr`
+//! - **UUIDs**: `[u8; 16]`, or `Uuid` behind the `uuid` feature
+//! - **Time**: `DateTime<Utc>`, `TimeDelta` (behind the `chrono` feature)
+//! - **Half floats**: `f16`, `bf16` (lossless, behind the `half` feature)
+//! - **Arrow**: primitive arrays with their nulls (behind the `arrow` feature)
+//! - **Polars**: numeric and temporal series and data frames (behind the
+//!   `polars` feature)
 //! - **Bytes**: Raw byte arrays
+//!
+//! # Errors
+//!
+//! Every fallible call returns [`CydecError`], whose variants separate
+//! foreign, newer, mistyped and truncated blobs from corrupt payloads.
 
+mod aggregate;
+mod archive;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "tokio")]
+mod async_stream;
+mod audio_codec;
+mod blocks;
+mod bloom;
+#[cfg(feature = "blosc")]
+mod blosc_chunk;
+mod candle_codec;
+mod codec;
+mod column_encoding;
+mod compressed_vec;
+mod concat;
+mod downsample;
+mod element;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod floating_codec;
+pub mod format;
+mod frame;
+#[cfg(feature = "tokio-util")]
+mod framed;
+#[cfg(any(feature = "influxdb", feature = "prometheus"))]
+mod gorilla;
+#[cfg(feature = "hdf5")]
+pub mod hdf5_filter;
+mod header;
+mod incremental;
+mod inspect;
 mod integer_codec;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod matrix;
+#[cfg(feature = "numpy")]
+mod numpy_io;
+mod options;
+mod parallel;
+#[cfg(feature = "parquet")]
+mod parquet_io;
+#[cfg(feature = "sqlx")]
+mod pg_column;
+#[cfg(feature = "polars")]
+mod polars_io;
+#[cfg(any(feature = "blosc", feature = "hdf5", feature = "zarr"))]
+mod raw_chunk;
+#[cfg(feature = "roaring")]
+mod roaring_set;
+mod segment;
+#[cfg(feature = "serde")]
+mod serde_blob;
+mod series_codec;
+#[cfg(feature = "hmac")]
+mod signature;
+#[cfg(feature = "arrow")]
+mod sql_kernels;
+mod stream;
+mod strided;
+pub mod test_vectors;
+#[cfg(feature = "chrono")]
+mod time_codec;
+#[cfg(feature = "influxdb")]
+mod tsm_block;
+mod untrusted;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod wire;
+#[cfg(feature = "prometheus")]
+mod xor_chunk;
+#[cfg(feature = "zarr")]
+mod zarr_codec;
 
-pub use floating_codec::FloatingCodec;
-pub use integer_codec::IntegerCodec;
+pub use aggregate::Aggregate;
+pub use archive::{Archive, ArchiveEntry, ArchiveWriter};
+#[cfg(feature = "tokio")]
+pub use async_stream::{AsyncCydecReader, AsyncCydecWriter};
+pub use blocks::BlockStats;
+#[cfg(feature = "blosc")]
+pub use blosc_chunk::{BLOSC_CYDEC_CODEC, compress_blosc, decompress_blosc};
+pub use candle_codec::{Candle, CandleCodec};
+pub use codec::Codec;
+pub use column_encoding::ColumnEncoding;
+pub use compressed_vec::CompressedVec;
+pub use concat::{DecodedArray, decompress_all};
+#[cfg(feature = "bytes")]
+pub use element::compress_to_bytes;
+pub use element::{
+    CompressedBlob, CydecElement, compress, compress_typed, decompress, decompress_typed,
+};
+pub use error::{CydecError, Result};
+pub use floating_codec::{FloatingCodec, QuantizationReport};
+pub use frame::{
+    ColumnPresence, ColumnStats, Frame, FrameBuilder, FrameColumn, FrameReader, FromFrame,
+    StatValue,
+};
+#[cfg(feature = "tokio-util")]
+pub use framed::CydecFrameCodec;
+pub use header::{Checksum, Header, with_checksum, with_metadata};
+pub use incremental::IncrementalCompressor;
+pub use inspect::{BlobInfo, inspect, validate};
+pub use integer_codec::{Backend, IntegerCodec};
+#[cfg(feature = "mmap")]
+pub use mapped::MappedBlob;
+pub use matrix::Matrix;
+#[cfg(feature = "numpy")]
+pub use numpy_io::{compress_npy, decompress_to_npy, decompress_to_npz};
+pub use options::CompressOptions;
+#[cfg(feature = "parquet")]
+pub use parquet_io::{compress_parquet_column, decompress_to_parquet};
+#[cfg(feature = "sqlx")]
+pub use pg_column::CompressedColumn;
+#[cfg(feature = "roaring")]
+pub use roaring_set::{
+    compress_roaring, compress_roaring_portable, decompress_roaring, decompress_roaring_portable,
+};
+pub use segment::{SegmentReader, SegmentWriter};
+#[cfg(feature = "serde")]
+pub use serde_blob::{serde_f64, serde_i64};
+pub use series_codec::SeriesCodec;
+#[cfg(feature = "hmac")]
+pub use signature::{decompress_signed, verify_signature, with_signature};
+#[cfg(feature = "arrow")]
+pub use sql_kernels::{compress_rows, decompress_rows};
+pub use stream::{CydecReader, CydecWriter, StreamElement};
+pub use strided::Strided;
+#[cfg(feature = "chrono")]
+pub use time_codec::{TimeCodec, TimeUnit};
+#[cfg(feature = "influxdb")]
+pub use tsm_block::{TsmBlock, TsmValues};
+pub use untrusted::decompress_untrusted;
+pub use wire::{read_framed, write_framed, write_framed_checksummed};
+#[cfg(feature = "prometheus")]
+pub use xor_chunk::{decode_xor_chunk, encode_xor_chunk};
+#[cfg(feature = "zarr")]
+pub use zarr_codec::ZarrCodec;
@@ -0,0 +1,99 @@
+use crate::blocks::decode_i64_range;
+use crate::error::{Result, invalid};
+use crate::{CydecElement, IntegerCodec};
+use memmap2::Mmap;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+/// A blob file mapped into memory instead of read into a buffer.
+///
+/// The OS pages in only what decoding touches, so on a blocked i64 blob
+/// [`MappedBlob::decompress_block_i64`] and
+/// [`MappedBlob::decompress_i64_range`] cost one block each, whatever the
+/// file size.
+///
+/// The file must not be modified or truncated while it is mapped; doing so
+/// is undefined behavior, as with any memory map.
+#[derive(Debug)]
+pub struct MappedBlob {
+    map: Mmap,
+}
+
+impl MappedBlob {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: documented on the type, the caller keeps the file unchanged
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { map })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Decompress the whole file as element type `T`
+    pub fn decompress<T: CydecElement>(&self) -> Result<Vec<T>> {
+        T::decompress_blob(&self.map)
+    }
+
+    pub fn decompress_i64(&self) -> Result<Vec<i64>> {
+        IntegerCodec::default().decompress_i64(&self.map)
+    }
+
+    /// Number of blocks in an i64 blob; a plain blob is one block
+    pub fn block_count(&self) -> Result<usize> {
+        if self.map.is_empty() {
+            return Ok(0);
+        }
+        Ok(IntegerCodec::open_i64_blob(&self.map)?.2.len())
+    }
+
+    pub fn decompress_block_i64(&self, index: usize) -> Result<Vec<i64>> {
+        let (_, backend, blocks) = IntegerCodec::open_i64_blob(&self.map)?;
+        let Some(block) = blocks.get(index) else {
+            return Err(invalid!(
+                "block {index} out of bounds for {} blocks",
+                blocks.len()
+            ));
+        };
+        IntegerCodec::decode_i64_payload_with(block.payload, block.count, backend)
+    }
+
+    /// Values at `range`, decoding only the blocks that overlap it
+    pub fn decompress_i64_range(&self, range: Range<usize>) -> Result<Vec<i64>> {
+        decode_i64_range(&self.map, range)
+    }
+}
+
+impl AsRef<[u8]> for MappedBlob {
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped_file_decodes_blocks_on_demand() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..50_000).map(|i| i * 1_000 + i % 13).collect();
+        let path = std::env::temp_dir().join(format!("cydec-mapped-{}.bin", std::process::id()));
+        std::fs::write(&path, c.compress_i64_chunked(&data, 8_192)?)?;
+
+        let blob = MappedBlob::open(&path)?;
+        assert_eq!(blob.block_count()?, 7);
+        assert_eq!(blob.decompress_block_i64(2)?, data[16_384..24_576]);
+        assert_eq!(blob.decompress_i64_range(8_000..8_500)?, data[8_000..8_500]);
+        assert_eq!(blob.decompress_i64_range(49_990..50_000)?, data[49_990..]);
+        assert!(blob.decompress_i64_range(0..50_001).is_err());
+        assert!(blob.decompress_block_i64(7).is_err());
+        assert_eq!(blob.decompress::<i64>()?, data);
+
+        drop(blob);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
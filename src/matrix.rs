@@ -0,0 +1,257 @@
+use crate::error::{Result, invalid};
+use crate::header;
+use crate::{CydecError, FloatingCodec, IntegerCodec};
+
+/// Row-major 2-D array returned by the matrix codecs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix<T> {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<T>,
+}
+
+impl<T: Copy> Matrix<T> {
+    pub fn new(rows: usize, cols: usize, data: Vec<T>) -> Result<Self> {
+        check_shape(data.len(), rows, cols)?;
+        Ok(Self { rows, cols, data })
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<T> {
+        if row < self.rows && col < self.cols {
+            Some(self.data[row * self.cols + col])
+        } else {
+            None
+        }
+    }
+
+    /// Values of one column, top to bottom
+    pub fn column(&self, col: usize) -> impl Iterator<Item = T> + '_ {
+        self.data
+            .iter()
+            .skip(col)
+            .step_by(self.cols.max(1))
+            .copied()
+    }
+}
+
+fn check_shape(len: usize, rows: usize, cols: usize) -> Result<()> {
+    if rows.checked_mul(cols) != Some(len) {
+        return Err(invalid!(
+            "matrix shape {rows}x{cols} does not match {len} values"
+        ));
+    }
+    Ok(())
+}
+
+/// Row-major to column-major, so deltas run down each column
+fn to_columns<T: Copy, U>(data: &[T], cols: usize, f: impl Fn(T) -> U) -> Vec<U> {
+    (0..cols)
+        .flat_map(|c| data.iter().skip(c).step_by(cols).map(|&x| f(x)))
+        .collect()
+}
+
+fn to_rows<T: Copy>(columns: &[T], rows: usize, cols: usize) -> Vec<T> {
+    (0..rows * cols)
+        .map(|i| columns[(i % cols) * rows + i / cols])
+        .collect()
+}
+
+fn write_header(buf: &mut Vec<u8>, type_tag: u8, rows: usize, cols: usize) {
+    // header: magic + version + len + type
+    buf.extend_from_slice(b"CYDEC"); // 0..5
+    buf.push(1); // 5: version
+    buf.push(1); // 6: codec LZ4
+    buf.push(type_tag); // 7: type (24 = i64 matrix, 25 = f64 matrix)
+    buf.extend_from_slice(&((rows * cols) as u64).to_le_bytes()); // 8..16
+    buf.extend_from_slice(&(rows as u64).to_le_bytes()); // 16..24
+    buf.extend_from_slice(&(cols as u64).to_le_bytes()); // 24..32
+}
+
+fn read_header(blob: &[u8], type_tag: u8, type_name: &'static str) -> Result<(usize, usize)> {
+    if blob.len() < 32 {
+        return Err(CydecError::Truncated {
+            needed: 32,
+            got: blob.len(),
+        });
+    }
+    if &blob[0..5] != b"CYDEC" {
+        return Err(CydecError::BadMagic);
+    }
+    if blob[5] != 1 {
+        return Err(CydecError::unsupported_version(blob[5]));
+    }
+    if blob[6] != 1 {
+        return Err(CydecError::UnsupportedBackend { found: blob[6] });
+    }
+    if blob[7] != type_tag {
+        return Err(CydecError::TypeMismatch {
+            expected: type_name,
+            found: blob[7],
+        });
+    }
+    let n = header::usize_at(blob, 8)?;
+    let rows = header::usize_at(blob, 16)?;
+    let cols = header::usize_at(blob, 24)?;
+    check_shape(n, rows, cols)?;
+    Ok((rows, cols))
+}
+
+impl IntegerCodec {
+    /// Compress a row-major `rows x cols` i64 matrix with per-column deltas
+    pub fn compress_matrix_i64(&self, data: &[i64], rows: usize, cols: usize) -> Result<Vec<u8>> {
+        check_shape(data.len(), rows, cols)?;
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let columns = to_columns(data, cols, |x| x);
+        let mut buf = Vec::with_capacity(data.len() + 32);
+        write_header(&mut buf, 24, rows, cols);
+        buf.extend_from_slice(&Self::encode_i64_payload(&columns));
+        Ok(buf)
+    }
+
+    pub fn decompress_matrix_i64(&self, blob: &[u8]) -> Result<Matrix<i64>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Matrix::new(0, 0, Vec::new());
+        }
+        let (rows, cols) = read_header(blob, 24, "i64 matrix")?;
+        let columns = Self::decode_i64_payload(&blob[32..], rows * cols)?;
+        Matrix::new(rows, cols, to_rows(&columns, rows, cols))
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn compress_array2_i64(&self, array: &ndarray::Array2<i64>) -> Result<Vec<u8>> {
+        let (rows, cols) = array.dim();
+        let data: Vec<i64> = array.iter().copied().collect();
+        self.compress_matrix_i64(&data, rows, cols)
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn decompress_array2_i64(&self, blob: &[u8]) -> Result<ndarray::Array2<i64>> {
+        let m = self.decompress_matrix_i64(blob)?;
+        ndarray::Array2::from_shape_vec((m.rows, m.cols), m.data)
+            .map_err(|e| CydecError::Corrupt(e.to_string()))
+    }
+}
+
+impl FloatingCodec {
+    /// Compress a row-major `rows x cols` f64 matrix with per-column deltas
+    pub fn compress_matrix_f64(
+        &self,
+        data: &[f64],
+        rows: usize,
+        cols: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<u8>> {
+        check_shape(data.len(), rows, cols)?;
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let scale_factor = scale.unwrap_or(Self::DEFAULT_F64_SCALE);
+        let columns = to_columns(data, cols, |f| (f * scale_factor).round() as i64);
+        let mut buf = Vec::with_capacity(data.len() + 40);
+        write_header(&mut buf, 25, rows, cols);
+        buf.extend_from_slice(&scale_factor.to_le_bytes()); // 32..40
+        buf.extend_from_slice(&IntegerCodec::encode_i64_payload(&columns));
+        Ok(buf)
+    }
+
+    pub fn decompress_matrix_f64(&self, blob: &[u8], scale: Option<f64>) -> Result<Matrix<f64>> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Matrix::new(0, 0, Vec::new());
+        }
+        let (rows, cols) = read_header(blob, 25, "f64 matrix")?;
+        if blob.len() < 40 {
+            return Err(CydecError::Truncated {
+                needed: 40,
+                got: blob.len(),
+            });
+        }
+        let scale_factor =
+            scale.unwrap_or_else(|| f64::from_le_bytes(blob[32..40].try_into().unwrap()));
+        let columns = IntegerCodec::decode_i64_payload(&blob[40..], rows * cols)?;
+        let columns: Vec<f64> = columns.iter().map(|&i| i as f64 / scale_factor).collect();
+        Matrix::new(rows, cols, to_rows(&columns, rows, cols))
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn compress_array2_f64(
+        &self,
+        array: &ndarray::Array2<f64>,
+        scale: Option<f64>,
+    ) -> Result<Vec<u8>> {
+        let (rows, cols) = array.dim();
+        let data: Vec<f64> = array.iter().copied().collect();
+        self.compress_matrix_f64(&data, rows, cols, scale)
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn decompress_array2_f64(
+        &self,
+        blob: &[u8],
+        scale: Option<f64>,
+    ) -> Result<ndarray::Array2<f64>> {
+        let m = self.decompress_matrix_f64(blob, scale)?;
+        ndarray::Array2::from_shape_vec((m.rows, m.cols), m.data)
+            .map_err(|e| CydecError::Corrupt(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_matrix_i64() -> Result<()> {
+        let c = IntegerCodec::default();
+        // 1000 rows of 4 slowly drifting sensors with very different levels
+        let (rows, cols) = (1_000, 4);
+        let data: Vec<i64> = (0..rows as i64)
+            .flat_map(|r| [r, 1_000_000 + r * 3, -50_000 - r, 7])
+            .collect();
+        let blob = c.compress_matrix_i64(&data, rows, cols)?;
+        let m = c.decompress_matrix_i64(&blob)?;
+        assert_eq!((m.rows, m.cols), (rows, cols));
+        assert_eq!(m.data, data);
+        assert_eq!(m.get(10, 1), Some(1_000_030));
+        assert_eq!(
+            m.column(2).take(2).collect::<Vec<_>>(),
+            vec![-50_000, -50_001]
+        );
+
+        // column-wise deltas beat the row-major stream
+        assert!(blob.len() < c.compress_i64(&data)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_matrix_f64() -> Result<()> {
+        let c = FloatingCodec::default();
+        let data: Vec<f64> = (0..300).map(|i| i as f64 * 0.5).collect();
+        let blob = c.compress_matrix_f64(&data, 100, 3, None)?;
+        let m = c.decompress_matrix_f64(&blob, None)?;
+        assert_eq!((m.rows, m.cols), (100, 3));
+        assert_eq!(m.data, data);
+        Ok(())
+    }
+
+    #[test]
+    fn shape_mismatch_is_an_error() {
+        let c = IntegerCodec::default();
+        assert!(c.compress_matrix_i64(&[1, 2, 3], 2, 2).is_err());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn roundtrip_array2() -> Result<()> {
+        let c = FloatingCodec::default();
+        let a = ndarray::Array2::from_shape_fn((50, 6), |(r, c)| r as f64 + c as f64 * 0.25);
+        let blob = c.compress_array2_f64(&a, None)?;
+        assert_eq!(a, c.decompress_array2_f64(&blob, None)?);
+        Ok(())
+    }
+}
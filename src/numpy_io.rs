@@ -0,0 +1,206 @@
+use crate::concat::{DecodedArray, decode};
+use crate::error::{Result, invalid};
+use crate::format::TypeTag;
+use crate::{Codec, FloatingCodec, Header, IntegerCodec};
+use npyz::npz::NpzWriter;
+use npyz::zip::write::FileOptions;
+use npyz::{AutoSerialize, DType, NpyFile, Order, TypeChar, WriteOptions, WriterBuilder};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Evaluate `$body` with `$values` bound to the `Vec` inside `$array`;
+/// 128-bit integers have no NumPy dtype
+macro_rules! with_values {
+    ($array:expr, $values:ident => $body:expr) => {
+        match $array {
+            DecodedArray::I64($values) => $body,
+            DecodedArray::U64($values) => $body,
+            DecodedArray::I32($values) => $body,
+            DecodedArray::U32($values) => $body,
+            DecodedArray::I16($values) => $body,
+            DecodedArray::U16($values) => $body,
+            DecodedArray::I8($values) => $body,
+            DecodedArray::Bytes($values) => $body,
+            DecodedArray::F64($values) => $body,
+            DecodedArray::F32($values) => $body,
+            DecodedArray::I128(_) | DecodedArray::U128(_) => {
+                Err(invalid!("numpy has no 128-bit integer dtype"))
+            }
+        }
+    };
+}
+
+/// Compress the array in the `.npy` file at `path`.
+///
+/// One-dimensional arrays of any integer or float dtype, in either byte
+/// order, compress with their element type's pipeline, floats at the
+/// default scale; `datetime64` and `timedelta64` arrays compress as i64.
+/// Two-dimensional `int64` and `float64` arrays compress as matrices.
+pub fn compress_npy(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let npy = NpyFile::new(BufReader::new(File::open(path)?))?;
+    let DType::Plain(ty) = npy.dtype() else {
+        return Err(invalid!("structured npy arrays are not supported"));
+    };
+    let c = Codec::default();
+    match (npy.shape().len(), ty.type_char(), ty.size_field()) {
+        (1, TypeChar::Int, 1) => c.compress(&npy.into_vec::<i8>()?),
+        (1, TypeChar::Int, 2) => c.compress(&npy.into_vec::<i16>()?),
+        (1, TypeChar::Int, 4) => c.compress(&npy.into_vec::<i32>()?),
+        (1, TypeChar::Int | TypeChar::DateTime | TypeChar::TimeDelta, 8) => {
+            c.compress(&npy.into_vec::<i64>()?)
+        }
+        (1, TypeChar::Uint, 1) => c.compress(&npy.into_vec::<u8>()?),
+        (1, TypeChar::Uint, 2) => c.compress(&npy.into_vec::<u16>()?),
+        (1, TypeChar::Uint, 4) => c.compress(&npy.into_vec::<u32>()?),
+        (1, TypeChar::Uint, 8) => c.compress(&npy.into_vec::<u64>()?),
+        (1, TypeChar::Float, 4) => c.compress(&npy.into_vec::<f32>()?),
+        (1, TypeChar::Float, 8) => c.compress(&npy.into_vec::<f64>()?),
+        (2, TypeChar::Int, 8) => {
+            let (rows, cols, order) = matrix_shape(&npy)?;
+            let data = row_major(npy.into_vec::<i64>()?, rows, cols, order);
+            IntegerCodec::default().compress_matrix_i64(&data, rows, cols)
+        }
+        (2, TypeChar::Float, 8) => {
+            let (rows, cols, order) = matrix_shape(&npy)?;
+            let data = row_major(npy.into_vec::<f64>()?, rows, cols, order);
+            FloatingCodec::default().compress_matrix_f64(&data, rows, cols, None)
+        }
+        (ndim, _, _) => Err(invalid!(
+            "npy arrays of {ndim} dimensions and dtype {ty} are not supported"
+        )),
+    }
+}
+
+/// Decompress `blob` into a `.npy` file at `path`, which numpy reads with
+/// `np.load`. Matrices become two-dimensional arrays.
+pub fn decompress_to_npy(path: impl AsRef<Path>, blob: &[u8]) -> Result<()> {
+    let (array, shape) = decoded(blob)?;
+    let mut out = BufWriter::new(File::create(path)?);
+    with_values!(array, values => {
+        write_array(WriteOptions::new().writer(&mut out), values, &shape)
+    })?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Decompress each named blob of `arrays` into one `.npz` archive at
+/// `path`, as `np.savez_compressed` would write it.
+pub fn decompress_to_npz(path: impl AsRef<Path>, arrays: &[(&str, &[u8])]) -> Result<()> {
+    let mut npz = NpzWriter::create(path)?;
+    for (name, blob) in arrays {
+        let (array, shape) = decoded(blob)?;
+        with_values!(array, values => {
+            write_array(npz.array(name, FileOptions::default())?, values, &shape)
+        })?;
+    }
+    npz.zip_writer().finish().map_err(io::Error::from)?;
+    Ok(())
+}
+
+/// Rows, columns and layout of a two-dimensional array
+fn matrix_shape<R: io::Read>(npy: &NpyFile<R>) -> Result<(usize, usize, Order)> {
+    let dim = |d: u64| usize::try_from(d).map_err(|_| invalid!("npy dimension {d} is too large"));
+    Ok((dim(npy.shape()[0])?, dim(npy.shape()[1])?, npy.order()))
+}
+
+fn row_major<T: Copy>(data: Vec<T>, rows: usize, cols: usize, order: Order) -> Vec<T> {
+    match order {
+        Order::C => data,
+        Order::Fortran => (0..rows * cols)
+            .map(|i| data[(i % cols) * rows + i / cols])
+            .collect(),
+    }
+}
+
+/// Values of `blob` and the shape to write them with
+fn decoded(blob: &[u8]) -> Result<(DecodedArray, Vec<u64>)> {
+    if blob.is_empty() {
+        return Ok((DecodedArray::I64(Vec::new()), vec![0]));
+    }
+    let header = Header::parse(blob)?;
+    if header.type_tag == TypeTag::I64Matrix as u8 {
+        let m = IntegerCodec::default().decompress_matrix_i64(blob)?;
+        return Ok((
+            DecodedArray::I64(m.data),
+            vec![m.rows as u64, m.cols as u64],
+        ));
+    }
+    if header.type_tag == TypeTag::F64Matrix as u8 {
+        let m = FloatingCodec::default().decompress_matrix_f64(blob, None)?;
+        return Ok((
+            DecodedArray::F64(m.data),
+            vec![m.rows as u64, m.cols as u64],
+        ));
+    }
+    let array = decode(blob)?;
+    let len = with_values!(&array, values => Ok(values.len() as u64))?;
+    Ok((array, vec![len]))
+}
+
+fn write_array<T, B>(builder: B, values: Vec<T>, shape: &[u64]) -> Result<()>
+where
+    T: AutoSerialize,
+    B: WriterBuilder<T> + npyz::write_options::HasWriter,
+    B::Writer: Write,
+{
+    let mut writer = builder.default_dtype().shape(shape).begin_nd()?;
+    writer.extend(values)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy_files_roundtrip_through_blobs() -> Result<()> {
+        let dir = std::env::temp_dir();
+        let npy = dir.join(format!("cydec-{}.npy", std::process::id()));
+        let npz = dir.join(format!("cydec-{}.npz", std::process::id()));
+        let c = Codec::default();
+
+        let ts: Vec<i64> = (0..5_000).map(|i| 1_700_000_000_000 + i * 250).collect();
+        npyz::to_file_1d(&npy, ts.iter().copied())?;
+        let ts_blob = compress_npy(&npy)?;
+        assert_eq!(c.decompress_i64(&ts_blob)?, ts);
+
+        let levels: Vec<u16> = (0..3_000).map(|i| (i * 31 % 4_096) as u16).collect();
+        let levels_blob = c.compress(&levels)?;
+        decompress_to_npy(&npy, &levels_blob)?;
+        assert_eq!(NpyFile::new(File::open(&npy)?)?.into_vec::<u16>()?, levels);
+        assert_eq!(compress_npy(&npy)?, levels_blob);
+
+        // a Fortran-ordered 3x2 matrix, as numpy writes a transposed array
+        let mut out = BufWriter::new(File::create(&npy)?);
+        let mut writer = WriteOptions::<i64>::new()
+            .default_dtype()
+            .shape(&[3, 2])
+            .order(Order::Fortran)
+            .writer(&mut out)
+            .begin_nd()?;
+        writer.extend([1, 2, 3, 10, 20, 30])?;
+        writer.finish()?;
+        out.flush()?;
+        drop(out);
+        let matrix = IntegerCodec::default().decompress_matrix_i64(&compress_npy(&npy)?)?;
+        assert_eq!(matrix.data, [1, 10, 2, 20, 3, 30]);
+
+        let matrix_blob =
+            FloatingCodec::default().compress_matrix_f64(&[0.5, 1.5, 2.5, 3.5], 2, 2, None)?;
+        decompress_to_npz(&npz, &[("ts", &ts_blob), ("prices", &matrix_blob)])?;
+        let mut archive = npyz::npz::NpzArchive::open(&npz)?;
+        let prices = archive.by_name("prices")?.unwrap();
+        assert_eq!(prices.shape(), [2, 2]);
+        assert_eq!(prices.into_vec::<f64>()?, [0.5, 1.5, 2.5, 3.5]);
+        assert_eq!(archive.by_name("ts")?.unwrap().into_vec::<i64>()?, ts);
+
+        let wide = c.compress_i128(&[1, 2])?;
+        assert!(decompress_to_npy(&npy, &wide).is_err());
+
+        std::fs::remove_file(npy)?;
+        std::fs::remove_file(npz)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use crate::error::Result;
+use crate::{Backend, Checksum, CydecError};
+use std::collections::BTreeMap;
+
+/// Per-call settings for the `*_with` compression methods.
+///
+/// One long-lived codec can then serve requests with different backends,
+/// levels or scales without building a new codec for each call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressOptions {
+    pub backend: Backend,
+    /// Backend compression level; 0 picks the backend default, LZ4 ignores it
+    pub level: i32,
+    /// Float scale factor; `None` uses the type's default scale
+    pub scale: Option<f64>,
+    /// Bloom filter bits per value appended to i64 blobs, for
+    /// [`crate::IntegerCodec::might_contain_i64`]; 0 writes no filter.
+    /// 10 bits give about 1% false positives.
+    pub filter_bits: u32,
+    /// Key-value pairs stored in the blob header, such as a series name or
+    /// units, read back by [`crate::inspect`]. Every blob carries its own
+    /// copy, so keep it small.
+    pub metadata: BTreeMap<String, String>,
+    /// Checksum of the payload that decoding verifies, see
+    /// [`crate::with_checksum`]; [`Checksum::None`] keeps blobs minimal
+    pub checksum: Checksum,
+    /// Decode every blob before returning it and compare it with the input:
+    /// integers must match exactly, floats to within half a step of their
+    /// scale. A mismatch fails the call with [`CydecError::VerifyFailed`]
+    /// instead of handing back a blob that would not read back. Roughly
+    /// doubles the cost of compression.
+    pub verify: bool,
+}
+
+impl CompressOptions {
+    /// LZ4 at its default level with the type's default scale
+    pub const fn new() -> Self {
+        Self {
+            backend: Backend::Lz4,
+            level: 0,
+            scale: None,
+            filter_bits: 0,
+            metadata: BTreeMap::new(),
+            checksum: Checksum::None,
+            verify: false,
+        }
+    }
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check that `decoded` holds exactly the values of `data`
+pub(crate) fn verify_exact<T: PartialEq + std::fmt::Debug>(
+    data: &[T],
+    decoded: Result<Vec<T>>,
+) -> Result<()> {
+    let decoded = read_back(data.len(), decoded)?;
+    match data.iter().zip(&decoded).position(|(a, b)| a != b) {
+        None => Ok(()),
+        Some(i) => Err(CydecError::VerifyFailed(format!(
+            "value {i} reads back as {:?} instead of {:?}",
+            decoded[i], data[i]
+        ))),
+    }
+}
+
+/// Check that `decoded` is within half a quantization step of `data` at
+/// `scale`, allowing for the rounding of the scaling itself. NaN,
+/// infinities and values clamped to the i64 range fail.
+pub(crate) fn verify_scaled(data: &[f64], decoded: Result<Vec<f64>>, scale: f64) -> Result<()> {
+    let decoded = read_back(data.len(), decoded)?;
+    let step = 0.5 / scale;
+    let off = data.iter().zip(&decoded).position(|(&a, &b)| {
+        let diff = (a - b).abs();
+        !a.is_finite() || diff.is_nan() || diff > step + 2.0 * a.abs() * f64::EPSILON
+    });
+    match off {
+        None => Ok(()),
+        Some(i) => Err(CydecError::VerifyFailed(format!(
+            "value {i} reads back as {} instead of {} at scale {scale}",
+            decoded[i], data[i]
+        ))),
+    }
+}
+
+/// The decoded values, if the blob decoded to `expected` of them
+fn read_back<T>(expected: usize, decoded: Result<Vec<T>>) -> Result<Vec<T>> {
+    let decoded =
+        decoded.map_err(|e| CydecError::VerifyFailed(format!("blob does not decode: {e}")))?;
+    if decoded.len() != expected {
+        return Err(CydecError::VerifyFailed(format!(
+            "read back {} values instead of {expected}",
+            decoded.len()
+        )));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatingCodec, IntegerCodec};
+
+    #[test]
+    fn verify_catches_blobs_that_do_not_read_back() -> Result<()> {
+        let options = CompressOptions {
+            verify: true,
+            checksum: Checksum::Crc32c,
+            ..CompressOptions::new()
+        };
+        let ints = IntegerCodec::default();
+        let long: Vec<i64> = (0..70_000).map(|i| i * i % 1_009 - 500).collect();
+        let blob = ints.compress_i64_with(&long, &options)?;
+        let unverified = CompressOptions {
+            verify: false,
+            ..options.clone()
+        };
+        assert_eq!(blob, ints.compress_i64_with(&long, &unverified)?);
+        let counts: Vec<u64> = vec![3, u64::MAX, 0, 7];
+        ints.compress_u64_with(&counts, &options)?;
+        ints.compress_i64_with(&[], &options)?;
+
+        let floats = FloatingCodec::default();
+        let prices: Vec<f64> = (0..5_000).map(|i| 100.0 + i as f64 / 7.0).collect();
+        floats.compress_f64_with(&prices, &options)?;
+        let coarse = CompressOptions {
+            scale: Some(100.0),
+            ..options.clone()
+        };
+        floats.compress_f64_with(&[1e12, -0.004, 0.005, 1.0 / 3.0], &coarse)?;
+        for bad in [f64::NAN, f64::INFINITY, 1e300] {
+            let err = floats.compress_f64_with(&[1.0, bad], &options);
+            assert!(matches!(err, Err(CydecError::VerifyFailed(_))), "{bad}");
+            // without verify the lossy blob comes back as before
+            floats.compress_f64_with(&[1.0, bad], &CompressOptions::new())?;
+        }
+
+        assert!(verify_exact(&[1, 2, 3], Ok(vec![1, 2, 4])).is_err());
+        assert!(verify_exact(&[1, 2, 3], Ok(vec![1, 2])).is_err());
+        assert!(verify_exact::<i64>(&[1], Err(CydecError::BadMagic)).is_err());
+        assert!(verify_scaled(&[0.5], Ok(vec![0.52]), 100.0).is_err());
+        verify_scaled(&[0.5], Ok(vec![0.504]), 100.0)?;
+        Ok(())
+    }
+}
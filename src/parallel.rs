@@ -0,0 +1,19 @@
+//! Iteration for the `*_many` batch calls: rayon's parallel iterators with
+//! the `parallel` feature, and plain slice iterators without it, for
+//! targets such as `wasm32-unknown-unknown` that have no threads.
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::prelude::*;
+
+/// Sequential stand-in for rayon's `par_iter`
+#[cfg(not(feature = "parallel"))]
+pub(crate) trait ParIter<T> {
+    fn par_iter(&self) -> std::slice::Iter<'_, T>;
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<T> ParIter<T> for [T] {
+    fn par_iter(&self) -> std::slice::Iter<'_, T> {
+        self.iter()
+    }
+}
@@ -0,0 +1,140 @@
+use crate::CydecError;
+use crate::arrow::{compress_array, decode_array, inferred_field};
+use crate::error::{Result, corrupt, invalid};
+use arrow_array::{ArrayRef, RecordBatch, new_empty_array};
+use arrow_schema::Schema;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::ProjectionMask;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::errors::ParquetError;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Compress column `column` of the Parquet file at `path` into one blob,
+/// nulls included, as [`crate::Codec::compress_arrow`] would.
+///
+/// Only that column is read. It must be a top-level column of a type
+/// [`crate::Frame::from_record_batch`] accepts.
+pub fn compress_parquet_column(path: impl AsRef<Path>, column: &str) -> Result<Vec<u8>> {
+    let builder =
+        ParquetRecordBatchReaderBuilder::try_new(File::open(path)?).map_err(read_error)?;
+    let field = builder
+        .schema()
+        .field_with_name(column)
+        .map_err(|_| invalid!("no parquet column {column:?}"))?
+        .clone();
+    let index = builder.schema().index_of(column).unwrap();
+    let mask = ProjectionMask::roots(builder.parquet_schema(), [index]);
+    let batches = builder
+        .with_projection(mask)
+        .build()
+        .map_err(read_error)?
+        .map(|batch| batch.map(|b| b.column(0).clone()))
+        .collect::<Result<Vec<ArrayRef>, _>>()
+        .map_err(|e| corrupt!("parquet: {e}"))?;
+    let array = match batches.as_slice() {
+        [] => new_empty_array(field.data_type()),
+        [one] => one.clone(),
+        many => {
+            let parts: Vec<_> = many.iter().map(|a| a.as_ref()).collect();
+            arrow_select::concat::concat(&parts).map_err(|e| corrupt!("parquet: {e}"))?
+        }
+    };
+    compress_array(column, array.as_ref())
+}
+
+/// Decode `columns`, each a name and a blob of the same length, into one
+/// Parquet file at `path`.
+///
+/// Column types follow from the blobs' element types, and nulls stored
+/// by [`crate::Codec::compress_arrow`] are written as nulls.
+pub fn decompress_to_parquet(path: impl AsRef<Path>, columns: &[(&str, &[u8])]) -> Result<()> {
+    let fields = columns
+        .iter()
+        .map(|(name, blob)| inferred_field(name, blob))
+        .collect::<Result<Vec<_>>>()?;
+    let arrays = fields
+        .iter()
+        .zip(columns)
+        .map(|(field, (name, blob))| decode_array(name, blob, field.data_type()))
+        .collect::<Result<Vec<_>>>()?;
+    let batch =
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(|e| invalid!("{e}"))?;
+    let mut writer =
+        ArrowWriter::try_new(File::create(path)?, batch.schema(), None).map_err(write_error)?;
+    writer.write(&batch).map_err(write_error)?;
+    writer.close().map_err(write_error)?;
+    Ok(())
+}
+
+fn read_error(e: ParquetError) -> CydecError {
+    corrupt!("parquet: {e}")
+}
+
+fn write_error(e: ParquetError) -> CydecError {
+    CydecError::Io(std::io::Error::other(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Codec;
+    use arrow_array::types::{Float64Type, Int64Type};
+    use arrow_array::{Float64Array, Int64Array};
+    use parquet::file::properties::WriterProperties;
+
+    #[test]
+    fn columns_move_between_parquet_and_blobs() -> Result<()> {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("cydec-in-{}.parquet", std::process::id()));
+        let output = dir.join(format!("cydec-out-{}.parquet", std::process::id()));
+
+        let ts = Int64Array::from(
+            (0..10_000)
+                .map(|i| 1_700_000_000 + i * 60)
+                .collect::<Vec<_>>(),
+        );
+        let close: Float64Array = (0..10_000)
+            .map(|i| (i % 100 != 0).then_some(50.0 + i as f64 / 4.0))
+            .collect();
+        let batch = RecordBatch::try_from_iter([
+            ("ts", Arc::new(ts.clone()) as ArrayRef),
+            ("close", Arc::new(close.clone()) as ArrayRef),
+        ])
+        .unwrap();
+        // several row groups, read back as several batches
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(3_000)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&input)?, batch.schema(), Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let c = Codec::default();
+        let ts_blob = compress_parquet_column(&input, "ts")?;
+        let close_blob = compress_parquet_column(&input, "close")?;
+        assert_eq!(c.decompress_arrow::<Int64Type>(&ts_blob)?, ts);
+        assert_eq!(c.decompress_arrow::<Float64Type>(&close_blob)?, close);
+        assert!(compress_parquet_column(&input, "volume").is_err());
+
+        decompress_to_parquet(&output, &[("ts", &ts_blob), ("close", &close_blob)])?;
+        let back: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(File::open(&output)?)
+            .unwrap()
+            .with_batch_size(20_000)
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].column(0).as_ref(), &ts as &dyn arrow_array::Array);
+        assert_eq!(back[0].column(1).null_count(), 100);
+        assert!(!back[0].schema().field(0).is_nullable());
+        assert!(back[0].schema().field(1).is_nullable());
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use crate::error::Result;
+use crate::{CompressedBlob, CydecElement, compress_typed};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres};
+use sqlx::{Decode, Encode, Type};
+
+/// A compressed series stored in a Postgres `bytea` column.
+///
+/// It binds as a query parameter and decodes from a row like any other
+/// sqlx type, so `query!` and `query_as!` check it at compile time with a
+/// type override such as `close as "close: CompressedColumn<f64>"`.
+/// Decoding a row only copies the bytes; a blob of the wrong element type
+/// is reported by [`CompressedColumn::decompress`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompressedColumn<T>(CompressedBlob<T>);
+
+// manual impl: deriving would require `T: Clone`
+impl<T> Clone for CompressedColumn<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: CydecElement> CompressedColumn<T> {
+    pub fn compress(values: &[T]) -> Result<Self> {
+        compress_typed(values).map(Self)
+    }
+
+    pub fn decompress(&self) -> Result<Vec<T>> {
+        self.0.decompress()
+    }
+
+    pub fn as_blob(&self) -> &CompressedBlob<T> {
+        &self.0
+    }
+
+    pub fn into_blob(self) -> CompressedBlob<T> {
+        self.0
+    }
+}
+
+impl<T> From<CompressedBlob<T>> for CompressedColumn<T> {
+    fn from(blob: CompressedBlob<T>) -> Self {
+        Self(blob)
+    }
+}
+
+impl<T> Type<Postgres> for CompressedColumn<T> {
+    fn type_info() -> PgTypeInfo {
+        <Vec<u8> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Vec<u8> as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<T> Encode<'_, Postgres> for CompressedColumn<T> {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&[u8] as Encode<Postgres>>::encode(self.0.as_ref(), buf)
+    }
+}
+
+impl<'r, T: CydecElement> Decode<'r, Postgres> for CompressedColumn<T> {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <Vec<u8> as Decode<Postgres>>::decode(value)?;
+        Ok(Self(CompressedBlob::from_bytes_unchecked(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::TypeInfo;
+
+    #[test]
+    fn columns_bind_as_bytea() -> Result<()> {
+        let ty = <CompressedColumn<i64> as Type<Postgres>>::type_info();
+        assert_eq!(ty.name(), "BYTEA");
+        let ts: Vec<i64> = (0..1_000).map(|i| 1_700_000_000 + i * 60).collect();
+        let column = CompressedColumn::compress(&ts)?;
+        assert_eq!(column.decompress()?, ts);
+
+        let mut buf = PgArgumentBuffer::default();
+        let is_null = column.encode_by_ref(&mut buf).unwrap();
+        assert!(matches!(is_null, IsNull::No));
+        assert_eq!(buf.as_slice(), column.as_blob().as_bytes());
+        Ok(())
+    }
+}
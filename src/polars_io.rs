@@ -0,0 +1,293 @@
+use crate::arrow::{compress_array, decode_array, inferred_field, read_schema, write_schema};
+use crate::error::{Result, corrupt, invalid};
+use crate::header::{self, ARROW_SCHEMA, Extension};
+use crate::{Codec, Frame};
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, PrimitiveArray, RecordBatch, RecordBatchOptions, make_array};
+use arrow_buffer::{NullBuffer, ScalarBuffer};
+use arrow_schema::{DataType as ArrowType, Field, Schema, TimeUnit as ArrowUnit};
+use polars_core::datatypes::{DataType, NumericNative, PolarsNumericType, TimeUnit, TimeZone};
+use polars_core::frame::DataFrame;
+use polars_core::frame::column::Column;
+use polars_core::prelude::{ChunkedArray, IntoSeries, NewChunkedArray};
+use polars_core::series::Series;
+use std::sync::Arc;
+
+/// Evaluate `$body` with `$a` and `$p` naming the Arrow and Polars types of
+/// the physical Polars dtype `$dtype`
+macro_rules! with_physical_type {
+    ($dtype:expr, $name:expr, $a:ident, $p:ident => $body:expr) => {
+        with_physical_type!(@arms $dtype, $name, $a, $p, $body;
+            Int8 => Int8Type, Int16 => Int16Type, Int32 => Int32Type,
+            Int64 => Int64Type, UInt8 => UInt8Type, UInt16 => UInt16Type,
+            UInt32 => UInt32Type, UInt64 => UInt64Type, Float32 => Float32Type,
+            Float64 => Float64Type)
+    };
+    (@arms $dtype:expr, $name:expr, $a:ident, $p:ident, $body:expr;
+        $($variant:ident => $ty:ident),*) => {
+        match $dtype {
+            $(DataType::$variant => {
+                type $a = arrow_array::types::$ty;
+                type $p = polars_core::datatypes::$ty;
+                $body
+            })*
+            other => Err(invalid!(
+                "column {:?}: polars type {other} is not supported",
+                $name
+            )),
+        }
+    };
+}
+
+impl Codec {
+    /// Compress a numeric or temporal Polars series, nulls included, with
+    /// the pipeline of its physical type. The series name and dtype,
+    /// timezone included, are stored with the blob for
+    /// [`Codec::decompress_to_series`].
+    pub fn compress_series(&self, series: &Series) -> Result<Vec<u8>> {
+        let (field, array) = to_arrow(series)?;
+        let blob = compress_array(field.name(), array.as_ref())?;
+        let value = write_schema(&Schema::new(vec![field]))?;
+        header::with_extensions(
+            blob,
+            &[Extension {
+                kind: ARROW_SCHEMA,
+                value: &value,
+            }],
+        )
+    }
+
+    /// Decode a blob into a Polars series. Blobs from
+    /// [`Codec::compress_series`] get their name and dtype back; any other
+    /// blob gives an unnamed series of its element type.
+    pub fn decompress_to_series(&self, blob: &[u8]) -> Result<Series> {
+        let extensions = header::extensions(blob)?;
+        let field = match extensions.iter().find(|e| e.kind == ARROW_SCHEMA) {
+            Some(entry) => {
+                let schema = read_schema(entry.value)?;
+                match schema.fields().len() {
+                    1 => schema.field(0).clone(),
+                    n => return Err(corrupt!("series schema holds {n} fields")),
+                }
+            }
+            None => inferred_field("", blob)?,
+        };
+        let array = decode_array(field.name(), blob, field.data_type())?;
+        to_series(&field, array.as_ref())
+    }
+}
+
+impl Frame<Vec<u8>> {
+    /// Compress every column of `df` into one frame, as
+    /// [`Frame::from_record_batch`] does; [`Frame::to_data_frame`] restores
+    /// the column names and dtypes.
+    pub fn from_data_frame(df: &DataFrame) -> Result<Self> {
+        let (fields, columns): (Vec<Field>, Vec<ArrayRef>) = df
+            .get_columns()
+            .iter()
+            .map(|column| to_arrow(column.as_materialized_series()))
+            .collect::<Result<_>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(df.height()));
+        let batch =
+            RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options)
+                .map_err(|e| invalid!("{e}"))?;
+        Frame::from_record_batch(&batch)
+    }
+}
+
+impl<B: AsRef<[u8]>> Frame<B> {
+    /// Decode every column into a Polars data frame. Columns must have a
+    /// Polars equivalent: integers, floats, dates, nanosecond times, and
+    /// timestamps and durations in milli-, micro- or nanoseconds.
+    pub fn to_data_frame(&self) -> Result<DataFrame> {
+        let batch = self.to_record_batch()?;
+        let columns = batch
+            .schema()
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, array)| to_series(field, array.as_ref()).map(Column::from))
+            .collect::<Result<Vec<_>>>()?;
+        DataFrame::new(columns).map_err(|e| corrupt!("{e}"))
+    }
+}
+
+/// Arrow field and array holding `series`
+fn to_arrow(series: &Series) -> Result<(Field, ArrayRef)> {
+    let name = series.name().as_str();
+    let data_type = arrow_type(name, series.dtype())?;
+    let physical = series.to_physical_repr();
+    let array = with_physical_type!(physical.dtype(), name, A, P => {
+        let ca = physical.unpack::<P>().map_err(|e| invalid!("{e}"))?;
+        Ok(arrow_values::<A, P>(ca).into_data())
+    })?;
+    let data = array
+        .into_builder()
+        .data_type(data_type.clone())
+        .build()
+        .map_err(|e| invalid!("{e}"))?;
+    Ok((Field::new(name, data_type, true), make_array(data)))
+}
+
+/// Polars series holding `array`, with the dtype matching `field`
+fn to_series(field: &Field, array: &dyn Array) -> Result<Series> {
+    let name = field.name();
+    let dtype = polars_type(name, field.data_type())?;
+    let physical = dtype.to_physical();
+    let data = array
+        .to_data()
+        .into_builder()
+        .data_type(arrow_type(name, &physical)?)
+        .build()
+        .map_err(|e| corrupt!("{e}"))?;
+    let array = make_array(data);
+    let series = with_physical_type!(&physical, name, A, P => {
+        Ok(polars_values::<A, P>(name, array.as_primitive::<A>()).into_series())
+    })?;
+    series.cast(&dtype).map_err(|e| corrupt!("{e}"))
+}
+
+fn arrow_values<A, P>(ca: &ChunkedArray<P>) -> PrimitiveArray<A>
+where
+    A: arrow_array::types::ArrowPrimitiveType,
+    A::Native: NumericNative,
+    P: PolarsNumericType<Native = A::Native>,
+{
+    let values: ScalarBuffer<A::Native> = ca
+        .downcast_iter()
+        .flat_map(|chunk| chunk.values().iter().copied())
+        .collect();
+    let nulls =
+        (ca.null_count() > 0).then(|| NullBuffer::new(ca.iter().map(|v| v.is_some()).collect()));
+    PrimitiveArray::new(values, nulls)
+}
+
+fn polars_values<A, P>(name: &str, array: &PrimitiveArray<A>) -> ChunkedArray<P>
+where
+    A: arrow_array::types::ArrowPrimitiveType,
+    P: PolarsNumericType<Native = A::Native>,
+{
+    if array.null_count() == 0 {
+        ChunkedArray::from_vec(name.into(), array.values().to_vec())
+    } else {
+        ChunkedArray::from_iter_options(name.into(), array.iter())
+    }
+}
+
+/// Arrow type of a Polars dtype
+fn arrow_type(name: &str, dtype: &DataType) -> Result<ArrowType> {
+    let unit = |unit: &TimeUnit| match unit {
+        TimeUnit::Milliseconds => ArrowUnit::Millisecond,
+        TimeUnit::Microseconds => ArrowUnit::Microsecond,
+        TimeUnit::Nanoseconds => ArrowUnit::Nanosecond,
+    };
+    Ok(match dtype {
+        DataType::Int8 => ArrowType::Int8,
+        DataType::Int16 => ArrowType::Int16,
+        DataType::Int32 => ArrowType::Int32,
+        DataType::Int64 => ArrowType::Int64,
+        DataType::UInt8 => ArrowType::UInt8,
+        DataType::UInt16 => ArrowType::UInt16,
+        DataType::UInt32 => ArrowType::UInt32,
+        DataType::UInt64 => ArrowType::UInt64,
+        DataType::Float32 => ArrowType::Float32,
+        DataType::Float64 => ArrowType::Float64,
+        DataType::Date => ArrowType::Date32,
+        DataType::Time => ArrowType::Time64(ArrowUnit::Nanosecond),
+        DataType::Datetime(u, tz) => {
+            ArrowType::Timestamp(unit(u), tz.as_ref().map(|tz| Arc::from(tz.as_str())))
+        }
+        DataType::Duration(u) => ArrowType::Duration(unit(u)),
+        other => {
+            return Err(invalid!(
+                "column {name:?}: polars type {other} is not supported"
+            ));
+        }
+    })
+}
+
+/// Polars dtype of an Arrow type
+fn polars_type(name: &str, data_type: &ArrowType) -> Result<DataType> {
+    let unit = |unit: &ArrowUnit| match unit {
+        ArrowUnit::Millisecond => Ok(TimeUnit::Milliseconds),
+        ArrowUnit::Microsecond => Ok(TimeUnit::Microseconds),
+        ArrowUnit::Nanosecond => Ok(TimeUnit::Nanoseconds),
+        ArrowUnit::Second => Err(invalid!(
+            "column {name:?}: polars has no unit of seconds for {data_type}"
+        )),
+    };
+    Ok(match data_type {
+        ArrowType::Int8 => DataType::Int8,
+        ArrowType::Int16 => DataType::Int16,
+        ArrowType::Int32 => DataType::Int32,
+        ArrowType::Int64 => DataType::Int64,
+        ArrowType::UInt8 => DataType::UInt8,
+        ArrowType::UInt16 => DataType::UInt16,
+        ArrowType::UInt32 => DataType::UInt32,
+        ArrowType::UInt64 => DataType::UInt64,
+        ArrowType::Float32 => DataType::Float32,
+        ArrowType::Float64 => DataType::Float64,
+        ArrowType::Date32 => DataType::Date,
+        ArrowType::Time64(ArrowUnit::Nanosecond) => DataType::Time,
+        ArrowType::Timestamp(u, tz) => {
+            let tz = TimeZone::opt_try_new(tz.as_deref()).map_err(|e| corrupt!("{e}"))?;
+            DataType::Datetime(unit(u)?, tz)
+        }
+        ArrowType::Duration(u) => DataType::Duration(unit(u)?),
+        other => {
+            return Err(invalid!(
+                "column {name:?}: arrow type {other} has no polars equivalent"
+            ));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars_core::prelude::NamedFrom;
+
+    #[test]
+    fn polars_data_survives_blobs_and_frames() -> Result<()> {
+        let ts = Series::new(
+            "ts".into(),
+            (0..5_000i64)
+                .map(|i| 1_700_000_000_000 + i * 60_000)
+                .collect::<Vec<_>>(),
+        )
+        .cast(&DataType::Datetime(
+            TimeUnit::Milliseconds,
+            Some(TimeZone::UTC),
+        ))
+        .unwrap();
+        let close = Series::new(
+            "close".into(),
+            (0..5_000)
+                .map(|i| (i % 50 != 0).then_some(100.0 + i as f64 / 8.0))
+                .collect::<Vec<_>>(),
+        );
+        let volume = Series::new(
+            "volume".into(),
+            (0..5_000u32).map(|i| i * 7 % 1_000).collect::<Vec<_>>(),
+        );
+
+        let c = Codec::default();
+        for series in [&ts, &close, &volume] {
+            let back = c.decompress_to_series(&c.compress_series(series)?)?;
+            assert!(back.equals_missing(series), "{}", series.name());
+            assert_eq!(back.name(), series.name());
+            assert_eq!(back.dtype(), series.dtype());
+        }
+        let plain = c.decompress_to_series(&c.compress(&[3i64, 1, 4])?)?;
+        assert_eq!(plain.dtype(), &DataType::Int64);
+
+        let df = DataFrame::new(vec![ts.into(), close.into(), volume.into()]).unwrap();
+        let frame = Frame::from_data_frame(&df)?;
+        assert_eq!(frame.column::<f64>("close")?.len(), 5_000);
+        assert!(frame.to_data_frame()?.equals_missing(&df));
+
+        let strings = Series::new("s".into(), ["a", "b"]);
+        assert!(c.compress_series(&strings).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+// Blosc chunks only decode through here
+#![cfg_attr(not(any(feature = "hdf5", feature = "zarr")), allow(dead_code))]
+
+use crate::concat::{DecodedArray, decode};
+use crate::error::{Result, bail, invalid};
+use crate::{ColumnEncoding, CydecElement, FloatingCodec};
+
+/// Element type of a chunk of little-endian values, as array stores such
+/// as HDF5 and Zarr hand them to a codec
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RawType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+}
+
+impl RawType {
+    pub(crate) fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::I64 | Self::U64 | Self::F64 => 8,
+        }
+    }
+}
+
+/// Compress a chunk of little-endian values of type `ty`.
+///
+/// Floats are stored losslessly, f64 with the XOR encoding and f32 as the
+/// integers of its bit patterns, unless `scale` asks for fixed-scale
+/// quantization. Integer types ignore `scale`.
+pub(crate) fn encode(ty: RawType, chunk: &[u8], scale: Option<f64>) -> Result<Vec<u8>> {
+    match ty {
+        RawType::U8 => u8::compress_slice(chunk),
+        RawType::I8 => i8::compress_slice(&values(chunk, |b: [u8; 1]| b[0] as i8)?),
+        RawType::I16 => i16::compress_slice(&values(chunk, i16::from_le_bytes)?),
+        RawType::U16 => u16::compress_slice(&values(chunk, u16::from_le_bytes)?),
+        RawType::I32 => i32::compress_slice(&values(chunk, i32::from_le_bytes)?),
+        RawType::U32 => u32::compress_slice(&values(chunk, u32::from_le_bytes)?),
+        RawType::I64 => i64::compress_slice(&values(chunk, i64::from_le_bytes)?),
+        RawType::U64 => u64::compress_slice(&values(chunk, u64::from_le_bytes)?),
+        RawType::F32 => match scale {
+            None => u32::compress_slice(&values(chunk, u32::from_le_bytes)?),
+            Some(scale) => FloatingCodec::default()
+                .compress_f32(&values(chunk, f32::from_le_bytes)?, Some(scale as f32)),
+        },
+        RawType::F64 => match scale {
+            None => f64::compress_encoded(&values(chunk, f64::from_le_bytes)?, ColumnEncoding::Xor),
+            Some(scale) => FloatingCodec::default()
+                .compress_f64(&values(chunk, f64::from_le_bytes)?, Some(scale)),
+        },
+    }
+}
+
+/// Little-endian bytes of the values in a compressed chunk; `ty`, when
+/// known, must have the width of the stored values
+pub(crate) fn decode_bytes(blob: &[u8], ty: Option<RawType>) -> Result<Vec<u8>> {
+    let (bytes, size) = decode_values(blob)?;
+    if let Some(ty) = ty
+        && !bytes.is_empty()
+        && ty.size() != size
+    {
+        bail!(
+            "chunk holds {size}-byte values, expected {}-byte {ty:?}",
+            ty.size()
+        );
+    }
+    Ok(bytes)
+}
+
+/// Little-endian bytes of the values in a compressed chunk and the width
+/// of one value
+pub(crate) fn decode_values(blob: &[u8]) -> Result<(Vec<u8>, usize)> {
+    if blob.is_empty() {
+        return Ok((Vec::new(), 1));
+    }
+    Ok(match decode(blob)? {
+        DecodedArray::I64(v) => (le_bytes(&v, i64::to_le_bytes), 8),
+        DecodedArray::U64(v) => (le_bytes(&v, u64::to_le_bytes), 8),
+        DecodedArray::I32(v) => (le_bytes(&v, i32::to_le_bytes), 4),
+        DecodedArray::U32(v) => (le_bytes(&v, u32::to_le_bytes), 4),
+        DecodedArray::I16(v) => (le_bytes(&v, i16::to_le_bytes), 2),
+        DecodedArray::U16(v) => (le_bytes(&v, u16::to_le_bytes), 2),
+        DecodedArray::I8(v) => (v.iter().map(|&x| x as u8).collect(), 1),
+        DecodedArray::Bytes(v) => (v, 1),
+        DecodedArray::F64(v) => (le_bytes(&v, f64::to_le_bytes), 8),
+        DecodedArray::F32(v) => (le_bytes(&v, f32::to_le_bytes), 4),
+        DecodedArray::I128(v) => (le_bytes(&v, i128::to_le_bytes), 16),
+        DecodedArray::U128(v) => (le_bytes(&v, u128::to_le_bytes), 16),
+    })
+}
+
+fn le_bytes<T: Copy, const N: usize>(values: &[T], write: impl Fn(T) -> [u8; N]) -> Vec<u8> {
+    values.iter().flat_map(|&x| write(x)).collect()
+}
+
+fn values<T, const N: usize>(chunk: &[u8], read: impl Fn([u8; N]) -> T) -> Result<Vec<T>> {
+    if !chunk.len().is_multiple_of(N) {
+        return Err(invalid!(
+            "chunk of {} bytes is not a whole number of {N}-byte values",
+            chunk.len()
+        ));
+    }
+    Ok(chunk
+        .chunks_exact(N)
+        .map(|b| read(b.try_into().unwrap()))
+        .collect())
+}
@@ -0,0 +1,65 @@
+use crate::CydecElement;
+use crate::error::{Result, invalid};
+use roaring::RoaringBitmap;
+
+/// Compress the ids of `bitmap` as a u32 blob.
+///
+/// Ids come out of the bitmap in ascending order, so the delta pipeline
+/// turns dense ranges and regular postings into small gaps; the blob reads
+/// back with [`decompress_roaring`] or as a plain u32 column.
+pub fn compress_roaring(bitmap: &RoaringBitmap) -> Result<Vec<u8>> {
+    u32::compress_slice(&bitmap.iter().collect::<Vec<_>>())
+}
+
+/// Decode a u32 blob into a bitmap; ids need not be sorted or distinct
+pub fn decompress_roaring(blob: &[u8]) -> Result<RoaringBitmap> {
+    Ok(u32::decompress_blob(blob)?.into_iter().collect())
+}
+
+/// Compress a bitmap in the portable Roaring serialization, as CRoaring,
+/// the Java library and `RoaringBitmap::serialize_into` write it, so
+/// containers read from another index go straight into a blob. Malformed
+/// input is an [`crate::CydecError::InvalidArgument`].
+pub fn compress_roaring_portable(serialized: &[u8]) -> Result<Vec<u8>> {
+    let bitmap = RoaringBitmap::deserialize_from(serialized)
+        .map_err(|e| invalid!("not a portable roaring bitmap: {e}"))?;
+    compress_roaring(&bitmap)
+}
+
+/// Decode a u32 blob back into the portable Roaring serialization
+pub fn decompress_roaring_portable(blob: &[u8]) -> Result<Vec<u8>> {
+    let bitmap = decompress_roaring(blob)?;
+    let mut out = Vec::with_capacity(bitmap.serialized_size());
+    bitmap.serialize_into(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_sets_roundtrip_through_blobs() -> Result<()> {
+        let mut postings: RoaringBitmap = (10_000..200_000).collect();
+        postings.extend((0..5_000).map(|i| 1_000_000 + i * 37));
+        postings.insert(u32::MAX);
+
+        let blob = compress_roaring(&postings)?;
+        assert!(blob.len() < postings.serialized_size());
+        assert_eq!(decompress_roaring(&blob)?, postings);
+        assert_eq!(u32::decompress_blob(&blob)?.len() as u64, postings.len());
+        assert!(
+            decompress_roaring(&u32::compress_slice(&[9, 3, 9, 1])?)?
+                .iter()
+                .eq([1, 3, 9])
+        );
+
+        let mut portable = Vec::new();
+        postings.serialize_into(&mut portable)?;
+        let blob = compress_roaring_portable(&portable)?;
+        assert_eq!(decompress_roaring_portable(&blob)?, portable);
+        assert!(compress_roaring_portable(&portable[..portable.len() / 2]).is_err());
+        assert!(decompress_roaring(&i64::compress_slice(&[1, 2])?).is_err());
+        Ok(())
+    }
+}
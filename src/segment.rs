@@ -0,0 +1,279 @@
+use crate::error::{Result, invalid};
+use crate::{CydecError, IntegerCodec};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Segment magic + format version, at the start of the file
+const SEGMENT_MAGIC: &[u8; 5] = b"CYSEG";
+const SEGMENT_VERSION: u8 = 1;
+const SEGMENT_HEADER_LEN: usize = 8;
+
+/// Per-record header: u32 payload length + u32 CRC-32 of the payload
+const RECORD_HEADER_LEN: usize = 8;
+
+fn segment_header() -> [u8; SEGMENT_HEADER_LEN] {
+    let mut header = [0u8; SEGMENT_HEADER_LEN];
+    header[..5].copy_from_slice(SEGMENT_MAGIC);
+    header[5] = SEGMENT_VERSION;
+    header
+}
+
+/// Append-only log of compressed blobs, the storage side of a TSDB.
+///
+/// Each record is `[u32 len][u32 crc32][blob]` after an 8-byte file header
+/// (`CYSEG` + version + 2 reserved bytes). A crash can only leave a torn or
+/// partly written last record; [`SegmentWriter::open`] cuts the file back
+/// to the last record whose checksum holds before appending again.
+#[derive(Debug)]
+pub struct SegmentWriter {
+    file: File,
+    codec: IntegerCodec,
+}
+
+impl SegmentWriter {
+    /// Open `path` for appending, creating it if needed and dropping any
+    /// torn tail left by a crash
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(&segment_header())?;
+        } else {
+            let mut reader = SegmentReader::new(BufReader::new(&mut file));
+            while reader.next_blob()?.is_some() {}
+            let valid = reader.valid_len();
+            file.set_len(valid)?;
+        }
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            file,
+            codec: IntegerCodec::default(),
+        })
+    }
+
+    /// Codec used by [`SegmentWriter::append_i64`]
+    pub fn with_codec(mut self, codec: IntegerCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    pub fn append_i64(&mut self, values: &[i64]) -> Result<()> {
+        let blob = self.codec.compress_i64(values)?;
+        self.append_blob(&blob)
+    }
+
+    /// Append an already compressed blob as one record
+    pub fn append_blob(&mut self, blob: &[u8]) -> Result<()> {
+        let len = u32::try_from(blob.len())
+            .map_err(|_| invalid!("blob of {} bytes is too large for a record", blob.len()))?;
+        let mut record = Vec::with_capacity(blob.len() + RECORD_HEADER_LEN);
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&crc32fast::hash(blob).to_le_bytes());
+        record.extend_from_slice(blob);
+        self.file.write_all(&record)?;
+        Ok(())
+    }
+
+    /// Flush appended records to stable storage
+    pub fn sync(&mut self) -> Result<()> {
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Reads the records of a segment back in order.
+///
+/// A short or checksum-failing record ends the segment rather than raising
+/// an error, since that is what a crash mid-append leaves behind;
+/// [`SegmentReader::is_torn`] tells whether that happened. Only a missing
+/// segment header or an I/O failure is an error.
+#[derive(Debug)]
+pub struct SegmentReader<R: Read> {
+    inner: R,
+    started: bool,
+    valid_len: u64,
+    torn: bool,
+    done: bool,
+}
+
+impl SegmentReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> SegmentReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            started: false,
+            valid_len: 0,
+            torn: false,
+            done: false,
+        }
+    }
+
+    /// Bytes up to the end of the last intact record
+    pub fn valid_len(&self) -> u64 {
+        self.valid_len
+    }
+
+    /// Whether reading stopped at a damaged record rather than a clean end
+    pub fn is_torn(&self) -> bool {
+        self.torn
+    }
+
+    /// Next intact blob, or `None` at the end of the valid records
+    pub fn next_blob(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.done {
+            return Ok(None);
+        }
+        if !self.started {
+            let mut header = [0u8; SEGMENT_HEADER_LEN];
+            let got = read_up_to(&mut self.inner, &mut header)?;
+            if got < SEGMENT_HEADER_LEN {
+                return Err(CydecError::Truncated {
+                    needed: SEGMENT_HEADER_LEN,
+                    got,
+                });
+            }
+            if &header[..5] != SEGMENT_MAGIC {
+                return Err(CydecError::BadMagic);
+            }
+            if header[5] != SEGMENT_VERSION {
+                return Err(CydecError::UnsupportedVersion {
+                    found: header[5],
+                    max: SEGMENT_VERSION,
+                });
+            }
+            self.started = true;
+            self.valid_len = SEGMENT_HEADER_LEN as u64;
+        }
+
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        match read_up_to(&mut self.inner, &mut header)? {
+            0 => return Ok(self.stop(false)),
+            RECORD_HEADER_LEN => {}
+            _ => return Ok(self.stop(true)),
+        }
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let mut blob = Vec::new();
+        (&mut self.inner).take(len as u64).read_to_end(&mut blob)?;
+        if blob.len() < len || crc32fast::hash(&blob) != crc {
+            return Ok(self.stop(true));
+        }
+        self.valid_len += (RECORD_HEADER_LEN + len) as u64;
+        Ok(Some(blob))
+    }
+
+    /// Decompress every intact record as i64 and concatenate the values
+    pub fn read_all_i64(&mut self) -> Result<Vec<i64>> {
+        let codec = IntegerCodec::default();
+        let mut out = Vec::new();
+        while let Some(blob) = self.next_blob()? {
+            out.extend(codec.decompress_i64(&blob)?);
+        }
+        Ok(out)
+    }
+
+    fn stop(&mut self, torn: bool) -> Option<Vec<u8>> {
+        self.done = true;
+        self.torn = torn;
+        None
+    }
+}
+
+impl<R: Read> Iterator for SegmentReader<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>>> {
+        self.next_blob().transpose()
+    }
+}
+
+/// Fill `buf` from `r`, stopping early only at end of input
+fn read_up_to(r: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(k) => filled += k,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cydec-{name}-{}.seg", std::process::id()))
+    }
+
+    #[test]
+    fn recovers_after_a_torn_append() -> Result<()> {
+        let path = temp_path("torn");
+        let _ = std::fs::remove_file(&path);
+        let batches: Vec<Vec<i64>> = (0..4).map(|b| (b * 100..b * 100 + 100).collect()).collect();
+
+        let mut w = SegmentWriter::open(&path)?;
+        for batch in &batches[..3] {
+            w.append_i64(batch)?;
+        }
+        w.sync()?;
+        drop(w);
+
+        // simulate a crash halfway through the next record
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(&[200, 0, 0, 0, 1, 2, 3, 4, 5])?;
+        drop(file);
+
+        let mut r = SegmentReader::open(&path)?;
+        assert_eq!(r.read_all_i64()?, batches[..3].concat());
+        assert!(r.is_torn());
+
+        let mut w = SegmentWriter::open(&path)?;
+        w.append_i64(&batches[3])?;
+        drop(w);
+        let mut r = SegmentReader::open(&path)?;
+        assert_eq!(r.read_all_i64()?, batches.concat());
+        assert!(!r.is_torn());
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn checksum_mismatch_ends_the_segment() -> Result<()> {
+        let path = temp_path("crc");
+        let _ = std::fs::remove_file(&path);
+        let mut w = SegmentWriter::open(&path)?;
+        w.append_i64(&[1, 2, 3])?;
+        w.append_i64(&[4, 5, 6])?;
+        drop(w);
+
+        let mut bytes = std::fs::read(&path)?;
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let blobs: Vec<Vec<u8>> = SegmentReader::new(bytes.as_slice()).collect::<Result<_>>()?;
+        assert_eq!(blobs.len(), 1);
+        assert!(
+            SegmentReader::new(&b"not a segment"[..])
+                .next()
+                .unwrap()
+                .is_err()
+        );
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{ColumnEncoding, CompressedBlob, CydecElement};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::de::{self, Error as _, SeqAccess, Visitor};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// Binary formats get the blob as a byte string; human-readable ones such
+// as JSON and YAML get it as standard base64.
+
+impl<T> Serialize for CompressedBlob<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&STANDARD.encode(self.as_ref()))
+        } else {
+            serializer.serialize_bytes(self.as_ref())
+        }
+    }
+}
+
+/// Deserializes without decompressing, so a blob of the wrong element type
+/// only fails at [`CompressedBlob::decompress`].
+impl<'de, T: CydecElement> Deserialize<'de> for CompressedBlob<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BlobVisitor)?
+        } else {
+            deserializer.deserialize_byte_buf(BlobVisitor)?
+        };
+        Ok(CompressedBlob::from_bytes_unchecked(bytes))
+    }
+}
+
+/// Accepts base64 text, a byte string, or a sequence of bytes for formats
+/// without byte strings
+struct BlobVisitor;
+
+impl<'de> Visitor<'de> for BlobVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a compressed blob as bytes or base64")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+        STANDARD
+            .decode(v)
+            .map_err(|e| E::custom(format_args!("compressed blob is not base64: {e}")))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1 << 20));
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes)
+    }
+}
+
+/// `#[serde(with = "cydec::serde_f64")]` for a `Vec<f64>` field: the
+/// values are stored as a compressed blob, losslessly, with the XOR
+/// encoding of [`ColumnEncoding::Xor`]
+pub mod serde_f64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        let blob = f64::compress_encoded(values, ColumnEncoding::Xor).map_err(S::Error::custom)?;
+        CompressedBlob::<f64>::from_bytes_unchecked(blob).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+        let blob = CompressedBlob::<f64>::deserialize(deserializer)?;
+        blob.decompress().map_err(D::Error::custom)
+    }
+}
+
+/// `#[serde(with = "cydec::serde_i64")]` for a `Vec<i64>` field: the
+/// values are stored as a compressed blob
+pub mod serde_i64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[i64], serializer: S) -> Result<S::Ok, S::Error> {
+        crate::compress_typed(values)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<i64>, D::Error> {
+        let blob = CompressedBlob::<i64>::deserialize(deserializer)?;
+        blob.decompress().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress_typed;
+
+    #[derive(Serialize, Deserialize)]
+    struct State {
+        name: String,
+        prices: CompressedBlob<f64>,
+    }
+
+    #[test]
+    fn blobs_embed_in_serde_formats() -> crate::Result<()> {
+        let prices: Vec<f64> = (0..2_000).map(|i| 100.0 + i as f64 * 0.25).collect();
+        let state = State {
+            name: "book".into(),
+            prices: compress_typed(&prices)?,
+        };
+
+        let json = serde_json::to_string(&state).unwrap();
+        let encoded = STANDARD.encode(state.prices.as_bytes());
+        assert!(json.contains(&format!("\"prices\":\"{encoded}\"")));
+        let back: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.name, "book");
+        assert_eq!(back.prices.decompress()?, prices);
+
+        let binary = bincode::serialize(&state).unwrap();
+        // length-prefixed raw bytes, no base64 overhead
+        assert!(binary.len() < state.prices.as_bytes().len() + 32);
+        let back: State = bincode::deserialize(&binary).unwrap();
+        assert_eq!(back.prices, state.prices);
+
+        let bad = json.replace(&encoded, "not base64!");
+        assert!(serde_json::from_str::<State>(&bad).is_err());
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Series {
+        #[serde(with = "crate::serde_i64")]
+        ts: Vec<i64>,
+        #[serde(with = "crate::serde_f64")]
+        close: Vec<f64>,
+    }
+
+    #[test]
+    fn with_modules_compress_fields() {
+        let mut close: Vec<f64> = (0..5_000).map(|i| 100.0 + (i % 97) as f64 / 3.0).collect();
+        close[..4].copy_from_slice(&[f64::NAN, -0.0, f64::INFINITY, 1e-300]);
+        let series = Series {
+            ts: (0..5_000).map(|i| 1_700_000_000_000 + i * 1_000).collect(),
+            close,
+        };
+
+        let binary = bincode::serialize(&series).unwrap();
+        assert!(binary.len() < 5_000 * 16 / 4);
+        let back: Series = bincode::deserialize(&binary).unwrap();
+        assert_eq!(back.ts, series.ts);
+        let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&back.close), bits(&series.close));
+
+        let json = serde_json::to_string(&series).unwrap();
+        let back: Series = serde_json::from_str(&json).unwrap();
+        assert_eq!(bits(&back.close), bits(&series.close));
+        assert!(serde_json::from_str::<Series>(r#"{"ts":"AAAA","close":""}"#).is_err());
+    }
+}
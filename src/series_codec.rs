@@ -0,0 +1,407 @@
+use crate::blocks::{BLOCKED_FLAG, BlockStats, STATS_FLAG, push_block, read_blocks_at};
+use crate::error::{Result, invalid};
+use crate::format::TypeTag;
+use crate::header;
+use crate::integer_codec::reserve_len;
+use crate::{Backend, CydecError, FloatingCodec, IntegerCodec};
+use std::ops::Range;
+
+/// Type byte of a series longer than [`IntegerCodec::BLOCK_LEN`]: blocks of
+/// both columns, each with the statistics of its timestamps
+pub(crate) const BLOCKED_SERIES: u8 = TypeTag::Series as u8 | BLOCKED_FLAG | STATS_FLAG;
+
+/// Codec for a `(timestamps, values)` series stored in a single blob.
+///
+/// Timestamps are delta-of-delta encoded, which makes regularly sampled
+/// series nearly free; values go through the scaled f64 pipeline.
+#[derive(Clone, Debug)]
+pub struct SeriesCodec {
+    pub scale: f64,
+}
+
+impl Default for SeriesCodec {
+    fn default() -> Self {
+        Self {
+            scale: FloatingCodec::DEFAULT_F64_SCALE,
+        }
+    }
+}
+
+impl SeriesCodec {
+    pub fn compress(&self, timestamps: &[i64], values: &[f64]) -> Result<Vec<u8>> {
+        if timestamps.len() != values.len() {
+            return Err(invalid!(
+                "series length mismatch: {} timestamps, {} values",
+                timestamps.len(),
+                values.len()
+            ));
+        }
+        if timestamps.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let scaled: Vec<i64> = values
+            .iter()
+            .map(|&f| (f * self.scale).round() as i64)
+            .collect();
+        let blocked = timestamps.len() > IntegerCodec::BLOCK_LEN;
+
+        let mut buf = Vec::with_capacity(timestamps.len() + values.len() + 28);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(if blocked { BLOCKED_SERIES } else { 27 }); // 7: type (27 = timestamp/value series)
+        buf.extend_from_slice(&(timestamps.len() as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&self.scale.to_le_bytes()); // 16..24
+
+        if blocked {
+            // each block: [u32 ts payload len][ts payload][value payload]
+            for (ts, scaled) in timestamps
+                .chunks(IntegerCodec::BLOCK_LEN)
+                .zip(scaled.chunks(IntegerCodec::BLOCK_LEN))
+            {
+                let ts_payload = IntegerCodec::encode_dod_payload(ts);
+                let mut payload = (ts_payload.len() as u32).to_le_bytes().to_vec();
+                payload.extend_from_slice(&ts_payload);
+                payload.extend_from_slice(&IntegerCodec::encode_i64_payload(scaled));
+                push_block(&mut buf, ts.len(), Some(&BlockStats::of(ts)), &payload)?;
+            }
+            return Ok(buf);
+        }
+
+        let ts_payload = IntegerCodec::encode_dod_payload(timestamps);
+        buf.extend_from_slice(&(ts_payload.len() as u32).to_le_bytes()); // 24..28
+        buf.extend_from_slice(&ts_payload);
+        buf.extend_from_slice(&IntegerCodec::encode_i64_payload(&scaled));
+        Ok(buf)
+    }
+
+    /// Decompress both columns; the scale is read from the blob
+    pub fn decompress(&self, blob: &[u8]) -> Result<(Vec<i64>, Vec<f64>)> {
+        if blob.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let (n, scale, chunks) = open_series(blob)?;
+        let mut timestamps = Vec::with_capacity(reserve_len(n, blob.len()));
+        let mut values = Vec::with_capacity(reserve_len(n, blob.len()));
+        for chunk in &chunks {
+            chunk.decode_each(scale, |t, x| {
+                timestamps.push(t);
+                values.push(x);
+            })?;
+        }
+        Ok((timestamps, values))
+    }
+
+    /// The `(timestamp, value)` pairs with a timestamp in `range`, in
+    /// stored order.
+    ///
+    /// Long series are stored in blocks that carry the min and max of their
+    /// timestamps, so only the blocks covering `range` are decompressed.
+    /// Timestamps need not be sorted; every pair inside `range` is returned.
+    pub fn slice_by_time(&self, blob: &[u8], range: Range<i64>) -> Result<Vec<(i64, f64)>> {
+        if blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (_, scale, chunks) = open_series(blob)?;
+        let mut out = Vec::new();
+        for chunk in chunks.iter().filter(|c| {
+            c.stats
+                .is_none_or(|s| s.min < range.end && s.max >= range.start)
+        }) {
+            chunk.decode_each(scale, |t, x| {
+                if range.contains(&t) {
+                    out.push((t, x));
+                }
+            })?;
+        }
+        Ok(out)
+    }
+
+    /// Compress several value columns that share one timestamp column.
+    ///
+    /// The timestamps are stored once; every column must have the same
+    /// length as `timestamps`.
+    pub fn compress_multi(&self, timestamps: &[i64], columns: &[&[f64]]) -> Result<Vec<u8>> {
+        if let Some(col) = columns.iter().find(|c| c.len() != timestamps.len()) {
+            return Err(invalid!(
+                "series length mismatch: {} timestamps, {} values",
+                timestamps.len(),
+                col.len()
+            ));
+        }
+        if timestamps.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::with_capacity(timestamps.len() * (columns.len() + 1) + 28);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(28); // 7: type (28 = multi-series frame)
+        buf.extend_from_slice(&(timestamps.len() as u64).to_le_bytes()); // 8..16
+        buf.extend_from_slice(&self.scale.to_le_bytes()); // 16..24
+        buf.extend_from_slice(&(columns.len() as u32).to_le_bytes()); // 24..28
+
+        // each payload is prefixed with its byte length
+        let mut push_payload = |payload: Vec<u8>| {
+            buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&payload);
+        };
+        push_payload(IntegerCodec::encode_dod_payload(timestamps));
+        for col in columns {
+            let scaled: Vec<i64> = col
+                .iter()
+                .map(|&f| (f * self.scale).round() as i64)
+                .collect();
+            push_payload(IntegerCodec::encode_i64_payload(&scaled));
+        }
+        Ok(buf)
+    }
+
+    /// Decompress a frame written by [`SeriesCodec::compress_multi`]
+    pub fn decompress_multi(&self, blob: &[u8]) -> Result<(Vec<i64>, Vec<Vec<f64>>)> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let n = open_blob(blob, 28, 28, "multi-series frame")?;
+        let scale = f64::from_le_bytes(blob[16..24].try_into().unwrap());
+        let count = u32::from_le_bytes(blob[24..28].try_into().unwrap()) as usize;
+
+        let mut rest = &blob[28..];
+        let mut next_payload = || -> Result<&[u8]> {
+            if rest.len() < 4 {
+                return Err(CydecError::Truncated {
+                    needed: 4,
+                    got: rest.len(),
+                });
+            }
+            let len = u32::from_le_bytes(rest[0..4].try_into().unwrap()) as usize;
+            if rest.len() - 4 < len {
+                return Err(CydecError::Truncated {
+                    needed: len,
+                    got: rest.len() - 4,
+                });
+            }
+            let (payload, tail) = rest[4..].split_at(len);
+            rest = tail;
+            Ok(payload)
+        };
+        let timestamps = IntegerCodec::decode_dod_payload(next_payload()?, n)?;
+        let columns = (0..count)
+            .map(|_| {
+                Ok(IntegerCodec::decode_i64_payload(next_payload()?, n)?
+                    .into_iter()
+                    .map(|i| i as f64 / scale)
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<f64>>>>()?;
+        Ok((timestamps, columns))
+    }
+}
+
+/// Both columns of a run of points: the whole series, or one block of a
+/// blocked one
+pub(crate) struct Chunk<'a> {
+    pub count: usize,
+    /// Timestamp statistics, stored for blocks
+    pub stats: Option<BlockStats>,
+    pub ts_payload: &'a [u8],
+    pub value_payload: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    /// Split a `[u32 ts payload len][ts payload][value payload]` region
+    fn split(count: usize, stats: Option<BlockStats>, bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < 4 {
+            return Err(CydecError::Truncated {
+                needed: 4,
+                got: bytes.len(),
+            });
+        }
+        let ts_len = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        if bytes.len() - 4 < ts_len {
+            return Err(CydecError::Truncated {
+                needed: ts_len,
+                got: bytes.len() - 4,
+            });
+        }
+        let (ts_payload, value_payload) = bytes[4..].split_at(ts_len);
+        Ok(Self {
+            count,
+            stats,
+            ts_payload,
+            value_payload,
+        })
+    }
+
+    fn decode_each(&self, scale: f64, mut f: impl FnMut(i64, f64)) -> Result<()> {
+        let timestamps = IntegerCodec::decode_dod_payload(self.ts_payload, self.count)?;
+        IntegerCodec::decode_i64_payload_each(
+            self.value_payload,
+            self.count,
+            Backend::Lz4,
+            |i, x| f(timestamps[i], x as f64 / scale),
+        )
+    }
+}
+
+/// Open a single series blob: element count, scale and its chunks
+pub(crate) fn open_series(blob: &[u8]) -> Result<(usize, f64, Vec<Chunk<'_>>)> {
+    let blob = header::strip(blob)?;
+    let blocked = blob.get(7) == Some(&BLOCKED_SERIES);
+    let n = open_blob(
+        blob,
+        if blocked { 24 } else { 28 },
+        if blocked { BLOCKED_SERIES } else { 27 },
+        "series",
+    )?;
+    let scale = f64::from_le_bytes(blob[16..24].try_into().unwrap());
+    if !blocked {
+        return Ok((n, scale, vec![Chunk::split(n, None, &blob[24..])?]));
+    }
+    let (n, blocks) = read_blocks_at(blob, 24, n as u64)?;
+    let chunks = blocks
+        .iter()
+        .map(|b| Chunk::split(b.count, b.stats, b.payload))
+        .collect::<Result<_>>()?;
+    Ok((n, scale, chunks))
+}
+
+/// Check the common header and return the element count
+fn open_blob(blob: &[u8], min_len: usize, type_tag: u8, type_name: &'static str) -> Result<usize> {
+    if blob.len() < min_len {
+        return Err(CydecError::Truncated {
+            needed: min_len,
+            got: blob.len(),
+        });
+    }
+    if &blob[0..5] != b"CYDEC" {
+        return Err(CydecError::BadMagic);
+    }
+    if blob[5] != 1 {
+        return Err(CydecError::unsupported_version(blob[5]));
+    }
+    if blob[6] != 1 {
+        return Err(CydecError::UnsupportedBackend { found: blob[6] });
+    }
+    if blob[7] != type_tag {
+        return Err(CydecError::TypeMismatch {
+            expected: type_name,
+            found: blob[7],
+        });
+    }
+    header::usize_at(blob, 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_series() -> Result<()> {
+        let codec = SeriesCodec::default();
+        let ts: Vec<i64> = (0..10_000).map(|i| 1_700_000_000_000 + i * 1_000).collect();
+        let values: Vec<f64> = (0..10_000)
+            .map(|i| 100.0 + (i as f64 / 50.0).sin())
+            .collect();
+        let blob = codec.compress(&ts, &values)?;
+        let (ts_back, values_back) = codec.decompress(&blob)?;
+        assert_eq!(ts, ts_back);
+        for (original, decompressed) in values.iter().zip(values_back.iter()) {
+            assert!((original - decompressed).abs() < 1e-9);
+        }
+
+        // the regular timestamp column costs almost nothing on top of the values
+        let values_only = FloatingCodec::default().compress_f64(&values, None)?;
+        assert!(blob.len() < values_only.len() + 200);
+        Ok(())
+    }
+
+    #[test]
+    fn irregular_timestamps_roundtrip() -> Result<()> {
+        let codec = SeriesCodec { scale: 100.0 };
+        let ts = vec![5, 3, 1_000_000, i64::MIN, i64::MAX, 0];
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let (ts_back, values_back) = codec.decompress(&codec.compress(&ts, &values)?)?;
+        assert_eq!(ts, ts_back);
+        assert_eq!(values, values_back);
+        Ok(())
+    }
+
+    #[test]
+    fn slice_by_time_reads_covering_blocks() -> Result<()> {
+        let codec = SeriesCodec::default();
+        let ts: Vec<i64> = (0..200_000).map(|i| 1_000 + i * 10).collect();
+        let values: Vec<f64> = (0..200_000).map(|i| (i % 977) as f64 * 0.5).collect();
+        let blob = codec.compress(&ts, &values)?;
+        assert_eq!(blob[7], BLOCKED_SERIES);
+        assert_eq!(codec.decompress(&blob)?, (ts.clone(), values.clone()));
+
+        let expected = |range: Range<i64>| -> Vec<(i64, f64)> {
+            ts.iter()
+                .copied()
+                .zip(values.iter().copied())
+                .filter(|(t, _)| range.contains(t))
+                .collect()
+        };
+        for range in [0..1_005, 655_000..655_400, 1_500_005..9_999_999, 5..5] {
+            assert_eq!(codec.slice_by_time(&blob, range.clone())?, expected(range));
+        }
+
+        // short series are a single chunk, unsorted timestamps are fine
+        let short = codec.compress(&[30, 10, 20], &[3.0, 1.0, 2.0])?;
+        assert_eq!(codec.slice_by_time(&short, 15..31)?, [(30, 3.0), (20, 2.0)]);
+        assert!(codec.slice_by_time(&[], 0..10)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn length_mismatch_is_an_error() {
+        let codec = SeriesCodec::default();
+        assert!(codec.compress(&[1, 2, 3], &[1.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn multi_series_shares_timestamps() -> Result<()> {
+        let codec = SeriesCodec::default();
+        let ts: Vec<i64> = (0..5_000).map(|i| 1_700_000_000_000 + i * 60_000).collect();
+        let columns: Vec<Vec<f64>> = (0..20)
+            .map(|k| {
+                (0..5_000)
+                    .map(|i| k as f64 + (i as f64 / 30.0).cos())
+                    .collect()
+            })
+            .collect();
+        let refs: Vec<&[f64]> = columns.iter().map(|c| c.as_slice()).collect();
+        let blob = codec.compress_multi(&ts, &refs)?;
+        let (ts_back, columns_back) = codec.decompress_multi(&blob)?;
+        assert_eq!(ts, ts_back);
+        assert_eq!(columns.len(), columns_back.len());
+        for (col, back) in columns.iter().zip(columns_back.iter()) {
+            for (original, decompressed) in col.iter().zip(back.iter()) {
+                assert!((original - decompressed).abs() < 1e-9);
+            }
+        }
+
+        // one frame is smaller than twenty single series blobs
+        let separate: usize = refs
+            .iter()
+            .map(|c| codec.compress(&ts, c).map(|b| b.len()))
+            .sum::<Result<usize>>()?;
+        assert!(blob.len() < separate);
+        assert!(codec.decompress(&blob).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn multi_series_length_mismatch_is_an_error() {
+        let codec = SeriesCodec::default();
+        let a = [1.0, 2.0, 3.0];
+        let b = [1.0, 2.0];
+        assert!(codec.compress_multi(&[1, 2, 3], &[&a, &b]).is_err());
+    }
+}
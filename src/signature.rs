@@ -0,0 +1,124 @@
+use crate::error::{Result, invalid};
+use crate::header::{self, Extension, SIGNATURE};
+use crate::{CydecElement, CydecError};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Tag over every extension entry but the signature, in order, then the
+/// wrapped blob. The fixed header repeats the wrapped blob's fields, so
+/// this covers every byte a decoder reads.
+fn tag(blob: &[u8], key: &[u8]) -> Result<HmacSha256> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+    for entry in header::extensions(blob)? {
+        if entry.kind != SIGNATURE {
+            mac.update(&entry.kind.to_le_bytes());
+            mac.update(&(entry.value.len() as u32).to_le_bytes());
+            mac.update(entry.value);
+        }
+    }
+    mac.update(header::strip(blob)?);
+    Ok(mac)
+}
+
+/// Sign `blob` with an HMAC-SHA256 under `key`, replacing any signature it
+/// has; empty blobs stay empty.
+///
+/// The tag covers the payload and the other extensions, so changing a
+/// value or a metadata entry makes [`verify_signature`] fail. Appending
+/// to a signed blob or changing its metadata drops the signature; sign it
+/// again afterwards.
+pub fn with_signature(blob: Vec<u8>, key: &[u8]) -> Result<Vec<u8>> {
+    if blob.is_empty() {
+        return Ok(blob);
+    }
+    let unsigned = header::with_extensions(blob, &[])?;
+    let tag = tag(&unsigned, key)?.finalize().into_bytes();
+    header::with_extensions(
+        unsigned,
+        &[Extension {
+            kind: SIGNATURE,
+            value: &tag,
+        }],
+    )
+}
+
+/// Check the signature of `blob` against `key` in constant time; an
+/// unsigned blob is an error too, so stripping the signature does not get
+/// a tampered blob through
+pub fn verify_signature(blob: &[u8], key: &[u8]) -> Result<()> {
+    let entries = header::extensions(blob)?;
+    let Some(stored) = entries.iter().find(|e| e.kind == SIGNATURE) else {
+        return Err(invalid!("blob is not signed"));
+    };
+    tag(blob, key)?
+        .verify_slice(stored.value)
+        .map_err(|_| CydecError::BadSignature)
+}
+
+/// [`verify_signature`], then decode as `T`
+pub fn decompress_signed<T: CydecElement>(blob: &[u8], key: &[u8]) -> Result<Vec<T>> {
+    verify_signature(blob, key)?;
+    T::decompress_blob(blob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checksum, IntegerCodec};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn tampering_fails_verification() -> Result<()> {
+        let key = b"archive key";
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..10_000).map(|i| i * 3 % 101).collect();
+        let meta = BTreeMap::from([("symbol".to_string(), "ABC".to_string())]);
+        let blob = header::with_metadata(c.compress_i64(&data)?, &meta)?;
+        let blob = header::with_checksum(blob, Checksum::Xxh3)?;
+        let signed = with_signature(blob, key)?;
+        assert_eq!(decompress_signed::<i64>(&signed, key)?, data);
+        // still an ordinary blob for readers without the key
+        assert_eq!(c.decompress_i64(&signed)?, data);
+
+        let wrong_key = decompress_signed::<i64>(&signed, b"other key");
+        assert!(matches!(wrong_key, Err(CydecError::BadSignature)));
+
+        // a forged payload with a matching checksum is still caught
+        let mut forged = c.compress_i64(&[1, 2, 3])?;
+        forged = header::with_metadata(forged, &meta)?;
+        forged = header::with_checksum(forged, Checksum::Xxh3)?;
+        let stolen = &header::extensions(&signed)?
+            .into_iter()
+            .find(|e| e.kind == SIGNATURE)
+            .unwrap()
+            .value
+            .to_vec();
+        let forged = header::with_extensions(
+            forged,
+            &[Extension {
+                kind: SIGNATURE,
+                value: stolen,
+            }],
+        )?;
+        assert!(matches!(
+            verify_signature(&forged, key),
+            Err(CydecError::BadSignature)
+        ));
+
+        // changing the metadata drops the signature instead of keeping a
+        // stale one
+        let relabeled = header::with_metadata(
+            signed.clone(),
+            &BTreeMap::from([("symbol".to_string(), "XYZ".to_string())]),
+        )?;
+        assert!(matches!(
+            verify_signature(&relabeled, key),
+            Err(CydecError::InvalidArgument(_))
+        ));
+        assert!(verify_signature(&c.compress_i64(&data)?, key).is_err());
+        assert_eq!(with_signature(Vec::new(), key)?, Vec::<u8>::new());
+        Ok(())
+    }
+}
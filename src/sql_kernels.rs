@@ -0,0 +1,139 @@
+use crate::Archive;
+use crate::arrow::{compress_array, decode_array};
+use crate::error::{Result, corrupt, invalid};
+use arrow_array::{
+    Array, ArrayRef, BinaryArray, ListArray, RecordBatch, StringArray, UInt8Array, new_empty_array,
+};
+use arrow_buffer::OffsetBuffer;
+use arrow_schema::{DataType, Field, Schema};
+use std::sync::Arc;
+
+/// Compress each row of a list column into a blob of its own, the kernel of
+/// a row-wise `cydec_compress` SQL function. Null rows stay null, and nulls
+/// inside a row survive as they do for [`crate::Codec::compress_arrow`].
+pub fn compress_rows(list: &ListArray) -> Result<BinaryArray> {
+    let blobs = (0..list.len())
+        .map(|i| {
+            (!list.is_null(i))
+                .then(|| compress_array("item", list.value(i).as_ref()))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(blobs.into_iter().collect())
+}
+
+/// Decode each blob of a binary column into a list of `item_type` values,
+/// the kernel of a `cydec_decompress` SQL function; null rows stay null
+pub fn decompress_rows(blobs: &BinaryArray, item_type: &DataType) -> Result<ListArray> {
+    let mut lengths = Vec::with_capacity(blobs.len());
+    let mut rows: Vec<ArrayRef> = Vec::with_capacity(blobs.len());
+    for i in 0..blobs.len() {
+        if blobs.is_null(i) {
+            lengths.push(0);
+            continue;
+        }
+        let row = decode_array("item", blobs.value(i), item_type)?;
+        lengths.push(row.len());
+        rows.push(row);
+    }
+    let values = if rows.is_empty() {
+        new_empty_array(item_type)
+    } else {
+        let rows: Vec<&dyn Array> = rows.iter().map(|row| row.as_ref()).collect();
+        arrow_select::concat::concat(&rows).map_err(|e| invalid!("{e}"))?
+    };
+    ListArray::try_new(
+        Arc::new(Field::new_list_field(item_type.clone(), true)),
+        OffsetBuffer::from_lengths(lengths),
+        values,
+        blobs.nulls().cloned(),
+    )
+    .map_err(|e| corrupt!("{e}"))
+}
+
+impl<B: AsRef<[u8]>> Archive<B> {
+    /// The archive as a table with one row per entry: its `name`, the
+    /// `type_tag` of its header and the `blob` itself, in the order the
+    /// entries were added. A table function over archive files serves this
+    /// batch, and [`decompress_rows`] decodes the blobs.
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        let entries = self.entries();
+        let names: StringArray = entries.iter().map(|e| Some(e.name.as_str())).collect();
+        let tags: UInt8Array = entries.iter().map(|e| e.type_tag).collect();
+        let blobs: BinaryArray = entries.iter().map(|e| self.get(&e.name)).collect();
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("type_tag", DataType::UInt8, false),
+            Field::new("blob", DataType::Binary, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![Arc::new(names), Arc::new(tags), Arc::new(blobs)],
+        )
+        .map_err(|e| corrupt!("{e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArchiveWriter, CydecElement};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type, UInt8Type};
+
+    #[test]
+    fn rows_roundtrip_through_blobs() -> Result<()> {
+        let series = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(
+                (0..500)
+                    .map(|i| Some(1_700_000_000 + i * 60))
+                    .collect::<Vec<_>>(),
+            ),
+            None,
+            Some(vec![Some(7), None, Some(9)]),
+            Some(vec![]),
+        ]);
+        let blobs = compress_rows(&series)?;
+        assert!(blobs.is_null(1));
+        assert!(blobs.value(0).len() < 500);
+        assert_eq!(i64::decompress_blob(blobs.value(0))?[1], 1_700_000_060);
+        let decoded = decompress_rows(&blobs, &DataType::Int64)?;
+        assert_eq!(decoded, series);
+
+        assert!(decompress_rows(&blobs, &DataType::Utf8).is_err());
+        let prices = ListArray::from_iter_primitive::<Float64Type, _, _>(vec![Some(vec![
+            Some(101.25),
+            Some(101.5),
+        ])]);
+        let decoded = decompress_rows(&compress_rows(&prices)?, &DataType::Float64)?;
+        assert_eq!(
+            decoded.value(0).as_primitive::<Float64Type>().values(),
+            &[101.25, 101.5]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn archives_read_as_tables() -> Result<()> {
+        let ts = i64::compress_slice(&[1, 2, 3])?;
+        let close = f64::compress_slice(&[101.25, 101.5, 99.0])?;
+        let mut writer = ArchiveWriter::new(Vec::new())?;
+        writer.add("ts", &ts)?;
+        writer.add("close", &close)?;
+        let archive = Archive::from_bytes(writer.finish()?)?;
+
+        let batch = archive.to_record_batch()?;
+        assert_eq!(batch.num_rows(), 2);
+        let names = batch.column(0).as_string::<i32>();
+        assert_eq!(names.value(1), "close");
+        assert_eq!(batch.column(1).as_primitive::<UInt8Type>().value(0), ts[7]);
+        let blobs = batch.column(2).as_binary::<i32>();
+        assert_eq!(blobs.value(0), ts);
+        let decoded = decompress_rows(&blobs.slice(1, 1), &DataType::Float64)?;
+        assert_eq!(
+            decoded.value(0).as_primitive::<Float64Type>().values(),
+            &[101.25, 101.5, 99.0]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+use crate::blocks::{
+    BLOCKED_FLAG, FILTER_FLAG, STATS_FLAG, STREAMED_LEN, block_header_len, has_flag, push_block,
+};
+use crate::error::{Result, bail};
+use crate::header;
+use crate::{Backend, CydecError, IntegerCodec};
+use std::io::{self, Read, Write};
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for i64 {}
+    impl Sealed for u8 {}
+}
+
+/// Element types that can be streamed: `i64` values, or `u8` for raw bytes.
+///
+/// Both use the blocked layout, so a finished stream is an ordinary blob
+/// for [`IntegerCodec::decompress_i64`] or [`IntegerCodec::decompress_bytes`].
+pub trait StreamElement: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    const TYPE_TAG: u8;
+    #[doc(hidden)]
+    const TYPE_NAME: &'static str;
+    #[doc(hidden)]
+    fn encode_block(values: &[Self], backend: Backend) -> Vec<u8>;
+    #[doc(hidden)]
+    fn decode_block(payload: &[u8], count: usize, backend: Backend) -> Result<Vec<Self>>;
+}
+
+impl StreamElement for i64 {
+    const TYPE_TAG: u8 = 0;
+    const TYPE_NAME: &'static str = "i64";
+
+    fn encode_block(values: &[Self], backend: Backend) -> Vec<u8> {
+        IntegerCodec::encode_i64_payload_with(values, backend, 0)
+    }
+
+    fn decode_block(payload: &[u8], count: usize, backend: Backend) -> Result<Vec<Self>> {
+        IntegerCodec::decode_i64_payload_with(payload, count, backend)
+    }
+}
+
+impl StreamElement for u8 {
+    const TYPE_TAG: u8 = 4;
+    const TYPE_NAME: &'static str = "raw bytes";
+
+    fn encode_block(values: &[Self], backend: Backend) -> Vec<u8> {
+        backend.pack(values, 0)
+    }
+
+    fn decode_block(payload: &[u8], count: usize, backend: Backend) -> Result<Vec<Self>> {
+        let raw = backend.unpack(payload)?;
+        if raw.len() != count {
+            bail!("decompressed length mismatch");
+        }
+        Ok(raw)
+    }
+}
+
+/// Serialize one block of a stream, preceded by the stream header when it
+/// is the first one
+pub(crate) fn encode_stream_block<T: StreamElement>(
+    values: &[T],
+    backend: Backend,
+    first: bool,
+) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if first {
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(backend.id()); // 6: codec
+        buf.push(T::TYPE_TAG | BLOCKED_FLAG); // 7: type, blocked
+        buf.extend_from_slice(&STREAMED_LEN.to_le_bytes()); // 8..16
+    }
+    push_block(
+        &mut buf,
+        values.len(),
+        None,
+        &T::encode_block(values, backend),
+    )?;
+    Ok(buf)
+}
+
+/// Compresses values as they arrive and writes each full block straight to
+/// `W`, so a series never has to fit in memory.
+///
+/// Each block restarts the delta chain, which keeps blocks independent at
+/// the cost of one absolute value per block. The header's length field is
+/// left as a "streamed" marker because the total is unknown while writing;
+/// decoders take the sum of the block counts instead. Nothing is written
+/// for an empty stream, matching the empty blob of the one-shot API.
+///
+/// Call [`CydecWriter::finish`] to write the last partial block; dropping
+/// the writer discards it.
+///
+/// ```
+/// use cydec::{CydecWriter, IntegerCodec};
+///
+/// let mut w = CydecWriter::new(Vec::new());
+/// for day in 0..10i64 {
+///     w.write_values(&[day * 86_400, day * 86_400 + 60])?;
+/// }
+/// let blob = w.finish()?;
+/// assert_eq!(IntegerCodec::default().decompress_i64(&blob)?.len(), 20);
+/// # Ok::<(), cydec::CydecError>(())
+/// ```
+#[derive(Debug)]
+pub struct CydecWriter<W: Write, T: StreamElement = i64> {
+    inner: W,
+    backend: Backend,
+    block_len: usize,
+    pending: Vec<T>,
+    started: bool,
+}
+
+impl<W: Write, T: StreamElement> CydecWriter<W, T> {
+    pub const DEFAULT_BLOCK_LEN: usize = 65_536;
+
+    pub fn new(inner: W) -> Self {
+        Self::with_backend(inner, Backend::Lz4)
+    }
+
+    pub fn with_backend(inner: W, backend: Backend) -> Self {
+        Self {
+            inner,
+            backend,
+            block_len: Self::DEFAULT_BLOCK_LEN,
+            pending: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Values per block; larger blocks compress better but buffer more
+    pub fn with_block_len(mut self, block_len: usize) -> Self {
+        assert!(
+            block_len > 0 && block_len <= u32::MAX as usize,
+            "block length must fit in a block header"
+        );
+        self.block_len = block_len;
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn write_values(&mut self, values: &[T]) -> Result<()> {
+        let mut values = values;
+        while !values.is_empty() {
+            let take = (self.block_len - self.pending.len()).min(values.len());
+            self.pending.extend_from_slice(&values[..take]);
+            values = &values[take..];
+            if self.pending.len() == self.block_len {
+                self.write_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the buffered partial block and return the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        if !self.pending.is_empty() {
+            self.write_block()?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_block(&mut self) -> Result<()> {
+        let buf = encode_stream_block(&self.pending, self.backend, !self.started)?;
+        self.inner.write_all(&buf)?;
+        self.started = true;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Raw bytes go through the byte codec; `flush` only flushes what full
+/// blocks have already been written, the partial block waits for
+/// [`CydecWriter::finish`].
+impl<W: Write> Write for CydecWriter<W, u8> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_values(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Fill `buf` from `r`, stopping early only at end of input
+fn read_full(r: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(k) => filled += k,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// Decoding progress shared by the blocking and async readers, which only
+/// differ in how they pull bytes.
+#[derive(Debug)]
+pub(crate) struct StreamState<T> {
+    /// Set once the header has been read
+    pub backend: Option<Backend>,
+    pub blocked: bool,
+    /// Block statistics between each block header and its payload, skipped
+    pub stats_len: usize,
+    /// A Bloom filter follows the blocks, so stop after the declared length
+    filtered: bool,
+    /// Length from the header, checked against the values read at the end
+    pub declared: u64,
+    read: u64,
+    block: Vec<T>,
+    pos: usize,
+    pub done: bool,
+}
+
+impl<T: StreamElement> StreamState<T> {
+    pub fn new() -> Self {
+        Self {
+            backend: None,
+            blocked: false,
+            stats_len: 0,
+            filtered: false,
+            declared: 0,
+            read: 0,
+            block: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Decoded values not handed out yet
+    pub fn buffered(&self) -> &[T] {
+        &self.block[self.pos..]
+    }
+
+    pub fn consume(&mut self, k: usize) {
+        self.pos += k;
+    }
+
+    /// Validate the blob header and remember its layout
+    pub fn start(&mut self, header: &[u8; 16]) -> Result<()> {
+        if &header[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if header[5] != 1 {
+            return Err(CydecError::unsupported_version(header[5]));
+        }
+        let backend = Backend::from_id(header[6])?;
+        let flags = header[7] & (BLOCKED_FLAG | STATS_FLAG | FILTER_FLAG);
+        if header[7] & !flags != T::TYPE_TAG || (flags != 0 && flags & BLOCKED_FLAG == 0) {
+            return Err(CydecError::TypeMismatch {
+                expected: T::TYPE_NAME,
+                found: header[7],
+            });
+        }
+        self.backend = Some(backend);
+        self.blocked = flags != 0;
+        self.stats_len = block_header_len(header[7]) - 8;
+        self.filtered = has_flag(header[7], FILTER_FLAG);
+        self.declared = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        Ok(())
+    }
+
+    pub fn push(&mut self, values: Vec<T>) {
+        self.read += values.len() as u64;
+        self.block = values;
+        self.pos = 0;
+    }
+
+    /// Every block has been read and only a trailer is left
+    pub fn at_trailer(&self) -> bool {
+        self.filtered && self.read == self.declared
+    }
+
+    /// Input is exhausted; check the total against the header
+    pub fn end(&mut self) -> Result<()> {
+        self.done = true;
+        if self.backend.is_some() && self.declared != STREAMED_LEN && self.read != self.declared {
+            bail!(
+                "block counts add up to {}, header says {}",
+                self.read,
+                self.declared
+            );
+        }
+        Ok(())
+    }
+
+    /// Stop after an error so iteration does not resume mid-stream
+    pub fn fail(&mut self) {
+        self.done = true;
+        self.block.clear();
+        self.pos = 0;
+    }
+}
+
+/// Split a block header into value count and payload length
+pub(crate) fn block_header(header: &[u8; 8]) -> (usize, usize) {
+    let count = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+    let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+    (count, len)
+}
+
+/// Decodes a blob one block at a time as it is read from `R`, so the full
+/// array is never materialized.
+///
+/// Reads what [`CydecWriter`] writes as well as blocked blobs from
+/// [`IntegerCodec::append_i64`] and [`IntegerCodec::concat_i64`]. A plain
+/// (unblocked) blob is a single block, so it is read whole before the first
+/// value comes out.
+///
+/// ```
+/// use cydec::{CydecReader, IntegerCodec};
+///
+/// let blob = IntegerCodec::default().compress_i64(&[3, 1, 4, 1, 5])?;
+/// let values: Vec<i64> = CydecReader::new(blob.as_slice()).collect::<Result<_, _>>()?;
+/// assert_eq!(values, [3, 1, 4, 1, 5]);
+/// # Ok::<(), cydec::CydecError>(())
+/// ```
+#[derive(Debug)]
+pub struct CydecReader<R: Read, T: StreamElement = i64> {
+    inner: R,
+    state: StreamState<T>,
+}
+
+impl<R: Read, T: StreamElement> CydecReader<R, T> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            state: StreamState::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Copy the next values into `out`; returns how many were written,
+    /// 0 once the stream is exhausted
+    pub fn read_values(&mut self, out: &mut [T]) -> Result<usize> {
+        let mut written = 0;
+        while written < out.len() && self.fill_block()? {
+            let buffered = self.state.buffered();
+            let take = buffered.len().min(out.len() - written);
+            out[written..written + take].copy_from_slice(&buffered[..take]);
+            self.state.consume(take);
+            written += take;
+        }
+        Ok(written)
+    }
+
+    /// Make sure a value is buffered; `false` at the end of the stream
+    fn fill_block(&mut self) -> Result<bool> {
+        while self.state.buffered().is_empty() && !self.state.done {
+            let Some(backend) = self.state.backend else {
+                let mut header = [0u8; 16];
+                match read_full(&mut self.inner, &mut header)? {
+                    // empty input is an empty blob
+                    0 => self.state.end()?,
+                    // skip the extension area; the wrapped blob's header follows
+                    16 if header::is_v2(&header) => {
+                        let mut len = [0u8; 4];
+                        let got = read_full(&mut self.inner, &mut len)?;
+                        if got < 4 {
+                            return Err(CydecError::Truncated { needed: 4, got });
+                        }
+                        let area = u32::from_le_bytes(len) as u64;
+                        let skipped = io::copy(&mut (&mut self.inner).take(area), &mut io::sink())?;
+                        if skipped < area {
+                            return Err(CydecError::Truncated {
+                                needed: area as usize,
+                                got: skipped as usize,
+                            });
+                        }
+                    }
+                    16 => self.state.start(&header)?,
+                    got => return Err(CydecError::Truncated { needed: 16, got }),
+                }
+                continue;
+            };
+            if !self.state.blocked {
+                let mut payload = Vec::new();
+                self.inner.read_to_end(&mut payload)?;
+                let n = self.state.declared as usize;
+                self.state.push(T::decode_block(&payload, n, backend)?);
+                self.state.end()?;
+                continue;
+            }
+            if self.state.at_trailer() {
+                self.state.end()?;
+                continue;
+            }
+            let mut header = [0u8; 8];
+            match read_full(&mut self.inner, &mut header)? {
+                0 => self.state.end()?,
+                8 => {
+                    let (count, len) = block_header(&header);
+                    let skip = self.state.stats_len;
+                    let mut payload = vec![0u8; skip + len];
+                    let got = read_full(&mut self.inner, &mut payload)?;
+                    if got < skip + len {
+                        return Err(CydecError::Truncated {
+                            needed: skip + len,
+                            got,
+                        });
+                    }
+                    self.state
+                        .push(T::decode_block(&payload[skip..], count, backend)?);
+                }
+                got => return Err(CydecError::Truncated { needed: 8, got }),
+            }
+        }
+        Ok(!self.state.buffered().is_empty())
+    }
+}
+
+/// Yields values until the end of the stream or the first error
+impl<R: Read, T: StreamElement> Iterator for CydecReader<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        match self.fill_block() {
+            Ok(true) => {
+                let x = self.state.buffered()[0];
+                self.state.consume(1);
+                Some(Ok(x))
+            }
+            Ok(false) => None,
+            Err(e) => {
+                self.state.fail();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for CydecReader<R, u8> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_values(buf).map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn streamed_values_decode_as_one_blob() -> Result<()> {
+        let c = IntegerCodec::default();
+        let data: Vec<i64> = (0..100_000)
+            .map(|i| 1_700_000_000 + i * 15 + i % 7)
+            .collect();
+        let mut w = CydecWriter::new(Vec::new()).with_block_len(10_000);
+        for chunk in data.chunks(777) {
+            w.write_values(chunk)?;
+        }
+        let blob = w.finish()?;
+        assert_eq!(data, c.decompress_i64(&blob)?);
+        assert_eq!(data, c.decompress_i64_iter(&blob)?.collect::<Vec<_>>());
+
+        let empty = CydecWriter::<_, i64>::new(Vec::new()).finish()?;
+        assert!(empty.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn streamed_bytes_through_io_write() -> Result<()> {
+        let text = "streaming bytes through std::io::Write\n".repeat(5_000);
+        let mut w = CydecWriter::<_, u8>::new(Vec::new()).with_block_len(4_096);
+        w.write_all(text.as_bytes())?;
+        let blob = w.finish()?;
+        assert!(blob.len() < text.len() / 10);
+        assert_eq!(
+            text.as_bytes(),
+            IntegerCodec::default().decompress_bytes(&blob)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reader_decodes_written_and_plain_blobs() -> Result<()> {
+        let data: Vec<i64> = (0..50_000).map(|i| i * i % 1_009 - 500).collect();
+        let mut w = CydecWriter::new(Vec::new()).with_block_len(4_096);
+        w.write_values(&data)?;
+        let streamed = w.finish()?;
+
+        let mut r = CydecReader::new(streamed.as_slice());
+        let mut out = Vec::new();
+        let mut buf = [0i64; 333];
+        loop {
+            let k = r.read_values(&mut buf)?;
+            if k == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..k]);
+        }
+        assert_eq!(data, out);
+
+        let plain = IntegerCodec::default().compress_i64(&data)?;
+        let out: Vec<i64> = CydecReader::new(plain.as_slice()).collect::<Result<_>>()?;
+        assert_eq!(data, out);
+        assert_eq!(CydecReader::<_, i64>::new(&[][..]).count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn reader_streams_bytes_and_reports_truncation() -> Result<()> {
+        let text = "read me back in pieces\n".repeat(3_000);
+        let mut w = CydecWriter::<_, u8>::new(Vec::new()).with_block_len(1_000);
+        w.write_all(text.as_bytes())?;
+        let blob = w.finish()?;
+
+        let mut out = String::new();
+        CydecReader::<_, u8>::new(blob.as_slice()).read_to_string(&mut out)?;
+        assert_eq!(text, out);
+
+        let cut = &blob[..blob.len() - 3];
+        let err = CydecReader::<_, u8>::new(cut).find_map(|r| r.err());
+        assert!(matches!(err, Some(CydecError::Truncated { .. })));
+        assert!(
+            CydecReader::<_, i64>::new(blob.as_slice())
+                .next()
+                .unwrap()
+                .is_err()
+        );
+        Ok(())
+    }
+}
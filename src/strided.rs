@@ -0,0 +1,194 @@
+use crate::error::{Result, invalid};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+/// Read-only view of `len` values spaced `stride` elements apart, such as
+/// one column of a row-major matrix or an `ndarray::ArrayView1`.
+///
+/// [`IntegerCodec::compress_i64_strided`](crate::IntegerCodec::compress_i64_strided)
+/// and
+/// [`FloatingCodec::compress_f64_strided`](crate::FloatingCodec::compress_f64_strided)
+/// read the values through the view, so a column compresses without being
+/// gathered into a `Vec` first, into the same bytes as a slice of it would.
+#[derive(Clone, Copy, Debug)]
+pub struct Strided<'a, T> {
+    ptr: *const T,
+    len: usize,
+    stride: isize,
+    data: PhantomData<&'a [T]>,
+}
+
+// SAFETY: a view only reads, like the `&[T]` it stands for
+unsafe impl<T: Sync> Send for Strided<'_, T> {}
+unsafe impl<T: Sync> Sync for Strided<'_, T> {}
+
+impl<'a, T: Copy> Strided<'a, T> {
+    /// `len` values of `data` from index `start`, `stride` apart
+    pub fn new(data: &'a [T], start: usize, len: usize, stride: usize) -> Result<Self> {
+        let last = len
+            .checked_sub(1)
+            .map_or(Some(start), |k| k.checked_mul(stride)?.checked_add(start));
+        let fits = match last {
+            Some(last) if len == 0 => last <= data.len(),
+            Some(last) => last < data.len(),
+            None => false,
+        };
+        if !fits || isize::try_from(stride).is_err() {
+            return Err(invalid!(
+                "{len} values {stride} apart from index {start} overrun {} values",
+                data.len()
+            ));
+        }
+        Ok(Self {
+            ptr: data.as_ptr().wrapping_add(start),
+            len,
+            stride: stride as isize,
+            data: PhantomData,
+        })
+    }
+
+    /// Column `col` of the row-major matrix `data` with `cols` columns
+    pub fn column(data: &'a [T], cols: usize, col: usize) -> Result<Self> {
+        if col >= cols || !data.len().is_multiple_of(cols) {
+            return Err(invalid!(
+                "no column {col} in {} values of {cols} columns",
+                data.len()
+            ));
+        }
+        Self::new(data, col, data.len() / cols, cols)
+    }
+
+    /// View of `len` values at `ptr`, `stride` elements apart.
+    ///
+    /// # Safety
+    ///
+    /// For every `i` below `len`, `ptr.offset(i * stride)` must point to an
+    /// initialized `T` that stays valid and unmodified for `'a`.
+    pub unsafe fn from_raw_parts(ptr: *const T, len: usize, stride: isize) -> Self {
+        Self {
+            ptr,
+            len,
+            stride,
+            data: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        // SAFETY: in bounds, as every constructor checks or requires
+        (index < self.len).then(|| unsafe { *self.ptr.offset(index as isize * self.stride) })
+    }
+
+    /// The values in order
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + 'a {
+        let Self { ptr, stride, .. } = *self;
+        // SAFETY: as for `get`
+        (0..self.len).map(move |i| unsafe { *ptr.offset(i as isize * stride) })
+    }
+
+    /// The values at `range`, which must lie within the view
+    pub(crate) fn slice(&self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.len);
+        Self {
+            ptr: self.ptr.wrapping_offset(range.start as isize * self.stride),
+            len: range.len(),
+            ..*self
+        }
+    }
+}
+
+impl<'a, T: Copy> From<&'a [T]> for Strided<'a, T> {
+    fn from(data: &'a [T]) -> Self {
+        Self {
+            ptr: data.as_ptr(),
+            len: data.len(),
+            stride: 1,
+            data: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Copy> From<&'a Vec<T>> for Strided<'a, T> {
+    fn from(data: &'a Vec<T>) -> Self {
+        data.as_slice().into()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<'a, T: Copy> From<ndarray::ArrayView1<'a, T>> for Strided<'a, T> {
+    fn from(view: ndarray::ArrayView1<'a, T>) -> Self {
+        // SAFETY: the view's own layout, borrowed for 'a
+        unsafe { Self::from_raw_parts(view.as_ptr(), view.len(), view.strides()[0]) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FloatingCodec, IntegerCodec};
+
+    #[test]
+    fn strided_columns_compress_like_slices() -> Result<()> {
+        let (rows, cols) = (70_000, 3);
+        let matrix: Vec<i64> = (0..rows * cols).map(|i| (i as i64 * 37) % 1_009).collect();
+        let ints = IntegerCodec::default();
+        for col in 0..cols {
+            let column: Vec<i64> = matrix.iter().skip(col).step_by(cols).copied().collect();
+            let view = Strided::column(&matrix, cols, col)?;
+            assert_eq!(view.len(), rows);
+            assert_eq!(
+                ints.compress_i64_strided(view)?,
+                ints.compress_i64(&column)?
+            );
+        }
+        let short = Strided::new(&matrix, 5, 100, 7)?;
+        let expected: Vec<i64> = matrix[5..].iter().step_by(7).take(100).copied().collect();
+        assert_eq!(short.iter().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            ints.compress_i64_strided(short)?,
+            ints.compress_i64(&expected)?
+        );
+
+        let floats = FloatingCodec::default();
+        let prices: Vec<f64> = matrix.iter().map(|&x| x as f64 / 8.0).collect();
+        let column: Vec<f64> = prices.iter().skip(1).step_by(cols).copied().collect();
+        assert_eq!(
+            floats.compress_f64_strided(Strided::column(&prices, cols, 1)?, None)?,
+            floats.compress_f64(&column, None)?
+        );
+
+        assert!(Strided::new(&matrix, 0, rows + 1, cols).is_err());
+        assert!(Strided::new(&matrix, matrix.len(), 0, 1)?.is_empty());
+        assert!(Strided::column(&matrix, cols, cols).is_err());
+        assert!(Strided::column(&matrix[1..], cols, 0).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_views_need_no_copy() -> Result<()> {
+        let a = ndarray::Array2::from_shape_fn((500, 4), |(r, c)| (r * 10 + c) as i64);
+        let ints = IntegerCodec::default();
+        for col in 0..4 {
+            let expected: Vec<i64> = a.column(col).to_vec();
+            assert_eq!(
+                ints.compress_i64_strided(a.column(col))?,
+                ints.compress_i64(&expected)?
+            );
+        }
+        let reversed = a.column(2).slice_move(ndarray::s![..;-1]);
+        let expected: Vec<i64> = reversed.to_vec();
+        assert_eq!(
+            ints.compress_i64_strided(reversed)?,
+            ints.compress_i64(&expected)?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+//! Canonical datasets and their exact compressed bytes, for checking that a
+//! port of the format (Python, Go, ...) reads and writes blobs byte for byte
+//! like this crate.
+//!
+//! [`generate`] is deterministic: the inputs come from fixed formulas and a
+//! fixed-seed generator, so every call and every platform produces the same
+//! vectors. [`write_dir`] dumps them as plain files for tooling outside Rust.
+
+use crate::error::Result;
+use crate::{Backend, CompressOptions, FloatingCodec, IntegerCodec};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Input values of a [`TestVector`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum VectorInput {
+    I64(Vec<i64>),
+    U64(Vec<u64>),
+    I32(Vec<i32>),
+    U32(Vec<u32>),
+    I16(Vec<i16>),
+    U16(Vec<u16>),
+    I8(Vec<i8>),
+    Bytes(Vec<u8>),
+    /// `None` compresses with [`FloatingCodec::DEFAULT_F64_SCALE`]
+    F64 {
+        values: Vec<f64>,
+        scale: Option<f64>,
+    },
+    /// `None` compresses with [`FloatingCodec::DEFAULT_F32_SCALE`]
+    F32 {
+        values: Vec<f32>,
+        scale: Option<f32>,
+    },
+}
+
+impl VectorInput {
+    /// Element type, named as in [`crate::CydecError::TypeMismatch`]
+    pub fn element_type(&self) -> &'static str {
+        match self {
+            VectorInput::I64(_) => "i64",
+            VectorInput::U64(_) => "u64",
+            VectorInput::I32(_) => "i32",
+            VectorInput::U32(_) => "u32",
+            VectorInput::I16(_) => "i16",
+            VectorInput::U16(_) => "u16",
+            VectorInput::I8(_) => "i8",
+            VectorInput::Bytes(_) => "bytes",
+            VectorInput::F64 { .. } => "f64",
+            VectorInput::F32 { .. } => "f32",
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            VectorInput::I64(v) => v.len(),
+            VectorInput::U64(v) => v.len(),
+            VectorInput::I32(v) => v.len(),
+            VectorInput::U32(v) => v.len(),
+            VectorInput::I16(v) => v.len(),
+            VectorInput::U16(v) => v.len(),
+            VectorInput::I8(v) => v.len(),
+            VectorInput::Bytes(v) => v.len(),
+            VectorInput::F64 { values, .. } => values.len(),
+            VectorInput::F32 { values, .. } => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The values as little-endian bytes, packed back to back
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        fn pack<T, const N: usize>(values: &[T], f: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+            values.iter().flat_map(f).collect()
+        }
+        match self {
+            VectorInput::I64(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::U64(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::I32(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::U32(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::I16(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::U16(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::I8(v) => pack(v, |x| x.to_le_bytes()),
+            VectorInput::Bytes(v) => v.clone(),
+            VectorInput::F64 { values, .. } => pack(values, |x| x.to_le_bytes()),
+            VectorInput::F32 { values, .. } => pack(values, |x| x.to_le_bytes()),
+        }
+    }
+}
+
+/// One canonical dataset and the blob this crate writes for it
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestVector {
+    /// Stable identifier, also used as the file name by [`write_dir`]
+    pub name: &'static str,
+    pub input: VectorInput,
+    /// Metadata passed through [`CompressOptions::metadata`]
+    pub metadata: BTreeMap<String, String>,
+    /// Final compression stage the blob was written with
+    pub backend: Backend,
+    /// The exact compressed bytes
+    pub bytes: Vec<u8>,
+}
+
+/// 64-bit LCG (Knuth's MMIX constants), so the inputs do not depend on the
+/// `rand` crate's algorithms staying the same
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    /// Uniform in `-spread..=spread`
+    fn step(&mut self, spread: i64) -> i64 {
+        (self.next() % (2 * spread as u64 + 1)) as i64 - spread
+    }
+
+    fn walk(&mut self, start: i64, spread: i64, n: usize) -> Vec<i64> {
+        let mut x = start;
+        (0..n)
+            .map(|_| {
+                x += self.step(spread);
+                x
+            })
+            .collect()
+    }
+}
+
+/// Build every canonical vector and compress it with the default codecs
+pub fn generate() -> Result<Vec<TestVector>> {
+    let mut rng = Lcg(0x5eed);
+    let walk = rng.walk(1_000_000, 50, 10_000);
+    let counters = {
+        let mut total = 1u64 << 40;
+        (0..5_000)
+            .map(|_| {
+                total += rng.next() % 1_000;
+                total
+            })
+            .collect()
+    };
+    let prices = rng
+        .walk(10_000, 25, 10_000)
+        .into_iter()
+        .map(|x| x as f64 / 100.0)
+        .collect();
+    let text = "the quick brown fox jumps over the lazy dog. "
+        .repeat(40)
+        .into_bytes();
+    let metadata = BTreeMap::from([
+        ("name".to_string(), "ramp".to_string()),
+        ("unit".to_string(), "s".to_string()),
+    ]);
+
+    let inputs = [
+        ("i64_empty", VectorInput::I64(Vec::new())),
+        ("i64_single", VectorInput::I64(vec![42])),
+        ("i64_ramp", VectorInput::I64((0..1_000).collect())),
+        ("i64_walk", VectorInput::I64(walk.clone())),
+        (
+            "i64_extremes",
+            VectorInput::I64(vec![i64::MIN, -1, 0, 1, i64::MAX, i64::MIN, i64::MAX]),
+        ),
+        (
+            "i64_blocked",
+            VectorInput::I64(rng.walk(0, 1_000, IntegerCodec::BLOCK_LEN + 5_000)),
+        ),
+        ("u64_counters", VectorInput::U64(counters)),
+        (
+            "i32_walk",
+            VectorInput::I32(walk.iter().map(|&x| x as i32 - 1_000_000).collect()),
+        ),
+        (
+            "u32_ramp",
+            VectorInput::U32((0..2_000).map(|i| i * 3).collect()),
+        ),
+        (
+            "i16_wave",
+            VectorInput::I16((0..2_000).map(|i| (i % 200 - 100) * 300).collect()),
+        ),
+        ("u16_ramp", VectorInput::U16((0..2_000).collect())),
+        (
+            "i8_wave",
+            VectorInput::I8((0..1_000).map(|i| (i % 256 - 128) as i8).collect()),
+        ),
+        ("bytes_text", VectorInput::Bytes(text)),
+        (
+            "f64_prices",
+            VectorInput::F64 {
+                values: prices,
+                scale: Some(100.0),
+            },
+        ),
+        (
+            "f64_default_scale",
+            VectorInput::F64 {
+                values: (0..1_000).map(|i| i as f64 * 0.125 - 60.0).collect(),
+                scale: None,
+            },
+        ),
+        (
+            "f32_sensor",
+            VectorInput::F32 {
+                values: (0..1_000).map(|i| (i % 97) as f32 * 0.5).collect(),
+                scale: Some(1_000.0),
+            },
+        ),
+    ];
+
+    let mut vectors = inputs
+        .into_iter()
+        .map(|(name, input)| build(name, input, CompressOptions::default()))
+        .collect::<Result<Vec<_>>>()?;
+    vectors.push(build(
+        "i64_metadata",
+        VectorInput::I64((0..1_000).collect()),
+        CompressOptions {
+            metadata,
+            ..Default::default()
+        },
+    )?);
+    vectors.push(build(
+        "i64_lz4_frame",
+        VectorInput::I64((0..1_000).collect()),
+        CompressOptions {
+            backend: Backend::Lz4Frame,
+            ..Default::default()
+        },
+    )?);
+    Ok(vectors)
+}
+
+fn build(name: &'static str, input: VectorInput, options: CompressOptions) -> Result<TestVector> {
+    let ints = IntegerCodec::default();
+    let floats = FloatingCodec::default();
+    let bytes = match &input {
+        VectorInput::I64(v) => ints.compress_i64_with(v, &options)?,
+        VectorInput::U64(v) => ints.compress_u64_with(v, &options)?,
+        VectorInput::I32(v) => ints.compress_i32(v)?,
+        VectorInput::U32(v) => ints.compress_u32(v)?,
+        VectorInput::I16(v) => ints.compress_i16(v)?,
+        VectorInput::U16(v) => ints.compress_u16(v)?,
+        VectorInput::I8(v) => ints.compress_i8(v)?,
+        VectorInput::Bytes(v) => ints.compress_bytes(v)?,
+        VectorInput::F64 { values, scale } => {
+            let options = CompressOptions {
+                scale: *scale,
+                ..options.clone()
+            };
+            floats.compress_f64_with(values, &options)?
+        }
+        VectorInput::F32 { values, scale } => floats.compress_f32(values, *scale)?,
+    };
+    Ok(TestVector {
+        name,
+        input,
+        metadata: options.metadata,
+        backend: options.backend,
+        bytes,
+    })
+}
+
+/// Write every vector to `dir` as `<name>.input` (the values, little
+/// endian, back to back) and `<name>.cydec` (the blob), plus an `index.tsv`
+/// listing name, element type, element count and scale, one vector per line
+pub fn write_dir(dir: impl AsRef<Path>) -> Result<()> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut index = String::from("name\ttype\tlen\tscale\n");
+    for vector in generate()? {
+        std::fs::write(
+            dir.join(format!("{}.input", vector.name)),
+            vector.input.to_le_bytes(),
+        )?;
+        std::fs::write(dir.join(format!("{}.cydec", vector.name)), &vector.bytes)?;
+        let scale = match vector.input {
+            VectorInput::F64 { scale, .. } => scale.map(|s| s.to_string()),
+            VectorInput::F32 { scale, .. } => scale.map(|s| s.to_string()),
+            _ => None,
+        };
+        let _ = writeln!(
+            index,
+            "{}\t{}\t{}\t{}",
+            vector.name,
+            vector.input.element_type(),
+            vector.input.len(),
+            scale.as_deref().unwrap_or("-"),
+        );
+    }
+    std::fs::write(dir.join("index.tsv"), index)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_are_deterministic_and_decode() -> Result<()> {
+        let vectors = generate()?;
+        assert_eq!(vectors, generate()?);
+        let ints = IntegerCodec::default();
+        let floats = FloatingCodec::default();
+        for v in &vectors {
+            let ok = match &v.input {
+                VectorInput::I64(x) => *x == ints.decompress_i64(&v.bytes)?,
+                VectorInput::U64(x) => *x == ints.decompress_u64(&v.bytes)?,
+                VectorInput::I32(x) => *x == ints.decompress_i32(&v.bytes)?,
+                VectorInput::U32(x) => *x == ints.decompress_u32(&v.bytes)?,
+                VectorInput::I16(x) => *x == ints.decompress_i16(&v.bytes)?,
+                VectorInput::U16(x) => *x == ints.decompress_u16(&v.bytes)?,
+                VectorInput::I8(x) => *x == ints.decompress_i8(&v.bytes)?,
+                VectorInput::Bytes(x) => *x == ints.decompress_bytes(&v.bytes)?,
+                VectorInput::F64 { values, .. } => {
+                    *values == floats.decompress_f64(&v.bytes, None)?
+                }
+                VectorInput::F32 { values, .. } => {
+                    *values == floats.decompress_f32(&v.bytes, None)?
+                }
+            };
+            assert!(ok, "{} does not round-trip", v.name);
+            assert_eq!(crate::inspect(&v.bytes)?.metadata, v.metadata);
+        }
+
+        // pinned bytes, so an accidental format change fails here first
+        let ramp = vectors.iter().find(|v| v.name == "i64_ramp").unwrap();
+        assert_eq!(ramp.bytes[..16], *b"CYDEC\x01\x01\x00\xe8\x03\0\0\0\0\0\0");
+        Ok(())
+    }
+
+    #[test]
+    fn write_dir_dumps_inputs_and_blobs() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("cydec-vectors-{}", std::process::id()));
+        write_dir(&dir)?;
+        let index = std::fs::read_to_string(dir.join("index.tsv"))?;
+        assert!(index.contains("f64_prices\tf64\t10000\t100\n"));
+        let input = std::fs::read(dir.join("u16_ramp.input"))?;
+        assert_eq!(input.len(), 4_000);
+        assert_eq!(input[2..4], [1, 0]);
+        let blob = std::fs::read(dir.join("u16_ramp.cydec"))?;
+        assert_eq!(
+            IntegerCodec::default().decompress_u16(&blob)?,
+            (0..2_000).collect::<Vec<u16>>()
+        );
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
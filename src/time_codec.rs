@@ -0,0 +1,220 @@
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::header;
+use crate::{CydecError, IntegerCodec};
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Resolution timestamps and durations are normalized to before compression
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimeUnit {
+    fn id(self) -> u8 {
+        match self {
+            TimeUnit::Seconds => 0,
+            TimeUnit::Millis => 1,
+            TimeUnit::Micros => 2,
+            TimeUnit::Nanos => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        Ok(match id {
+            0 => TimeUnit::Seconds,
+            1 => TimeUnit::Millis,
+            2 => TimeUnit::Micros,
+            3 => TimeUnit::Nanos,
+            _ => bail!("unknown time unit {id}"),
+        })
+    }
+}
+
+/// Codec for `DateTime<Utc>` and `TimeDelta` columns.
+///
+/// Values are normalized to i64 ticks of [`TimeCodec::unit`] and compressed
+/// with the i64 pipeline; the unit is recorded in the header so decompression
+/// rebuilds the typed values regardless of how the codec is configured.
+/// Sub-unit precision is truncated towards negative infinity.
+#[derive(Clone, Debug)]
+pub struct TimeCodec {
+    pub unit: TimeUnit,
+}
+
+impl Default for TimeCodec {
+    fn default() -> Self {
+        Self {
+            unit: TimeUnit::Nanos,
+        }
+    }
+}
+
+impl TimeCodec {
+    pub fn compress_datetimes(&self, data: &[DateTime<Utc>]) -> Result<Vec<u8>> {
+        let ticks = data
+            .iter()
+            .map(|dt| match self.unit {
+                TimeUnit::Seconds => Some(dt.timestamp()),
+                TimeUnit::Millis => Some(dt.timestamp_millis()),
+                TimeUnit::Micros => Some(dt.timestamp_micros()),
+                TimeUnit::Nanos => dt.timestamp_nanos_opt(),
+            })
+            .collect::<Option<Vec<i64>>>()
+            .ok_or_else(|| invalid!("timestamp out of range for {:?}", self.unit))?;
+        Ok(Self::encode(&ticks, 16, self.unit))
+    }
+
+    pub fn decompress_datetimes(&self, blob: &[u8]) -> Result<Vec<DateTime<Utc>>> {
+        let (ticks, unit) = Self::decode(blob, 16, "datetime")?;
+        ticks
+            .into_iter()
+            .map(|t| {
+                match unit {
+                    TimeUnit::Seconds => DateTime::from_timestamp(t, 0),
+                    TimeUnit::Millis => DateTime::from_timestamp_millis(t),
+                    TimeUnit::Micros => DateTime::from_timestamp_micros(t),
+                    TimeUnit::Nanos => Some(DateTime::from_timestamp_nanos(t)),
+                }
+                .ok_or_else(|| corrupt!("timestamp {t} out of range"))
+            })
+            .collect()
+    }
+
+    pub fn compress_durations(&self, data: &[TimeDelta]) -> Result<Vec<u8>> {
+        let ticks = data
+            .iter()
+            .map(|d| match self.unit {
+                TimeUnit::Seconds => Some(d.num_seconds()),
+                TimeUnit::Millis => Some(d.num_milliseconds()),
+                TimeUnit::Micros => d.num_microseconds(),
+                TimeUnit::Nanos => d.num_nanoseconds(),
+            })
+            .collect::<Option<Vec<i64>>>()
+            .ok_or_else(|| invalid!("duration out of range for {:?}", self.unit))?;
+        Ok(Self::encode(&ticks, 17, self.unit))
+    }
+
+    pub fn decompress_durations(&self, blob: &[u8]) -> Result<Vec<TimeDelta>> {
+        let (ticks, unit) = Self::decode(blob, 17, "duration")?;
+        ticks
+            .into_iter()
+            .map(|t| {
+                match unit {
+                    TimeUnit::Seconds => TimeDelta::try_seconds(t),
+                    TimeUnit::Millis => TimeDelta::try_milliseconds(t),
+                    TimeUnit::Micros => Some(TimeDelta::microseconds(t)),
+                    TimeUnit::Nanos => Some(TimeDelta::nanoseconds(t)),
+                }
+                .ok_or_else(|| corrupt!("duration {t} out of range"))
+            })
+            .collect()
+    }
+
+    fn encode(ticks: &[i64], type_tag: u8, unit: TimeUnit) -> Vec<u8> {
+        if ticks.is_empty() {
+            return Vec::new();
+        }
+
+        let mut buf = Vec::with_capacity(ticks.len() * 2);
+        // header: magic + version + len + type
+        buf.extend_from_slice(b"CYDEC"); // 0..5
+        buf.push(1); // 5: version
+        buf.push(1); // 6: codec LZ4
+        buf.push(type_tag); // 7: type (16 = datetime, 17 = duration)
+        buf.extend_from_slice(&(ticks.len() as u64).to_le_bytes()); // 8..16
+        buf.push(unit.id()); // 16: time unit
+
+        buf.extend_from_slice(&IntegerCodec::encode_i64_payload(ticks));
+        buf
+    }
+
+    fn decode(blob: &[u8], type_tag: u8, type_name: &'static str) -> Result<(Vec<i64>, TimeUnit)> {
+        let blob = header::strip(blob)?;
+        if blob.is_empty() {
+            return Ok((Vec::new(), TimeUnit::Nanos));
+        }
+        if blob.len() < 17 {
+            return Err(CydecError::Truncated {
+                needed: 17,
+                got: blob.len(),
+            });
+        }
+        if &blob[0..5] != b"CYDEC" {
+            return Err(CydecError::BadMagic);
+        }
+        if blob[5] != 1 {
+            return Err(CydecError::unsupported_version(blob[5]));
+        }
+        if blob[6] != 1 {
+            return Err(CydecError::UnsupportedBackend { found: blob[6] });
+        }
+        if blob[7] != type_tag {
+            return Err(CydecError::TypeMismatch {
+                expected: type_name,
+                found: blob[7],
+            });
+        }
+        let n = header::usize_at(blob, 8)?;
+        let unit = TimeUnit::from_id(blob[16])?;
+
+        Ok((IntegerCodec::decode_i64_payload(&blob[17..], n)?, unit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_datetimes() -> Result<()> {
+        let start = DateTime::from_timestamp(1_700_000_000, 123_456_789).unwrap();
+        let v: Vec<DateTime<Utc>> = (0..10_000)
+            .map(|i| start + TimeDelta::milliseconds(i * 250))
+            .collect();
+        let c = TimeCodec::default();
+        let blob = c.compress_datetimes(&v)?;
+        assert!(blob.len() < v.len());
+        assert_eq!(v, c.decompress_datetimes(&blob)?);
+        Ok(())
+    }
+
+    #[test]
+    fn unit_is_read_from_the_header() -> Result<()> {
+        let v = vec![
+            DateTime::from_timestamp(1_700_000_000, 999_999_999).unwrap(),
+            DateTime::from_timestamp(1_700_000_001, 500_000_000).unwrap(),
+        ];
+        let millis = TimeCodec {
+            unit: TimeUnit::Millis,
+        };
+        let blob = millis.compress_datetimes(&v)?;
+        let back = TimeCodec::default().decompress_datetimes(&blob)?;
+        assert_eq!(back[0].timestamp_millis(), 1_700_000_000_999);
+        assert_eq!(back[0].timestamp_subsec_nanos(), 999_000_000);
+        assert_eq!(back[1], v[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_durations() -> Result<()> {
+        let v: Vec<TimeDelta> = (-500..500)
+            .map(|i| TimeDelta::microseconds(i * 37))
+            .collect();
+        let c = TimeCodec {
+            unit: TimeUnit::Micros,
+        };
+        let blob = c.compress_durations(&v)?;
+        assert_eq!(v, c.decompress_durations(&blob)?);
+        assert!(c.decompress_datetimes(&blob).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn nanos_out_of_range_is_an_error() {
+        let far = DateTime::from_timestamp(10_000_000_000, 0).unwrap();
+        assert!(TimeCodec::default().compress_datetimes(&[far]).is_err());
+    }
+}
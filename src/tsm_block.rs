@@ -0,0 +1,361 @@
+use crate::error::{Result, bail, corrupt, invalid};
+use crate::gorilla::{BitReader, XorWindow};
+use crate::{ColumnEncoding, CydecError, FrameBuilder, IntegerCodec};
+use integer_encoding::VarInt;
+use std::collections::BTreeMap;
+
+/// Block types, the first byte of a block
+const FLOAT_BLOCK: u8 = 0;
+const INTEGER_BLOCK: u8 = 1;
+const BOOLEAN_BLOCK: u8 = 2;
+const STRING_BLOCK: u8 = 3;
+const UNSIGNED_BLOCK: u8 = 4;
+
+/// Encodings in the top four bits of a timestamp or integer section
+const UNCOMPRESSED: u8 = 0;
+const PACKED_SIMPLE: u8 = 1;
+const RLE: u8 = 2;
+/// The only float encoding
+const FLOAT_GORILLA: u8 = 1;
+
+/// Bits of the NaN that ends a float section
+const FLOAT_END: u64 = 0x7ff8_0000_0000_0001;
+
+/// Points a block may claim; InfluxDB writes at most 1000 by default
+const MAX_POINTS: usize = 1 << 20;
+
+/// Values of a simple8b word per selector: `(count, bits)`, where a width of
+/// 0 stands for runs of ones
+const SELECTORS: [(usize, u32); 16] = [
+    (240, 0),
+    (120, 0),
+    (60, 1),
+    (30, 2),
+    (20, 3),
+    (15, 4),
+    (12, 5),
+    (10, 6),
+    (8, 7),
+    (7, 8),
+    (6, 10),
+    (5, 12),
+    (4, 15),
+    (3, 20),
+    (2, 30),
+    (1, 60),
+];
+
+/// Values of a [`TsmBlock`], by the field type of the series
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum TsmValues {
+    Float(Vec<f64>),
+    Integer(Vec<i64>),
+    Unsigned(Vec<u64>),
+}
+
+impl TsmValues {
+    fn len(&self) -> usize {
+        match self {
+            Self::Float(v) => v.len(),
+            Self::Integer(v) => v.len(),
+            Self::Unsigned(v) => v.len(),
+        }
+    }
+}
+
+/// A block of an InfluxDB TSM file: one series' timestamps, in nanoseconds,
+/// and their field values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TsmBlock {
+    pub timestamps: Vec<i64>,
+    pub values: TsmValues,
+}
+
+impl TsmBlock {
+    /// Decode a block as it is stored in a TSM file after its CRC-32: the
+    /// block type, the length of the timestamp section as a varint, the
+    /// timestamps, then the values.
+    ///
+    /// Timestamps may be uncompressed, simple8b-packed or run-length
+    /// encoded, floats Gorilla XOR encoded, and integer and unsigned values
+    /// any of the timestamp encodings over zigzag deltas. Boolean and string
+    /// blocks are not supported.
+    pub fn decode(block: &[u8]) -> Result<Self> {
+        let Some((&kind, rest)) = block.split_first() else {
+            return Err(CydecError::Truncated { needed: 1, got: 0 });
+        };
+        let (ts_len, n) =
+            u64::decode_var(rest).ok_or_else(|| corrupt!("tsm block: bad timestamps length"))?;
+        let rest = &rest[n..];
+        if ts_len > rest.len() as u64 {
+            return Err(CydecError::Truncated {
+                needed: usize::try_from(ts_len).unwrap_or(usize::MAX),
+                got: rest.len(),
+            });
+        }
+        let (ts, data) = rest.split_at(ts_len as usize);
+        let timestamps = decode_timestamps(ts)?;
+        let values = match kind {
+            FLOAT_BLOCK => TsmValues::Float(decode_floats(data)?),
+            INTEGER_BLOCK => TsmValues::Integer(decode_integers(data)?),
+            UNSIGNED_BLOCK => {
+                TsmValues::Unsigned(decode_integers(data)?.iter().map(|&v| v as u64).collect())
+            }
+            BOOLEAN_BLOCK => return Err(invalid!("tsm boolean blocks are not supported")),
+            STRING_BLOCK => return Err(invalid!("tsm string blocks are not supported")),
+            other => bail!("unknown tsm block type {other}"),
+        };
+        if timestamps.len() != values.len() {
+            bail!(
+                "tsm block has {} timestamps and {} values",
+                timestamps.len(),
+                values.len()
+            );
+        }
+        Ok(Self { timestamps, values })
+    }
+
+    /// The block as a frame with columns `time` and `value`, timestamps
+    /// delta-of-delta encoded and floats stored losslessly
+    pub fn to_frame(&self) -> Result<Vec<u8>> {
+        let mut encodings = BTreeMap::from([("time".to_owned(), ColumnEncoding::DeltaOfDelta)]);
+        if let TsmValues::Float(_) = self.values {
+            encodings.insert("value".to_owned(), ColumnEncoding::Xor);
+        }
+        let mut builder = FrameBuilder::new().with_encodings(encodings);
+        builder.add("time", &self.timestamps)?;
+        match &self.values {
+            TsmValues::Float(v) => builder.add("value", v)?,
+            TsmValues::Integer(v) => builder.add("value", v)?,
+            TsmValues::Unsigned(v) => builder.add("value", v)?,
+        }
+        builder.finish()
+    }
+}
+
+fn decode_timestamps(data: &[u8]) -> Result<Vec<i64>> {
+    let Some((&head, rest)) = data.split_first() else {
+        return Ok(Vec::new());
+    };
+    let div = 10u64.pow(u32::from(head & 0xf));
+    let mut deltas = match head >> 4 {
+        UNCOMPRESSED => words(rest)?.collect(),
+        PACKED_SIMPLE => {
+            let (first, packed) = first_word(rest)?;
+            let mut deltas = vec![first];
+            simple8b(packed, &mut deltas)?;
+            deltas[1..]
+                .iter_mut()
+                .for_each(|d| *d = d.wrapping_mul(div));
+            deltas
+        }
+        RLE => {
+            let (first, rest) = first_word(rest)?;
+            let (delta, rest) = uvarint(rest)?;
+            let (count, _) = uvarint(rest)?;
+            let mut deltas = vec![delta.wrapping_mul(div); points(count)?];
+            if let Some(d) = deltas.first_mut() {
+                *d = first;
+            }
+            deltas
+        }
+        other => bail!("unknown tsm timestamp encoding {other}"),
+    };
+    prefix_sum(&mut deltas);
+    Ok(deltas.into_iter().map(|t| t as i64).collect())
+}
+
+fn decode_integers(data: &[u8]) -> Result<Vec<i64>> {
+    let Some((&head, rest)) = data.split_first() else {
+        return Ok(Vec::new());
+    };
+    let mut deltas: Vec<u64> = match head >> 4 {
+        UNCOMPRESSED => words(rest)?.collect(),
+        PACKED_SIMPLE => {
+            let (first, packed) = first_word(rest)?;
+            let mut deltas = vec![first];
+            simple8b(packed, &mut deltas)?;
+            deltas
+        }
+        RLE => {
+            let (first, rest) = first_word(rest)?;
+            let (delta, rest) = uvarint(rest)?;
+            let (repeats, _) = uvarint(rest)?;
+            let mut deltas = vec![delta; points(repeats.saturating_add(1))?];
+            deltas[0] = first;
+            deltas
+        }
+        other => bail!("unknown tsm integer encoding {other}"),
+    };
+    deltas
+        .iter_mut()
+        .for_each(|d| *d = IntegerCodec::unzigzag_i64(*d) as u64);
+    prefix_sum(&mut deltas);
+    Ok(deltas.into_iter().map(|v| v as i64).collect())
+}
+
+fn decode_floats(data: &[u8]) -> Result<Vec<f64>> {
+    let Some(&head) = data.first() else {
+        return Ok(Vec::new());
+    };
+    if head >> 4 != FLOAT_GORILLA {
+        bail!("unknown tsm float encoding {}", head >> 4);
+    }
+    if data.len() == 1 {
+        return Ok(Vec::new());
+    }
+    let mut r = BitReader::new(data, 1);
+    let mut window = XorWindow::new();
+    let mut values = Vec::new();
+    let mut bits = r.read_bits(64)?;
+    while bits != FLOAT_END {
+        values.push(f64::from_bits(bits));
+        bits = window.read(&mut r, bits)?;
+    }
+    Ok(values)
+}
+
+/// Running sums of `deltas`, in place
+fn prefix_sum(deltas: &mut [u64]) {
+    for i in 1..deltas.len() {
+        deltas[i] = deltas[i].wrapping_add(deltas[i - 1]);
+    }
+}
+
+fn points(count: u64) -> Result<usize> {
+    match usize::try_from(count) {
+        Ok(n) if n <= MAX_POINTS => Ok(n),
+        _ => bail!("tsm block claims {count} points, more than {MAX_POINTS}"),
+    }
+}
+
+fn words(data: &[u8]) -> Result<impl Iterator<Item = u64> + '_> {
+    if !data.len().is_multiple_of(8) {
+        bail!("tsm section of {} bytes is not whole words", data.len());
+    }
+    Ok(data
+        .chunks_exact(8)
+        .map(|w| u64::from_be_bytes(w.try_into().unwrap())))
+}
+
+fn first_word(data: &[u8]) -> Result<(u64, &[u8])> {
+    match data.split_first_chunk::<8>() {
+        Some((first, rest)) => Ok((u64::from_be_bytes(*first), rest)),
+        None => Err(CydecError::Truncated {
+            needed: 8,
+            got: data.len(),
+        }),
+    }
+}
+
+fn uvarint(data: &[u8]) -> Result<(u64, &[u8])> {
+    let (value, n) = u64::decode_var(data).ok_or_else(|| corrupt!("tsm block: bad varint"))?;
+    Ok((value, &data[n..]))
+}
+
+/// Append the values of simple8b `data`, big-endian words that each pack
+/// as many values as fit at the width named by their top four bits
+fn simple8b(data: &[u8], out: &mut Vec<u64>) -> Result<()> {
+    for word in words(data)? {
+        let (count, bits) = SELECTORS[(word >> 60) as usize];
+        if out.len() + count > MAX_POINTS {
+            bail!("tsm block holds more than {MAX_POINTS} points");
+        }
+        if bits == 0 {
+            out.extend(std::iter::repeat_n(1, count));
+        } else {
+            let mask = (1 << bits) - 1;
+            out.extend((0..count as u32).map(|i| word >> (i * bits) & mask));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Frame;
+    use crate::gorilla::BitWriter;
+
+    /// A block of `kind` from its timestamp and value sections
+    fn block(kind: u8, ts: &[u8], values: &[u8]) -> Vec<u8> {
+        let mut out = vec![kind];
+        out.extend_from_slice(&(ts.len() as u64).encode_var_vec());
+        out.extend_from_slice(ts);
+        out.extend_from_slice(values);
+        out
+    }
+
+    fn be(words: &[u64]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn blocks_decode_per_encoding() -> Result<()> {
+        let start = 1_700_000_000_000_000_000u64;
+        // every 10 s: four deltas of 1 at divisor 10^10 in one 4x15-bit word
+        let mut packed = vec![PACKED_SIMPLE << 4 | 10];
+        packed.extend(be(&[start, 12 << 60 | 1 | 1 << 15 | 1 << 30 | 1 << 45]));
+        let mut rle = vec![RLE << 4 | 10, 0, 0, 0, 0, 0, 0, 0, 0];
+        rle[1..9].copy_from_slice(&start.to_be_bytes());
+        rle.extend_from_slice(&[1, 5]);
+        let mut raw = vec![UNCOMPRESSED << 4];
+        raw.extend(be(&[start, 1, 20_000_000_000, 1, u64::MAX]));
+        let every_10s: Vec<i64> = (0..5)
+            .map(|i| (start + i * 10_000_000_000) as i64)
+            .collect();
+        let s = start as i64;
+        let ragged = vec![
+            s,
+            s + 1,
+            s + 20_000_000_001,
+            s + 20_000_000_002,
+            s + 20_000_000_001,
+        ];
+
+        let mut w = BitWriter::new(vec![FLOAT_GORILLA << 4]);
+        let floats = [21.5, 21.5, 21.75, -3.0, f64::INFINITY];
+        w.write_bits(floats[0].to_bits(), 64);
+        let mut window = XorWindow::new();
+        for pair in floats.windows(2) {
+            window.write(&mut w, pair[0].to_bits(), pair[1].to_bits());
+        }
+        window.write(&mut w, f64::INFINITY.to_bits(), FLOAT_END);
+        let float_block = TsmBlock::decode(&block(FLOAT_BLOCK, &packed, &w.out))?;
+        assert_eq!(float_block.timestamps, every_10s);
+        assert_eq!(float_block.values, TsmValues::Float(floats.to_vec()));
+
+        // 10, 12, 11, 7, 8: zigzag deltas 20, 4, 1, 7, 2 in a 3x20-bit word
+        let mut ints = vec![PACKED_SIMPLE << 4];
+        ints.extend(be(&[20, 13 << 60 | 4 | 1 << 20 | 7 << 40, 15 << 60 | 2]));
+        let int_block = TsmBlock::decode(&block(INTEGER_BLOCK, &rle, &ints))?;
+        assert_eq!(int_block.timestamps, every_10s);
+        assert_eq!(int_block.values, TsmValues::Integer(vec![10, 12, 11, 7, 8]));
+
+        // 5, then four steps of 3: zigzag 10 and 6
+        let mut uints = vec![RLE << 4];
+        uints.extend(be(&[10]));
+        uints.extend_from_slice(&[6, 4]);
+        let uint_block = TsmBlock::decode(&block(UNSIGNED_BLOCK, &raw, &uints))?;
+        assert_eq!(uint_block.timestamps, ragged);
+        assert_eq!(
+            uint_block.values,
+            TsmValues::Unsigned(vec![5, 8, 11, 14, 17])
+        );
+
+        let frame = Frame::from_bytes(float_block.to_frame()?)?;
+        assert_eq!(frame.column::<i64>("time")?, every_10s);
+        assert_eq!(frame.column::<f64>("value")?, floats);
+        let frame = Frame::from_bytes(int_block.to_frame()?)?;
+        assert_eq!(frame.column::<i64>("value")?, [10, 12, 11, 7, 8]);
+        let frame = Frame::from_bytes(uint_block.to_frame()?)?;
+        assert_eq!(frame.column::<u64>("value")?, [5, 8, 11, 14, 17]);
+
+        assert!(TsmBlock::decode(&block(BOOLEAN_BLOCK, &packed, &[0x10])).is_err());
+        assert!(TsmBlock::decode(&block(FLOAT_BLOCK, &rle, &w.out[..20])).is_err());
+        assert!(TsmBlock::decode(&block(INTEGER_BLOCK, &packed, &ints[..9])).is_err());
+        assert!(TsmBlock::decode(&block(INTEGER_BLOCK, &raw[..12], &ints)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Header;
+use crate::blocks::STREAMED_LEN;
+use crate::concat::{DecodedArray, decode};
+use crate::error::{Result, bail, invalid};
+
+/// Decode a blob from a source that cannot be trusted: arbitrary,
+/// truncated or deliberately crafted bytes.
+///
+/// Any input either decodes or fails with an error. It never panics or
+/// overflows, and nothing is allocated for a header's claims before the
+/// payload backs them: no more than `max_len` values, plus unpacked
+/// payloads no larger than the compressed bytes can expand to. Blobs
+/// declaring more than `max_len` values are refused before any payload
+/// is touched. Handles the types [`DecodedArray`] holds; an empty input
+/// has no type and is reported as [`crate::CydecError::Truncated`].
+///
+/// The `fuzz` directory holds a `cargo fuzz` target for this function.
+pub fn decompress_untrusted(blob: &[u8], max_len: usize) -> Result<DecodedArray> {
+    let header = Header::parse(blob)?;
+    let declared = match header.len {
+        // streamed blobs add their length up from the block headers
+        STREAMED_LEN => crate::inspect(blob)?.len,
+        n => usize::try_from(n).unwrap_or(usize::MAX),
+    };
+    if declared > max_len {
+        return Err(invalid!(
+            "blob declares {declared} values, the limit is {max_len}"
+        ));
+    }
+    let values = decode(blob)?;
+    let len = match &values {
+        DecodedArray::I64(v) => v.len(),
+        DecodedArray::U64(v) => v.len(),
+        DecodedArray::I32(v) => v.len(),
+        DecodedArray::U32(v) => v.len(),
+        DecodedArray::I16(v) => v.len(),
+        DecodedArray::U16(v) => v.len(),
+        DecodedArray::I8(v) => v.len(),
+        DecodedArray::I128(v) => v.len(),
+        DecodedArray::U128(v) => v.len(),
+        DecodedArray::Bytes(v) => v.len(),
+        DecodedArray::F64(v) => v.len(),
+        DecodedArray::F32(v) => v.len(),
+    };
+    if len != declared {
+        bail!("blob decoded to {len} values, header says {declared}");
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CydecError, IntegerCodec, test_vectors};
+
+    #[test]
+    fn hostile_headers_fail_before_allocating() -> Result<()> {
+        let blob = IntegerCodec::default().compress_i64(&[1, 2, 3])?;
+        assert_eq!(
+            decompress_untrusted(&blob, 3)?,
+            DecodedArray::I64(vec![1, 2, 3])
+        );
+        assert!(matches!(
+            decompress_untrusted(&blob, 2),
+            Err(CydecError::InvalidArgument(_))
+        ));
+
+        // a count of 2^60 and an LZ4 size prefix of 4 GiB over a few bytes
+        let mut huge = blob.clone();
+        huge[8..16].copy_from_slice(&(1u64 << 60).to_le_bytes());
+        assert!(decompress_untrusted(&huge, usize::MAX).is_err());
+        assert!(IntegerCodec::default().decompress_i64(&huge).is_err());
+        let mut prefix = blob.clone();
+        prefix[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decompress_untrusted(&prefix, usize::MAX).is_err());
+        assert!(matches!(
+            decompress_untrusted(&[], 10),
+            Err(CydecError::Truncated { .. })
+        ));
+
+        for v in test_vectors::generate()? {
+            if !v.bytes.is_empty() {
+                decompress_untrusted(&v.bytes, v.input.len())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! wasm-bindgen entry points for decoding blobs in the browser.
+//!
+//! Each decoder returns the typed array matching its element type, such
+//! as `Float64Array` for f64 or `BigInt64Array` for i64, and throws an
+//! `Error` carrying the [`CydecError`](crate::CydecError) message when the
+//! blob does not decode.
+
+use crate::concat::{DecodedArray, decode};
+use crate::error::invalid;
+use crate::{CydecElement, Frame};
+use wasm_bindgen::prelude::*;
+
+/// Decode a blob of any integer, float or byte type into the typed array
+/// matching it
+#[wasm_bindgen]
+pub fn decompress(blob: &[u8]) -> Result<JsValue, JsError> {
+    if blob.is_empty() {
+        return Ok(Vec::<i64>::new().into());
+    }
+    typed_array(decode(blob)?)
+}
+
+#[wasm_bindgen(js_name = decompressI64)]
+pub fn decompress_i64(blob: &[u8]) -> Result<Vec<i64>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressU64)]
+pub fn decompress_u64(blob: &[u8]) -> Result<Vec<u64>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressI32)]
+pub fn decompress_i32(blob: &[u8]) -> Result<Vec<i32>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressU32)]
+pub fn decompress_u32(blob: &[u8]) -> Result<Vec<u32>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressI16)]
+pub fn decompress_i16(blob: &[u8]) -> Result<Vec<i16>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressU16)]
+pub fn decompress_u16(blob: &[u8]) -> Result<Vec<u16>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressI8)]
+pub fn decompress_i8(blob: &[u8]) -> Result<Vec<i8>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressBytes)]
+pub fn decompress_bytes(blob: &[u8]) -> Result<Vec<u8>, JsError> {
+    values(blob)
+}
+
+/// Decode an f64 blob, fixed-scale or lossless
+#[wasm_bindgen(js_name = decompressF64)]
+pub fn decompress_f64(blob: &[u8]) -> Result<Vec<f64>, JsError> {
+    values(blob)
+}
+
+#[wasm_bindgen(js_name = decompressF32)]
+pub fn decompress_f32(blob: &[u8]) -> Result<Vec<f32>, JsError> {
+    values(blob)
+}
+
+/// Column names of a frame, in the order they were written
+#[wasm_bindgen(js_name = frameColumns)]
+pub fn frame_columns(frame: &[u8]) -> Result<Vec<String>, JsError> {
+    let frame = Frame::from_bytes(frame)?;
+    Ok(frame.columns().iter().map(|c| c.name.clone()).collect())
+}
+
+/// Decode column `name` of a frame into the typed array matching it
+#[wasm_bindgen(js_name = decompressFrameColumn)]
+pub fn decompress_frame_column(frame: &[u8], name: &str) -> Result<JsValue, JsError> {
+    let frame = Frame::from_bytes(frame)?;
+    let blob = frame
+        .column_blob(name)
+        .ok_or_else(|| invalid!("no frame column {name:?}"))?;
+    decompress(blob)
+}
+
+fn values<T: CydecElement>(blob: &[u8]) -> Result<Vec<T>, JsError> {
+    Ok(T::decompress_blob(blob)?)
+}
+
+fn typed_array(array: DecodedArray) -> Result<JsValue, JsError> {
+    Ok(match array {
+        DecodedArray::I64(v) => v.into(),
+        DecodedArray::U64(v) => v.into(),
+        DecodedArray::I32(v) => v.into(),
+        DecodedArray::U32(v) => v.into(),
+        DecodedArray::I16(v) => v.into(),
+        DecodedArray::U16(v) => v.into(),
+        DecodedArray::I8(v) => v.into(),
+        DecodedArray::Bytes(v) => v.into(),
+        DecodedArray::F64(v) => v.into(),
+        DecodedArray::F32(v) => v.into(),
+        DecodedArray::I128(_) | DecodedArray::U128(_) => {
+            return Err(invalid!("JavaScript has no 128-bit typed array").into());
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FrameBuilder;
+
+    // only the paths that stay in Rust; building a JsValue needs a JS host
+    #[test]
+    fn browser_entry_points_decode_blobs() -> crate::Result<()> {
+        let prices: Vec<f64> = (0..1_000).map(|i| 100.0 + i as f64 / 4.0).collect();
+        let blob = crate::compress(&prices)?;
+        assert_eq!(decompress_f64(&blob).unwrap(), prices);
+
+        let ts: Vec<i64> = (0..1_000).map(|i| 1_700_000_000_000 + i * 1_000).collect();
+        let mut builder = FrameBuilder::new();
+        builder.add("ts", &ts)?;
+        builder.add("close", &prices)?;
+        let frame = builder.finish()?;
+        assert_eq!(frame_columns(&frame).unwrap(), ["ts", "close"]);
+        let column = Frame::from_bytes(&frame)?
+            .column_blob("ts")
+            .unwrap()
+            .to_vec();
+        assert_eq!(decompress_i64(&column).unwrap(), ts);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::CydecError;
+use crate::error::{Result, bail, invalid};
+use std::io::{Read, Write};
+
+/// Set on the length prefix when a CRC-32 of the blob follows it
+const CHECKSUM_BIT: u32 = 1 << 31;
+
+fn write_frame(w: &mut impl Write, blob: &[u8], checksum: bool) -> Result<()> {
+    let len = u32::try_from(blob.len())
+        .ok()
+        .filter(|&len| len & CHECKSUM_BIT == 0)
+        .ok_or_else(|| invalid!("blob of {} bytes is too large to frame", blob.len()))?;
+    let prefix = if checksum { len | CHECKSUM_BIT } else { len };
+    w.write_all(&prefix.to_le_bytes())?;
+    w.write_all(blob)?;
+    if checksum {
+        w.write_all(&crc32fast::hash(blob).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write `blob` behind a u32 little-endian length prefix.
+///
+/// Framed blobs can be concatenated on a socket or in a file and split
+/// apart again with [`read_framed`]. These frames are the same as the ones
+/// of the `tokio-util` frame codec.
+pub fn write_framed(w: &mut impl Write, blob: &[u8]) -> Result<()> {
+    write_frame(w, blob, false)
+}
+
+/// Like [`write_framed`], followed by a CRC-32 of the blob that
+/// [`read_framed`] verifies. The top bit of the length marks the checksum,
+/// which limits blobs to 2 GiB.
+pub fn write_framed_checksummed(w: &mut impl Write, blob: &[u8]) -> Result<()> {
+    write_frame(w, blob, true)
+}
+
+/// Read one frame written by [`write_framed`] or
+/// [`write_framed_checksummed`]; `None` at a clean end of input
+pub fn read_framed(r: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut prefix = [0u8; 4];
+    let mut got = 0;
+    while got < 4 {
+        match r.read(&mut prefix[got..]) {
+            Ok(0) if got == 0 => return Ok(None),
+            Ok(0) => return Err(CydecError::Truncated { needed: 4, got }),
+            Ok(k) => got += k,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let prefix = u32::from_le_bytes(prefix);
+    let len = (prefix & !CHECKSUM_BIT) as usize;
+
+    let mut blob = Vec::new();
+    r.take(len as u64).read_to_end(&mut blob)?;
+    if blob.len() < len {
+        return Err(CydecError::Truncated {
+            needed: len,
+            got: blob.len(),
+        });
+    }
+    if prefix & CHECKSUM_BIT != 0 {
+        let mut crc = [0u8; 4];
+        r.read_exact(&mut crc)
+            .map_err(|_| CydecError::Truncated { needed: 4, got: 0 })?;
+        if crc32fast::hash(&blob) != u32::from_le_bytes(crc) {
+            bail!("frame checksum mismatch");
+        }
+    }
+    if !blob.is_empty() && &blob[..blob.len().min(5)] != b"CYDEC" {
+        return Err(CydecError::BadMagic);
+    }
+    Ok(Some(blob))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntegerCodec;
+
+    #[test]
+    fn framed_blobs_split_back_apart() -> Result<()> {
+        let c = IntegerCodec::default();
+        let batches: Vec<Vec<i64>> = (0..6).map(|b| (0..b * 50).collect()).collect();
+        let mut wire = Vec::new();
+        for (i, batch) in batches.iter().enumerate() {
+            let blob = c.compress_i64(batch)?;
+            if i % 2 == 0 {
+                write_framed(&mut wire, &blob)?;
+            } else {
+                write_framed_checksummed(&mut wire, &blob)?;
+            }
+        }
+
+        let mut r = wire.as_slice();
+        let mut out = Vec::new();
+        while let Some(blob) = read_framed(&mut r)? {
+            out.push(c.decompress_i64(&blob)?);
+        }
+        assert_eq!(batches, out);
+        Ok(())
+    }
+
+    #[test]
+    fn damaged_frames_are_detected() -> Result<()> {
+        let blob = IntegerCodec::default().compress_i64(&[7, 8, 9])?;
+        let mut wire = Vec::new();
+        write_framed_checksummed(&mut wire, &blob)?;
+
+        let mut flipped = wire.clone();
+        flipped[10] ^= 1;
+        assert!(read_framed(&mut flipped.as_slice()).is_err());
+
+        let cut = &wire[..wire.len() - 6];
+        assert!(matches!(
+            read_framed(&mut &cut[..]),
+            Err(CydecError::Truncated { .. })
+        ));
+        Ok(())
+    }
+}
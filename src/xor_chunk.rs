@@ -0,0 +1,206 @@
+use crate::error::{Result, bail, invalid};
+use crate::gorilla::{BitReader, BitWriter, XorWindow};
+use crate::{CydecError, IntegerCodec};
+
+/// Samples a chunk can hold, the limit of its 16-bit sample count
+const MAX_SAMPLES: usize = u16::MAX as usize;
+
+/// Encode a series as a Prometheus XOR chunk, the Gorilla encoding the
+/// TSDB stores and remote read streams as `XOR` chunks.
+///
+/// The chunk is the encoding's data only, without the encoding byte that
+/// precedes it on disk. Timestamps are delta-of-delta encoded and values
+/// XORed with their predecessor, bit for bit, so NaNs such as Prometheus'
+/// staleness marker survive. A chunk holds at most 65535 samples.
+pub fn encode_xor_chunk(timestamps: &[i64], values: &[f64]) -> Result<Vec<u8>> {
+    if timestamps.len() != values.len() {
+        return Err(invalid!(
+            "series length mismatch: {} timestamps, {} values",
+            timestamps.len(),
+            values.len()
+        ));
+    }
+    if timestamps.len() > MAX_SAMPLES {
+        return Err(invalid!(
+            "xor chunks hold at most {MAX_SAMPLES} samples, got {}",
+            timestamps.len()
+        ));
+    }
+    let mut w = BitWriter::new((timestamps.len() as u16).to_be_bytes().to_vec());
+    let mut window = XorWindow::new();
+    let mut delta = 0i64;
+    for (i, (&t, &v)) in timestamps.iter().zip(values).enumerate() {
+        match i {
+            0 => {
+                write_uvarint(&mut w, IntegerCodec::zigzag_i64(t));
+                w.write_bits(v.to_bits(), 64);
+                continue;
+            }
+            1 => {
+                delta = t.wrapping_sub(timestamps[0]);
+                write_uvarint(&mut w, delta as u64);
+            }
+            _ => {
+                let d = t.wrapping_sub(timestamps[i - 1]);
+                let dod = d.wrapping_sub(delta);
+                delta = d;
+                match dod {
+                    0 => w.write_bit(false),
+                    _ if fits(dod, 14) => {
+                        w.write_bits(0b10, 2);
+                        w.write_bits(dod as u64, 14);
+                    }
+                    _ if fits(dod, 17) => {
+                        w.write_bits(0b110, 3);
+                        w.write_bits(dod as u64, 17);
+                    }
+                    _ if fits(dod, 20) => {
+                        w.write_bits(0b1110, 4);
+                        w.write_bits(dod as u64, 20);
+                    }
+                    _ => {
+                        w.write_bits(0b1111, 4);
+                        w.write_bits(dod as u64, 64);
+                    }
+                }
+            }
+        }
+        window.write(&mut w, values[i - 1].to_bits(), v.to_bits());
+    }
+    Ok(w.out)
+}
+
+/// Decode a Prometheus XOR chunk, as written by the TSDB or returned in a
+/// remote-read response, into its timestamps and values.
+///
+/// `chunk` is the encoding's data, without a leading encoding byte.
+/// Padding after the last sample is ignored.
+pub fn decode_xor_chunk(chunk: &[u8]) -> Result<(Vec<i64>, Vec<f64>)> {
+    let [hi, lo, ..] = *chunk else {
+        return Err(CydecError::Truncated {
+            needed: 2,
+            got: chunk.len(),
+        });
+    };
+    let n = usize::from(u16::from_be_bytes([hi, lo]));
+    let mut r = BitReader::new(chunk, 2);
+    let mut timestamps = Vec::with_capacity(n.min(chunk.len() * 8));
+    let mut values: Vec<f64> = Vec::with_capacity(timestamps.capacity());
+    let mut window = XorWindow::new();
+    let mut delta = 0i64;
+    for i in 0..n {
+        match i {
+            0 => {
+                timestamps.push(IntegerCodec::unzigzag_i64(read_uvarint(&mut r)?));
+                values.push(f64::from_bits(r.read_bits(64)?));
+                continue;
+            }
+            1 => delta = read_uvarint(&mut r)? as i64,
+            _ => {
+                let mut prefix = 0;
+                while prefix < 4 && r.read_bit()? {
+                    prefix += 1;
+                }
+                let dod = match prefix {
+                    0 => 0,
+                    4 => r.read_bits(64)? as i64,
+                    _ => {
+                        let sz = [14, 17, 20][prefix - 1];
+                        let bits = r.read_bits(sz)? as i64;
+                        if bits > 1 << (sz - 1) {
+                            bits - (1 << sz)
+                        } else {
+                            bits
+                        }
+                    }
+                };
+                delta = delta.wrapping_add(dod);
+            }
+        }
+        timestamps.push(timestamps[i - 1].wrapping_add(delta));
+        values.push(f64::from_bits(
+            window.read(&mut r, values[i - 1].to_bits())?,
+        ));
+    }
+    Ok((timestamps, values))
+}
+
+/// Whether `x` lies in the range Prometheus stores in an `n`-bit bucket
+fn fits(x: i64, n: u32) -> bool {
+    (-((1 << (n - 1)) - 1)..=1 << (n - 1)).contains(&x)
+}
+
+/// A LEB128 varint, its bytes starting at the current bit
+fn write_uvarint(w: &mut BitWriter, mut x: u64) {
+    while x >= 0x80 {
+        w.write_bits(x & 0x7f | 0x80, 8);
+        x >>= 7;
+    }
+    w.write_bits(x, 8);
+}
+
+fn read_uvarint(r: &mut BitReader) -> Result<u64> {
+    let mut x = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = r.read_bits(8)?;
+        if shift == 63 && byte > 1 {
+            break;
+        }
+        x |= (byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(x);
+        }
+    }
+    bail!("xor chunk varint overflows 64 bits")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_match_the_prometheus_layout() -> Result<()> {
+        let mut expected = vec![0, 2, 0];
+        expected.extend_from_slice(&1.0f64.to_bits().to_be_bytes());
+        expected.extend_from_slice(&[0xe8, 0x07, 0x00]); // delta 1000, then an unchanged value
+        let chunk = encode_xor_chunk(&[0, 1_000], &[1.0, 1.0])?;
+        assert_eq!(chunk, expected);
+        assert_eq!(decode_xor_chunk(&chunk)?, (vec![0, 1_000], vec![1.0, 1.0]));
+        assert_eq!(encode_xor_chunk(&[], &[])?, [0, 0]);
+        assert_eq!(decode_xor_chunk(&[0, 0])?, (vec![], vec![]));
+        Ok(())
+    }
+
+    #[test]
+    fn samples_roundtrip_bit_for_bit() -> Result<()> {
+        let stale = f64::from_bits(0x7ff0_0000_0000_0002);
+        let mut ts = vec![-5_000, 1_699_999_985_000];
+        let mut values = vec![-0.0, 12.5];
+        let mut t = ts[1];
+        for i in 0..2_000i64 {
+            // scrape jitter, missed scrapes and the odd clock jump
+            t += match i % 97 {
+                13 => 120_000,
+                41 => -3_600_000_000,
+                _ => 15_000 + (i * 7_919) % 2_001 - 1_000,
+            };
+            ts.push(t);
+            values.push(match i % 11 {
+                0 => stale,
+                1 | 2 => 12.5,
+                _ => (i as f64 / 3.0).sin() * 1e3,
+            });
+        }
+        let chunk = encode_xor_chunk(&ts, &values)?;
+        let (ts2, values2) = decode_xor_chunk(&chunk)?;
+        assert_eq!(ts2, ts);
+        let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&values2), bits(&values));
+
+        assert!(decode_xor_chunk(&chunk[..chunk.len() / 2]).is_err());
+        assert!(decode_xor_chunk(&[0]).is_err());
+        assert!(encode_xor_chunk(&ts, &values[1..]).is_err());
+        assert!(encode_xor_chunk(&vec![0; 70_000], &vec![0.0; 70_000]).is_err());
+        Ok(())
+    }
+}
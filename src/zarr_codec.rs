@@ -0,0 +1,180 @@
+use crate::error::{Result, invalid};
+use crate::raw_chunk::{self, RawType};
+use serde_json::{Map, Value, json};
+
+/// Zarr v3 array-to-bytes codec backed by the integer and float pipelines.
+///
+/// A chunk goes in and comes out as its elements in C order,
+/// little-endian, the layout Zarr's `bytes` codec gives them. Integer
+/// chunks take the delta pipeline of their type, which suits chunks along
+/// a time dimension. Floats are lossless unless the configuration holds a
+/// `scale`, which quantizes them to multiples of `1 / scale`.
+///
+/// In `zarr.json` the codec is written as
+/// `{"name": "cydec", "configuration": {"scale": 1000000.0}}`, or with an
+/// empty configuration for the lossless default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZarrCodec {
+    data_type: RawType,
+    scale: Option<f64>,
+}
+
+impl ZarrCodec {
+    /// Codec name in array metadata
+    pub const NAME: &'static str = "cydec";
+
+    /// Lossless codec for an array of the Zarr data type `data_type`, such
+    /// as `"int64"` or `"float64"`
+    pub fn new(data_type: &str) -> Result<Self> {
+        let data_type = match data_type {
+            "int8" => RawType::I8,
+            "uint8" => RawType::U8,
+            "int16" => RawType::I16,
+            "uint16" => RawType::U16,
+            "int32" => RawType::I32,
+            "uint32" => RawType::U32,
+            "int64" => RawType::I64,
+            "uint64" => RawType::U64,
+            "float32" => RawType::F32,
+            "float64" => RawType::F64,
+            other => return Err(invalid!("zarr data type {other:?} is not supported")),
+        };
+        Ok(Self {
+            data_type,
+            scale: None,
+        })
+    }
+
+    /// Quantize float chunks to multiples of `1 / scale`
+    pub fn with_scale(mut self, scale: f64) -> Result<Self> {
+        if !matches!(self.data_type, RawType::F32 | RawType::F64) {
+            return Err(invalid!(
+                "scale applies to float arrays, not {}",
+                self.data_type()
+            ));
+        }
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(invalid!("scale must be positive and finite, got {scale}"));
+        }
+        self.scale = Some(scale);
+        Ok(self)
+    }
+
+    /// Codec for an array of `data_type` from its entry in the `codecs`
+    /// list of the array's `zarr.json`
+    pub fn from_metadata(data_type: &str, metadata: &str) -> Result<Self> {
+        let metadata: Value =
+            serde_json::from_str(metadata).map_err(|e| invalid!("zarr codec metadata: {e}"))?;
+        if metadata["name"] != Self::NAME {
+            return Err(invalid!("zarr codec {} is not cydec", metadata["name"]));
+        }
+        let codec = Self::new(data_type)?;
+        let empty = Map::new();
+        let configuration = match &metadata["configuration"] {
+            Value::Null => &empty,
+            Value::Object(configuration) => configuration,
+            other => {
+                return Err(invalid!(
+                    "zarr codec configuration {other} is not an object"
+                ));
+            }
+        };
+        if let Some(key) = configuration.keys().find(|k| *k != "scale") {
+            return Err(invalid!("unknown cydec codec option {key:?}"));
+        }
+        match configuration.get("scale") {
+            None => Ok(codec),
+            Some(scale) => match scale.as_f64() {
+                Some(scale) => codec.with_scale(scale),
+                None => Err(invalid!("scale {scale} is not a number")),
+            },
+        }
+    }
+
+    /// The codec's entry for the `codecs` list of `zarr.json`
+    pub fn metadata(&self) -> String {
+        let configuration = match self.scale {
+            Some(scale) => json!({ "scale": scale }),
+            None => json!({}),
+        };
+        json!({ "name": Self::NAME, "configuration": configuration }).to_string()
+    }
+
+    /// Zarr name of the element type
+    pub fn data_type(&self) -> &'static str {
+        match self.data_type {
+            RawType::I8 => "int8",
+            RawType::U8 => "uint8",
+            RawType::I16 => "int16",
+            RawType::U16 => "uint16",
+            RawType::I32 => "int32",
+            RawType::U32 => "uint32",
+            RawType::I64 => "int64",
+            RawType::U64 => "uint64",
+            RawType::F32 => "float32",
+            RawType::F64 => "float64",
+        }
+    }
+
+    /// Compress a chunk of little-endian elements
+    pub fn encode(&self, chunk: &[u8]) -> Result<Vec<u8>> {
+        raw_chunk::encode(self.data_type, chunk, self.scale)
+    }
+
+    /// Decode an encoded chunk back into little-endian elements
+    pub fn decode(&self, encoded: &[u8]) -> Result<Vec<u8>> {
+        raw_chunk::decode_bytes(encoded, Some(self.data_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn le<T, const N: usize>(values: &[T], write: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(write).collect()
+    }
+
+    #[test]
+    fn chunks_roundtrip_per_configuration() -> Result<()> {
+        let ts: Vec<i64> = (0..10_000).map(|i| 1_700_000_000_000 + i * 1_000).collect();
+        let chunk = le(&ts, |x| x.to_le_bytes());
+        let codec = ZarrCodec::new("int64")?;
+        let encoded = codec.encode(&chunk)?;
+        assert!(encoded.len() < chunk.len() / 50);
+        assert_eq!(codec.decode(&encoded)?, chunk);
+        assert_eq!(ZarrCodec::from_metadata("int64", &codec.metadata())?, codec);
+
+        let temps: Vec<f64> = (0..5_000).map(|i| 15.0 + (i as f64 / 90.0).sin()).collect();
+        let chunk = le(&temps, |x| x.to_le_bytes());
+        let lossless = ZarrCodec::from_metadata("float64", r#"{"name": "cydec"}"#)?;
+        assert_eq!(lossless.decode(&lossless.encode(&chunk)?)?, chunk);
+
+        let scaled = ZarrCodec::new("float64")?.with_scale(1_000.0)?;
+        let reread = ZarrCodec::from_metadata("float64", &scaled.metadata())?;
+        assert_eq!(reread, scaled);
+        let decoded = scaled.decode(&scaled.encode(&chunk)?)?;
+        for (bytes, &t) in decoded.chunks_exact(8).zip(&temps) {
+            let x = f64::from_le_bytes(bytes.try_into().unwrap());
+            assert!((x - t).abs() <= 0.5e-3 + 1e-12);
+        }
+
+        let samples = le(&[1.5f32, -2.25, f32::NAN], |x| x.to_le_bytes());
+        let codec = ZarrCodec::new("float32")?;
+        assert_eq!(codec.decode(&codec.encode(&samples)?)?, samples);
+        assert!(ZarrCodec::new("int16")?.decode(&encoded).is_err());
+        assert!(codec.encode(&samples[..5]).is_err());
+
+        assert!(ZarrCodec::new("complex64").is_err());
+        assert!(ZarrCodec::new("int32")?.with_scale(10.0).is_err());
+        assert!(ZarrCodec::from_metadata("int64", r#"{"name": "zstd"}"#).is_err());
+        assert!(
+            ZarrCodec::from_metadata(
+                "int64",
+                r#"{"name": "cydec", "configuration": {"level": 3}}"#
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+}
@@ -130,12 +130,7 @@ fn test_time_series_compression_ratio() -> Result<()> {
     let codec = IntegerCodec::default();
 
     // Simulate time-series: slowly changing values
-    let mut data = Vec::new();
-    let mut val = 1000i64;
-    for _ in 0..10_000 {
-        data.push(val);
-        val += 1; // Small delta
-    }
+    let data: Vec<i64> = (1000..11_000).collect(); // Small delta
 
     let compressed = codec.compress_i64(&data)?;
 
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use cydec::{FloatingCodec, IntegerCodec};
+use cydec::{CydecError, FloatingCodec, IntegerCodec};
 
 // Empty data edge cases
 
@@ -237,6 +237,36 @@ fn test_u32_extremes() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_i16_extremes() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let data = vec![i16::MIN, i16::MAX, 0, i16::MIN];
+    let compressed = codec.compress_i16(&data)?;
+    let decompressed = codec.decompress_i16(&compressed)?;
+    assert_eq!(data, decompressed);
+    Ok(())
+}
+
+#[test]
+fn test_i8_extremes() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let data = vec![i8::MIN, i8::MAX, 0, -1, i8::MIN];
+    let compressed = codec.compress_i8(&data)?;
+    let decompressed = codec.decompress_i8(&compressed)?;
+    assert_eq!(data, decompressed);
+    Ok(())
+}
+
+#[test]
+fn test_u16_extremes() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let data = vec![0u16, u16::MAX, 0];
+    let compressed = codec.compress_u16(&data)?;
+    let decompressed = codec.decompress_u16(&compressed)?;
+    assert_eq!(data, decompressed);
+    Ok(())
+}
+
 // Very large datasets
 
 #[test]
@@ -340,7 +370,12 @@ fn test_corrupted_version() -> Result<()> {
 
     let result = codec.decompress_i64(&compressed);
     assert!(result.is_err(), "Should fail with bad version");
-    assert!(result.unwrap_err().to_string().contains("bad version"));
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported version 99")
+    );
     Ok(())
 }
 
@@ -375,6 +410,43 @@ fn test_truncated_blob() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_error_variants() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let compressed = codec.compress_i64(&[1i64, 2, 3])?;
+
+    let mut bad = compressed.clone();
+    bad[0] = b'X';
+    assert!(matches!(
+        codec.decompress_i64(&bad),
+        Err(CydecError::BadMagic)
+    ));
+
+    let mut bad = compressed.clone();
+    bad[5] = 99;
+    assert!(matches!(
+        codec.decompress_i64(&bad),
+        Err(CydecError::UnsupportedVersion { found: 99, max: 2 })
+    ));
+
+    assert!(matches!(
+        codec.decompress_u64(&compressed),
+        Err(CydecError::TypeMismatch {
+            expected: "u64",
+            found: 0
+        })
+    ));
+
+    assert!(matches!(
+        codec.decompress_i64(&compressed[0..10]),
+        Err(CydecError::Truncated {
+            needed: 16,
+            got: 10
+        })
+    ));
+    Ok(())
+}
+
 #[test]
 fn test_empty_blob_decompression() -> Result<()> {
     let codec = IntegerCodec::default();
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use cydec::{CydecError, FloatingCodec, IntegerCodec, format};
+
+// Blobs written by earlier releases, frozen byte for byte. Every future
+// build must keep reading them, so never regenerate them.
+
+const I64_V1: &str = "435944454301010005000000000000000700000070020202c201d101";
+const U64_V1: &str = "435944454301010104000000000000000d000000d0070001f7ffffffffffffffff01";
+const I32_V1: &str = "435944454301010203000000000000000300000030010202";
+const BYTES_V1: &str = "4359444543010104080000000000000008000000806379646563207631";
+const F64_V1: &str = "4359444543010104030000000000000000000000000059400600000060ac029601d704";
+const F32_V1: &str = "435944454301010502000000000000000000c8420300000030649601";
+const I64_V2_METADATA: &str = "435944454302010003000000000000001000000001000a0000000400756e697402006d73435944454301010003000000000000000300000030141414";
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_frozen_v1_blobs_decode() -> Result<()> {
+    let ints = IntegerCodec::default();
+    let floats = FloatingCodec::default();
+    assert_eq!(ints.decompress_i64(&unhex(I64_V1))?, [1, 2, 3, 100, -5]);
+    assert_eq!(ints.decompress_u64(&unhex(U64_V1))?, [7, 7, 8, u64::MAX]);
+    assert_eq!(ints.decompress_i32(&unhex(I32_V1))?, [-1, 0, 1]);
+    assert_eq!(ints.decompress_bytes(&unhex(BYTES_V1))?, b"cydec v1");
+    assert_eq!(
+        floats.decompress_f64(&unhex(F64_V1), None)?,
+        [1.5, 2.25, -0.75]
+    );
+    assert_eq!(floats.decompress_f32(&unhex(F32_V1), None)?, [0.5, 1.25]);
+    Ok(())
+}
+
+#[test]
+fn test_frozen_v2_blob_decodes() -> Result<()> {
+    let blob = unhex(I64_V2_METADATA);
+    assert_eq!(blob[5], format::VERSION_2);
+    assert_eq!(IntegerCodec::default().decompress_i64(&blob)?, [10, 20, 30]);
+    let info = cydec::inspect(&blob)?;
+    assert_eq!(info.metadata["unit"], "ms");
+    Ok(())
+}
+
+#[test]
+fn test_newer_versions_are_rejected() -> Result<()> {
+    let mut blob = unhex(I64_V1);
+    blob[5] = format::MAX_VERSION + 1;
+    let newer =
+        |r: cydec::Result<_>| matches!(r, Err(CydecError::UnsupportedVersion { found: 3, max: 2 }));
+    let ints = IntegerCodec::default();
+    assert!(newer(ints.decompress_i64(&blob).map(drop)));
+    assert!(newer(ints.decompress_u64(&blob).map(drop)));
+    assert!(newer(
+        FloatingCodec::default()
+            .decompress_f64(&blob, None)
+            .map(drop)
+    ));
+    assert!(newer(cydec::inspect(&blob).map(drop)));
+    assert!(newer(cydec::validate(&blob)));
+    assert!(newer(cydec::Header::parse(&blob).map(drop)));
+    Ok(())
+}
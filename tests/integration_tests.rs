@@ -161,6 +161,44 @@ fn test_parallel_u64() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_parallel_i32() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let arrays: Vec<Vec<i32>> = vec![
+        vec![1, 2, 3, 4, 5],
+        vec![-10, -20, -30, -40, -50],
+        vec![i32::MIN, 0, i32::MAX],
+    ];
+    let compressed = codec.compress_many_i32(&arrays)?;
+    let decompressed = codec.decompress_many_i32(&compressed)?;
+    assert_eq!(arrays, decompressed);
+    Ok(())
+}
+
+#[test]
+fn test_parallel_u32() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let arrays: Vec<Vec<u32>> = vec![
+        vec![1, 2, 3, 4, 5],
+        vec![10, 20, 30, 40, 50],
+        vec![0, u32::MAX],
+    ];
+    let compressed = codec.compress_many_u32(&arrays)?;
+    let decompressed = codec.decompress_many_u32(&compressed)?;
+    assert_eq!(arrays, decompressed);
+    Ok(())
+}
+
+#[test]
+fn test_parallel_bytes() -> Result<()> {
+    let codec = IntegerCodec::default();
+    let arrays: Vec<Vec<u8>> = vec![b"first payload".to_vec(), vec![], vec![0xff; 1_000]];
+    let compressed = codec.compress_many_bytes(&arrays)?;
+    let decompressed = codec.decompress_many_bytes(&compressed)?;
+    assert_eq!(arrays, decompressed);
+    Ok(())
+}
+
 #[test]
 fn test_parallel_f64() -> Result<()> {
     let codec = FloatingCodec::default();
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use cydec::{
+    Backend, Candle, CandleCodec, Checksum, ColumnEncoding, CompressOptions, FloatingCodec,
+    FrameBuilder, IntegerCodec, SeriesCodec, test_vectors,
+};
+use std::collections::BTreeMap;
+
+// Every pipeline must write the same bytes on every target, so blobs from
+// 32-bit ARM and big-endian devices decode anywhere. CI runs this file on
+// such targets; the CRCs were taken on x86_64. Inputs come from integer
+// arithmetic only, never from libm.
+
+/// CRC32 of every blob written by [`blobs`]
+const PINNED: &[(&str, u32)] = &[
+    ("i64_empty", 0x00000000),
+    ("i64_single", 0x5ff782f0),
+    ("i64_ramp", 0xb8a733f3),
+    ("i64_walk", 0x0d00c679),
+    ("i64_extremes", 0xaa170817),
+    ("i64_blocked", 0xaa6f2abd),
+    ("u64_counters", 0x77b2f73f),
+    ("i32_walk", 0x644b594c),
+    ("u32_ramp", 0x867001c4),
+    ("i16_wave", 0x6f4f6a8c),
+    ("u16_ramp", 0x2d21f467),
+    ("i8_wave", 0x796bf972),
+    ("bytes_text", 0x3eee3a80),
+    ("f64_prices", 0x0f4106a1),
+    ("f64_default_scale", 0xeb3e76ca),
+    ("f32_sensor", 0x6862d247),
+    ("i64_metadata", 0xf32ecd85),
+    ("i64_lz4_frame", 0x589d2975),
+    ("i64_walk", 0xb8f13529),
+    ("i64_filtered", 0xe0d8bdf3),
+    ("i64_lz4_frame", 0x656fef99),
+    ("i64_crc32c", 0xffb7f124),
+    ("i64_xxhash64", 0x699c6ca0),
+    ("i64_xxh3", 0x4c668143),
+    ("i32", 0x966176a2),
+    ("i128", 0xb6c0a9f9),
+    ("categorical", 0x8368febc),
+    ("jagged", 0x83108875),
+    ("pcm_i16", 0x1c31d9cc),
+    ("pcm_i32", 0xe4e44e96),
+    ("matrix_i64", 0xfe110e86),
+    ("f64", 0x43d9985e),
+    ("f64_scaled", 0x433fafe9),
+    ("f32", 0x6de46363),
+    ("f64_significant_3", 0x7eae276d),
+    ("f64_significant_9", 0x7dae31a4),
+    ("f64_significant_15", 0xf60558c6),
+    ("f64_adaptive_prices", 0x835fdbd3),
+    ("f64_adaptive_wide", 0xff8f1aea),
+    ("f64_groomed", 0x66ca1fba),
+    ("track", 0xf8dc8f48),
+    ("matrix_f64", 0x03b540f7),
+    ("map", 0x815f5d4e),
+    ("candles", 0x81057677),
+    ("series", 0x807577e9),
+    ("multi_series", 0x701264a3),
+    ("frame", 0xf7400277),
+];
+
+fn lcg(seed: &mut u64) -> u64 {
+    *seed = seed
+        .wrapping_mul(6_364_136_223_846_793_005)
+        .wrapping_add(1_442_695_040_888_963_407);
+    *seed >> 33
+}
+
+fn blobs() -> Result<Vec<(String, Vec<u8>)>> {
+    let ints = IntegerCodec::default();
+    let floats = FloatingCodec::default();
+    let mut seed = 0x0dd_ba11;
+    let walk: Vec<i64> = (0..80_000)
+        .scan(0i64, |x, _| {
+            *x += lcg(&mut seed) as i64 % 2_001 - 1_000;
+            Some(*x)
+        })
+        .collect();
+    let prices: Vec<f64> = walk.iter().map(|&x| 1_000.0 + x as f64 / 64.0).collect();
+    // magnitudes from 1e-300 to 1e300 exercise every decimal exponent
+    let wide: Vec<f64> = (0..6_000)
+        .map(|i| {
+            (1 + lcg(&mut seed) % 999_983) as f64
+                * format!("1e{}", i % 601 - 300).parse::<f64>().unwrap()
+        })
+        .collect();
+
+    let mut out: Vec<(String, Vec<u8>)> = test_vectors::generate()?
+        .into_iter()
+        .map(|v| (v.name.to_string(), v.bytes))
+        .collect();
+    let mut add = |name: &str, blob: Vec<u8>| out.push((name.to_string(), blob));
+
+    add("i64_walk", ints.compress_i64(&walk)?);
+    let filtered = CompressOptions {
+        filter_bits: 10,
+        ..CompressOptions::new()
+    };
+    add("i64_filtered", ints.compress_i64_with(&walk, &filtered)?);
+    let frame = CompressOptions {
+        backend: Backend::Lz4Frame,
+        ..CompressOptions::new()
+    };
+    add("i64_lz4_frame", ints.compress_i64_with(&walk, &frame)?);
+    for (name, checksum) in [
+        ("i64_crc32c", Checksum::Crc32c),
+        ("i64_xxhash64", Checksum::XxHash64),
+        ("i64_xxh3", Checksum::Xxh3),
+    ] {
+        let options = CompressOptions {
+            checksum,
+            metadata: BTreeMap::from([("unit".to_string(), "ms".to_string())]),
+            ..CompressOptions::new()
+        };
+        add(name, ints.compress_i64_with(&walk[..1_000], &options)?);
+    }
+    add(
+        "i32",
+        ints.compress_i32(&walk.iter().map(|&x| x as i32).collect::<Vec<_>>())?,
+    );
+    add("i128", ints.compress_i128(&[i128::MIN, -1, 0, i128::MAX])?);
+    add(
+        "categorical",
+        ints.compress_categorical(&walk.iter().map(|&x| (x & 7) as u32).collect::<Vec<_>>())?,
+    );
+    add(
+        "jagged",
+        ints.compress_jagged_i64(&[walk[..5].to_vec(), vec![], walk[5..9].to_vec()])?,
+    );
+    add(
+        "pcm_i16",
+        ints.compress_pcm_i16(&walk.iter().map(|&x| x as i16).collect::<Vec<_>>())?,
+    );
+    add(
+        "pcm_i32",
+        ints.compress_pcm_i32(&walk.iter().map(|&x| x as i32 * 3).collect::<Vec<_>>())?,
+    );
+    add(
+        "matrix_i64",
+        ints.compress_matrix_i64(&walk[..600], 100, 6)?,
+    );
+
+    add("f64", floats.compress_f64(&prices, None)?);
+    add("f64_scaled", floats.compress_f64(&prices, Some(64.0))?);
+    add(
+        "f32",
+        floats.compress_f32(&prices.iter().map(|&x| x as f32).collect::<Vec<_>>(), None)?,
+    );
+    for digits in [3, 9, 15] {
+        add(
+            &format!("f64_significant_{digits}"),
+            floats.compress_f64_significant(&wide, digits)?,
+        );
+    }
+    add(
+        "f64_adaptive_prices",
+        floats.compress_f64_adaptive(&prices)?,
+    );
+    add("f64_adaptive_wide", floats.compress_f64_adaptive(&wide)?);
+    add("f64_groomed", floats.compress_f64_groomed(&wide, 20)?);
+    let track: Vec<(f64, f64)> = walk[..2_000]
+        .iter()
+        .map(|&x| (x as f64 / 1e4 % 90.0, x as f64 / 3e3 % 180.0))
+        .collect();
+    add("track", floats.compress_track(&track, 6)?);
+    add(
+        "matrix_f64",
+        floats.compress_matrix_f64(&prices[..600], 100, 6, None)?,
+    );
+    let map: BTreeMap<u64, f64> = (0..500).map(|i| (i * 37, prices[i as usize])).collect();
+    add("map", floats.compress_map(&map, None)?);
+
+    let candles: Vec<Candle> = (0..1_000)
+        .map(|i| Candle {
+            timestamp: 1_700_000_000_000 + i as i64 * 60_000,
+            open: prices[i],
+            high: prices[i] + 0.5,
+            low: prices[i] - 0.25,
+            close: prices[i + 1],
+            volume: (walk[i] & 0xfff) as f64,
+        })
+        .collect();
+    add("candles", CandleCodec::default().compress(&candles)?);
+    let ts: Vec<i64> = (0..5_000).map(|i| 1_700_000_000_000 + i * 1_000).collect();
+    let series = SeriesCodec::default();
+    add("series", series.compress(&ts, &prices[..5_000])?);
+    add(
+        "multi_series",
+        series.compress_multi(&ts, &[&prices[..5_000], &wide[..5_000]])?,
+    );
+
+    let mut builder = FrameBuilder::new().with_encodings(BTreeMap::from([
+        ("ts".to_string(), ColumnEncoding::DeltaOfDelta),
+        ("price".to_string(), ColumnEncoding::Xor),
+        ("volume".to_string(), ColumnEncoding::Varint),
+    ]));
+    builder.add("ts", &ts)?;
+    builder.add("price", &prices[..5_000])?;
+    builder.add(
+        "volume",
+        &walk[..5_000]
+            .iter()
+            .map(|&x| x.unsigned_abs())
+            .collect::<Vec<_>>(),
+    )?;
+    builder.add("level", &walk[..5_000])?;
+    add("frame", builder.finish()?);
+    Ok(out)
+}
+
+#[test]
+fn test_output_matches_pinned_bytes() -> Result<()> {
+    let blobs = blobs()?;
+    let crcs: Vec<(&str, u32)> = blobs
+        .iter()
+        .map(|(name, blob)| (name.as_str(), crc32fast::hash(blob)))
+        .collect();
+    assert_eq!(crcs, PINNED);
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use cydec::Result;
 use cydec::{FloatingCodec, IntegerCodec};
 use proptest::prelude::*;
 
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use cydec::{
+    CandleCodec, Checksum, ColumnEncoding, CompressOptions, CydecReader, FloatingCodec, Frame,
+    FrameBuilder, IntegerCodec, SeriesCodec, test_vectors,
+};
+use proptest::prelude::*;
+
+// Damaged and crafted blobs must come back as errors from every decoder,
+// never as a panic, an overflow or an allocation the input cannot back.
+
+fn seeds() -> Result<Vec<Vec<u8>>> {
+    let ints = IntegerCodec::default();
+    let floats = FloatingCodec::default();
+    let values: Vec<i64> = (0..300).map(|i| i * i % 97 - 40).collect();
+    let prices: Vec<f64> = values.iter().map(|&x| x as f64 * 0.25).collect();
+    let mut seeds: Vec<Vec<u8>> = test_vectors::generate()?
+        .into_iter()
+        .map(|v| v.bytes)
+        .filter(|b| !b.is_empty() && b.len() < 20_000)
+        .collect();
+    seeds.push(ints.compress_i128(&[1, -5, i128::MAX])?);
+    seeds.push(ints.compress_uuid_bytes(&[[7; 16], [9; 16]])?);
+    seeds.push(ints.compress_ipv6(&["::1".parse()?])?);
+    seeds.push(ints.compress_categorical(&[1u32, 5, 1, 9])?);
+    seeds.push(ints.compress_jagged_i64(&[vec![1, 2], vec![], vec![3]])?);
+    seeds.push(ints.compress_pcm_i16(&[1, 5, -3, 8, 100, -100])?);
+    seeds.push(ints.compress_matrix_i64(&values[..30], 5, 6)?);
+    seeds.push(floats.compress_track(&[(51.5, -0.1), (51.6, -0.2)], 5)?);
+    seeds.push(floats.compress_f64_adaptive(&prices)?);
+    seeds.push(floats.compress_f64_groomed(&prices, 20)?);
+    seeds.push(SeriesCodec::default().compress_multi(&values, &[&prices, &prices])?);
+    let filtered = CompressOptions {
+        filter_bits: 10,
+        checksum: Checksum::Crc32c,
+        ..CompressOptions::new()
+    };
+    seeds.push(ints.compress_i64_with(&(0..70_000).collect::<Vec<_>>(), &filtered)?);
+    let encodings = [
+        ("ts".to_string(), ColumnEncoding::DeltaOfDelta),
+        ("price".to_string(), ColumnEncoding::Xor),
+    ];
+    let mut frame = FrameBuilder::new().with_encodings(encodings.into());
+    frame.add("ts", &values)?;
+    frame.add("price", &prices)?;
+    seeds.push(frame.finish()?);
+    Ok(seeds)
+}
+
+/// Run every decoder over `blob`, ignoring what they return
+fn decode_everything(blob: &[u8]) {
+    let ints = IntegerCodec::default();
+    let floats = FloatingCodec::default();
+    let _ = cydec::decompress_untrusted(blob, 1 << 20);
+    let _ = cydec::decompress_all(blob);
+    let _ = cydec::inspect(blob);
+    let _ = cydec::validate(blob);
+    let _ = ints.decompress_i64(blob);
+    let _ = ints.decompress_u64(blob);
+    let _ = ints.decompress_i32(blob);
+    let _ = ints.decompress_u16(blob);
+    let _ = ints.decompress_i128(blob);
+    let _ = ints.decompress_bytes(blob);
+    let _ = ints.decompress_uuid_bytes(blob);
+    let _ = ints.decompress_ipv6(blob);
+    let _ = ints.decompress_categorical::<u32>(blob);
+    let _ = ints.decompress_jagged_i64(blob);
+    let _ = ints.decompress_pcm_i16(blob);
+    let _ = ints.decompress_matrix_i64(blob);
+    let _ = floats.decompress_f64(blob, None);
+    let _ = floats.decompress_f64_range(blob, 3..70, None);
+    let _ = floats.decompress_f32(blob, None);
+    let _ = floats.decompress_track(blob);
+    let _ = floats.decompress_map(blob, None);
+    let _ = CandleCodec::default().decompress(blob);
+    let _ = SeriesCodec::default().decompress_multi(blob);
+    let _ = CydecReader::<_, i64>::new(blob).take(1 << 20).count();
+    if let Ok(frame) = Frame::from_bytes(blob) {
+        for column in frame.columns() {
+            let _ = frame.column::<i64>(&column.name);
+            let _ = frame.column::<f64>(&column.name);
+        }
+    }
+}
+
+#[test]
+fn test_mutated_blobs_never_panic() -> Result<()> {
+    let seeds = seeds()?;
+    let mut state = 0x5eed_u64;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize
+    };
+    for round in 0..6_000 {
+        let mut blob = seeds[round % seeds.len()].clone();
+        let at = next() % blob.len();
+        match next() % 6 {
+            0 => blob[at] = next() as u8,
+            1 => blob.truncate(at),
+            2 => blob.insert(at, next() as u8),
+            // the fields decoders size things by
+            3 => {
+                let n = [0, 1, 129, 1 << 40, u32::MAX as u64 + 1, u64::MAX][next() % 6];
+                blob[8..16].copy_from_slice(&n.to_le_bytes());
+            }
+            4 => blob[7] = (next() % 39) as u8 | [0, 0x80, 0xc0, 0xe0][next() % 4],
+            _ => {
+                blob.truncate(16 + next() % 8);
+                blob.extend((0..next() % 300).map(|_| next() as u8));
+            }
+        }
+        decode_everything(&blob);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn prop_arbitrary_bytes_never_panic(tail in prop::collection::vec(any::<u8>(), 0..200), codec in 0u8..4, tag in any::<u8>()) {
+        let mut blob = b"CYDEC\x01".to_vec();
+        blob.extend([codec, tag]);
+        blob.extend(tail);
+        decode_everything(&blob);
+    }
+}